@@ -0,0 +1,198 @@
+//! Cost of dispatching engine commands to a `qagame` module
+//!
+//! Run with `cargo bench --bench dispatch`. This uses no benchmark framework, each case is timed
+//! with [`Instant`](std::time::Instant) over many calls and printed in nanoseconds per call.
+//!
+//! `old` is how `vmMain` used to dispatch: every call was timed for the watchdog, decoded with
+//! `TryFrom` plus one `match` over all commands, and counted in a `BTreeMap` behind a `Mutex`, as was
+//! the watchdog budget. `new` is [`qagame::dispatch_watched`](quake3_native_vm::qagame::dispatch_watched),
+//! which [`game_module!`](quake3_native_vm::game_module) uses now.
+
+use quake3_native_vm::backend::Mock;
+use quake3_native_vm::qagame::{self, Deny, Exports, Module, Syscalls};
+use quake3_native_vm::{ffi, VmResult};
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 10_000_000;
+
+/// Does nothing, so that only the dispatch is measured
+struct Noop;
+
+impl Module for Noop {
+    fn dll_entry(_syscalls: Syscalls) -> Box<Self> {
+        Box::new(Noop)
+    }
+
+    fn init(&self, _level_time: ffi::c_int, _random_seed: ffi::c_int, _restart: bool) {}
+
+    fn shutdown(&self, _restart: bool) {}
+
+    fn client_connect(
+        &self,
+        _client_number: ffi::c_int,
+        _first_time: bool,
+        _is_bot: bool,
+    ) -> Result<(), Deny> {
+        Ok(())
+    }
+
+    fn client_think(&self, client_number: ffi::c_int) {
+        black_box(client_number);
+    }
+
+    fn client_userinfo_changed(&self, _client_number: ffi::c_int) {}
+
+    fn client_disconnect(&self, _client_number: ffi::c_int) {}
+
+    fn client_begin(&self, _client_number: ffi::c_int) {}
+
+    fn client_command(&self, _client_number: ffi::c_int) {}
+
+    fn run_frame(&self, level_time: ffi::c_int) {
+        black_box(level_time);
+    }
+
+    fn console_command(&self) -> bool {
+        false
+    }
+
+    fn botai_start_frame(&self, _level_time: ffi::c_int) -> bool {
+        false
+    }
+}
+
+/// Command counts of the old metrics
+static OLD_COMMANDS: Mutex<BTreeMap<&str, u64>> = Mutex::new(BTreeMap::new());
+
+/// Frame durations of the old metrics
+static OLD_FRAMES: Mutex<Duration> = Mutex::new(Duration::ZERO);
+
+/// Budget of the old watchdog, off
+static OLD_BUDGET: Mutex<Option<Duration>> = Mutex::new(None);
+
+/// `vmMain` of `game_module!` as before the handler table, for comparison
+fn old_vm_main(module: &dyn Module, command: ffi::c_int, args: [ffi::c_int; 12]) -> VmResult {
+    let start = Instant::now();
+    let result = old_dispatch(module, command, args);
+    if let Ok(command) = Exports::try_from(command) {
+        let budget = *OLD_BUDGET.lock().unwrap();
+        if let Some(budget) = budget {
+            if start.elapsed() > budget {
+                println!("WARNING: {} over budget", command.name());
+            }
+        }
+    }
+    result
+}
+
+/// `qagame::dispatch` as before the handler table
+fn old_dispatch(module: &dyn Module, command: ffi::c_int, args: [ffi::c_int; 12]) -> VmResult {
+    let command = match Exports::try_from(command) {
+        Ok(command) => command,
+        Err(_) => panic!("Unknown game command {:?}", command),
+    };
+    *OLD_COMMANDS
+        .lock()
+        .unwrap()
+        .entry(command.name())
+        .or_default() += 1;
+
+    match command {
+        Exports::GAME_INIT => {
+            module.init(args[0], args[1], args[2] != 0);
+            VmResult::ok()
+        }
+        Exports::GAME_SHUTDOWN => {
+            module.shutdown(args[0] != 0);
+            VmResult::ok()
+        }
+        Exports::GAME_CLIENT_CONNECT => {
+            match module.client_connect(args[0], args[1] != 0, args[2] != 0) {
+                Ok(()) => VmResult::ok(),
+                Err(deny) => deny.into(),
+            }
+        }
+        Exports::GAME_CLIENT_THINK => {
+            module.client_think(args[0]);
+            VmResult::ok()
+        }
+        Exports::GAME_CLIENT_USERINFO_CHANGED => {
+            module.client_userinfo_changed(args[0]);
+            VmResult::ok()
+        }
+        Exports::GAME_CLIENT_DISCONNECT => {
+            module.client_disconnect(args[0]);
+            VmResult::ok()
+        }
+        Exports::GAME_CLIENT_BEGIN => {
+            module.client_begin(args[0]);
+            VmResult::ok()
+        }
+        Exports::GAME_CLIENT_COMMAND => {
+            module.client_command(args[0]);
+            VmResult::ok()
+        }
+        Exports::GAME_RUN_FRAME => {
+            let start = Instant::now();
+            module.run_frame(args[0]);
+            *OLD_FRAMES.lock().unwrap() += start.elapsed();
+            VmResult::ok()
+        }
+        Exports::GAME_CONSOLE_COMMAND => VmResult::bool(module.console_command()),
+        Exports::BOTAI_START_FRAME => VmResult::bool(module.botai_start_frame(args[0])),
+    }
+}
+
+/// No traps are made, the watchdog is off
+fn no_trap(_num: ffi::intptr_t, _args: &[ffi::intptr_t]) -> ffi::intptr_t {
+    0
+}
+
+/// `vmMain` of `game_module!` now
+fn new_vm_main(module: &dyn Module, command: ffi::c_int, args: [ffi::c_int; 12]) -> VmResult {
+    let syscalls = Syscalls::with_engine(Mock(no_trap));
+    qagame::dispatch_watched(&syscalls, module, command, args)
+}
+
+type Dispatch = fn(&dyn Module, ffi::c_int, [ffi::c_int; 12]) -> VmResult;
+
+/// Mean duration of dispatching `command` with `dispatch`
+fn time(dispatch: Dispatch, module: &dyn Module, command: Exports) -> Duration {
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        let mut args = [0; 12];
+        args[0] = i as ffi::c_int;
+        black_box(dispatch(
+            black_box(module),
+            black_box(command as ffi::c_int),
+            args,
+        ));
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let module = Noop;
+    let dispatchers: [(&str, Dispatch); 2] = [("old", old_vm_main), ("new", new_vm_main)];
+
+    for command in [
+        Exports::GAME_CLIENT_THINK,
+        Exports::GAME_RUN_FRAME,
+        Exports::GAME_CLIENT_COMMAND,
+    ] {
+        for (name, dispatch) in dispatchers {
+            // Warm up caches and the old metrics map
+            time(dispatch, &module, command);
+            let duration = time(dispatch, &module, command);
+            println!(
+                "{:<20} {:<5} {:>8.1} ns/call",
+                command.name(),
+                name,
+                duration.as_secs_f64() * 1e9
+            );
+        }
+    }
+}
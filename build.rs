@@ -0,0 +1,30 @@
+//! Generates the `raw` module with the `bindgen` feature, nothing otherwise
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "bindgen")]
+    generate_raw();
+}
+
+/// Write bindings for `include/raw.h` to `$OUT_DIR/raw.rs`
+#[cfg(feature = "bindgen")]
+fn generate_raw() {
+    use std::env;
+    use std::path::PathBuf;
+
+    println!("cargo:rerun-if-changed=include");
+
+    let bindings = bindgen::Builder::default()
+        .header("include/raw.h")
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        // `#![forbid(unsafe_code)]` rules out the generated layout tests,
+        // `raw.rs` checks layouts at compile time instead
+        .layout_tests(false)
+        .prepend_enum_name(false)
+        .generate()
+        .expect("Could not generate bindings for include/raw.h");
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("raw.rs");
+    bindings.write_to_file(out).expect("Could not write raw.rs");
+}
@@ -1,4 +1,4 @@
-use quake3_native_vm::qagame::{Module, Syscalls};
+use quake3_native_vm::qagame::{Deny, Module, Syscalls};
 use quake3_native_vm::{ffi, game_module};
 
 struct HelloQuake3 {
@@ -7,7 +7,7 @@ struct HelloQuake3 {
 
 impl Module for HelloQuake3 {
     fn dll_entry(syscalls: Syscalls) -> Box<HelloQuake3> {
-        Box::new(HelloQuake3 { syscalls: syscalls })
+        Box::new(HelloQuake3 { syscalls })
     }
 
     fn init(&self, level_time: ffi::c_int, random_seed: ffi::c_int, restart: bool) {
@@ -26,12 +26,12 @@ impl Module for HelloQuake3 {
         client_number: ffi::c_int,
         first_time: bool,
         is_bot: bool,
-    ) -> ffi::intptr_t {
+    ) -> Result<(), Deny> {
         println!(
             "client_connect: client_number={}, first_time={}, is_bot={}",
             client_number, first_time, is_bot
         );
-        todo!();
+        self.syscalls.error("client_connect: not implemented");
     }
 
     fn client_think(&self, client_number: ffi::c_int) {
@@ -69,4 +69,4 @@ impl Module for HelloQuake3 {
     }
 }
 
-game_module!(HelloQuake3);
+game_module!(HelloQuake3, builtins, crash_report);
@@ -34,7 +34,7 @@ impl NativeVM for ProxyModule {
         match rent_libloading::RentSymbol::try_new(Box::new(lib), |lib| unsafe {
             lib.get(VMMAIN_EXPORT_NAME)
         }) {
-            Ok(proxy_vm_main) => return Box::new(Self { proxy_vm_main }),
+            Ok(proxy_vm_main) => Box::new(Self { proxy_vm_main }),
             Err(e) => panic!("couldn't rent vmMain Symbol: {}", e.0),
         }
     }
@@ -0,0 +1,69 @@
+/* Excerpt of ioquake3's code/game/bg_public.h, see ../README.md */
+
+#define GAME_VERSION "baseq3-1"
+
+#define DEFAULT_GRAVITY 800
+
+#define CS_GAME_VERSION 20
+#define CS_LEVEL_START_TIME 21
+
+#define MASK_SOLID (CONTENTS_SOLID)
+#define MASK_PLAYERSOLID (CONTENTS_SOLID|CONTENTS_PLAYERCLIP|CONTENTS_BODY)
+#define MASK_DEADSOLID (CONTENTS_SOLID|CONTENTS_PLAYERCLIP)
+#define MASK_WATER (CONTENTS_WATER|CONTENTS_LAVA|CONTENTS_SLIME)
+#define MASK_OPAQUE (CONTENTS_SOLID|CONTENTS_SLIME|CONTENTS_LAVA)
+#define MASK_SHOT (CONTENTS_SOLID|CONTENTS_BODY|CONTENTS_CORPSE)
+
+typedef enum {
+	PM_NORMAL,
+	PM_NOCLIP,
+	PM_SPECTATOR,
+	PM_DEAD,
+	PM_FREEZE,
+	PM_INTERMISSION,
+	PM_SPINTERMISSION
+} pmtype_t;
+
+typedef enum {
+	STAT_HEALTH,
+	STAT_HOLDABLE_ITEM,
+	STAT_WEAPONS,
+	STAT_ARMOR,
+	STAT_DEAD_YAW,
+	STAT_CLIENTS_READY,
+	STAT_MAX_HEALTH
+} statIndex_t;
+
+typedef enum {
+	PW_NONE,
+
+	PW_QUAD,
+	PW_BATTLESUIT,
+	PW_HASTE,
+	PW_INVIS,
+	PW_REGEN,
+	PW_FLIGHT,
+
+	PW_REDFLAG,
+	PW_BLUEFLAG,
+	PW_NEUTRALFLAG,
+
+	PW_SCOUT,
+	PW_GUARD,
+	PW_DOUBLER,
+	PW_AMMOREGEN,
+	PW_INVULNERABILITY,
+
+	PW_NUM_POWERUPS
+} powerup_t;
+
+typedef enum {
+	CHAN_AUTO,
+	CHAN_LOCAL,
+	CHAN_WEAPON,
+	CHAN_VOICE,
+	CHAN_ITEM,
+	CHAN_BODY,
+	CHAN_LOCAL_SOUND,
+	CHAN_ANNOUNCER
+} soundChannel_t;
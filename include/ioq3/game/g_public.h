@@ -0,0 +1,93 @@
+/* Excerpt of ioquake3's code/game/g_public.h, see ../README.md */
+
+typedef struct {
+	entityState_t unused;
+
+	qboolean linked;
+
+	int linkcount;
+
+	int svFlags;
+
+	int singleClient;
+
+	qboolean bmodel;
+
+	vec3_t mins, maxs;
+	int contents;
+
+	vec3_t absmin, absmax;
+
+	vec3_t currentOrigin;
+	vec3_t currentAngles;
+
+	int ownerNum;
+} entityShared_t;
+
+typedef struct {
+	entityState_t s;
+	entityShared_t r;
+} sharedEntity_t;
+
+typedef enum {
+	G_PRINT,
+	G_ERROR,
+	G_MILLISECONDS,
+	G_CVAR_REGISTER,
+	G_CVAR_UPDATE,
+	G_CVAR_SET,
+	G_CVAR_VARIABLE_INTEGER_VALUE,
+	G_CVAR_VARIABLE_STRING_BUFFER,
+	G_ARGC,
+	G_ARGV,
+	G_FS_FOPEN_FILE,
+	G_FS_READ,
+	G_FS_WRITE,
+	G_FS_FCLOSE_FILE,
+	G_SEND_CONSOLE_COMMAND,
+	G_LOCATE_GAME_DATA,
+	G_DROP_CLIENT,
+	G_SEND_SERVER_COMMAND,
+	G_SET_CONFIGSTRING,
+	G_GET_CONFIGSTRING,
+	G_GET_USERINFO,
+	G_SET_USERINFO,
+	G_GET_SERVERINFO,
+	G_SET_BRUSH_MODEL,
+	G_TRACE,
+	G_POINT_CONTENTS,
+	G_IN_PVS,
+	G_IN_PVS_IGNORE_PORTALS,
+	G_ADJUST_AREA_PORTAL_STATE,
+	G_AREAS_CONNECTED,
+	G_LINKENTITY,
+	G_UNLINKENTITY,
+	G_ENTITIES_IN_BOX,
+	G_ENTITY_CONTACT,
+	G_BOT_ALLOCATE_CLIENT,
+	G_BOT_FREE_CLIENT,
+	G_GET_USERCMD,
+	G_GET_ENTITY_TOKEN,
+	G_FS_GETFILELIST,
+	G_DEBUG_POLYGON_CREATE,
+	G_DEBUG_POLYGON_DELETE,
+	G_REAL_TIME,
+	G_SNAPVECTOR,
+	G_TRACECAPSULE,
+	G_ENTITY_CONTACTCAPSULE,
+	G_FS_SEEK
+} gameImport_t;
+
+typedef enum {
+	GAME_INIT,
+	GAME_SHUTDOWN,
+	GAME_CLIENT_CONNECT,
+	GAME_CLIENT_BEGIN,
+	GAME_CLIENT_USERINFO_CHANGED,
+	GAME_CLIENT_DISCONNECT,
+	GAME_CLIENT_COMMAND,
+	GAME_CLIENT_THINK,
+	GAME_RUN_FRAME,
+	GAME_CONSOLE_COMMAND,
+	BOTAI_START_FRAME
+} gameExport_t;
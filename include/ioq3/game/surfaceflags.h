@@ -0,0 +1,32 @@
+/* Excerpt of ioquake3's code/game/surfaceflags.h, see ../README.md */
+
+#define CONTENTS_SOLID 1
+#define CONTENTS_LAVA 8
+#define CONTENTS_SLIME 16
+#define CONTENTS_WATER 32
+#define CONTENTS_FOG 64
+
+#define CONTENTS_NOTTEAM1 0x0080
+#define CONTENTS_NOTTEAM2 0x0100
+#define CONTENTS_NOBOTCLIP 0x0200
+
+#define CONTENTS_AREAPORTAL 0x8000
+
+#define CONTENTS_PLAYERCLIP 0x10000
+#define CONTENTS_MONSTERCLIP 0x20000
+#define CONTENTS_TELEPORTER 0x40000
+#define CONTENTS_JUMPPAD 0x80000
+#define CONTENTS_CLUSTERPORTAL 0x100000
+#define CONTENTS_DONOTENTER 0x200000
+#define CONTENTS_BOTCLIP 0x400000
+#define CONTENTS_MOVER 0x800000
+
+#define CONTENTS_ORIGIN 0x1000000
+
+#define CONTENTS_BODY 0x2000000
+#define CONTENTS_CORPSE 0x4000000
+#define CONTENTS_DETAIL 0x8000000
+#define CONTENTS_STRUCTURAL 0x10000000
+#define CONTENTS_TRANSLUCENT 0x20000000
+#define CONTENTS_TRIGGER 0x40000000
+#define CONTENTS_NODROP 0x80000000
@@ -0,0 +1,260 @@
+/* Excerpt of ioquake3's code/qcommon/q_shared.h, see ../README.md */
+
+typedef unsigned char byte;
+
+typedef enum {qfalse, qtrue} qboolean;
+
+typedef float vec_t;
+typedef vec_t vec3_t[3];
+
+typedef int qhandle_t;
+typedef int sfxHandle_t;
+
+#define MAX_QPATH 64
+#define MAX_STRING_CHARS 1024
+#define MAX_INFO_STRING 1024
+#define MAX_CVAR_VALUE_STRING 256
+
+#define CVAR_ARCHIVE 0x0001
+#define CVAR_USERINFO 0x0002
+#define CVAR_SERVERINFO 0x0004
+#define CVAR_SYSTEMINFO 0x0008
+#define CVAR_INIT 0x0010
+#define CVAR_LATCH 0x0020
+#define CVAR_ROM 0x0040
+#define CVAR_USER_CREATED 0x0080
+#define CVAR_TEMP 0x0100
+#define CVAR_CHEAT 0x0200
+#define CVAR_NORESTART 0x0400
+
+typedef int cvarHandle_t;
+
+typedef struct {
+	cvarHandle_t handle;
+	int modificationCount;
+	float value;
+	int integer;
+	char string[MAX_CVAR_VALUE_STRING];
+} vmCvar_t;
+
+typedef enum {
+	EXEC_NOW,
+	EXEC_INSERT,
+	EXEC_APPEND
+} cbufExec_t;
+
+#define GENTITYNUM_BITS 10
+#define MAX_CLIENTS 64
+#define MAX_GENTITIES (1<<GENTITYNUM_BITS)
+
+#define MAX_MAP_AREA_BYTES 32
+
+typedef struct cplane_s {
+	vec3_t normal;
+	float dist;
+	byte type;
+	byte signbits;
+	byte pad[2];
+} cplane_t;
+
+typedef struct {
+	qboolean allsolid;
+	qboolean startsolid;
+	float fraction;
+	vec3_t endpos;
+	cplane_t plane;
+	int surfaceFlags;
+	int contents;
+	int entityNum;
+} trace_t;
+
+#define MAX_STATS 16
+#define MAX_PERSISTANT 16
+#define MAX_POWERUPS 16
+#define MAX_WEAPONS 16
+
+#define MAX_PS_EVENTS 2
+
+typedef struct playerState_s {
+	int commandTime;
+	int pm_type;
+	int bobCycle;
+	int pm_flags;
+	int pm_time;
+
+	vec3_t origin;
+	vec3_t velocity;
+	int weaponTime;
+	int gravity;
+	int speed;
+	int delta_angles[3];
+
+	int groundEntityNum;
+
+	int legsTimer;
+	int legsAnim;
+
+	int torsoTimer;
+	int torsoAnim;
+
+	int movementDir;
+
+	vec3_t grapplePoint;
+
+	int eFlags;
+
+	int eventSequence;
+	int events[MAX_PS_EVENTS];
+	int eventParms[MAX_PS_EVENTS];
+
+	int externalEvent;
+	int externalEventParm;
+	int externalEventTime;
+
+	int clientNum;
+	int weapon;
+	int weaponstate;
+
+	vec3_t viewangles;
+	int viewheight;
+
+	int damageEvent;
+	int damageYaw;
+	int damagePitch;
+	int damageCount;
+
+	int stats[MAX_STATS];
+	int persistant[MAX_PERSISTANT];
+	int powerups[MAX_POWERUPS];
+	int ammo[MAX_WEAPONS];
+
+	int generic1;
+	int loopSound;
+	int jumppad_ent;
+
+	int ping;
+	int pmove_framecount;
+	int jumppad_frame;
+	int entityEventSequence;
+} playerState_t;
+
+typedef struct usercmd_s {
+	int serverTime;
+	int angles[3];
+	int buttons;
+	byte weapon;
+	signed char forwardmove, rightmove, upmove;
+} usercmd_t;
+
+#define SNAPFLAG_RATE_DELAYED 1
+#define SNAPFLAG_NOT_ACTIVE 2
+#define SNAPFLAG_SERVERCOUNT 4
+
+typedef enum {
+	TR_STATIONARY,
+	TR_INTERPOLATE,
+	TR_LINEAR,
+	TR_LINEAR_STOP,
+	TR_SINE,
+	TR_GRAVITY
+} trType_t;
+
+typedef struct {
+	trType_t trType;
+	int trTime;
+	int trDuration;
+	vec3_t trBase;
+	vec3_t trDelta;
+} trajectory_t;
+
+typedef struct entityState_s {
+	int number;
+	int eType;
+	int eFlags;
+
+	trajectory_t pos;
+	trajectory_t apos;
+
+	int time;
+	int time2;
+
+	vec3_t origin;
+	vec3_t origin2;
+
+	vec3_t angles;
+	vec3_t angles2;
+
+	int otherEntityNum;
+	int otherEntityNum2;
+
+	int groundEntityNum;
+
+	int constantLight;
+	int loopSound;
+
+	int modelindex;
+	int modelindex2;
+	int clientNum;
+	int frame;
+
+	int solid;
+
+	int event;
+	int eventParm;
+
+	int powerups;
+	int weapon;
+	int legsAnim;
+	int torsoAnim;
+
+	int generic1;
+} entityState_t;
+
+typedef struct orientation_s {
+	vec3_t origin;
+	vec3_t axis[3];
+} orientation_t;
+
+typedef enum {
+	FS_READ,
+	FS_WRITE,
+	FS_APPEND,
+	FS_APPEND_SYNC
+} fsMode_t;
+
+typedef enum {
+	FMV_IDLE,
+	FMV_PLAY,
+	FMV_EOF,
+	FMV_ID_BLT,
+	FMV_ID_IDLE,
+	FMV_LOOPED,
+	FMV_ID_WAIT
+} e_status;
+
+#define CIN_system 1
+#define CIN_loop 2
+#define CIN_hold 4
+#define CIN_silent 8
+#define CIN_shader 16
+
+#define KEYCATCH_CONSOLE 0x0001
+#define KEYCATCH_UI 0x0002
+#define KEYCATCH_MESSAGE 0x0004
+#define KEYCATCH_CGAME 0x0008
+
+typedef enum {
+	CA_UNINITIALIZED,
+	CA_DISCONNECTED,
+	CA_AUTHORIZING,
+	CA_CONNECTING,
+	CA_CHALLENGING,
+	CA_CONNECTED,
+	CA_LOADING,
+	CA_PRIMED,
+	CA_ACTIVE,
+	CA_CINEMATIC
+} connstate_t;
+
+#define CS_SERVERINFO 0
+#define CS_SYSTEMINFO 1
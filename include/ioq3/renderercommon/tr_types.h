@@ -0,0 +1,76 @@
+/* Excerpt of ioquake3's code/renderercommon/tr_types.h, see ../README.md */
+
+typedef enum {
+	RT_MODEL,
+	RT_POLY,
+	RT_SPRITE,
+	RT_BEAM,
+	RT_RAIL_CORE,
+	RT_RAIL_RINGS,
+	RT_LIGHTNING,
+	RT_PORTALSURFACE,
+
+	RT_MAX_REF_ENTITY_TYPE
+} refEntityType_t;
+
+typedef struct {
+	refEntityType_t reType;
+	int renderfx;
+
+	qhandle_t hModel;
+
+	vec3_t lightingOrigin;
+	float shadowPlane;
+
+	vec3_t axis[3];
+	qboolean nonNormalizedAxes;
+	vec3_t origin;
+	int frame;
+
+	vec3_t oldorigin;
+	int oldframe;
+	float backlerp;
+
+	int skinNum;
+	qhandle_t customSkin;
+	qhandle_t customShader;
+
+	byte shaderRGBA[4];
+	float shaderTexCoord[2];
+	float shaderTime;
+
+	float radius;
+	float rotation;
+} refEntity_t;
+
+typedef enum {
+	STEREO_CENTER,
+	STEREO_LEFT,
+	STEREO_RIGHT
+} stereoFrame_t;
+
+#define GLYPH_START 0
+#define GLYPH_END 255
+#define GLYPHS_PER_FONT GLYPH_END - GLYPH_START + 1
+
+typedef struct {
+	int height;
+	int top;
+	int bottom;
+	int pitch;
+	int xSkip;
+	int imageWidth;
+	int imageHeight;
+	float s;
+	float t;
+	float s2;
+	float t2;
+	qhandle_t glyph;
+	char shaderName[32];
+} glyphInfo_t;
+
+typedef struct {
+	glyphInfo_t glyphs [GLYPHS_PER_FONT];
+	float glyphScale;
+	char name[MAX_QPATH];
+} fontInfo_t;
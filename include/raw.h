@@ -0,0 +1,8 @@
+/* Input of `bindgen` for the `raw` module, see `build.rs` */
+#include "ioq3/qcommon/q_shared.h"
+#include "ioq3/renderercommon/tr_types.h"
+#include "ioq3/game/surfaceflags.h"
+#include "ioq3/game/bg_public.h"
+#include "ioq3/game/g_public.h"
+#include "ioq3/cgame/cg_public.h"
+#include "ioq3/ui/ui_public.h"
@@ -0,0 +1,41 @@
+#!/bin/sh
+# Copy the module interface headers of the engines verbatim into `include/upstream/`
+#
+# Usage: include/vendor.sh <ioquake3 commit> <Quake III Arena commit>
+#
+# Commits can be given as branch names, e.g. `HEAD`, and are resolved to full hashes,
+# which are written to `COMMIT` next to the headers. Commit the result to pin them.
+# `tests/trap_numbers.rs` checks the trap numbers against these headers.
+set -eu
+
+if [ $# -ne 2 ]; then
+    echo "usage: $0 <ioquake3 commit> <Quake III Arena commit>" >&2
+    exit 1
+fi
+
+cd "$(dirname "$0")"
+
+vendor() {
+    repo=$1
+    commit=$2
+    dir=upstream/$3
+
+    case $commit in
+    *[!0-9a-f]* | "") commit=$(git ls-remote "https://github.com/$repo" "$commit" | cut -f1 | head -n1) ;;
+    esac
+    if [ ${#commit} -ne 40 ]; then
+        echo "Could not resolve $2 in $repo" >&2
+        exit 1
+    fi
+
+    rm -rf "$dir"
+    for header in game/g_public.h cgame/cg_public.h ui/ui_public.h; do
+        mkdir -p "$dir/$(dirname "$header")"
+        curl -fsSL -o "$dir/$header" "https://raw.githubusercontent.com/$repo/$commit/code/$header"
+    done
+    echo "$commit" >"$dir/COMMIT"
+    echo "$repo $commit"
+}
+
+vendor ioquake/ioq3 "$1" ioq3
+vendor id-Software/Quake-III-Arena "$2" quake3
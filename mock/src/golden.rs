@@ -0,0 +1,324 @@
+//! Golden files of the commands a module got and what it did
+//!
+//! A golden file records [`Step`](Step)s, i.e. the commands from the engine with their arguments, together with
+//! the syscalls the module made and the value it returned for each, as run by [`lockstep::run`](lockstep::run).
+//! Tests replay the steps from the file and fail on the first line that differs, which catches regressions
+//! in [`dispatch`](quake3_native_vm::qagame::dispatch) as well as in the module.
+//!
+//! Golden files are plain text, so changes show up in code review:
+//!
+//! ```text
+//! # Golden trace, update with UPDATE_GOLDEN=1
+//! dll_entry
+//!   G_CVAR_REGISTER(_, "g_echo", "1", 1)
+//!   = 0
+//! > GAME_CLIENT_COMMAND 2
+//! argv "say" "hello"
+//!   G_ARGC()
+//!   G_ARGV(1, _, 1024)
+//!   G_SEND_SERVER_COMMAND(2, "print \"hello\n\"")
+//!   = 0
+//! ```
+//!
+//! Lines starting with `#` are comments. Steps start with `>`, followed by their modifiers, e.g. `argv`,
+//! and the trace of the step, which is indented.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! # use quake3_native_vm::qagame::Module;
+//! # fn test<MyGame: Module>() {
+//! use quake3_native_vm_mock::golden;
+//! use quake3_native_vm_mock::lockstep::Step;
+//! use quake3_native_vm_mock::MockEngine;
+//! use quake3_native_vm::qagame::Exports;
+//!
+//! let steps = [
+//!     Step::new(Exports::GAME_INIT, &[0, 1234, 0]),
+//!     Step::new(Exports::GAME_CLIENT_COMMAND, &[2]).argv(&["say", "hello"]),
+//! ];
+//! golden::assert_golden::<MyGame>(MockEngine::new(), "tests/golden/say.txt", &steps);
+//! # }
+//! ```
+
+use crate::lockstep::{self, Step, Trace};
+use crate::MockEngine;
+use quake3_native_vm::ffi;
+use quake3_native_vm::qagame::{Exports, Module};
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::Path;
+
+/// Environment variable to set to write the golden files instead of checking them, e.g. `UPDATE_GOLDEN=1 cargo test`
+pub const UPDATE_VAR: &str = "UPDATE_GOLDEN";
+
+const HEADER: &str = "# Golden trace, update with UPDATE_GOLDEN=1\n";
+
+/// Error for golden files that can't be read or don't match
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GoldenError {
+    /// Line `line` of the golden file is not a step, modifier or trace line
+    Parse {
+        /// Line number in the golden file, starting at `1`
+        line: usize,
+        /// What is wrong with the line
+        message: String,
+    },
+    /// Line `line` of the golden file differs from what the module did
+    Mismatch {
+        /// Line number in the golden file, starting at `1`, or one past the end if lines are missing
+        line: usize,
+        /// Line in the golden file, empty if it ended
+        expected: String,
+        /// What the module did instead, empty if it did less
+        actual: String,
+    },
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            Self::Mismatch {
+                line,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "line {} differs\nexpected: {}\n  actual: {}",
+                line, expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for GoldenError {}
+
+/// Golden file for `traces` of [`lockstep::run`](lockstep::run) with `steps`
+fn render(steps: &[Step], traces: &[Trace]) -> String {
+    let mut text = String::from(HEADER);
+    let mut traces = traces.iter();
+    if let Some(trace) = traces.next() {
+        writeln!(text, "dll_entry\n{}", trace).unwrap();
+    }
+    for (step, trace) in steps.iter().zip(traces) {
+        let len = step
+            .args
+            .iter()
+            .rposition(|&arg| arg != 0)
+            .map_or(0, |i| i + 1);
+        write!(text, "> {}", step.command.name()).unwrap();
+        for arg in &step.args[..len] {
+            write!(text, " {}", arg).unwrap();
+        }
+        text.push('\n');
+        if let Some(argv) = &step.argv {
+            text.push_str("argv");
+            for arg in argv {
+                write!(text, " {:?}", arg).unwrap();
+            }
+            text.push('\n');
+        }
+        for (client_number, userinfo) in &step.userinfo {
+            writeln!(text, "userinfo {} {:?}", client_number, userinfo).unwrap();
+        }
+        writeln!(text, "{}", trace).unwrap();
+    }
+    text
+}
+
+/// Golden file of module `M` run with `steps` against `engine`
+pub fn record<M: Module>(engine: MockEngine, steps: &[Step]) -> String {
+    render(steps, &lockstep::run::<M>(engine, steps))
+}
+
+/// Parse a string quoted like by `{:?}` from the start of `text`, returning it and the rest of `text`
+fn parse_quoted(text: &str) -> Result<(String, &str), String> {
+    let mut chars = text
+        .strip_prefix('"')
+        .ok_or_else(|| format!("expected a quoted string at {:?}", text))?
+        .char_indices();
+    let mut value = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &text[1 + i + 1..])),
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some('0') => value.push('\0'),
+                Some(c @ ('\\' | '"' | '\'')) => value.push(c),
+                Some('u') => {
+                    let hex: String = chars
+                        .by_ref()
+                        .map(|(_, c)| c)
+                        .skip_while(|&c| c == '{')
+                        .take_while(|&c| c != '}')
+                        .collect();
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("invalid escape \\u{{{}}}", hex))?;
+                    value.push(c);
+                }
+                c => return Err(format!("invalid escape {:?}", c)),
+            },
+            c => value.push(c),
+        }
+    }
+    Err("unterminated string".to_owned())
+}
+
+/// Parse a step line, without the `> `
+fn parse_step(text: &str) -> Result<Step, String> {
+    let mut words = text.split_whitespace();
+    let name = words.next().ok_or("missing command")?;
+    let command = Exports::ALL
+        .iter()
+        .copied()
+        .find(|command| command.name() == name)
+        .ok_or_else(|| format!("unknown command {}", name))?;
+    let args = words
+        .map(|arg| arg.parse().map_err(|_| format!("invalid argument {}", arg)))
+        .collect::<Result<Vec<ffi::c_int>, String>>()?;
+    if args.len() > 12 {
+        return Err(format!("too many arguments for {}", name));
+    }
+    Ok(Step::new(command, &args))
+}
+
+/// Apply the modifier line `text` to `step`, returning whether it was one
+fn parse_modifier(step: &mut Step, text: &str) -> Result<bool, String> {
+    if let Some(mut rest) = text.strip_prefix("argv") {
+        let mut argv = Vec::new();
+        while let Some(quoted) = rest.strip_prefix(' ') {
+            let (arg, tail) = parse_quoted(quoted)?;
+            argv.push(arg);
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            return Err(format!("unexpected {:?}", rest));
+        }
+        step.argv = Some(argv);
+        Ok(true)
+    } else if let Some(rest) = text.strip_prefix("userinfo ") {
+        let (client_number, quoted) = rest.split_once(' ').ok_or("missing userinfo")?;
+        let client_number = client_number
+            .parse()
+            .map_err(|_| format!("invalid client number {}", client_number))?;
+        let (userinfo, rest) = parse_quoted(quoted)?;
+        if !rest.is_empty() {
+            return Err(format!("unexpected {:?}", rest));
+        }
+        step.userinfo.push((client_number, userinfo));
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Steps recorded in the golden file `text`
+pub fn parse_steps(text: &str) -> Result<Vec<Step>, GoldenError> {
+    let mut steps: Vec<Step> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let error = |message| GoldenError::Parse {
+            line: i + 1,
+            message,
+        };
+        if line.is_empty() || line.starts_with('#') || line.starts_with("  ") || line == "dll_entry"
+        {
+            continue;
+        }
+        if let Some(step) = line.strip_prefix("> ") {
+            steps.push(parse_step(step).map_err(error)?);
+            continue;
+        }
+        let modified = match steps.last_mut() {
+            Some(step) => parse_modifier(step, line).map_err(error)?,
+            None => false,
+        };
+        if !modified {
+            return Err(error(format!("unexpected line {:?}", line)));
+        }
+    }
+    Ok(steps)
+}
+
+/// Lines of `text` with their line number, without comments
+fn significant_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim_end()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Compare the golden file `expected` with `actual`, line by line
+fn compare(expected: &str, actual: &str) -> Result<(), GoldenError> {
+    let mut expected_lines = significant_lines(expected);
+    let mut actual_lines = significant_lines(actual).map(|(_, line)| line);
+    let mut last = 0;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return Ok(()),
+            (Some((line, expected)), actual) if Some(expected) != actual => {
+                return Err(GoldenError::Mismatch {
+                    line,
+                    expected: expected.to_owned(),
+                    actual: actual.unwrap_or_default().to_owned(),
+                })
+            }
+            (Some((line, _)), _) => last = line,
+            (None, Some(actual)) => {
+                return Err(GoldenError::Mismatch {
+                    line: last + 1,
+                    expected: String::new(),
+                    actual: actual.to_owned(),
+                })
+            }
+        }
+    }
+}
+
+/// Replay the steps of the golden file `text` with module `M` against `engine` and compare what it did
+pub fn replay<M: Module>(engine: MockEngine, text: &str) -> Result<(), GoldenError> {
+    let steps = parse_steps(text)?;
+    compare(text, &record::<M>(engine, &steps))
+}
+
+/// Check module `M` run with `steps` against `engine` with the golden file at `path`
+///
+/// If the file does not exist or [`UPDATE_VAR`](UPDATE_VAR) is set, the file is written instead.
+///
+/// # Panics
+///
+/// Panics with the first difference if the golden file does not match, or if its steps differ from `steps`.
+pub fn assert_golden<M: Module>(engine: MockEngine, path: impl AsRef<Path>, steps: &[Step]) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_VAR).is_some() || !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(path, record::<M>(engine, steps)).unwrap();
+        return;
+    }
+
+    let text = fs::read_to_string(path).unwrap();
+    match parse_steps(&text) {
+        Ok(recorded) if recorded == steps => {}
+        Ok(_) => panic!(
+            "Steps in {} changed, update it with {}=1",
+            path.display(),
+            UPDATE_VAR
+        ),
+        Err(err) => panic!("{}: {}", path.display(), err),
+    }
+    if let Err(err) = replay::<M>(engine, &text) {
+        panic!(
+            "{}: {}\nUpdate it with {}=1 if this is expected",
+            path.display(),
+            err,
+            UPDATE_VAR
+        );
+    }
+}
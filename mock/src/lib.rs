@@ -0,0 +1,329 @@
+//! Mock engine to run a [`Module`](quake3_native_vm::qagame::Module) without Quake 3
+//!
+//! Implements the engine's [`Syscall`](quake3_native_vm::Syscall) in Rust with the `fixed-arity-syscall` feature.
+//! This is a separate crate for tests, so that `quake3-native-vm` itself can forbid unsafe code.
+//!
+//! The mock engine records every syscall as a [`Call`](Call), with the C strings the module passes decoded,
+//! so that the calls of two runs can be compared. It answers the traps from its own state, e.g. the
+//! arguments of the current command for `G_ARGC` and `G_ARGV` or the cvars set with `G_CVAR_SET`.
+//! Files do not exist and no entities are linked.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm_mock::{Arg, MockEngine};
+//!
+//! let mut engine = MockEngine::new().argv(&["say", "hello"]);
+//! let (result, calls) = engine.enter(|syscalls| {
+//!     syscalls.print(format!("{}\n", syscalls.args_raw()));
+//! });
+//! assert!(result.is_ok());
+//! assert_eq!(calls.last().unwrap().to_string(), r#"G_PRINT("hello\n")"#);
+//! assert_eq!(calls.last().unwrap().args, vec![Arg::Str("hello\n".into())]);
+//! ```
+
+#![deny(missing_docs, unused_imports)]
+
+pub mod golden;
+pub mod lockstep;
+
+use quake3_native_vm::engine;
+use quake3_native_vm::qagame::{Imports, Syscalls};
+use quake3_native_vm::{ffi, panic_message};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Argument of a recorded [`Call`](Call)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Arg {
+    /// Integer, e.g. a client number or length
+    Int(ffi::intptr_t),
+    /// C string passed to the engine, decoded lossily as UTF-8
+    Str(String),
+    /// Bytes passed to the engine, e.g. for `G_FS_WRITE`
+    Bytes(Vec<u8>),
+    /// Pointer to module memory the engine writes to, whose address differs between runs
+    Out,
+}
+
+impl fmt::Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arg::Int(n) => write!(f, "{}", n),
+            Arg::Str(s) => write!(f, "{:?}", s),
+            Arg::Bytes(bytes) => write!(f, "{:?}", String::from_utf8_lossy(bytes)),
+            Arg::Out => f.write_str("_"),
+        }
+    }
+}
+
+/// Syscall recorded by the [`MockEngine`](MockEngine)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Call {
+    /// Trap number as passed by the module
+    pub num: ffi::intptr_t,
+    /// The wrapped trap for [`num`](Call::num), if any
+    pub import: Option<Imports>,
+    /// Arguments of the trap, empty for unknown traps
+    pub args: Vec<Arg>,
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.import {
+            Some(import) => f.write_str(import.name())?,
+            None => write!(f, "{}", self.num)?,
+        }
+        f.write_str("(")?;
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", arg)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// How to decode an argument of a trap
+#[derive(Clone, Copy)]
+enum Kind {
+    Int,
+    Str,
+    /// Bytes with the length in the next argument
+    Bytes,
+    Out,
+}
+
+/// Argument kinds of `import`, see the [`Syscalls`](Syscalls) wrappers
+fn kinds(import: Imports) -> &'static [Kind] {
+    use Kind::*;
+    match import {
+        Imports::G_PRINT | Imports::G_ERROR => &[Str],
+        Imports::G_CVAR_REGISTER => &[Out, Str, Str, Int],
+        Imports::G_CVAR_UPDATE => &[Out],
+        Imports::G_CVAR_SET => &[Str, Str],
+        Imports::G_CVAR_VARIABLE_STRING_BUFFER => &[Str, Out, Int],
+        Imports::G_ARGC => &[],
+        Imports::G_ARGV => &[Int, Out, Int],
+        Imports::G_FS_FOPEN_FILE => &[Str, Out, Int],
+        Imports::G_FS_READ => &[Out, Int, Int],
+        Imports::G_FS_WRITE => &[Bytes, Int, Int],
+        Imports::G_FS_FCLOSE_FILE => &[Int],
+        Imports::G_SEND_CONSOLE_COMMAND => &[Int, Str],
+        Imports::G_LOCATE_GAME_DATA => &[Out, Int, Int, Out, Int],
+        Imports::G_SEND_SERVER_COMMAND => &[Int, Str],
+        Imports::G_SET_CONFIGSTRING => &[Int, Str],
+        Imports::G_GET_USERINFO => &[Int, Out, Int],
+        Imports::G_ENTITIES_IN_BOX => &[Out, Out, Out, Int],
+        Imports::G_BOT_ALLOCATE_CLIENT => &[],
+        Imports::G_BOT_FREE_CLIENT => &[Int],
+        Imports::G_GET_USERCMD => &[Int, Out],
+        Imports::G_FS_GETFILELIST => &[Str, Str, Out, Int],
+    }
+}
+
+fn decode(import: Imports, args: &[ffi::intptr_t]) -> Vec<Arg> {
+    let kinds = kinds(import);
+    kinds
+        .iter()
+        .enumerate()
+        .map(|(i, kind)| match kind {
+            Kind::Int => Arg::Int(args[i]),
+            Kind::Str if args[i] == 0 => Arg::Int(0),
+            // SAFETY: The wrappers pass NUL-terminated strings that outlive the syscall
+            Kind::Str => Arg::Str(
+                unsafe { CStr::from_ptr(args[i] as *const c_char) }
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            Kind::Bytes => {
+                let len = usize::try_from(args[i + 1]).unwrap_or(0);
+                if args[i] == 0 || len == 0 {
+                    Arg::Bytes(Vec::new())
+                } else {
+                    // SAFETY: The wrappers pass a slice and its length
+                    Arg::Bytes(
+                        unsafe { std::slice::from_raw_parts(args[i] as *const u8, len) }.to_vec(),
+                    )
+                }
+            }
+            Kind::Out => Arg::Out,
+        })
+        .collect()
+}
+
+/// Copy `text` into the buffer `ptr` of `size` bytes like `Q_strncpyz`, truncating and NUL-terminating it
+fn write_string(ptr: ffi::intptr_t, size: ffi::intptr_t, text: &[u8]) {
+    let size = usize::try_from(size).unwrap_or(0);
+    if ptr == 0 || size == 0 {
+        return;
+    }
+    // SAFETY: The wrappers pass a buffer and its size
+    let buffer = unsafe { std::slice::from_raw_parts_mut(ptr as *mut u8, size) };
+    let len = text.len().min(size - 1);
+    buffer[..len].copy_from_slice(&text[..len]);
+    buffer[len] = 0;
+}
+
+/// Engine state the traps are answered from
+#[derive(Clone, Debug, Default)]
+pub struct MockEngine {
+    argv: Vec<String>,
+    cvars: HashMap<String, String>,
+    userinfo: HashMap<ffi::c_int, String>,
+}
+
+thread_local! {
+    /// Engine and calls of the current [`MockEngine::enter`](MockEngine::enter)
+    static CURRENT: RefCell<Option<(MockEngine, Vec<Call>)>> = RefCell::new(None);
+}
+
+impl MockEngine {
+    /// Engine without cvars, userinfo or command arguments
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the arguments of the current command, `argv[0]` being the command name
+    pub fn argv(mut self, argv: &[&str]) -> Self {
+        self.set_argv(argv);
+        self
+    }
+
+    /// Set the cvar `name` to `value`
+    pub fn cvar(mut self, name: &str, value: &str) -> Self {
+        self.set_cvar(name, value);
+        self
+    }
+
+    /// Set the userinfo of `client_number`
+    pub fn userinfo(mut self, client_number: ffi::c_int, userinfo: &str) -> Self {
+        self.set_userinfo(client_number, userinfo);
+        self
+    }
+
+    /// Like [`argv`](MockEngine::argv), for an existing engine
+    pub fn set_argv(&mut self, argv: &[&str]) {
+        self.argv = argv.iter().map(|arg| arg.to_string()).collect();
+    }
+
+    /// Like [`cvar`](MockEngine::cvar), for an existing engine
+    pub fn set_cvar(&mut self, name: &str, value: &str) {
+        self.cvars.insert(name.to_owned(), value.to_owned());
+    }
+
+    /// Like [`userinfo`](MockEngine::userinfo), for an existing engine
+    pub fn set_userinfo(&mut self, client_number: ffi::c_int, userinfo: &str) {
+        self.userinfo.insert(client_number, userinfo.to_owned());
+    }
+
+    /// Value of the cvar `name`, e.g. as set by the module
+    pub fn cvar_value(&self, name: &str) -> Option<&str> {
+        self.cvars.get(name).map(String::as_str)
+    }
+
+    /// [`Syscalls`](Syscalls) into the mock engine, which only answer within [`enter`](MockEngine::enter)
+    ///
+    /// Outside of it every trap returns `0` and is not recorded.
+    pub fn syscalls() -> Syscalls {
+        Syscalls::new(syscall)
+    }
+
+    /// Run `f` with this engine answering the syscalls, returning the recorded calls
+    ///
+    /// Panics of `f` are caught and returned as their message, e.g. for `G_ERROR`.
+    pub fn enter<T>(&mut self, f: impl FnOnce(Syscalls) -> T) -> (Result<T, String>, Vec<Call>) {
+        let engine = std::mem::take(self);
+        CURRENT.with(|current| *current.borrow_mut() = Some((engine, Vec::new())));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(Self::syscalls())))
+            .map_err(|payload| panic_message(payload.as_ref()).to_owned());
+        let (engine, calls) = CURRENT
+            .with(|current| current.borrow_mut().take())
+            .expect("MockEngine::enter is not reentrant");
+        *self = engine;
+        (result, calls)
+    }
+
+    /// Record the trap `num` with `args` and answer it
+    fn handle(
+        &mut self,
+        calls: &mut Vec<Call>,
+        num: ffi::intptr_t,
+        args: &[ffi::intptr_t],
+    ) -> ffi::intptr_t {
+        let import = Imports::ALL
+            .iter()
+            .copied()
+            .find(|import| import.number(engine::current()) == num);
+        let Some(import) = import else {
+            calls.push(Call {
+                num,
+                import: None,
+                args: Vec::new(),
+            });
+            return 0;
+        };
+        let decoded = decode(import, args);
+
+        let result = match import {
+            Imports::G_CVAR_SET => {
+                if let [Arg::Str(name), Arg::Str(value)] = &decoded[..] {
+                    self.cvars.insert(name.clone(), value.clone());
+                }
+                0
+            }
+            Imports::G_CVAR_VARIABLE_STRING_BUFFER => {
+                if let Arg::Str(name) = &decoded[0] {
+                    let value = self.cvars.get(name).map_or(&[][..], |v| v.as_bytes());
+                    write_string(args[1], args[2], value);
+                }
+                0
+            }
+            Imports::G_ARGC => self.argv.len() as ffi::intptr_t,
+            Imports::G_ARGV => {
+                let arg = usize::try_from(args[0])
+                    .ok()
+                    .and_then(|n| self.argv.get(n))
+                    .map_or(&[][..], |arg| arg.as_bytes());
+                write_string(args[1], args[2], arg);
+                0
+            }
+            Imports::G_GET_USERINFO => {
+                let userinfo = self
+                    .userinfo
+                    .get(&ffi::intptr_to_int(args[0]))
+                    .map_or(&[][..], |info| info.as_bytes());
+                write_string(args[1], args[2], userinfo);
+                0
+            }
+            // No files exist
+            Imports::G_FS_FOPEN_FILE => -1,
+            _ => 0,
+        };
+
+        calls.push(Call {
+            num,
+            import: Some(import),
+            args: decoded,
+        });
+        result
+    }
+}
+
+#[rustfmt::skip]
+extern "C" fn syscall(
+    num: ffi::intptr_t,
+    a0: ffi::intptr_t, a1: ffi::intptr_t, a2: ffi::intptr_t, a3: ffi::intptr_t, a4: ffi::intptr_t,
+    a5: ffi::intptr_t, a6: ffi::intptr_t, a7: ffi::intptr_t, a8: ffi::intptr_t, a9: ffi::intptr_t,
+    a10: ffi::intptr_t, a11: ffi::intptr_t, a12: ffi::intptr_t, a13: ffi::intptr_t, a14: ffi::intptr_t,
+) -> ffi::intptr_t {
+    let args = [a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10, a11, a12, a13, a14];
+    CURRENT.with(|current| match &mut *current.borrow_mut() {
+        Some((engine, calls)) => engine.handle(calls, num, &args),
+        None => 0,
+    })
+}
@@ -0,0 +1,323 @@
+//! Run two modules in lockstep and compare their syscalls
+//!
+//! To check that a refactoring of game code preserves its behavior, run the old and the new
+//! [`Module`](quake3_native_vm::qagame::Module) with the same [`Step`](Step)s against their own [`MockEngine`](MockEngine).
+//! After every step, the syscalls and return values of both are compared, and the first difference
+//! is reported as a [`Divergence`](Divergence).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use quake3_native_vm::ffi;
+//! # use quake3_native_vm::qagame::{Deny, Module, Syscalls};
+//! use quake3_native_vm_mock::lockstep::{self, Step};
+//! use quake3_native_vm_mock::MockEngine;
+//! use quake3_native_vm::qagame::Exports;
+//!
+//! # macro_rules! greeter {
+//! #     ($ty:ident, $greet:expr) => {
+//! #         struct $ty(Syscalls);
+//! #         impl Module for $ty {
+//! #             fn dll_entry(syscalls: Syscalls) -> Box<Self> { Box::new($ty(syscalls)) }
+//! #             fn init(&self, _: ffi::c_int, _: ffi::c_int, _: bool) {}
+//! #             fn shutdown(&self, _: bool) {}
+//! #             fn client_connect(&self, _: ffi::c_int, _: bool, _: bool) -> Result<(), Deny> { Ok(()) }
+//! #             fn client_think(&self, _: ffi::c_int) {}
+//! #             fn client_userinfo_changed(&self, _: ffi::c_int) {}
+//! #             fn client_disconnect(&self, _: ffi::c_int) {}
+//! #             fn client_begin(&self, n: ffi::c_int) { $greet(self.0, n) }
+//! #             fn client_command(&self, _: ffi::c_int) {}
+//! #             fn run_frame(&self, _: ffi::c_int) {}
+//! #             fn console_command(&self) -> bool { false }
+//! #             fn botai_start_frame(&self, _: ffi::c_int) -> bool { false }
+//! #         }
+//! #     };
+//! # }
+//! # use quake3_native_vm::qagame::Target;
+//! // `OldGame` and `NewGame` greet clients in `client_begin`, with different code
+//! # greeter!(OldGame, |s: Syscalls, n| s.send_server_command(Target::Client(n), "print \"Welcome!\n\""));
+//! # greeter!(NewGame, |s: Syscalls, n| s.console_print(Target::Client(n), "Welcome!\n"));
+//! let steps = [
+//!     Step::new(Exports::GAME_INIT, &[0, 1234, 0]),
+//!     Step::new(Exports::GAME_CLIENT_BEGIN, &[3]),
+//! ];
+//! lockstep::assert_lockstep::<OldGame, NewGame>(MockEngine::new(), &steps);
+//! ```
+
+use crate::{Call, MockEngine};
+use quake3_native_vm::qagame::{dispatch, Deny, Exports, Module, Syscalls};
+use quake3_native_vm::{ffi, VmResult};
+use std::fmt;
+
+/// Input from the engine, a command with its arguments
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    /// Command for the module's dispatcher
+    pub command: Exports,
+    /// Arguments of the command, unused ones being `0`
+    pub args: [ffi::c_int; 12],
+    /// Arguments of the console or client command, for `G_ARGC` and `G_ARGV` during this step
+    pub argv: Option<Vec<String>>,
+    /// Userinfo to set before this step, e.g. for `GAME_CLIENT_CONNECT`
+    pub userinfo: Vec<(ffi::c_int, String)>,
+}
+
+impl Step {
+    /// `command` with `args`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 12 `args`.
+    pub fn new(command: Exports, args: &[ffi::c_int]) -> Self {
+        assert!(
+            args.len() <= 12,
+            "Too many arguments for {}",
+            command.name()
+        );
+        let mut padded = [0; 12];
+        padded[..args.len()].copy_from_slice(args);
+        Self {
+            command,
+            args: padded,
+            argv: None,
+            userinfo: Vec::new(),
+        }
+    }
+
+    /// Set the arguments of the current command for this step, e.g. for `GAME_CLIENT_COMMAND`
+    pub fn argv(mut self, argv: &[&str]) -> Self {
+        self.argv = Some(argv.iter().map(|arg| arg.to_string()).collect());
+        self
+    }
+
+    /// Set the userinfo of `client_number` before this step
+    pub fn userinfo(mut self, client_number: ffi::c_int, userinfo: &str) -> Self {
+        self.userinfo.push((client_number, userinfo.to_owned()));
+        self
+    }
+
+    /// Apply the engine state of this step to `engine`
+    fn prepare(&self, engine: &mut MockEngine) {
+        if let Some(argv) = &self.argv {
+            let argv: Vec<&str> = argv.iter().map(String::as_str).collect();
+            engine.set_argv(&argv);
+        }
+        for (client_number, userinfo) in &self.userinfo {
+            engine.set_userinfo(*client_number, userinfo);
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{:?}", self.command.name(), self.args)?;
+        if let Some(argv) = &self.argv {
+            write!(f, " argv {:?}", argv)?;
+        }
+        Ok(())
+    }
+}
+
+/// What a module did in one [`Step`](Step), or while being created
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace {
+    /// Syscalls in the order they were made
+    pub calls: Vec<Call>,
+    /// Value returned to the engine, or the message the module panicked with
+    pub result: Result<VmResult, String>,
+    /// Message of the [`Deny`](quake3_native_vm::qagame::Deny) returned from `GAME_CLIENT_CONNECT`,
+    /// whose raw value is only an address
+    pub deny: Option<String>,
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for call in &self.calls {
+            writeln!(f, "  {}", call)?;
+        }
+        match (&self.result, &self.deny) {
+            (Ok(_), Some(message)) => write!(f, "  = deny {:?}", message),
+            (Ok(result), None) => write!(f, "  = {}", result.into_raw()),
+            (Err(message), _) => write!(f, "  panicked: {}", message),
+        }
+    }
+}
+
+/// Where two modules diverged
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Position {
+    /// In [`Module::dll_entry`](Module::dll_entry)
+    DllEntry,
+    /// In the step with this index
+    Step(usize),
+}
+
+/// First difference between two modules run in lockstep
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// Where the modules diverged
+    pub position: Position,
+    /// The step the modules diverged in, `None` for [`Position::DllEntry`](Position::DllEntry)
+    pub step: Option<Step>,
+    /// What the first module did
+    pub left: Trace,
+    /// What the second module did
+    pub right: Trace,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.position, &self.step) {
+            (Position::Step(index), Some(step)) => {
+                writeln!(f, "Modules diverged in step {}: {}", index, step)?
+            }
+            _ => writeln!(f, "Modules diverged in dll_entry")?,
+        }
+        if let Some(index) = self
+            .left
+            .calls
+            .iter()
+            .zip(&self.right.calls)
+            .position(|(left, right)| left != right)
+        {
+            writeln!(f, "First differing syscall is #{}", index)?;
+        }
+        writeln!(f, "left:\n{}", self.left)?;
+        write!(f, "right:\n{}", self.right)
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+/// A module with its own mock engine
+struct Runner {
+    engine: MockEngine,
+    module: Box<dyn Module>,
+}
+
+impl Runner {
+    fn new<M: Module>(mut engine: MockEngine) -> (Self, Trace) {
+        let (module, calls) = engine.enter(M::dll_entry);
+        let trace = Trace {
+            calls,
+            result: module
+                .as_ref()
+                .map(|_| VmResult::ok())
+                .map_err(Clone::clone),
+            deny: None,
+        };
+        let module: Box<dyn Module> = match module {
+            Ok(module) => module,
+            // Keep going with a module that did nothing, the divergence is reported anyway
+            Err(_) => Box::new(Panicked),
+        };
+        (Self { engine, module }, trace)
+    }
+
+    fn step(&mut self, step: &Step) -> Trace {
+        step.prepare(&mut self.engine);
+        let module = &*self.module;
+        let (result, calls) = self
+            .engine
+            .enter(|_| dispatch(module, step.command as ffi::c_int, step.args));
+        let deny = match (step.command, &result) {
+            (Exports::GAME_CLIENT_CONNECT, Ok(result)) if result.is_deny() => Some(Deny::current()),
+            _ => None,
+        };
+        Trace {
+            calls,
+            result,
+            deny,
+        }
+    }
+}
+
+/// Run module `M` with `steps` against `engine`, returning a trace for [`dll_entry`](Module::dll_entry) and one per step
+pub fn run<M: Module>(engine: MockEngine, steps: &[Step]) -> Vec<Trace> {
+    let (mut runner, trace) = Runner::new::<M>(engine);
+    std::iter::once(trace)
+        .chain(steps.iter().map(|step| runner.step(step)))
+        .collect()
+}
+
+/// Run modules `A` and `B` with the same `steps` against a copy of `engine` each
+///
+/// Stops at the first step in which their syscalls or results differ.
+pub fn compare<A: Module, B: Module>(
+    engine: MockEngine,
+    steps: &[Step],
+) -> Result<(), Box<Divergence>> {
+    let (mut a, left) = Runner::new::<A>(engine.clone());
+    let (mut b, right) = Runner::new::<B>(engine);
+    if left != right {
+        return Err(Box::new(Divergence {
+            position: Position::DllEntry,
+            step: None,
+            left,
+            right,
+        }));
+    }
+
+    for (index, step) in steps.iter().enumerate() {
+        let left = a.step(step);
+        let right = b.step(step);
+        if left != right {
+            return Err(Box::new(Divergence {
+                position: Position::Step(index),
+                step: Some(step.clone()),
+                left,
+                right,
+            }));
+        }
+    }
+    Ok(())
+}
+
+/// Like [`compare`](compare), but panics with the [`Divergence`](Divergence) for use in tests
+pub fn assert_lockstep<A: Module, B: Module>(engine: MockEngine, steps: &[Step]) {
+    if let Err(divergence) = compare::<A, B>(engine, steps) {
+        panic!("{}", divergence);
+    }
+}
+
+/// Stand-in for a module whose `dll_entry` panicked
+struct Panicked;
+
+impl Module for Panicked {
+    fn dll_entry(_syscalls: Syscalls) -> Box<Self> {
+        Box::new(Panicked)
+    }
+
+    fn init(&self, _level_time: ffi::c_int, _random_seed: ffi::c_int, _restart: bool) {}
+
+    fn shutdown(&self, _restart: bool) {}
+
+    fn client_connect(
+        &self,
+        _client_number: ffi::c_int,
+        _first_time: bool,
+        _is_bot: bool,
+    ) -> Result<(), Deny> {
+        Ok(())
+    }
+
+    fn client_think(&self, _client_number: ffi::c_int) {}
+
+    fn client_userinfo_changed(&self, _client_number: ffi::c_int) {}
+
+    fn client_disconnect(&self, _client_number: ffi::c_int) {}
+
+    fn client_begin(&self, _client_number: ffi::c_int) {}
+
+    fn client_command(&self, _client_number: ffi::c_int) {}
+
+    fn run_frame(&self, _level_time: ffi::c_int) {}
+
+    fn console_command(&self) -> bool {
+        false
+    }
+
+    fn botai_start_frame(&self, _level_time: ffi::c_int) -> bool {
+        false
+    }
+}
@@ -0,0 +1,159 @@
+//! Admin commands against the mock engine
+//!
+//! Run with `cargo test -p quake3-native-vm-mock`.
+
+use quake3_native_vm::qagame::admin::{Admin, Permissions};
+use quake3_native_vm_mock::{Call, MockEngine};
+
+const PASSWORD: &str = "correct horse battery staple";
+
+/// Admin with the builtins at level 1, client 0 being an admin and clients 1 and 2 players
+fn setup() -> (Admin, MockEngine) {
+    let mut permissions = Permissions::default();
+    permissions.ips.insert("192.168.1.5".to_owned(), 2);
+    permissions.passwords.insert(PASSWORD.to_owned(), 3);
+    let mut admin = Admin::new(permissions);
+    admin.register_builtins(1);
+
+    let mut engine = MockEngine::new()
+        .userinfo(0, "\\name\\Sarge\\ip\\192.168.1.5:27960")
+        .userinfo(1, "\\name\\Visor\\ip\\10.0.0.1:27960")
+        .userinfo(2, "\\name\\Grunt\\ip\\10.0.0.2:27960");
+    let (result, _) = engine.enter(|syscalls| {
+        for client_number in 0..3 {
+            admin.client_userinfo_changed(&syscalls, client_number);
+        }
+    });
+    result.unwrap();
+    (admin, engine)
+}
+
+fn last(calls: &[Call]) -> String {
+    calls.last().unwrap().to_string()
+}
+
+#[test]
+fn console_kicks() {
+    let (admin, mut engine) = setup();
+    engine.set_argv(&["ADMIN_KICK", "1"]);
+    let (handled, calls) = engine.enter(|syscalls| admin.console_command(&syscalls));
+    assert!(handled.unwrap());
+    assert_eq!(
+        last(&calls),
+        r#"G_SEND_CONSOLE_COMMAND(2, "clientkick 1\n")"#
+    );
+}
+
+#[test]
+fn levels_from_userinfo() {
+    let (admin, _) = setup();
+    assert_eq!(admin.level(0), 2);
+    assert_eq!(admin.level(1), 0);
+    admin.client_disconnect(0);
+    assert_eq!(admin.level(0), 0);
+}
+
+#[test]
+fn guids_grant_nothing() {
+    let (admin, mut engine) = setup();
+    engine = engine.userinfo(
+        1,
+        "\\name\\Visor\\cl_guid\\8E2A0D3F9C6B41D7A5E3B2C1D0F9E8A7\\ip\\10.0.0.1:27960",
+    );
+    let (result, _) = engine.enter(|syscalls| admin.client_userinfo_changed(&syscalls, 1));
+    result.unwrap();
+    assert_eq!(admin.level(1), 0);
+}
+
+#[test]
+fn login_with_password() {
+    let (admin, mut engine) = setup();
+    engine.set_argv(&["login", "hunter2"]);
+    let (_, calls) = engine.enter(|syscalls| admin.client_command(&syscalls, 1));
+    assert_eq!(
+        last(&calls),
+        r#"G_SEND_SERVER_COMMAND(1, "print \"Wrong password.\n\"")"#
+    );
+    assert_eq!(admin.level(1), 0);
+
+    engine.set_argv(&["login", PASSWORD]);
+    let (handled, calls) = engine.enter(|syscalls| admin.client_command(&syscalls, 1));
+    assert!(handled.unwrap());
+    assert_eq!(
+        last(&calls),
+        r#"G_SEND_SERVER_COMMAND(1, "print \"Logged in with level 3.\n\"")"#
+    );
+
+    // Kept across userinfo changes, but not for the next client in the slot
+    let (result, _) = engine.enter(|syscalls| admin.client_userinfo_changed(&syscalls, 1));
+    result.unwrap();
+    assert_eq!(admin.level(1), 3);
+    admin.client_disconnect(1);
+    assert_eq!(admin.level(1), 0);
+}
+
+#[test]
+fn players_are_not_allowed() {
+    let (admin, mut engine) = setup();
+    engine.set_argv(&["mute", "2"]);
+    let (handled, calls) = engine.enter(|syscalls| admin.client_command(&syscalls, 1));
+    assert!(handled.unwrap());
+    assert_eq!(
+        last(&calls),
+        r#"G_SEND_SERVER_COMMAND(1, "print \"You are not allowed to use mute.\n\"")"#
+    );
+    assert!(!admin.is_muted(2));
+}
+
+#[test]
+fn admins_mute_players() {
+    let (admin, mut engine) = setup();
+    engine.set_argv(&["mute", "2"]);
+    let (handled, _) = engine.enter(|syscalls| admin.client_command(&syscalls, 0));
+    assert!(handled.unwrap());
+    assert!(admin.is_muted(2));
+
+    engine.set_argv(&["unmute", "2"]);
+    let (handled, _) = engine.enter(|syscalls| admin.client_command(&syscalls, 0));
+    assert!(handled.unwrap());
+    assert!(!admin.is_muted(2));
+}
+
+#[test]
+fn higher_levels_cannot_be_targeted() {
+    let (admin, mut engine) = setup();
+    let mut permissions = admin.permissions();
+    permissions.ips.insert("10.0.0.1".to_owned(), 1);
+    admin.set_permissions(permissions);
+
+    engine.set_argv(&["admin_kick", "0"]);
+    let (_, calls) = engine.enter(|syscalls| {
+        admin.client_userinfo_changed(&syscalls, 1);
+        admin.client_command(&syscalls, 1)
+    });
+    assert_eq!(admin.level(1), 1);
+    assert_eq!(
+        last(&calls),
+        r#"G_SEND_SERVER_COMMAND(1, "print \"You can't kick client 0.\n\"")"#
+    );
+}
+
+#[test]
+fn map_names_are_checked() {
+    let (admin, mut engine) = setup();
+    engine.set_argv(&["admin_map", "q3dm17"]);
+    let (_, calls) = engine.enter(|syscalls| admin.console_command(&syscalls));
+    assert_eq!(last(&calls), r#"G_SEND_CONSOLE_COMMAND(2, "map q3dm17\n")"#);
+
+    engine.set_argv(&["admin_map", "q3dm17;quit"]);
+    let (_, calls) = engine.enter(|syscalls| admin.console_command(&syscalls));
+    assert_eq!(last(&calls), r#"G_PRINT("Bad map name: q3dm17;quit\n")"#);
+}
+
+#[test]
+fn other_commands_are_not_handled() {
+    let (admin, mut engine) = setup();
+    engine.set_argv(&["say", "hello"]);
+    let (handled, _) = engine.enter(|syscalls| admin.client_command(&syscalls, 0));
+    assert!(!handled.unwrap());
+}
@@ -0,0 +1,63 @@
+//! Crash reports against the mock engine
+//!
+//! Run with `cargo test -p quake3-native-vm-mock`.
+
+use quake3_native_vm::qagame::crash::{self, CRASH_LOG};
+use quake3_native_vm::qagame::Exports;
+use quake3_native_vm_mock::MockEngine;
+
+fn panic_in_run_frame(engine: &mut MockEngine) -> Vec<String> {
+    let (result, calls) = engine.enter(|syscalls| {
+        crash::guard(&syscalls, None, Some(Exports::GAME_RUN_FRAME as _), || {
+            panic!("boom")
+        })
+    });
+    assert!(result.is_err());
+    calls.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn reports_after_unwinding() {
+    let mut engine = MockEngine::new();
+    crash::install();
+
+    let calls = panic_in_run_frame(&mut engine);
+    assert!(calls[0].starts_with(&format!("G_FS_FOPEN_FILE(\"{}\"", CRASH_LOG)));
+    assert!(calls
+        .iter()
+        .any(|call| call.starts_with("G_PRINT(\"^1panicked at 'boom', mock/tests/crash.rs:")));
+    assert!(calls
+        .last()
+        .unwrap()
+        .starts_with("G_ERROR(\"panicked at 'boom', mock/tests/crash.rs:"));
+}
+
+#[test]
+fn reinstalls_after_error() {
+    let mut engine = MockEngine::new();
+    crash::install();
+    panic_in_run_frame(&mut engine);
+
+    // Like the engine loading the module again after `G_ERROR`
+    crash::install();
+    let calls = panic_in_run_frame(&mut engine);
+    assert!(calls
+        .last()
+        .unwrap()
+        .starts_with("G_ERROR(\"panicked at 'boom'"));
+}
+
+#[test]
+fn errors_without_report() {
+    let mut engine = MockEngine::new();
+    let (result, calls) = std::thread::spawn(move || {
+        engine.enter(|syscalls| crash::guard(&syscalls, None, None, || panic!("boom")))
+    })
+    .join()
+    .unwrap();
+    assert!(result.is_err());
+    assert_eq!(
+        calls.last().unwrap().to_string(),
+        "G_ERROR(\"panicked at 'boom' in dllEntry\")"
+    );
+}
@@ -0,0 +1,123 @@
+//! Golden files of the commands a module got and what it did
+//!
+//! Run with `cargo test -p quake3-native-vm-mock`.
+//! Update the files in `tests/golden/` with `UPDATE_GOLDEN=1 cargo test -p quake3-native-vm-mock`.
+
+use quake3_native_vm::ffi;
+use quake3_native_vm::qagame::{Deny, Exports, Module, Syscalls, Target};
+use quake3_native_vm::shared::CvarFlags;
+use quake3_native_vm_mock::golden::{self, GoldenError};
+use quake3_native_vm_mock::lockstep::Step;
+use quake3_native_vm_mock::MockEngine;
+
+/// Echoes `say` commands and rejects clients named `Bad`
+struct Echo {
+    syscalls: Syscalls,
+}
+
+impl Module for Echo {
+    fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+        syscalls.cvar_register("g_echo", "1", CvarFlags::ARCHIVE);
+        Box::new(Echo { syscalls })
+    }
+
+    fn init(&self, level_time: ffi::c_int, _random_seed: ffi::c_int, restart: bool) {
+        self.syscalls
+            .print(format!("Echo at {}, restart {}\n", level_time, restart));
+    }
+
+    fn shutdown(&self, _restart: bool) {}
+
+    fn client_connect(
+        &self,
+        client_number: ffi::c_int,
+        _first_time: bool,
+        _is_bot: bool,
+    ) -> Result<(), Deny> {
+        if self
+            .syscalls
+            .get_userinfo(client_number)
+            .contains("\\name\\Bad")
+        {
+            Err(Deny::new("Bad name"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn client_think(&self, _client_number: ffi::c_int) {}
+
+    fn client_userinfo_changed(&self, _client_number: ffi::c_int) {}
+
+    fn client_disconnect(&self, _client_number: ffi::c_int) {}
+
+    fn client_begin(&self, _client_number: ffi::c_int) {}
+
+    fn client_command(&self, client_number: ffi::c_int) {
+        self.syscalls.console_print(
+            Target::Client(client_number),
+            &format!("{}\n", self.syscalls.args_raw()),
+        );
+    }
+
+    fn run_frame(&self, _level_time: ffi::c_int) {}
+
+    fn console_command(&self) -> bool {
+        false
+    }
+
+    fn botai_start_frame(&self, _level_time: ffi::c_int) -> bool {
+        false
+    }
+}
+
+fn steps() -> Vec<Step> {
+    vec![
+        Step::new(Exports::GAME_INIT, &[0, 1234, 0]),
+        Step::new(Exports::GAME_CLIENT_CONNECT, &[1, 1, 0]).userinfo(1, "\\name\\Bad"),
+        Step::new(Exports::GAME_CLIENT_CONNECT, &[2, 1, 0]).userinfo(2, "\\name\\Sarge"),
+        Step::new(Exports::GAME_CLIENT_COMMAND, &[2]).argv(&["say", "hello \"world\"", "\t!"]),
+        Step::new(Exports::GAME_SHUTDOWN, &[]),
+    ]
+}
+
+#[test]
+fn echo() {
+    golden::assert_golden::<Echo>(MockEngine::new(), "tests/golden/echo.txt", &steps());
+}
+
+#[test]
+fn steps_roundtrip() {
+    let text = golden::record::<Echo>(MockEngine::new(), &steps());
+    assert_eq!(golden::parse_steps(&text), Ok(steps()));
+    assert_eq!(golden::replay::<Echo>(MockEngine::new(), &text), Ok(()));
+}
+
+#[test]
+fn mismatch_is_reported() {
+    let text = golden::record::<Echo>(MockEngine::new(), &steps())
+        .replace("restart false", "restart true");
+    match golden::replay::<Echo>(MockEngine::new(), &text) {
+        Err(GoldenError::Mismatch {
+            line,
+            expected,
+            actual,
+        }) => {
+            assert_eq!(line, 6);
+            assert_eq!(expected, r#"  G_PRINT("Echo at 0, restart true\n")"#);
+            assert_eq!(actual, r#"  G_PRINT("Echo at 0, restart false\n")"#);
+        }
+        result => panic!("Unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn invalid_step_is_reported() {
+    assert_eq!(
+        golden::parse_steps("> GAME_FOO 1\n"),
+        Err(GoldenError::Parse {
+            line: 1,
+            message: "unknown command GAME_FOO".to_owned()
+        })
+    );
+}
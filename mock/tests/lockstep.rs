@@ -0,0 +1,138 @@
+//! Modules run in lockstep against the mock engine
+//!
+//! Run with `cargo test -p quake3-native-vm-mock`.
+
+use quake3_native_vm::ffi;
+use quake3_native_vm::qagame::{Deny, Exports, Module, Syscalls, Target};
+use quake3_native_vm::shared::CvarFlags;
+use quake3_native_vm_mock::lockstep::{self, Position, Step};
+use quake3_native_vm_mock::{Arg, MockEngine};
+
+/// Echoes `say` commands, the way it was first written
+struct Echo {
+    syscalls: Syscalls,
+}
+
+/// [`Echo`](Echo), refactored to use the higher level helpers
+struct RefactoredEcho {
+    syscalls: Syscalls,
+}
+
+/// [`Echo`](Echo), with a bug in the refactoring
+struct BrokenEcho {
+    syscalls: Syscalls,
+}
+
+macro_rules! echo {
+    ($ty:ident, |$syscalls:ident, $client_number:ident| $command:block) => {
+        impl Module for $ty {
+            fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+                syscalls.cvar_register("g_echo", "1", CvarFlags::ARCHIVE);
+                Box::new($ty { syscalls })
+            }
+
+            fn init(&self, _level_time: ffi::c_int, _random_seed: ffi::c_int, _restart: bool) {
+                self.syscalls.print("Echo\n");
+            }
+
+            fn shutdown(&self, _restart: bool) {}
+
+            fn client_connect(
+                &self,
+                _client_number: ffi::c_int,
+                _first_time: bool,
+                _is_bot: bool,
+            ) -> Result<(), Deny> {
+                Ok(())
+            }
+
+            fn client_think(&self, _client_number: ffi::c_int) {}
+
+            fn client_userinfo_changed(&self, _client_number: ffi::c_int) {}
+
+            fn client_disconnect(&self, _client_number: ffi::c_int) {}
+
+            fn client_begin(&self, _client_number: ffi::c_int) {}
+
+            fn client_command(&self, $client_number: ffi::c_int) {
+                let $syscalls = self.syscalls;
+                $command
+            }
+
+            fn run_frame(&self, _level_time: ffi::c_int) {}
+
+            fn console_command(&self) -> bool {
+                false
+            }
+
+            fn botai_start_frame(&self, _level_time: ffi::c_int) -> bool {
+                false
+            }
+        }
+    };
+}
+
+echo!(Echo, |syscalls, client_number| {
+    let mut text = String::new();
+    for n in 1..syscalls.argc() {
+        if n > 1 {
+            text.push(' ');
+        }
+        text.push_str(&syscalls.argv(n));
+    }
+    syscalls.send_server_command(
+        Target::Client(client_number),
+        format!("print \"{}\n\"", text),
+    );
+});
+
+echo!(RefactoredEcho, |syscalls, client_number| {
+    syscalls.console_print(
+        Target::Client(client_number),
+        &format!("{}\n", syscalls.args_raw()),
+    );
+});
+
+echo!(BrokenEcho, |syscalls, client_number| {
+    syscalls.console_print(Target::All, &format!("{}\n", syscalls.args_raw()));
+    let _ = client_number;
+});
+
+fn steps() -> Vec<Step> {
+    vec![
+        Step::new(Exports::GAME_INIT, &[0, 1234, 0]),
+        Step::new(Exports::GAME_CLIENT_COMMAND, &[2]).argv(&["say", "hello", "world"]),
+        Step::new(Exports::GAME_CLIENT_COMMAND, &[3]).argv(&["say"]),
+    ]
+}
+
+#[test]
+fn refactoring_is_in_lockstep() {
+    lockstep::assert_lockstep::<Echo, RefactoredEcho>(MockEngine::new(), &steps());
+}
+
+#[test]
+fn broken_refactoring_diverges() {
+    let divergence =
+        lockstep::compare::<Echo, BrokenEcho>(MockEngine::new(), &steps()).unwrap_err();
+    assert_eq!(divergence.position, Position::Step(1));
+    assert_eq!(
+        divergence.left.calls.last().unwrap().args,
+        vec![Arg::Int(2), Arg::Str("print \"hello world\n\"".into())]
+    );
+    assert_eq!(
+        divergence.right.calls.last().unwrap().args,
+        vec![Arg::Int(-1), Arg::Str("print \"hello world\n\"".into())]
+    );
+}
+
+#[test]
+fn run_records_dll_entry_and_steps() {
+    let traces = lockstep::run::<Echo>(MockEngine::new(), &steps());
+    assert_eq!(traces.len(), 4);
+    assert_eq!(
+        traces[0].calls[0].to_string(),
+        r#"G_CVAR_REGISTER(_, "g_echo", "1", 1)"#
+    );
+    assert_eq!(traces[1].calls[0].to_string(), r#"G_PRINT("Echo\n")"#);
+}
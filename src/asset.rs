@@ -0,0 +1,272 @@
+//! Registration of models, shaders, sounds and fonts for `cgame` and `ui`
+//!
+//! The engine loads an asset when it is registered and returns a handle to draw or play it with.
+//! Registering the same path again is wasted work, so the C modules keep every handle in
+//! `cgs.media` and `uiInfo`. An [`AssetCache`](AssetCache) does the same for any path,
+//! registering each one only once and counting what it registered.
+//!
+//! See [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::asset::{AssetCache, ModelHandle};
+//! use quake3_native_vm::backend::{Mock, Recording};
+//! use quake3_native_vm::cgame::Syscalls;
+//!
+//! // Every model gets handle 7
+//! let engine = Recording::new(Mock(|_, _: &[isize]| 7));
+//! let syscalls = Syscalls::with_engine(&engine);
+//!
+//! let mut assets = AssetCache::new();
+//! let rocket = assets.model(&syscalls, "models/ammo/rocket/rocket.md3");
+//! assert_eq!(rocket, ModelHandle(7));
+//! // Paths are case-insensitive like in the engine, so this is the same model
+//! assets.model(&syscalls, "models/ammo/rocket/Rocket.md3");
+//!
+//! assert_eq!(engine.take().len(), 1);
+//! assert_eq!(assets.stats().models, 1);
+//! assert_eq!(assets.stats().hits, 1);
+//! ```
+
+use crate::ffi;
+use crate::qagame::buffer_to_string;
+use crate::shared::MAX_QPATH;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Model registered with [`AssetTraps::r_register_model`](AssetTraps::r_register_model), `0` if it could not be loaded
+///
+/// See `qhandle_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ModelHandle(pub ffi::c_int);
+
+/// Shader registered with [`AssetTraps::r_register_shader`](AssetTraps::r_register_shader), `0` for the default shader
+///
+/// See `qhandle_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShaderHandle(pub ffi::c_int);
+
+/// Sound registered with [`AssetTraps::s_register_sound`](AssetTraps::s_register_sound), `0` for the default sound
+///
+/// See `sfxHandle_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SoundHandle(pub ffi::c_int);
+
+/// Number of glyphs in a [`FontInfo`](FontInfo), one per byte
+///
+/// See `GLYPHS_PER_FONT` in [ioquake3's `renderercommon/tr_types.h`](https://github.com/ioquake/ioq3/blob/master/code/renderercommon/tr_types.h).
+pub const GLYPHS_PER_FONT: usize = 256;
+
+/// Where a character is in the image of a [`FontInfo`](FontInfo), and how to draw it
+///
+/// See `glyphInfo_t` in [ioquake3's `renderercommon/tr_types.h`](https://github.com/ioquake/ioq3/blob/master/code/renderercommon/tr_types.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GlyphInfo {
+    /// Height of the character in pixels
+    pub height: ffi::c_int,
+    /// Pixels from the baseline to the top of the character
+    pub top: ffi::c_int,
+    /// Pixels from the baseline to the bottom of the character
+    pub bottom: ffi::c_int,
+    /// Bytes per row of the rendered character
+    pub pitch: ffi::c_int,
+    /// Pixels to advance after the character
+    pub x_skip: ffi::c_int,
+    /// Width of the character in the image
+    pub image_width: ffi::c_int,
+    /// Height of the character in the image
+    pub image_height: ffi::c_int,
+    /// Left texture coordinate
+    pub s: f32,
+    /// Top texture coordinate
+    pub t: f32,
+    /// Right texture coordinate
+    pub s2: f32,
+    /// Bottom texture coordinate
+    pub t2: f32,
+    /// Shader of the image with the character
+    pub glyph: ShaderHandle,
+    /// Name of the [`glyph`](GlyphInfo::glyph) shader
+    pub shader_name: [u8; 32],
+}
+
+assert_layout!(GlyphInfo, size = 80, align = 4);
+
+/// Font registered with [`AssetTraps::r_register_font`](AssetTraps::r_register_font)
+///
+/// See `fontInfo_t` in [ioquake3's `renderercommon/tr_types.h`](https://github.com/ioquake/ioq3/blob/master/code/renderercommon/tr_types.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FontInfo {
+    /// Glyph of every character
+    pub glyphs: [GlyphInfo; GLYPHS_PER_FONT],
+    /// Scale from the point size of the font to the virtual screen
+    pub glyph_scale: f32,
+    /// Name the font was registered as
+    pub name: [u8; MAX_QPATH],
+}
+
+assert_layout!(FontInfo, size = 20548, align = 4);
+
+impl FontInfo {
+    /// Glyph of `character`
+    pub fn glyph(&self, character: u8) -> &GlyphInfo {
+        &self.glyphs[usize::from(character)]
+    }
+
+    /// [`name`](FontInfo::name) up to its NUL
+    pub fn name(&self) -> String {
+        buffer_to_string(&self.name)
+    }
+}
+
+impl Default for FontInfo {
+    fn default() -> Self {
+        Self {
+            glyphs: [GlyphInfo::default(); GLYPHS_PER_FONT],
+            glyph_scale: 0.0,
+            name: [0; MAX_QPATH],
+        }
+    }
+}
+
+/// Asset traps of a module, implemented by the `Syscalls` of [`cgame`](crate::cgame::Syscalls) and [`ui`](crate::ui::Syscalls)
+///
+/// See `trap_R_RegisterModel` etc. in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+pub trait AssetTraps {
+    /// Load the model at `name`, e.g. `"models/powerups/health/large_cross.md3"`
+    fn r_register_model(&self, name: &str) -> ModelHandle;
+
+    /// Load the shader `name`, or the image of that name without a shader
+    fn r_register_shader(&self, name: &str) -> ShaderHandle;
+
+    /// Like [`r_register_shader`](AssetTraps::r_register_shader), but without mipmaps, e.g. for the HUD
+    fn r_register_shader_nomip(&self, name: &str) -> ShaderHandle;
+
+    /// Load the sound at `name`, e.g. `"sound/items/s_health.wav"`
+    ///
+    /// `compressed` is ignored by ioquake3.
+    fn s_register_sound(&self, name: &str, compressed: bool) -> SoundHandle;
+
+    /// Load the font `name` in `point_size` into `font`
+    fn r_register_font(&self, name: &str, point_size: ffi::c_int, font: &mut FontInfo);
+}
+
+/// What an [`AssetCache`](AssetCache) registered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AssetStats {
+    /// Number of models registered
+    pub models: usize,
+    /// Number of shaders registered, with and without mipmaps
+    pub shaders: usize,
+    /// Number of sounds registered
+    pub sounds: usize,
+    /// Number of fonts registered
+    pub fonts: usize,
+    /// Number of assets returned from the cache instead of registered again
+    pub hits: usize,
+}
+
+/// Handles of the registered assets by their path
+///
+/// Paths are compared case-insensitively and with `\` as `/`, like the engine does.
+/// Failed registrations are cached as well, so a missing asset is only looked up once.
+///
+/// Handles are only valid until the module is restarted, e.g. by `vid_restart`.
+#[derive(Clone, Debug, Default)]
+pub struct AssetCache {
+    models: HashMap<String, ModelHandle>,
+    shaders: HashMap<(String, bool), ShaderHandle>,
+    sounds: HashMap<String, SoundHandle>,
+    fonts: HashMap<(String, ffi::c_int), Arc<FontInfo>>,
+    hits: usize,
+}
+
+/// Key of `path` in an [`AssetCache`](AssetCache)
+fn key(path: &str) -> String {
+    path.to_ascii_lowercase().replace('\\', "/")
+}
+
+impl AssetCache {
+    /// Nothing registered yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Model at `path`, registered on first use
+    pub fn model<T: AssetTraps + ?Sized>(&mut self, traps: &T, path: &str) -> ModelHandle {
+        let hits = &mut self.hits;
+        *self
+            .models
+            .entry(key(path))
+            .and_modify(|_| *hits += 1)
+            .or_insert_with(|| traps.r_register_model(path))
+    }
+
+    /// Shader `name`, registered on first use
+    pub fn shader<T: AssetTraps + ?Sized>(&mut self, traps: &T, name: &str) -> ShaderHandle {
+        let hits = &mut self.hits;
+        *self
+            .shaders
+            .entry((key(name), false))
+            .and_modify(|_| *hits += 1)
+            .or_insert_with(|| traps.r_register_shader(name))
+    }
+
+    /// Shader `name` without mipmaps, registered on first use
+    pub fn shader_nomip<T: AssetTraps + ?Sized>(&mut self, traps: &T, name: &str) -> ShaderHandle {
+        let hits = &mut self.hits;
+        *self
+            .shaders
+            .entry((key(name), true))
+            .and_modify(|_| *hits += 1)
+            .or_insert_with(|| traps.r_register_shader_nomip(name))
+    }
+
+    /// Sound at `path`, registered on first use
+    pub fn sound<T: AssetTraps + ?Sized>(&mut self, traps: &T, path: &str) -> SoundHandle {
+        let hits = &mut self.hits;
+        *self
+            .sounds
+            .entry(key(path))
+            .and_modify(|_| *hits += 1)
+            .or_insert_with(|| traps.s_register_sound(path, false))
+    }
+
+    /// Font `name` in `point_size`, registered on first use
+    pub fn font<T: AssetTraps + ?Sized>(
+        &mut self,
+        traps: &T,
+        name: &str,
+        point_size: ffi::c_int,
+    ) -> Arc<FontInfo> {
+        let hits = &mut self.hits;
+        self.fonts
+            .entry((key(name), point_size))
+            .and_modify(|_| *hits += 1)
+            .or_insert_with(|| {
+                let mut font = Box::<FontInfo>::default();
+                traps.r_register_font(name, point_size, &mut font);
+                Arc::from(font)
+            })
+            .clone()
+    }
+
+    /// Counts of the registered assets and cache hits
+    pub fn stats(&self) -> AssetStats {
+        AssetStats {
+            models: self.models.len(),
+            shaders: self.shaders.len(),
+            sounds: self.sounds.len(),
+            fonts: self.fonts.len(),
+            hits: self.hits,
+        }
+    }
+
+    /// Forget all handles and counts
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,103 @@
+//! Backends for the syscall wrappers other than the engine
+//!
+//! Code that makes syscalls through e.g. [`qagame::Syscalls`](crate::qagame::Syscalls) can be tested without the
+//! engine by creating the wrapper [`with_engine`](crate::qagame::Syscalls::with_engine) one of these
+//! [`EngineSyscalls`](EngineSyscalls) backends instead.
+//!
+//! Pointers in the arguments are addresses of the module's memory, which are only valid during the syscall.
+//! See the `quake3-native-vm-mock` crate in `mock/` for an engine that decodes them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::backend::{Mock, Recording};
+//! use quake3_native_vm::ffi;
+//! use quake3_native_vm::qagame::{Imports, Syscalls};
+//!
+//! let recording = Recording::new(Mock(|num, _args: &[ffi::intptr_t]| {
+//!     if num == Imports::G_ARGC as ffi::intptr_t {
+//!         3
+//!     } else {
+//!         0
+//!     }
+//! }));
+//! let syscalls = Syscalls::with_engine(&recording);
+//!
+//! assert_eq!(syscalls.argc(), 3);
+//! assert_eq!(syscalls.argv(1), "");
+//! let calls = recording.take();
+//! assert_eq!(calls[0].num, Imports::G_ARGC as ffi::intptr_t);
+//! assert_eq!(calls[1].args[0], 1);
+//! ```
+
+use crate::{ffi, EngineSyscalls};
+use std::cell::RefCell;
+use std::fmt;
+
+/// Backend answering every trap with a function of its number and arguments
+#[derive(Clone, Copy)]
+pub struct Mock<F>(pub F);
+
+impl<F: Fn(ffi::intptr_t, &[ffi::intptr_t]) -> ffi::intptr_t> EngineSyscalls for Mock<F> {
+    fn syscall(&self, num: ffi::intptr_t, args: &[ffi::intptr_t]) -> ffi::intptr_t {
+        (self.0)(num, args)
+    }
+}
+
+impl<F> fmt::Debug for Mock<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Mock(..)")
+    }
+}
+
+/// Syscall recorded by [`Recording`](Recording)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedSyscall {
+    /// Trap number
+    pub num: ffi::intptr_t,
+    /// Arguments as passed, pointers being addresses
+    pub args: Vec<ffi::intptr_t>,
+    /// Value returned by the wrapped backend
+    pub result: ffi::intptr_t,
+}
+
+/// Backend recording every trap passed on to another backend
+///
+/// Share it by reference, e.g. `Syscalls::with_engine(&recording)`, to look at the calls afterwards.
+#[derive(Debug)]
+pub struct Recording<E> {
+    engine: E,
+    calls: RefCell<Vec<RecordedSyscall>>,
+}
+
+impl<E: EngineSyscalls> Recording<E> {
+    /// Record the syscalls to `engine`
+    pub fn new(engine: E) -> Self {
+        Self {
+            engine,
+            calls: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Calls so far
+    pub fn calls(&self) -> Vec<RecordedSyscall> {
+        self.calls.borrow().clone()
+    }
+
+    /// Calls so far, clearing the recording
+    pub fn take(&self) -> Vec<RecordedSyscall> {
+        self.calls.take()
+    }
+}
+
+impl<E: EngineSyscalls> EngineSyscalls for Recording<E> {
+    fn syscall(&self, num: ffi::intptr_t, args: &[ffi::intptr_t]) -> ffi::intptr_t {
+        let result = self.engine.syscall(num, args);
+        self.calls.borrow_mut().push(RecordedSyscall {
+            num,
+            args: args.to_vec(),
+            result,
+        });
+        result
+    }
+}
@@ -0,0 +1,426 @@
+//! Types shared between `qagame` and `cgame`
+//!
+//! Both modules run the same player movement code, the server to move players
+//! and the client to predict its own movement, so they have to agree on these.
+//!
+//! Values are those of baseq3, the `MISSIONPACK` build of Team Arena differs in places.
+//!
+//! See [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+
+pub mod item;
+
+use crate::ffi;
+use crate::shared::{Contents, PlayerState, Trace, UserCmd, Vec3};
+
+/// Version of the game logic, clients with a different `cgame` refuse to connect
+///
+/// See `GAME_VERSION` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+pub const GAME_VERSION: &str = "baseq3-1";
+
+/// Configstring with the [`GAME_VERSION`](GAME_VERSION)
+///
+/// See `CS_GAME_VERSION` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+pub const CS_GAME_VERSION: ffi::c_int = 20;
+
+/// Configstring with the level time the level started at
+///
+/// See `CS_LEVEL_START_TIME` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+pub const CS_LEVEL_START_TIME: ffi::c_int = 21;
+
+/// Gravity of `g_gravity` by default, in units per second squared
+///
+/// See `DEFAULT_GRAVITY` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+pub const DEFAULT_GRAVITY: f32 = 800.0;
+
+/// Maximum number of entities touched in one [`PmoveOutput`](PmoveOutput)
+///
+/// See `MAXTOUCH` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+pub const MAXTOUCH: usize = 32;
+
+/// Movement types for [`PlayerState::pm_type`](crate::shared::PlayerState::pm_type)
+///
+/// See `pmtype_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum PmType {
+    /// Can accelerate and turn
+    PM_NORMAL = 0,
+    /// Move freely through solids, without gravity
+    PM_NOCLIP = 1,
+    /// Still run into walls
+    PM_SPECTATOR = 2,
+    /// No acceleration or turning, but free falling
+    PM_DEAD = 3,
+    /// Stuck in place with no control
+    PM_FREEZE = 4,
+    /// No movement or status bar
+    PM_INTERMISSION = 5,
+    /// No movement or status bar, for single player
+    PM_SPINTERMISSION = 6,
+}
+
+impl TryFrom<ffi::c_int> for PmType {
+    type Error = &'static str;
+
+    fn try_from(pm_type: ffi::c_int) -> Result<Self, Self::Error> {
+        match pm_type {
+            0 => Ok(Self::PM_NORMAL),
+            1 => Ok(Self::PM_NOCLIP),
+            2 => Ok(Self::PM_SPECTATOR),
+            3 => Ok(Self::PM_DEAD),
+            4 => Ok(Self::PM_FREEZE),
+            5 => Ok(Self::PM_INTERMISSION),
+            6 => Ok(Self::PM_SPINTERMISSION),
+            _ => Err("Unknown movement type"),
+        }
+    }
+}
+
+/// Indices into [`PlayerState::stats`](crate::shared::PlayerState::stats)
+///
+/// See `statIndex_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum StatIndex {
+    /// Current health
+    STAT_HEALTH = 0,
+    /// Item index of the holdable item
+    STAT_HOLDABLE_ITEM = 1,
+    /// Bits of the weapons the player has
+    STAT_WEAPONS = 2,
+    /// Current armor
+    STAT_ARMOR = 3,
+    /// Direction to look at while dead
+    STAT_DEAD_YAW = 4,
+    /// Bits of the clients ready to exit the intermission
+    STAT_CLIENTS_READY = 5,
+    /// Health limit, changed by handicap
+    STAT_MAX_HEALTH = 6,
+}
+
+impl TryFrom<ffi::c_int> for StatIndex {
+    type Error = &'static str;
+
+    fn try_from(stat: ffi::c_int) -> Result<Self, Self::Error> {
+        match stat {
+            0 => Ok(Self::STAT_HEALTH),
+            1 => Ok(Self::STAT_HOLDABLE_ITEM),
+            2 => Ok(Self::STAT_WEAPONS),
+            3 => Ok(Self::STAT_ARMOR),
+            4 => Ok(Self::STAT_DEAD_YAW),
+            5 => Ok(Self::STAT_CLIENTS_READY),
+            6 => Ok(Self::STAT_MAX_HEALTH),
+            _ => Err("Unknown stat index"),
+        }
+    }
+}
+
+/// Indices into [`PlayerState::powerups`](crate::shared::PlayerState::powerups)
+///
+/// See `powerup_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Powerup {
+    /// No powerup
+    PW_NONE = 0,
+    /// Quad damage
+    PW_QUAD = 1,
+    /// Battle suit
+    PW_BATTLESUIT = 2,
+    /// Haste
+    PW_HASTE = 3,
+    /// Invisibility
+    PW_INVIS = 4,
+    /// Regeneration
+    PW_REGEN = 5,
+    /// Flight
+    PW_FLIGHT = 6,
+    /// Carrying the red flag
+    PW_REDFLAG = 7,
+    /// Carrying the blue flag
+    PW_BLUEFLAG = 8,
+    /// Carrying the neutral flag
+    PW_NEUTRALFLAG = 9,
+    /// Scout, Team Arena only
+    PW_SCOUT = 10,
+    /// Guard, Team Arena only
+    PW_GUARD = 11,
+    /// Doubler, Team Arena only
+    PW_DOUBLER = 12,
+    /// Ammo regeneration, Team Arena only
+    PW_AMMOREGEN = 13,
+    /// Invulnerability, Team Arena only
+    PW_INVULNERABILITY = 14,
+}
+
+impl TryFrom<ffi::c_int> for Powerup {
+    type Error = &'static str;
+
+    fn try_from(powerup: ffi::c_int) -> Result<Self, Self::Error> {
+        match powerup {
+            0 => Ok(Self::PW_NONE),
+            1 => Ok(Self::PW_QUAD),
+            2 => Ok(Self::PW_BATTLESUIT),
+            3 => Ok(Self::PW_HASTE),
+            4 => Ok(Self::PW_INVIS),
+            5 => Ok(Self::PW_REGEN),
+            6 => Ok(Self::PW_FLIGHT),
+            7 => Ok(Self::PW_REDFLAG),
+            8 => Ok(Self::PW_BLUEFLAG),
+            9 => Ok(Self::PW_NEUTRALFLAG),
+            10 => Ok(Self::PW_SCOUT),
+            11 => Ok(Self::PW_GUARD),
+            12 => Ok(Self::PW_DOUBLER),
+            13 => Ok(Self::PW_AMMOREGEN),
+            14 => Ok(Self::PW_INVULNERABILITY),
+            _ => Err("Unknown powerup"),
+        }
+    }
+}
+
+/// Number of [`Powerup`](Powerup)s
+pub const PW_NUM_POWERUPS: usize = 15;
+
+/// Channel a sound is played on
+///
+/// A new sound on the same channel of an entity replaces the previous one,
+/// except on [`CHAN_AUTO`](SoundChannel::CHAN_AUTO).
+///
+/// See `soundChannel_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum SoundChannel {
+    /// Any free channel
+    CHAN_AUTO = 0,
+    /// Menu sounds etc.
+    CHAN_LOCAL = 1,
+    /// Weapon sounds
+    CHAN_WEAPON = 2,
+    /// Pain and death sounds
+    CHAN_VOICE = 3,
+    /// Item pickups
+    CHAN_ITEM = 4,
+    /// Footsteps and falls
+    CHAN_BODY = 5,
+    /// Chat messages etc.
+    CHAN_LOCAL_SOUND = 6,
+    /// Announcer voices etc.
+    CHAN_ANNOUNCER = 7,
+}
+
+impl TryFrom<ffi::c_int> for SoundChannel {
+    type Error = &'static str;
+
+    fn try_from(channel: ffi::c_int) -> Result<Self, Self::Error> {
+        match channel {
+            0 => Ok(Self::CHAN_AUTO),
+            1 => Ok(Self::CHAN_LOCAL),
+            2 => Ok(Self::CHAN_WEAPON),
+            3 => Ok(Self::CHAN_VOICE),
+            4 => Ok(Self::CHAN_ITEM),
+            5 => Ok(Self::CHAN_BODY),
+            6 => Ok(Self::CHAN_LOCAL_SOUND),
+            7 => Ok(Self::CHAN_ANNOUNCER),
+            _ => Err("Unknown sound channel"),
+        }
+    }
+}
+
+/// Contents mask for traces, i.e. which [`Contents`](Contents) to collide with
+///
+/// The presets are the masks of the C game, which can be extended with [`with`](TraceMask::with)
+/// and [`without`](TraceMask::without).
+///
+/// See `MASK_SHOT` etc. in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::bg::TraceMask;
+/// use quake3_native_vm::shared::Contents;
+///
+/// // Shots that stop at water, like the lightning gun
+/// let mask = TraceMask::SHOT.with(TraceMask::WATER.contents());
+/// assert!(mask.hits(Contents::SLIME));
+/// assert!(!mask.hits(Contents::PLAYERCLIP));
+///
+/// // `pmove_t.tracemask` of spectators
+/// let mask = TraceMask::PLAYERSOLID.without(Contents::BODY | Contents::PLAYERCLIP);
+/// assert_eq!(mask, TraceMask::SOLID);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TraceMask(Contents);
+
+impl TraceMask {
+    /// Everything, see `MASK_ALL`
+    pub const ALL: Self = Self(Contents::from_bits_retain(-1));
+    /// World geometry, see `MASK_SOLID`
+    pub const SOLID: Self = Self(Contents::SOLID);
+    /// What blocks living players, see `MASK_PLAYERSOLID`
+    pub const PLAYERSOLID: Self = Self(
+        Contents::SOLID
+            .union(Contents::PLAYERCLIP)
+            .union(Contents::BODY),
+    );
+    /// What blocks dead players, see `MASK_DEADSOLID`
+    pub const DEADSOLID: Self = Self(Contents::SOLID.union(Contents::PLAYERCLIP));
+    /// Liquids, see `MASK_WATER`
+    pub const WATER: Self = Self(Contents::WATER.union(Contents::LAVA).union(Contents::SLIME));
+    /// What blocks sight, see `MASK_OPAQUE`
+    pub const OPAQUE: Self = Self(Contents::SOLID.union(Contents::SLIME).union(Contents::LAVA));
+    /// What blocks shots, see `MASK_SHOT`
+    pub const SHOT: Self = Self(
+        Contents::SOLID
+            .union(Contents::BODY)
+            .union(Contents::CORPSE),
+    );
+
+    /// Mask of exactly `contents`
+    pub const fn new(contents: Contents) -> Self {
+        Self(contents)
+    }
+
+    /// Also collide with `contents`
+    pub const fn with(self, contents: Contents) -> Self {
+        Self(self.0.union(contents))
+    }
+
+    /// Don't collide with `contents`
+    pub const fn without(self, contents: Contents) -> Self {
+        Self(self.0.difference(contents))
+    }
+
+    /// Contents to collide with
+    pub const fn contents(self) -> Contents {
+        self.0
+    }
+
+    /// Mask for the trace syscalls and [`PmoveInput::tracemask`](PmoveInput::tracemask)
+    pub const fn bits(self) -> ffi::c_int {
+        self.0.bits()
+    }
+
+    /// Whether a trace with this mask collides with `contents`
+    pub const fn hits(self, contents: Contents) -> bool {
+        self.0.intersects(contents)
+    }
+}
+
+impl From<Contents> for TraceMask {
+    fn from(contents: Contents) -> Self {
+        Self(contents)
+    }
+}
+
+impl From<TraceMask> for ffi::c_int {
+    fn from(mask: TraceMask) -> Self {
+        mask.bits()
+    }
+}
+
+/// Inputs of one player movement step besides the [`PlayerState`](crate::shared::PlayerState)
+///
+/// See the input fields of `pmove_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PmoveInput {
+    /// Command to execute
+    pub cmd: UserCmd,
+    /// Contents mask for traces, e.g. [`TraceMask::PLAYERSOLID`](TraceMask::PLAYERSOLID)
+    pub tracemask: ffi::c_int,
+    /// Print debug output if non-zero
+    pub debug_level: ffi::c_int,
+    /// Whether to skip footstep events, from the `g_footsteps` server cvar
+    pub no_footsteps: bool,
+    /// Whether the gauntlet hit something in this step
+    pub gauntlet_hit: bool,
+    /// Counter of movement steps
+    pub framecount: ffi::c_int,
+    /// Whether to move in fixed steps of `pmove_msec`
+    pub pmove_fixed: bool,
+    /// Step length for `pmove_fixed`, in milliseconds
+    pub pmove_msec: ffi::c_int,
+}
+
+/// Results of one player movement step besides the [`PlayerState`](crate::shared::PlayerState)
+///
+/// See the result fields of `pmove_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PmoveOutput {
+    /// Entities touched, at most [`MAXTOUCH`](MAXTOUCH)
+    pub touchents: Vec<ffi::c_int>,
+    /// Bounding box minimum
+    pub mins: Vec3,
+    /// Bounding box maximum
+    pub maxs: Vec3,
+    /// Contents of the water the player is in
+    pub watertype: ffi::c_int,
+    /// How deep the player is in water, `0` to `3`
+    pub waterlevel: ffi::c_int,
+    /// Horizontal speed
+    pub xyspeed: f32,
+}
+
+impl PmoveOutput {
+    /// Record that entity `entity_num` was touched, ignoring duplicates and more than [`MAXTOUCH`](MAXTOUCH)
+    ///
+    /// See `PM_AddTouchEnt` in [ioquake3's `game/bg_pmove.c`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_pmove.c).
+    pub fn add_touch(&mut self, entity_num: ffi::c_int) {
+        if self.touchents.len() < MAXTOUCH && !self.touchents.contains(&entity_num) {
+            self.touchents.push(entity_num);
+        }
+    }
+}
+
+/// Collision queries of player movement
+///
+/// Implemented with the trace syscalls of `qagame` on the server and of `cgame` on the client.
+///
+/// See the `trace` and `pointcontents` callbacks of `pmove_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+pub trait PmoveWorld {
+    /// Trace a box from `start` to `end`, ignoring entity `pass_entity_num`
+    fn trace(
+        &self,
+        start: Vec3,
+        mins: Vec3,
+        maxs: Vec3,
+        end: Vec3,
+        pass_entity_num: ffi::c_int,
+        content_mask: ffi::c_int,
+    ) -> Trace;
+
+    /// Contents at `point`, ignoring entity `pass_entity_num`
+    fn point_contents(&self, point: Vec3, pass_entity_num: ffi::c_int) -> ffi::c_int;
+}
+
+/// State of one player movement step
+///
+/// See `pmove_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[derive(Debug)]
+pub struct Pmove<'a> {
+    /// Player to move
+    pub ps: &'a mut PlayerState,
+    /// Inputs
+    pub input: PmoveInput,
+    /// Results
+    pub output: PmoveOutput,
+}
+
+impl<'a> Pmove<'a> {
+    /// Prepare moving `ps` with `input`
+    pub fn new(ps: &'a mut PlayerState, input: PmoveInput) -> Self {
+        Self {
+            ps,
+            input,
+            output: PmoveOutput::default(),
+        }
+    }
+
+    /// Movement type of the player, `None` if it is unknown
+    pub fn pm_type(&self) -> Option<PmType> {
+        PmType::try_from(self.ps.pm_type).ok()
+    }
+}
@@ -0,0 +1,738 @@
+//! Items that can be picked up
+//!
+//! [`ItemRegistry::stock`](ItemRegistry::stock) has the items of baseq3 in the same order as the C game,
+//! since clients identify items by their index, e.g. in [`EntityState::modelindex`](crate::shared::EntityState::modelindex).
+//!
+//! See `bg_itemlist` in [ioquake3's `game/bg_misc.c`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_misc.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::bg::item::{ItemRegistry, ItemType, Weapon};
+//!
+//! let items = ItemRegistry::stock();
+//!
+//! let rail = items.by_classname("weapon_railgun").unwrap();
+//! assert_eq!(rail.pickup_name, "Railgun");
+//! assert_eq!(rail.weapon(), Some(Weapon::WP_RAILGUN));
+//!
+//! let slugs = items.ammo_for(Weapon::WP_RAILGUN).unwrap();
+//! assert_eq!(slugs.gi_type, ItemType::IT_AMMO);
+//! assert_eq!(items.index_of(slugs), Some(24));
+//! ```
+
+use crate::bg::Powerup;
+use crate::ffi;
+
+/// Kinds of items
+///
+/// See `itemType_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum ItemType {
+    /// Invalid item
+    IT_BAD = 0,
+    /// Weapon, with a [`Weapon`](Weapon) tag
+    IT_WEAPON = 1,
+    /// Ammo, with the [`Weapon`](Weapon) tag it is for
+    IT_AMMO = 2,
+    /// Armor
+    IT_ARMOR = 3,
+    /// Health
+    IT_HEALTH = 4,
+    /// Timed powerup, with a [`Powerup`](crate::bg::Powerup) tag
+    IT_POWERUP = 5,
+    /// Item to use later, with a [`Holdable`](Holdable) tag
+    IT_HOLDABLE = 6,
+    /// Powerup that lasts until death, Team Arena only
+    IT_PERSISTANT_POWERUP = 7,
+    /// Team item, e.g. a flag, with a [`Powerup`](crate::bg::Powerup) tag
+    IT_TEAM = 8,
+}
+
+/// Weapons
+///
+/// See `weapon_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Weapon {
+    /// No weapon
+    WP_NONE = 0,
+    /// Gauntlet
+    WP_GAUNTLET = 1,
+    /// Machinegun
+    WP_MACHINEGUN = 2,
+    /// Shotgun
+    WP_SHOTGUN = 3,
+    /// Grenade launcher
+    WP_GRENADE_LAUNCHER = 4,
+    /// Rocket launcher
+    WP_ROCKET_LAUNCHER = 5,
+    /// Lightning gun
+    WP_LIGHTNING = 6,
+    /// Railgun
+    WP_RAILGUN = 7,
+    /// Plasma gun
+    WP_PLASMAGUN = 8,
+    /// BFG10K
+    WP_BFG = 9,
+    /// Grappling hook
+    WP_GRAPPLING_HOOK = 10,
+    /// Nailgun, Team Arena only
+    WP_NAILGUN = 11,
+    /// Proximity mine launcher, Team Arena only
+    WP_PROX_LAUNCHER = 12,
+    /// Chaingun, Team Arena only
+    WP_CHAINGUN = 13,
+}
+
+impl TryFrom<ffi::c_int> for Weapon {
+    type Error = &'static str;
+
+    fn try_from(weapon: ffi::c_int) -> Result<Self, Self::Error> {
+        match weapon {
+            0 => Ok(Self::WP_NONE),
+            1 => Ok(Self::WP_GAUNTLET),
+            2 => Ok(Self::WP_MACHINEGUN),
+            3 => Ok(Self::WP_SHOTGUN),
+            4 => Ok(Self::WP_GRENADE_LAUNCHER),
+            5 => Ok(Self::WP_ROCKET_LAUNCHER),
+            6 => Ok(Self::WP_LIGHTNING),
+            7 => Ok(Self::WP_RAILGUN),
+            8 => Ok(Self::WP_PLASMAGUN),
+            9 => Ok(Self::WP_BFG),
+            10 => Ok(Self::WP_GRAPPLING_HOOK),
+            11 => Ok(Self::WP_NAILGUN),
+            12 => Ok(Self::WP_PROX_LAUNCHER),
+            13 => Ok(Self::WP_CHAINGUN),
+            _ => Err("Unknown weapon"),
+        }
+    }
+}
+
+/// Items to use later
+///
+/// See `holdable_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Holdable {
+    /// No holdable item
+    HI_NONE = 0,
+    /// Personal teleporter
+    HI_TELEPORTER = 1,
+    /// Medkit
+    HI_MEDKIT = 2,
+    /// Kamikaze, Team Arena only
+    HI_KAMIKAZE = 3,
+    /// Portal, Team Arena only
+    HI_PORTAL = 4,
+    /// Invulnerability, Team Arena only
+    HI_INVULNERABILITY = 5,
+}
+
+/// Definition of an item
+///
+/// See `gitem_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Item {
+    /// Spawning name in maps
+    pub classname: &'static str,
+    /// Sound played on pickup
+    pub pickup_sound: Option<&'static str>,
+    /// Models of the item lying in the world, at most 4
+    pub world_models: &'static [&'static str],
+    /// Icon shader for the HUD
+    pub icon: &'static str,
+    /// Name shown on pickup
+    pub pickup_name: &'static str,
+    /// Amount for ammo, armor and health, seconds for powerups
+    pub quantity: ffi::c_int,
+    /// Kind of item
+    pub gi_type: ItemType,
+    /// [`Weapon`](Weapon), [`Powerup`](crate::bg::Powerup) or [`Holdable`](Holdable), depending on `gi_type`
+    pub gi_tag: ffi::c_int,
+    /// Space separated models and shaders to precache
+    pub precaches: &'static str,
+    /// Space separated sounds to precache
+    pub sounds: &'static str,
+}
+
+impl Item {
+    /// Weapon of a weapon or ammo item
+    pub fn weapon(&self) -> Option<Weapon> {
+        match self.gi_type {
+            ItemType::IT_WEAPON | ItemType::IT_AMMO => Weapon::try_from(self.gi_tag).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn item(
+    classname: &'static str,
+    pickup_sound: Option<&'static str>,
+    world_models: &'static [&'static str],
+    icon: &'static str,
+    pickup_name: &'static str,
+    quantity: ffi::c_int,
+    gi_type: ItemType,
+    gi_tag: ffi::c_int,
+    sounds: &'static str,
+) -> Item {
+    Item {
+        classname,
+        pickup_sound,
+        world_models,
+        icon,
+        pickup_name,
+        quantity,
+        gi_type,
+        gi_tag,
+        precaches: "",
+        sounds,
+    }
+}
+
+const ARMOR: Option<&str> = Some("sound/misc/ar2_pkup.wav");
+const WEAPON: Option<&str> = Some("sound/misc/w_pkup.wav");
+const AMMO: Option<&str> = Some("sound/misc/am_pkup.wav");
+
+/// baseq3 items, without the leading empty entry
+const STOCK: [Item; 35] = {
+    use ItemType::*;
+    use Weapon::*;
+
+    [
+        item(
+            "item_armor_shard",
+            Some("sound/misc/ar1_pkup.wav"),
+            &[
+                "models/powerups/armor/shard.md3",
+                "models/powerups/armor/shard_sphere.md3",
+            ],
+            "icons/iconr_shard",
+            "Armor Shard",
+            5,
+            IT_ARMOR,
+            0,
+            "",
+        ),
+        item(
+            "item_armor_combat",
+            ARMOR,
+            &["models/powerups/armor/armor_yel.md3"],
+            "icons/iconr_yellow",
+            "Armor",
+            50,
+            IT_ARMOR,
+            0,
+            "",
+        ),
+        item(
+            "item_armor_body",
+            ARMOR,
+            &["models/powerups/armor/armor_red.md3"],
+            "icons/iconr_red",
+            "Heavy Armor",
+            100,
+            IT_ARMOR,
+            0,
+            "",
+        ),
+        item(
+            "item_health_small",
+            Some("sound/items/s_health.wav"),
+            &[
+                "models/powerups/health/small_cross.md3",
+                "models/powerups/health/small_sphere.md3",
+            ],
+            "icons/iconh_green",
+            "5 Health",
+            5,
+            IT_HEALTH,
+            0,
+            "",
+        ),
+        item(
+            "item_health",
+            Some("sound/items/n_health.wav"),
+            &[
+                "models/powerups/health/medium_cross.md3",
+                "models/powerups/health/medium_sphere.md3",
+            ],
+            "icons/iconh_yellow",
+            "25 Health",
+            25,
+            IT_HEALTH,
+            0,
+            "",
+        ),
+        item(
+            "item_health_large",
+            Some("sound/items/l_health.wav"),
+            &[
+                "models/powerups/health/large_cross.md3",
+                "models/powerups/health/large_sphere.md3",
+            ],
+            "icons/iconh_red",
+            "50 Health",
+            50,
+            IT_HEALTH,
+            0,
+            "",
+        ),
+        item(
+            "item_health_mega",
+            Some("sound/items/m_health.wav"),
+            &[
+                "models/powerups/health/mega_cross.md3",
+                "models/powerups/health/mega_sphere.md3",
+            ],
+            "icons/iconh_mega",
+            "Mega Health",
+            100,
+            IT_HEALTH,
+            0,
+            "",
+        ),
+        item(
+            "weapon_gauntlet",
+            WEAPON,
+            &["models/weapons2/gauntlet/gauntlet.md3"],
+            "icons/iconw_gauntlet",
+            "Gauntlet",
+            0,
+            IT_WEAPON,
+            WP_GAUNTLET as ffi::c_int,
+            "",
+        ),
+        item(
+            "weapon_shotgun",
+            WEAPON,
+            &["models/weapons2/shotgun/shotgun.md3"],
+            "icons/iconw_shotgun",
+            "Shotgun",
+            10,
+            IT_WEAPON,
+            WP_SHOTGUN as ffi::c_int,
+            "",
+        ),
+        item(
+            "weapon_machinegun",
+            WEAPON,
+            &["models/weapons2/machinegun/machinegun.md3"],
+            "icons/iconw_machinegun",
+            "Machinegun",
+            40,
+            IT_WEAPON,
+            WP_MACHINEGUN as ffi::c_int,
+            "",
+        ),
+        item(
+            "weapon_grenadelauncher",
+            WEAPON,
+            &["models/weapons2/grenadel/grenadel.md3"],
+            "icons/iconw_grenade",
+            "Grenade Launcher",
+            10,
+            IT_WEAPON,
+            WP_GRENADE_LAUNCHER as ffi::c_int,
+            "sound/weapons/grenade/hgrenb1a.wav sound/weapons/grenade/hgrenb2a.wav",
+        ),
+        item(
+            "weapon_rocketlauncher",
+            WEAPON,
+            &["models/weapons2/rocketl/rocketl.md3"],
+            "icons/iconw_rocket",
+            "Rocket Launcher",
+            10,
+            IT_WEAPON,
+            WP_ROCKET_LAUNCHER as ffi::c_int,
+            "",
+        ),
+        item(
+            "weapon_lightning",
+            WEAPON,
+            &["models/weapons2/lightning/lightning.md3"],
+            "icons/iconw_lightning",
+            "Lightning Gun",
+            100,
+            IT_WEAPON,
+            WP_LIGHTNING as ffi::c_int,
+            "",
+        ),
+        item(
+            "weapon_railgun",
+            WEAPON,
+            &["models/weapons2/railgun/railgun.md3"],
+            "icons/iconw_railgun",
+            "Railgun",
+            10,
+            IT_WEAPON,
+            WP_RAILGUN as ffi::c_int,
+            "",
+        ),
+        item(
+            "weapon_plasmagun",
+            WEAPON,
+            &["models/weapons2/plasma/plasma.md3"],
+            "icons/iconw_plasma",
+            "Plasma Gun",
+            50,
+            IT_WEAPON,
+            WP_PLASMAGUN as ffi::c_int,
+            "",
+        ),
+        item(
+            "weapon_bfg",
+            WEAPON,
+            &["models/weapons2/bfg/bfg.md3"],
+            "icons/iconw_bfg",
+            "BFG10K",
+            20,
+            IT_WEAPON,
+            WP_BFG as ffi::c_int,
+            "",
+        ),
+        item(
+            "weapon_grapplinghook",
+            WEAPON,
+            &["models/weapons2/grapple/grapple.md3"],
+            "icons/iconw_grapple",
+            "Grappling Hook",
+            0,
+            IT_WEAPON,
+            WP_GRAPPLING_HOOK as ffi::c_int,
+            "",
+        ),
+        item(
+            "ammo_shells",
+            AMMO,
+            &["models/powerups/ammo/shotgunam.md3"],
+            "icons/icona_shotgun",
+            "Shells",
+            10,
+            IT_AMMO,
+            WP_SHOTGUN as ffi::c_int,
+            "",
+        ),
+        item(
+            "ammo_bullets",
+            AMMO,
+            &["models/powerups/ammo/machinegunam.md3"],
+            "icons/icona_machinegun",
+            "Bullets",
+            50,
+            IT_AMMO,
+            WP_MACHINEGUN as ffi::c_int,
+            "",
+        ),
+        item(
+            "ammo_grenades",
+            AMMO,
+            &["models/powerups/ammo/grenadeam.md3"],
+            "icons/icona_grenade",
+            "Grenades",
+            5,
+            IT_AMMO,
+            WP_GRENADE_LAUNCHER as ffi::c_int,
+            "",
+        ),
+        item(
+            "ammo_cells",
+            AMMO,
+            &["models/powerups/ammo/plasmaam.md3"],
+            "icons/icona_plasma",
+            "Cells",
+            30,
+            IT_AMMO,
+            WP_PLASMAGUN as ffi::c_int,
+            "",
+        ),
+        item(
+            "ammo_lightning",
+            AMMO,
+            &["models/powerups/ammo/lightningam.md3"],
+            "icons/icona_lightning",
+            "Lightning",
+            60,
+            IT_AMMO,
+            WP_LIGHTNING as ffi::c_int,
+            "",
+        ),
+        item(
+            "ammo_rockets",
+            AMMO,
+            &["models/powerups/ammo/rocketam.md3"],
+            "icons/icona_rocket",
+            "Rockets",
+            5,
+            IT_AMMO,
+            WP_ROCKET_LAUNCHER as ffi::c_int,
+            "",
+        ),
+        item(
+            "ammo_slugs",
+            AMMO,
+            &["models/powerups/ammo/railgunam.md3"],
+            "icons/icona_railgun",
+            "Slugs",
+            10,
+            IT_AMMO,
+            WP_RAILGUN as ffi::c_int,
+            "",
+        ),
+        item(
+            "ammo_bfg",
+            AMMO,
+            &["models/powerups/ammo/bfgam.md3"],
+            "icons/icona_bfg",
+            "Bfg Ammo",
+            15,
+            IT_AMMO,
+            WP_BFG as ffi::c_int,
+            "",
+        ),
+        item(
+            "holdable_teleporter",
+            Some("sound/items/holdable.wav"),
+            &["models/powerups/holdable/teleporter.md3"],
+            "icons/teleporter",
+            "Personal Teleporter",
+            60,
+            IT_HOLDABLE,
+            Holdable::HI_TELEPORTER as ffi::c_int,
+            "",
+        ),
+        item(
+            "holdable_medkit",
+            Some("sound/items/holdable.wav"),
+            &[
+                "models/powerups/holdable/medkit.md3",
+                "models/powerups/holdable/medkit_sphere.md3",
+            ],
+            "icons/medkit",
+            "Medkit",
+            60,
+            IT_HOLDABLE,
+            Holdable::HI_MEDKIT as ffi::c_int,
+            "sound/items/use_medkit.wav",
+        ),
+        item(
+            "item_quad",
+            Some("sound/items/quaddamage.wav"),
+            &[
+                "models/powerups/instant/quad.md3",
+                "models/powerups/instant/quad_ring.md3",
+            ],
+            "icons/quad",
+            "Quad Damage",
+            30,
+            IT_POWERUP,
+            Powerup::PW_QUAD as ffi::c_int,
+            "sound/items/damage2.wav sound/items/damage3.wav",
+        ),
+        item(
+            "item_enviro",
+            Some("sound/items/protect.wav"),
+            &[
+                "models/powerups/instant/enviro.md3",
+                "models/powerups/instant/enviro_ring.md3",
+            ],
+            "icons/envirosuit",
+            "Battle Suit",
+            30,
+            IT_POWERUP,
+            Powerup::PW_BATTLESUIT as ffi::c_int,
+            "sound/items/airout.wav sound/items/protect3.wav",
+        ),
+        item(
+            "item_haste",
+            Some("sound/items/haste.wav"),
+            &[
+                "models/powerups/instant/haste.md3",
+                "models/powerups/instant/haste_ring.md3",
+            ],
+            "icons/haste",
+            "Speed",
+            30,
+            IT_POWERUP,
+            Powerup::PW_HASTE as ffi::c_int,
+            "",
+        ),
+        item(
+            "item_invis",
+            Some("sound/items/invisibility.wav"),
+            &[
+                "models/powerups/instant/invis.md3",
+                "models/powerups/instant/invis_ring.md3",
+            ],
+            "icons/invis",
+            "Invisibility",
+            30,
+            IT_POWERUP,
+            Powerup::PW_INVIS as ffi::c_int,
+            "",
+        ),
+        item(
+            "item_regen",
+            Some("sound/items/regeneration.wav"),
+            &[
+                "models/powerups/instant/regen.md3",
+                "models/powerups/instant/regen_ring.md3",
+            ],
+            "icons/regen",
+            "Regeneration",
+            30,
+            IT_POWERUP,
+            Powerup::PW_REGEN as ffi::c_int,
+            "sound/items/regen.wav",
+        ),
+        item(
+            "item_flight",
+            Some("sound/items/flight.wav"),
+            &[
+                "models/powerups/instant/flight.md3",
+                "models/powerups/instant/flight_ring.md3",
+            ],
+            "icons/flight",
+            "Flight",
+            60,
+            IT_POWERUP,
+            Powerup::PW_FLIGHT as ffi::c_int,
+            "sound/items/flight.wav",
+        ),
+        item(
+            "team_CTF_redflag",
+            None,
+            &["models/flags/r_flag.md3"],
+            "icons/iconf_red1",
+            "Red Flag",
+            0,
+            IT_TEAM,
+            Powerup::PW_REDFLAG as ffi::c_int,
+            "",
+        ),
+        item(
+            "team_CTF_blueflag",
+            None,
+            &["models/flags/b_flag.md3"],
+            "icons/iconf_blu1",
+            "Blue Flag",
+            0,
+            IT_TEAM,
+            Powerup::PW_BLUEFLAG as ffi::c_int,
+            "",
+        ),
+    ]
+};
+
+/// Table of items, indexed from `1` like `bg_itemlist`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ItemRegistry {
+    items: Vec<Item>,
+}
+
+impl ItemRegistry {
+    /// Create a registry without items
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the baseq3 items
+    ///
+    /// Team Arena appends its items to these, so mods can [`push`](ItemRegistry::push) them without changing indices.
+    pub fn stock() -> Self {
+        Self {
+            items: STOCK.to_vec(),
+        }
+    }
+
+    /// Add `item`, returning its index
+    pub fn push(&mut self, item: Item) -> usize {
+        self.items.push(item);
+        self.items.len()
+    }
+
+    /// Item with `index`, `0` being no item
+    pub fn get(&self, index: usize) -> Option<&Item> {
+        index.checked_sub(1).and_then(|i| self.items.get(i))
+    }
+
+    /// Index of `item`, which has to be from this registry
+    pub fn index_of(&self, item: &Item) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|i| std::ptr::eq(i, item))
+            .map(|i| i + 1)
+    }
+
+    /// All items, in index order
+    pub fn iter(&self) -> impl Iterator<Item = &Item> {
+        self.items.iter()
+    }
+
+    /// Number of items
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether there are no items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Item spawned by `classname` in maps
+    pub fn by_classname(&self, classname: &str) -> Option<&Item> {
+        self.items.iter().find(|item| item.classname == classname)
+    }
+
+    /// Item with `pickup_name`, ignoring case
+    ///
+    /// See `BG_FindItem` in [ioquake3's `game/bg_misc.c`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_misc.c).
+    pub fn by_pickup_name(&self, pickup_name: &str) -> Option<&Item> {
+        self.items
+            .iter()
+            .find(|item| item.pickup_name.eq_ignore_ascii_case(pickup_name))
+    }
+
+    /// Item of `weapon`
+    ///
+    /// See `BG_FindItemForWeapon` in [ioquake3's `game/bg_misc.c`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_misc.c).
+    pub fn by_weapon(&self, weapon: Weapon) -> Option<&Item> {
+        self.find(ItemType::IT_WEAPON, weapon as ffi::c_int)
+    }
+
+    /// Ammo item for `weapon`
+    pub fn ammo_for(&self, weapon: Weapon) -> Option<&Item> {
+        self.find(ItemType::IT_AMMO, weapon as ffi::c_int)
+    }
+
+    /// Item of `powerup`, including team items like flags
+    ///
+    /// See `BG_FindItemForPowerup` in [ioquake3's `game/bg_misc.c`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_misc.c).
+    pub fn by_powerup(&self, powerup: Powerup) -> Option<&Item> {
+        self.items.iter().find(|item| {
+            matches!(
+                item.gi_type,
+                ItemType::IT_POWERUP | ItemType::IT_TEAM | ItemType::IT_PERSISTANT_POWERUP
+            ) && item.gi_tag == powerup as ffi::c_int
+        })
+    }
+
+    /// Item of `holdable`
+    ///
+    /// See `BG_FindItemForHoldable` in [ioquake3's `game/bg_misc.c`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_misc.c).
+    pub fn by_holdable(&self, holdable: Holdable) -> Option<&Item> {
+        self.find(ItemType::IT_HOLDABLE, holdable as ffi::c_int)
+    }
+
+    fn find(&self, gi_type: ItemType, gi_tag: ffi::c_int) -> Option<&Item> {
+        self.items
+            .iter()
+            .find(|item| item.gi_type == gi_type && item.gi_tag == gi_tag)
+    }
+}
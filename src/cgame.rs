@@ -0,0 +1,946 @@
+//! Code for the `cgame` Quake 3 module
+//!
+//! The `cgame` module contains the client-side logic of Quake 3, e.g.:
+//!
+//! * rendering the world and the HUD
+//! * predicting the player's own movement
+//! * sounds and effects of events
+//!
+//! It runs on the game client, so players need the same one as the server's `qagame`.
+//!
+//! Only the syscalls needed so far are wrapped, see [`Imports`](Imports).
+
+#[cfg(feature = "base")]
+pub mod base;
+pub mod interpolation;
+pub mod loading;
+pub mod looping;
+pub mod prediction;
+
+use crate::asset::{AssetTraps, FontInfo, ModelHandle, ShaderHandle, SoundHandle};
+use crate::bg::SoundChannel;
+use crate::cinematic::{CinematicFlags, CinematicTraps};
+use crate::metrics;
+use crate::qagame::{buffer_bytes, to_cstring, MAX_STRING_CHARS};
+use crate::render::RenderTraps;
+use crate::shared::{
+    EntityState, KeyCatcher, Orientation, PlayerState, UserCmd, Vec3, MAX_MAP_AREA_BYTES,
+};
+use crate::sound::SoundTraps;
+use crate::time::LevelTime;
+use crate::{ffi, EngineSyscalls, Syscall, VmResult};
+
+/// Number of user commands the engine keeps, older ones can't be [`get_usercmd`](Syscalls::get_usercmd)
+///
+/// See `CMD_BACKUP` in [ioquake3's `cgame/cg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_public.h).
+pub const CMD_BACKUP: ffi::c_int = 64;
+
+/// Maximum number of entities in a [`Snapshot`](Snapshot)
+///
+/// See `MAX_ENTITIES_IN_SNAPSHOT` in [ioquake3's `cgame/cg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_public.h).
+pub const MAX_ENTITIES_IN_SNAPSHOT: usize = 256;
+
+/// State of the game at one server frame, as received by the client
+///
+/// At about 53 KiB this is better kept in a `Box`, as [`get_snapshot`](Syscalls::get_snapshot) returns it.
+///
+/// See `snapshot_t` in [ioquake3's `cgame/cg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Snapshot {
+    /// `SNAPFLAG_*` bits, e.g. [`SNAPFLAG_NOT_ACTIVE`](crate::shared::SNAPFLAG_NOT_ACTIVE)
+    pub snap_flags: ffi::c_int,
+    /// Round trip time of the snapshot, in milliseconds
+    pub ping: ffi::c_int,
+    /// Server time the snapshot was taken at
+    pub server_time: ffi::c_int,
+    /// Bits of the areas visible from the player's position
+    pub areamask: [u8; MAX_MAP_AREA_BYTES],
+    /// Complete information about the current player at this time
+    pub ps: PlayerState,
+    /// Number of valid [`entities`](Snapshot::entities)
+    pub num_entities: ffi::c_int,
+    /// Entities to render, all but the player's own
+    pub entities: [EntityState; MAX_ENTITIES_IN_SNAPSHOT],
+    /// Number of server commands since the previous snapshot
+    pub num_server_commands: ffi::c_int,
+    /// Sequence number of the last server command in this snapshot
+    pub server_command_sequence: ffi::c_int,
+}
+
+assert_layout!(Snapshot, size = 53772, align = 4);
+
+impl Snapshot {
+    /// The valid entities of the snapshot
+    pub fn entities(&self) -> &[EntityState] {
+        let len = usize::try_from(self.num_entities).unwrap_or(0);
+        &self.entities[..len.min(MAX_ENTITIES_IN_SNAPSHOT)]
+    }
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            snap_flags: 0,
+            ping: 0,
+            server_time: 0,
+            areamask: [0; MAX_MAP_AREA_BYTES],
+            ps: PlayerState::default(),
+            num_entities: 0,
+            entities: [EntityState::default(); MAX_ENTITIES_IN_SNAPSHOT],
+            num_server_commands: 0,
+            server_command_sequence: 0,
+        }
+    }
+}
+
+/// Which eye a frame is drawn for, see [`Module::draw_active_frame`](Module::draw_active_frame)
+///
+/// See `stereoFrame_t` in [ioquake3's `renderercommon/tr_types.h`](https://github.com/ioquake/ioq3/blob/master/code/renderercommon/tr_types.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum StereoFrame {
+    /// Both eyes, i.e. without stereo rendering
+    STEREO_CENTER = 0,
+    /// The left eye
+    STEREO_LEFT = 1,
+    /// The right eye
+    STEREO_RIGHT = 2,
+}
+
+impl TryFrom<ffi::c_int> for StereoFrame {
+    type Error = &'static str;
+
+    fn try_from(frame: ffi::c_int) -> Result<Self, Self::Error> {
+        match frame {
+            0 => Ok(Self::STEREO_CENTER),
+            1 => Ok(Self::STEREO_LEFT),
+            2 => Ok(Self::STEREO_RIGHT),
+            _ => Err("Unknown stereo frame"),
+        }
+    }
+}
+
+/// System traps provided by the engine
+///
+/// See `cgameImport_t` in [ioquake3's `cgame/cg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Imports {
+    /// Print a message to the console
+    CG_PRINT = 0,
+    /// Disconnect with an error message
+    CG_ERROR = 1,
+    /// Get the number of arguments of the current command
+    CG_ARGC = 7,
+    /// Get an argument of the current command
+    CG_ARGV = 8,
+    /// Render a frame right away, e.g. while loading
+    CG_UPDATESCREEN = 17,
+    /// Play a sound without a position
+    CG_S_STARTLOCALSOUND = 29,
+    /// Stop the looping sounds added this frame
+    CG_S_CLEARLOOPINGSOUNDS = 30,
+    /// Play a looping sound of an entity for this frame
+    CG_S_ADDLOOPINGSOUND = 31,
+    /// Load a sound
+    CG_S_REGISTERSOUND = 34,
+    /// Start playing music
+    CG_S_STARTBACKGROUNDTRACK = 35,
+    /// Load a model
+    CG_R_REGISTERMODEL = 37,
+    /// Load a shader
+    CG_R_REGISTERSHADER = 39,
+    /// Get the interpolated orientation of a tag of a model
+    CG_R_LERPTAG = 48,
+    /// Get the number and server time of the latest snapshot
+    CG_GETCURRENTSNAPSHOTNUMBER = 51,
+    /// Get a snapshot by its number
+    CG_GETSNAPSHOT = 52,
+    /// Get the number of the latest user command
+    CG_GETCURRENTCMDNUMBER = 54,
+    /// Get a user command by its number
+    CG_GETUSERCMD = 55,
+    /// Load a shader without mipmaps
+    CG_R_REGISTERSHADERNOMIP = 57,
+    /// Load a font
+    CG_R_REGISTERFONT = 59,
+    /// Whether a key is held down
+    CG_KEY_ISDOWN = 60,
+    /// Get which parts of the client get key presses
+    CG_KEY_GETCATCHER = 61,
+    /// Set which parts of the client get key presses
+    CG_KEY_SETCATCHER = 62,
+    /// Get the key bound to a command
+    CG_KEY_GETKEY = 63,
+    /// Stop the music
+    CG_S_STOPBACKGROUNDTRACK = 69,
+    /// Start playing a RoQ cinematic
+    CG_CIN_PLAYCINEMATIC = 74,
+    /// Stop a cinematic
+    CG_CIN_STOPCINEMATIC = 75,
+    /// Advance a cinematic to the current time
+    CG_CIN_RUNCINEMATIC = 76,
+    /// Draw the current frame of a cinematic
+    CG_CIN_DRAWCINEMATIC = 77,
+    /// Move and resize a cinematic
+    CG_CIN_SETEXTENTS = 78,
+    /// Play a looping sound of an entity until it is stopped
+    CG_S_ADDREALLOOPINGSOUND = 80,
+    /// Stop the looping sound of an entity
+    CG_S_STOPLOOPINGSOUND = 81,
+}
+
+impl From<Imports> for isize {
+    fn from(import: Imports) -> Self {
+        match import {
+            Imports::CG_PRINT => 0,
+            Imports::CG_ERROR => 1,
+            Imports::CG_ARGC => 7,
+            Imports::CG_ARGV => 8,
+            Imports::CG_UPDATESCREEN => 17,
+            Imports::CG_S_STARTLOCALSOUND => 29,
+            Imports::CG_S_CLEARLOOPINGSOUNDS => 30,
+            Imports::CG_S_ADDLOOPINGSOUND => 31,
+            Imports::CG_S_REGISTERSOUND => 34,
+            Imports::CG_S_STARTBACKGROUNDTRACK => 35,
+            Imports::CG_R_REGISTERMODEL => 37,
+            Imports::CG_R_REGISTERSHADER => 39,
+            Imports::CG_R_LERPTAG => 48,
+            Imports::CG_GETCURRENTSNAPSHOTNUMBER => 51,
+            Imports::CG_GETSNAPSHOT => 52,
+            Imports::CG_GETCURRENTCMDNUMBER => 54,
+            Imports::CG_GETUSERCMD => 55,
+            Imports::CG_R_REGISTERSHADERNOMIP => 57,
+            Imports::CG_R_REGISTERFONT => 59,
+            Imports::CG_KEY_ISDOWN => 60,
+            Imports::CG_KEY_GETCATCHER => 61,
+            Imports::CG_KEY_SETCATCHER => 62,
+            Imports::CG_KEY_GETKEY => 63,
+            Imports::CG_S_STOPBACKGROUNDTRACK => 69,
+            Imports::CG_CIN_PLAYCINEMATIC => 74,
+            Imports::CG_CIN_STOPCINEMATIC => 75,
+            Imports::CG_CIN_RUNCINEMATIC => 76,
+            Imports::CG_CIN_DRAWCINEMATIC => 77,
+            Imports::CG_CIN_SETEXTENTS => 78,
+            Imports::CG_S_ADDREALLOOPINGSOUND => 80,
+            Imports::CG_S_STOPLOOPINGSOUND => 81,
+        }
+    }
+}
+
+impl Imports {
+    /// All wrapped traps, in ascending order
+    pub const ALL: [Imports; 31] = [
+        Imports::CG_PRINT,
+        Imports::CG_ERROR,
+        Imports::CG_ARGC,
+        Imports::CG_ARGV,
+        Imports::CG_UPDATESCREEN,
+        Imports::CG_S_STARTLOCALSOUND,
+        Imports::CG_S_CLEARLOOPINGSOUNDS,
+        Imports::CG_S_ADDLOOPINGSOUND,
+        Imports::CG_S_REGISTERSOUND,
+        Imports::CG_S_STARTBACKGROUNDTRACK,
+        Imports::CG_R_REGISTERMODEL,
+        Imports::CG_R_REGISTERSHADER,
+        Imports::CG_R_LERPTAG,
+        Imports::CG_GETCURRENTSNAPSHOTNUMBER,
+        Imports::CG_GETSNAPSHOT,
+        Imports::CG_GETCURRENTCMDNUMBER,
+        Imports::CG_GETUSERCMD,
+        Imports::CG_R_REGISTERSHADERNOMIP,
+        Imports::CG_R_REGISTERFONT,
+        Imports::CG_KEY_ISDOWN,
+        Imports::CG_KEY_GETCATCHER,
+        Imports::CG_KEY_SETCATCHER,
+        Imports::CG_KEY_GETKEY,
+        Imports::CG_S_STOPBACKGROUNDTRACK,
+        Imports::CG_CIN_PLAYCINEMATIC,
+        Imports::CG_CIN_STOPCINEMATIC,
+        Imports::CG_CIN_RUNCINEMATIC,
+        Imports::CG_CIN_DRAWCINEMATIC,
+        Imports::CG_CIN_SETEXTENTS,
+        Imports::CG_S_ADDREALLOOPINGSOUND,
+        Imports::CG_S_STOPLOOPINGSOUND,
+    ];
+
+    /// Name of the `cgameImport_t` value, e.g. `"CG_PRINT"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Imports::CG_PRINT => "CG_PRINT",
+            Imports::CG_ERROR => "CG_ERROR",
+            Imports::CG_ARGC => "CG_ARGC",
+            Imports::CG_ARGV => "CG_ARGV",
+            Imports::CG_UPDATESCREEN => "CG_UPDATESCREEN",
+            Imports::CG_S_STARTLOCALSOUND => "CG_S_STARTLOCALSOUND",
+            Imports::CG_S_CLEARLOOPINGSOUNDS => "CG_S_CLEARLOOPINGSOUNDS",
+            Imports::CG_S_ADDLOOPINGSOUND => "CG_S_ADDLOOPINGSOUND",
+            Imports::CG_S_REGISTERSOUND => "CG_S_REGISTERSOUND",
+            Imports::CG_S_STARTBACKGROUNDTRACK => "CG_S_STARTBACKGROUNDTRACK",
+            Imports::CG_R_REGISTERMODEL => "CG_R_REGISTERMODEL",
+            Imports::CG_R_REGISTERSHADER => "CG_R_REGISTERSHADER",
+            Imports::CG_R_LERPTAG => "CG_R_LERPTAG",
+            Imports::CG_GETCURRENTSNAPSHOTNUMBER => "CG_GETCURRENTSNAPSHOTNUMBER",
+            Imports::CG_GETSNAPSHOT => "CG_GETSNAPSHOT",
+            Imports::CG_GETCURRENTCMDNUMBER => "CG_GETCURRENTCMDNUMBER",
+            Imports::CG_GETUSERCMD => "CG_GETUSERCMD",
+            Imports::CG_R_REGISTERSHADERNOMIP => "CG_R_REGISTERSHADERNOMIP",
+            Imports::CG_R_REGISTERFONT => "CG_R_REGISTERFONT",
+            Imports::CG_KEY_ISDOWN => "CG_KEY_ISDOWN",
+            Imports::CG_KEY_GETCATCHER => "CG_KEY_GETCATCHER",
+            Imports::CG_KEY_SETCATCHER => "CG_KEY_SETCATCHER",
+            Imports::CG_KEY_GETKEY => "CG_KEY_GETKEY",
+            Imports::CG_S_STOPBACKGROUNDTRACK => "CG_S_STOPBACKGROUNDTRACK",
+            Imports::CG_CIN_PLAYCINEMATIC => "CG_CIN_PLAYCINEMATIC",
+            Imports::CG_CIN_STOPCINEMATIC => "CG_CIN_STOPCINEMATIC",
+            Imports::CG_CIN_RUNCINEMATIC => "CG_CIN_RUNCINEMATIC",
+            Imports::CG_CIN_DRAWCINEMATIC => "CG_CIN_DRAWCINEMATIC",
+            Imports::CG_CIN_SETEXTENTS => "CG_CIN_SETEXTENTS",
+            Imports::CG_S_ADDREALLOOPINGSOUND => "CG_S_ADDREALLOOPINGSOUND",
+            Imports::CG_S_STOPLOOPINGSOUND => "CG_S_STOPLOOPINGSOUND",
+        }
+    }
+}
+
+/// Functions exported by the module
+///
+/// See `cgameExport_t` in [ioquake3's `cgame/cg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Exports {
+    /// Initialize module upon connecting to a server
+    CG_INIT = 0,
+    /// Shutdown module upon disconnecting, changing levels etc.
+    CG_SHUTDOWN = 1,
+    /// Client console text command
+    CG_CONSOLE_COMMAND = 2,
+    /// Draw the current frame
+    CG_DRAW_ACTIVE_FRAME = 3,
+    /// Client number of the player under the crosshair
+    CG_CROSSHAIR_PLAYER = 4,
+    /// Client number of the last player that hurt this one
+    CG_LAST_ATTACKER = 5,
+    /// Key pressed or released while the module has key focus
+    CG_KEY_EVENT = 6,
+    /// Mouse moved while the module has key focus
+    CG_MOUSE_EVENT = 7,
+    /// Change of the key focus
+    CG_EVENT_HANDLING = 8,
+}
+
+impl TryFrom<ffi::c_int> for Exports {
+    type Error = &'static str;
+
+    fn try_from(cmd: ffi::c_int) -> Result<Self, Self::Error> {
+        match cmd {
+            0 => Ok(Self::CG_INIT),
+            1 => Ok(Self::CG_SHUTDOWN),
+            2 => Ok(Self::CG_CONSOLE_COMMAND),
+            3 => Ok(Self::CG_DRAW_ACTIVE_FRAME),
+            4 => Ok(Self::CG_CROSSHAIR_PLAYER),
+            5 => Ok(Self::CG_LAST_ATTACKER),
+            6 => Ok(Self::CG_KEY_EVENT),
+            7 => Ok(Self::CG_MOUSE_EVENT),
+            8 => Ok(Self::CG_EVENT_HANDLING),
+            _ => Err("Unknown command"),
+        }
+    }
+}
+
+impl Exports {
+    /// All functions, in ascending order
+    pub const ALL: [Exports; 9] = [
+        Exports::CG_INIT,
+        Exports::CG_SHUTDOWN,
+        Exports::CG_CONSOLE_COMMAND,
+        Exports::CG_DRAW_ACTIVE_FRAME,
+        Exports::CG_CROSSHAIR_PLAYER,
+        Exports::CG_LAST_ATTACKER,
+        Exports::CG_KEY_EVENT,
+        Exports::CG_MOUSE_EVENT,
+        Exports::CG_EVENT_HANDLING,
+    ];
+
+    /// Name of the `cgameExport_t` value, e.g. `"CG_INIT"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Exports::CG_INIT => "CG_INIT",
+            Exports::CG_SHUTDOWN => "CG_SHUTDOWN",
+            Exports::CG_CONSOLE_COMMAND => "CG_CONSOLE_COMMAND",
+            Exports::CG_DRAW_ACTIVE_FRAME => "CG_DRAW_ACTIVE_FRAME",
+            Exports::CG_CROSSHAIR_PLAYER => "CG_CROSSHAIR_PLAYER",
+            Exports::CG_LAST_ATTACKER => "CG_LAST_ATTACKER",
+            Exports::CG_KEY_EVENT => "CG_KEY_EVENT",
+            Exports::CG_MOUSE_EVENT => "CG_MOUSE_EVENT",
+            Exports::CG_EVENT_HANDLING => "CG_EVENT_HANDLING",
+        }
+    }
+}
+
+/// `cgame` specific wrapper around generic [`Syscall`](Syscall)
+///
+/// This is a `Copy` handle to the engine, so every part of a mod can keep its own.
+/// Traps must only be called from the engine's thread, while it is running the module.
+///
+/// See [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+///
+/// The engine is any [`EngineSyscalls`](EngineSyscalls) backend, the default being the engine's [`Syscall`](Syscall).
+#[derive(Clone, Copy)]
+pub struct Syscalls<S = Syscall> {
+    engine: S,
+}
+
+impl Syscalls {
+    /// See `dllEntry` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn new(syscall: Syscall) -> Self {
+        Self::with_engine(syscall)
+    }
+}
+
+impl<S: EngineSyscalls> Syscalls<S> {
+    /// Wrapper around another backend than the engine's [`Syscall`](Syscall), e.g. a [`backend::Mock`](crate::backend::Mock)
+    pub fn with_engine(engine: S) -> Self {
+        Self { engine }
+    }
+
+    fn call(&self, import: Imports, args: &[ffi::intptr_t]) -> ffi::intptr_t {
+        metrics::global().count(import);
+        self.engine.syscall(import.into(), args)
+    }
+
+    /// Disconnect with an error message
+    ///
+    /// The engine does not return from this, it `longjmp`s out of the module or exits.
+    ///
+    /// See `trap_Error` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn error<T: Into<Vec<u8>>>(&self, text: T) -> ! {
+        let msg = to_cstring(text);
+        self.call(Imports::CG_ERROR, &[ffi::ptr_to_intptr(msg.as_ptr())]);
+        unreachable!("CG_ERROR returned")
+    }
+
+    /// Print a message to the console
+    ///
+    /// See `trap_Print` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn print<T: Into<Vec<u8>>>(&self, text: T) {
+        let msg = to_cstring(text);
+        self.call(Imports::CG_PRINT, &[ffi::ptr_to_intptr(msg.as_ptr())]);
+    }
+
+    /// Number of arguments of the current console command, including the command name
+    ///
+    /// See `trap_Argc` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn argc(&self) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::CG_ARGC, &[]))
+    }
+
+    /// Argument `n` of the current console command, `0` being the command name
+    ///
+    /// See `trap_Argv` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn argv(&self, n: ffi::c_int) -> String {
+        String::from_utf8_lossy(&self.argv_bytes(n)).into_owned()
+    }
+
+    /// Argument `n` as sent, for arguments that are not valid UTF-8
+    pub fn argv_bytes(&self, n: ffi::c_int) -> Vec<u8> {
+        let mut buffer = vec![0u8; MAX_STRING_CHARS];
+        self.call(
+            Imports::CG_ARGV,
+            &[
+                ffi::int_to_intptr(n),
+                ffi::ptr_to_intptr(buffer.as_mut_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+            ],
+        );
+        buffer_bytes(&buffer).to_vec()
+    }
+
+    /// Number and server time of the latest snapshot
+    ///
+    /// See `trap_GetCurrentSnapshotNumber` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn get_current_snapshot_number(&self) -> (ffi::c_int, ffi::c_int) {
+        let mut snapshot_number: ffi::c_int = 0;
+        let mut server_time: ffi::c_int = 0;
+        self.call(
+            Imports::CG_GETCURRENTSNAPSHOTNUMBER,
+            &[
+                ffi::ptr_to_intptr(&mut snapshot_number as *mut ffi::c_int),
+                ffi::ptr_to_intptr(&mut server_time as *mut ffi::c_int),
+            ],
+        );
+        (snapshot_number, server_time)
+    }
+
+    /// Snapshot `snapshot_number`, `None` if it is too old or was dropped
+    ///
+    /// See `trap_GetSnapshot` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn get_snapshot(&self, snapshot_number: ffi::c_int) -> Option<Box<Snapshot>> {
+        let mut snapshot = Box::<Snapshot>::default();
+        let valid = self.call(
+            Imports::CG_GETSNAPSHOT,
+            &[
+                ffi::int_to_intptr(snapshot_number),
+                ffi::ptr_to_intptr(&mut *snapshot as *mut Snapshot),
+            ],
+        );
+        (ffi::intptr_to_int(valid) != 0).then_some(snapshot)
+    }
+
+    /// Number of the latest user command, counting up from the first one sent
+    ///
+    /// See `trap_GetCurrentCmdNumber` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn get_current_cmd_number(&self) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::CG_GETCURRENTCMDNUMBER, &[]))
+    }
+
+    /// User command `cmd_number`, `None` if it is older than [`CMD_BACKUP`](CMD_BACKUP) commands
+    ///
+    /// See `trap_GetUserCmd` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn get_usercmd(&self, cmd_number: ffi::c_int) -> Option<UserCmd> {
+        let mut cmd = UserCmd::default();
+        let valid = self.call(
+            Imports::CG_GETUSERCMD,
+            &[
+                ffi::int_to_intptr(cmd_number),
+                ffi::ptr_to_intptr(&mut cmd as *mut UserCmd),
+            ],
+        );
+        (ffi::intptr_to_int(valid) != 0).then_some(cmd)
+    }
+
+    /// Whether key `keynum` is held down
+    ///
+    /// See `trap_Key_IsDown` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn key_is_down(&self, keynum: ffi::c_int) -> bool {
+        self.call(Imports::CG_KEY_ISDOWN, &[ffi::int_to_intptr(keynum)]) != 0
+    }
+
+    /// Which parts of the client get key presses
+    ///
+    /// See `trap_Key_GetCatcher` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn key_get_catcher(&self) -> KeyCatcher {
+        KeyCatcher::from_bits_retain(ffi::intptr_to_int(
+            self.call(Imports::CG_KEY_GETCATCHER, &[]),
+        ))
+    }
+
+    /// Set which parts of the client get key presses, e.g. [`KeyCatcher::CGAME`](KeyCatcher::CGAME) for an in-game menu
+    ///
+    /// The engine keeps [`KeyCatcher::CONSOLE`](KeyCatcher::CONSOLE) if the console is open, only the player can close it.
+    ///
+    /// See `trap_Key_SetCatcher` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn key_set_catcher(&self, catcher: KeyCatcher) {
+        self.call(
+            Imports::CG_KEY_SETCATCHER,
+            &[ffi::int_to_intptr(catcher.bits())],
+        );
+    }
+
+    /// Key bound to the command `binding`, e.g. `"+attack"`, `None` if it is not bound
+    ///
+    /// See `trap_Key_GetKey` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn key_get_key(&self, binding: &str) -> Option<ffi::c_int> {
+        let binding = to_cstring(binding);
+        let keynum = ffi::intptr_to_int(self.call(
+            Imports::CG_KEY_GETKEY,
+            &[ffi::ptr_to_intptr(binding.as_ptr())],
+        ));
+        (keynum >= 0).then_some(keynum)
+    }
+
+    /// Stop the looping sounds added with [`s_add_looping_sound`](Syscalls::s_add_looping_sound),
+    /// or all of them with `kill_all`
+    ///
+    /// See [`looping::LoopingSounds`](looping::LoopingSounds), which calls this every frame.
+    ///
+    /// See `trap_S_ClearLoopingSounds` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn s_clear_looping_sounds(&self, kill_all: bool) {
+        self.call(
+            Imports::CG_S_CLEARLOOPINGSOUNDS,
+            &[ffi::int_to_intptr(kill_all.into())],
+        );
+    }
+
+    /// Play `sfx` looping at entity `entity_num` until the next [`s_clear_looping_sounds`](Syscalls::s_clear_looping_sounds)
+    ///
+    /// See `trap_S_AddLoopingSound` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn s_add_looping_sound(
+        &self,
+        entity_num: ffi::c_int,
+        origin: Vec3,
+        velocity: Vec3,
+        sfx: SoundHandle,
+    ) {
+        self.call(
+            Imports::CG_S_ADDLOOPINGSOUND,
+            &[
+                ffi::int_to_intptr(entity_num),
+                ffi::ptr_to_intptr(origin.as_ptr()),
+                ffi::ptr_to_intptr(velocity.as_ptr()),
+                ffi::int_to_intptr(sfx.0),
+            ],
+        );
+    }
+
+    /// Play `sfx` looping at entity `entity_num` until [`s_stop_looping_sound`](Syscalls::s_stop_looping_sound)
+    ///
+    /// See `trap_S_AddRealLoopingSound` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn s_add_real_looping_sound(
+        &self,
+        entity_num: ffi::c_int,
+        origin: Vec3,
+        velocity: Vec3,
+        sfx: SoundHandle,
+    ) {
+        self.call(
+            Imports::CG_S_ADDREALLOOPINGSOUND,
+            &[
+                ffi::int_to_intptr(entity_num),
+                ffi::ptr_to_intptr(origin.as_ptr()),
+                ffi::ptr_to_intptr(velocity.as_ptr()),
+                ffi::int_to_intptr(sfx.0),
+            ],
+        );
+    }
+
+    /// Stop the looping sound of entity `entity_num`
+    ///
+    /// See `trap_S_StopLoopingSound` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn s_stop_looping_sound(&self, entity_num: ffi::c_int) {
+        self.call(
+            Imports::CG_S_STOPLOOPINGSOUND,
+            &[ffi::int_to_intptr(entity_num)],
+        );
+    }
+}
+
+/// See `trap_R_RegisterModel` etc. in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+impl<S: EngineSyscalls> AssetTraps for Syscalls<S> {
+    fn r_register_model(&self, name: &str) -> ModelHandle {
+        let name = to_cstring(name);
+        ModelHandle(ffi::intptr_to_int(self.call(
+            Imports::CG_R_REGISTERMODEL,
+            &[ffi::ptr_to_intptr(name.as_ptr())],
+        )))
+    }
+
+    fn r_register_shader(&self, name: &str) -> ShaderHandle {
+        let name = to_cstring(name);
+        ShaderHandle(ffi::intptr_to_int(self.call(
+            Imports::CG_R_REGISTERSHADER,
+            &[ffi::ptr_to_intptr(name.as_ptr())],
+        )))
+    }
+
+    fn r_register_shader_nomip(&self, name: &str) -> ShaderHandle {
+        let name = to_cstring(name);
+        ShaderHandle(ffi::intptr_to_int(self.call(
+            Imports::CG_R_REGISTERSHADERNOMIP,
+            &[ffi::ptr_to_intptr(name.as_ptr())],
+        )))
+    }
+
+    fn s_register_sound(&self, name: &str, compressed: bool) -> SoundHandle {
+        let name = to_cstring(name);
+        SoundHandle(ffi::intptr_to_int(self.call(
+            Imports::CG_S_REGISTERSOUND,
+            &[
+                ffi::ptr_to_intptr(name.as_ptr()),
+                ffi::int_to_intptr(compressed.into()),
+            ],
+        )))
+    }
+
+    fn r_register_font(&self, name: &str, point_size: ffi::c_int, font: &mut FontInfo) {
+        let name = to_cstring(name);
+        self.call(
+            Imports::CG_R_REGISTERFONT,
+            &[
+                ffi::ptr_to_intptr(name.as_ptr()),
+                ffi::int_to_intptr(point_size),
+                ffi::ptr_to_intptr(font as *mut FontInfo),
+            ],
+        );
+    }
+}
+
+/// See `trap_R_LerpTag` etc. in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+impl<S: EngineSyscalls> RenderTraps for Syscalls<S> {
+    fn r_lerp_tag(
+        &self,
+        model: ModelHandle,
+        start_frame: ffi::c_int,
+        end_frame: ffi::c_int,
+        fraction: f32,
+        tag_name: &str,
+    ) -> Option<Orientation> {
+        let tag_name = to_cstring(tag_name);
+        let mut tag = Orientation::default();
+        let found = self.call(
+            Imports::CG_R_LERPTAG,
+            &[
+                ffi::ptr_to_intptr(&mut tag as *mut Orientation),
+                ffi::int_to_intptr(model.0),
+                ffi::int_to_intptr(start_frame),
+                ffi::int_to_intptr(end_frame),
+                ffi::float_to_intptr(fraction),
+                ffi::ptr_to_intptr(tag_name.as_ptr()),
+            ],
+        );
+        (ffi::intptr_to_int(found) != 0).then_some(tag)
+    }
+
+    fn update_screen(&self) {
+        self.call(Imports::CG_UPDATESCREEN, &[]);
+    }
+}
+
+/// See `trap_S_StartLocalSound` etc. in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+impl<S: EngineSyscalls> SoundTraps for Syscalls<S> {
+    fn s_start_local_sound(&self, sfx: SoundHandle, channel: SoundChannel) {
+        self.call(
+            Imports::CG_S_STARTLOCALSOUND,
+            &[
+                ffi::int_to_intptr(sfx.0),
+                ffi::int_to_intptr(channel as ffi::c_int),
+            ],
+        );
+    }
+
+    fn s_start_background_track(&self, intro: &str, looping: Option<&str>) {
+        let intro = to_cstring(intro);
+        // The engine loops the intro for an empty name
+        let looping = to_cstring(looping.unwrap_or(""));
+        self.call(
+            Imports::CG_S_STARTBACKGROUNDTRACK,
+            &[
+                ffi::ptr_to_intptr(intro.as_ptr()),
+                ffi::ptr_to_intptr(looping.as_ptr()),
+            ],
+        );
+    }
+
+    fn s_stop_background_track(&self) {
+        self.call(Imports::CG_S_STOPBACKGROUNDTRACK, &[]);
+    }
+}
+
+/// See `trap_CIN_PlayCinematic` etc. in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+impl<S: EngineSyscalls> CinematicTraps for Syscalls<S> {
+    fn cin_play_cinematic(
+        &self,
+        name: &str,
+        x: ffi::c_int,
+        y: ffi::c_int,
+        width: ffi::c_int,
+        height: ffi::c_int,
+        flags: CinematicFlags,
+    ) -> ffi::c_int {
+        let name = to_cstring(name);
+        ffi::intptr_to_int(self.call(
+            Imports::CG_CIN_PLAYCINEMATIC,
+            &[
+                ffi::ptr_to_intptr(name.as_ptr()),
+                ffi::int_to_intptr(x),
+                ffi::int_to_intptr(y),
+                ffi::int_to_intptr(width),
+                ffi::int_to_intptr(height),
+                ffi::int_to_intptr(flags.bits()),
+            ],
+        ))
+    }
+
+    fn cin_stop_cinematic(&self, handle: ffi::c_int) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::CG_CIN_STOPCINEMATIC, &[ffi::int_to_intptr(handle)]))
+    }
+
+    fn cin_run_cinematic(&self, handle: ffi::c_int) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::CG_CIN_RUNCINEMATIC, &[ffi::int_to_intptr(handle)]))
+    }
+
+    fn cin_draw_cinematic(&self, handle: ffi::c_int) {
+        self.call(Imports::CG_CIN_DRAWCINEMATIC, &[ffi::int_to_intptr(handle)]);
+    }
+
+    fn cin_set_extents(
+        &self,
+        handle: ffi::c_int,
+        x: ffi::c_int,
+        y: ffi::c_int,
+        width: ffi::c_int,
+        height: ffi::c_int,
+    ) {
+        self.call(
+            Imports::CG_CIN_SETEXTENTS,
+            &[
+                ffi::int_to_intptr(handle),
+                ffi::int_to_intptr(x),
+                ffi::int_to_intptr(y),
+                ffi::int_to_intptr(width),
+                ffi::int_to_intptr(height),
+            ],
+        );
+    }
+}
+
+/// See `vmMain` in [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c).
+pub trait Module: 'static + Sync + Send {
+    /// See `dllEntry` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    fn dll_entry(syscalls: Syscalls) -> Box<Self>
+    where
+        Self: Sized;
+
+    /// See `CG_Init` in [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c).
+    fn init(
+        &self,
+        server_message_num: ffi::c_int,
+        server_command_sequence: ffi::c_int,
+        client_num: ffi::c_int,
+    );
+
+    /// See `CG_Shutdown` in [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c).
+    fn shutdown(&self);
+
+    /// Return whether the command was handled
+    ///
+    /// See `CG_ConsoleCommand` in [ioquake3's `cgame/cg_consolecmds.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_consolecmds.c).
+    fn console_command(&self) -> bool;
+
+    /// Draw a frame at `server_time` for `stereo_view`, e.g. twice per frame with stereo rendering
+    ///
+    /// See `CG_DrawActiveFrame` in [ioquake3's `cgame/cg_view.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_view.c).
+    fn draw_active_frame(
+        &self,
+        server_time: LevelTime,
+        stereo_view: StereoFrame,
+        demo_playback: bool,
+    );
+
+    /// Client number of the player under the crosshair, for the engine's `+button` binds, `None` for nobody
+    ///
+    /// See `CG_CrosshairPlayer` in [ioquake3's `cgame/cg_draw.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_draw.c).
+    fn crosshair_player(&self) -> Option<ffi::c_int>;
+
+    /// Client number of the player who hit the player last, `None` for nobody
+    ///
+    /// See `CG_LastAttacker` in [ioquake3's `cgame/cg_draw.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_draw.c).
+    fn last_attacker(&self) -> Option<ffi::c_int>;
+
+    /// See `CG_KeyEvent` in [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c).
+    fn key_event(&self, key: ffi::c_int, down: bool);
+
+    /// See `CG_MouseEvent` in [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c).
+    fn mouse_event(&self, dx: ffi::c_int, dy: ffi::c_int);
+
+    /// See `CG_EventHandling` in [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c).
+    fn event_handling(&self, event_type: ffi::c_int);
+}
+
+/// Call the [`Module`](Module) function for `command` with its arguments from `args`
+///
+/// # Panics
+///
+/// Panics if `command` is not a known [`Exports`](Exports) value,
+/// or the stereo frame of [`Exports::CG_DRAW_ACTIVE_FRAME`](Exports::CG_DRAW_ACTIVE_FRAME) not a known [`StereoFrame`](StereoFrame).
+///
+/// See `vmMain` in [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c).
+pub fn dispatch(module: &dyn Module, command: ffi::c_int, args: [ffi::c_int; 12]) -> VmResult {
+    let command = match Exports::try_from(command) {
+        Ok(command) => command,
+        Err(_) => panic!("Unknown cgame command {:?}", command),
+    };
+    metrics::global().count(command);
+
+    match command {
+        Exports::CG_INIT => {
+            module.init(args[0], args[1], args[2]);
+            VmResult::ok()
+        }
+        Exports::CG_SHUTDOWN => {
+            module.shutdown();
+            VmResult::ok()
+        }
+        Exports::CG_CONSOLE_COMMAND => VmResult::bool(module.console_command()),
+        Exports::CG_DRAW_ACTIVE_FRAME => {
+            let stereo_view = match StereoFrame::try_from(args[1]) {
+                Ok(stereo_view) => stereo_view,
+                Err(_) => panic!("Unknown cgame stereo frame {:?}", args[1]),
+            };
+            module.draw_active_frame(LevelTime(args[0]), stereo_view, args[2] != 0);
+            VmResult::ok()
+        }
+        Exports::CG_CROSSHAIR_PLAYER => VmResult::int(module.crosshair_player().unwrap_or(-1)),
+        Exports::CG_LAST_ATTACKER => VmResult::int(module.last_attacker().unwrap_or(-1)),
+        Exports::CG_KEY_EVENT => {
+            module.key_event(args[0], args[1] != 0);
+            VmResult::ok()
+        }
+        Exports::CG_MOUSE_EVENT => {
+            module.mouse_event(args[0], args[1]);
+            VmResult::ok()
+        }
+        Exports::CG_EVENT_HANDLING => {
+            module.event_handling(args[0]);
+            VmResult::ok()
+        }
+    }
+}
+
+/// Create a [NativeVM](crate::NativeVM) impl for the id Quake 3 `cgame` module
+///
+/// Export names can be given like for [`native_vm!`](crate::native_vm), e.g.
+/// `cgame_module!(MyModule, dll_entry = ["dllEntry"], vm_main = ["vmMain"])`.
+#[macro_export]
+macro_rules! cgame_module {
+    ($ty:ty $(, $($exports:tt)+)?) => {
+        // Anonymous const, so the wrapper can't clash with names in the caller's module
+        const _: () = {
+            struct ModuleWrapper {
+                module: ::std::boxed::Box<dyn $crate::cgame::Module>,
+            }
+
+            impl $crate::NativeVM for ModuleWrapper {
+                fn dll_entry(syscall: $crate::Syscall) -> ::std::boxed::Box<Self> {
+                    ::std::boxed::Box::new(ModuleWrapper {
+                        module: <$ty as $crate::cgame::Module>::dll_entry(
+                            $crate::cgame::Syscalls::new(syscall),
+                        ),
+                    })
+                }
+
+                fn vm_main(
+                    &self,
+                    command: $crate::ffi::c_int,
+                    arg0: $crate::ffi::c_int,
+                    arg1: $crate::ffi::c_int,
+                    arg2: $crate::ffi::c_int,
+                    arg3: $crate::ffi::c_int,
+                    arg4: $crate::ffi::c_int,
+                    arg5: $crate::ffi::c_int,
+                    arg6: $crate::ffi::c_int,
+                    arg7: $crate::ffi::c_int,
+                    arg8: $crate::ffi::c_int,
+                    arg9: $crate::ffi::c_int,
+                    arg10: $crate::ffi::c_int,
+                    arg11: $crate::ffi::c_int,
+                ) -> $crate::ffi::intptr_t {
+                    $crate::cgame::dispatch(
+                        &*self.module,
+                        command,
+                        [
+                            arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8, arg9, arg10,
+                            arg11,
+                        ],
+                    )
+                    .into_raw()
+                }
+            }
+
+            $crate::native_vm!(ModuleWrapper $(, $($exports)+)?);
+        };
+    };
+}
@@ -0,0 +1,89 @@
+//! Minimal client game to build upon
+//!
+//! Requires the `base` feature.
+//!
+//! [`BaseCGame`](BaseCGame) loads and connects, but draws nothing, so the screen stays black
+//! while the console keeps working. Mods can keep one and delegate to it until they render on their own,
+//! like [`qagame::base::BaseGame`](crate::qagame::base::BaseGame) on the server.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use quake3_native_vm::cgame::base::BaseCGame;
+//! use quake3_native_vm::cgame_module;
+//!
+//! cgame_module!(BaseCGame);
+//! # fn main() {}
+//! ```
+
+use super::{Module, StereoFrame, Syscalls};
+use crate::ffi;
+use crate::time::LevelTime;
+use std::fmt;
+
+/// [`Module`](super::Module) that satisfies the engine, but does not render anything
+pub struct BaseCGame {
+    syscalls: Syscalls,
+}
+
+impl BaseCGame {
+    /// Create the module, to be initialized by the engine with [`init`](BaseCGame::init)
+    pub fn new(syscalls: Syscalls) -> Self {
+        Self { syscalls }
+    }
+
+    /// Syscalls of the engine
+    pub fn syscalls(&self) -> &Syscalls {
+        &self.syscalls
+    }
+}
+
+impl Module for BaseCGame {
+    fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+        Box::new(Self::new(syscalls))
+    }
+
+    fn init(
+        &self,
+        _server_message_num: ffi::c_int,
+        _server_command_sequence: ffi::c_int,
+        client_num: ffi::c_int,
+    ) {
+        self.syscalls
+            .print(format!("BaseCGame initialized as client {}\n", client_num));
+    }
+
+    fn shutdown(&self) {}
+
+    fn console_command(&self) -> bool {
+        false
+    }
+
+    fn draw_active_frame(
+        &self,
+        _server_time: LevelTime,
+        _stereo_view: StereoFrame,
+        _demo_playback: bool,
+    ) {
+    }
+
+    fn crosshair_player(&self) -> Option<ffi::c_int> {
+        None
+    }
+
+    fn last_attacker(&self) -> Option<ffi::c_int> {
+        None
+    }
+
+    fn key_event(&self, _key: ffi::c_int, _down: bool) {}
+
+    fn mouse_event(&self, _dx: ffi::c_int, _dy: ffi::c_int) {}
+
+    fn event_handling(&self, _event_type: ffi::c_int) {}
+}
+
+impl fmt::Debug for BaseCGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BaseCGame").finish_non_exhaustive()
+    }
+}
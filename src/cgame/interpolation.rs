@@ -0,0 +1,246 @@
+//! Interpolation between snapshots
+//!
+//! The server sends snapshots at its frame rate, e.g. 20 per second, while the client renders
+//! many more frames. To move entities smoothly, the client renders a little in the past, between
+//! the [`current`](Snapshots::current) snapshot and the [`next`](Snapshots::next) one, and
+//! interpolates the entities of both by the [`fraction`](Snapshots::fraction) of the time between them.
+//!
+//! See `CG_ProcessSnapshots` in [ioquake3's `cgame/cg_snapshot.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_snapshot.c)
+//! and `CG_InterpolateEntityPosition` in [ioquake3's `cgame/cg_ents.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_ents.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::cgame::interpolation::Snapshots;
+//! use quake3_native_vm::cgame::Snapshot;
+//! use quake3_native_vm::ffi;
+//! use quake3_native_vm::shared::{EntityState, TrType, Trajectory};
+//!
+//! /// Snapshot at `server_time` with a player at `x`
+//! fn snapshot(server_time: ffi::c_int, x: f32) -> Box<Snapshot> {
+//!     let mut snapshot = Box::<Snapshot>::default();
+//!     snapshot.server_time = server_time;
+//!     snapshot.num_entities = 1;
+//!     snapshot.entities[0] = EntityState {
+//!         number: 3,
+//!         pos: Trajectory {
+//!             tr_type: TrType::TR_INTERPOLATE as ffi::c_int,
+//!             ..Trajectory::stationary([x, 0.0, 0.0])
+//!         },
+//!         ..EntityState::default()
+//!     };
+//!     snapshot
+//! }
+//!
+//! let mut received = vec![snapshot(1100, 100.0), snapshot(1050, 50.0), snapshot(1000, 0.0)];
+//! let mut snapshots = Snapshots::new();
+//! snapshots.update(1025, || received.pop());
+//! assert_eq!(snapshots.current().unwrap().server_time, 1000);
+//! assert_eq!(snapshots.next().unwrap().server_time, 1050);
+//! assert_eq!(snapshots.fraction(1025), 0.5);
+//! assert_eq!(snapshots.entity_position(3, 1025).unwrap().origin, [25.0, 0.0, 0.0]);
+//!
+//! // Time passed the next snapshot, so it becomes the current one
+//! assert!(snapshots.update(1060, || received.pop()));
+//! assert_eq!(snapshots.current().unwrap().server_time, 1050);
+//! assert_eq!(snapshots.entity_position(3, 1060).unwrap().origin, [60.0, 0.0, 0.0]);
+//! ```
+
+use super::{Snapshot, Syscalls};
+use crate::shared::{EntityState, TrType, Vec3};
+use crate::{ffi, EngineSyscalls};
+
+/// Linear interpolation from `from` to `to` by `fraction`
+pub fn lerp(from: Vec3, to: Vec3, fraction: f32) -> Vec3 {
+    let mut result = from;
+    for (result, (from, to)) in result.iter_mut().zip(from.iter().zip(to)) {
+        *result = from + fraction * (to - from);
+    }
+    result
+}
+
+/// Interpolation of the angle `from` to `to` in degrees by `fraction`, the short way around
+///
+/// See `LerpAngle` in [ioquake3's `qcommon/q_math.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_math.c).
+///
+/// ```rust
+/// use quake3_native_vm::cgame::interpolation::lerp_angle;
+///
+/// assert_eq!(lerp_angle(350.0, 10.0, 0.5), 360.0);
+/// ```
+pub fn lerp_angle(from: f32, mut to: f32, fraction: f32) -> f32 {
+    if to - from > 180.0 {
+        to -= 360.0;
+    }
+    if to - from < -180.0 {
+        to += 360.0;
+    }
+    from + fraction * (to - from)
+}
+
+/// [`lerp_angle`](lerp_angle) of each of the angles
+pub fn lerp_angles(from: Vec3, to: Vec3, fraction: f32) -> Vec3 {
+    [
+        lerp_angle(from[0], to[0], fraction),
+        lerp_angle(from[1], to[1], fraction),
+        lerp_angle(from[2], to[2], fraction),
+    ]
+}
+
+/// Where to render an entity
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EntityPosition {
+    /// Position, see `cent->lerpOrigin`
+    pub origin: Vec3,
+    /// Angles, see `cent->lerpAngles`
+    pub angles: Vec3,
+}
+
+/// The pair of snapshots the client renders between
+///
+/// See `cg.snap` and `cg.nextSnap` in [ioquake3's `cgame/cg_local.h`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_local.h).
+#[derive(Clone, Debug, Default)]
+pub struct Snapshots {
+    current: Option<Box<Snapshot>>,
+    next: Option<Box<Snapshot>>,
+    processed: ffi::c_int,
+}
+
+impl Snapshots {
+    /// No snapshot received yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot the client is rendering from, `None` until the first one arrived
+    pub fn current(&self) -> Option<&Snapshot> {
+        self.current.as_deref()
+    }
+
+    /// Snapshot the client is interpolating to, `None` if it did not arrive yet
+    pub fn next(&self) -> Option<&Snapshot> {
+        self.next.as_deref()
+    }
+
+    /// Read the snapshots the engine received since the last call and advance to `time`
+    ///
+    /// Returns whether the current snapshot changed, see [`update`](Snapshots::update).
+    ///
+    /// See `CG_ProcessSnapshots` in [ioquake3's `cgame/cg_snapshot.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_snapshot.c).
+    pub fn process<S: EngineSyscalls>(&mut self, syscalls: &Syscalls<S>, time: ffi::c_int) -> bool {
+        let (latest, _) = syscalls.get_current_snapshot_number();
+        if latest < self.processed {
+            // The engine restarted its numbering, e.g. on a reconnect
+            self.processed = latest;
+        }
+        let processed = &mut self.processed;
+        let mut read_next = || {
+            while *processed < latest {
+                *processed += 1;
+                // Dropped snapshots are skipped
+                if let Some(snapshot) = syscalls.get_snapshot(*processed) {
+                    return Some(snapshot);
+                }
+            }
+            None
+        };
+        Self::advance(&mut self.current, &mut self.next, time, &mut read_next)
+    }
+
+    /// Advance to `time`, reading snapshots in the order they were received with `read_next`
+    ///
+    /// The next snapshot becomes the current one once `time` reached it.
+    /// Returns whether the current snapshot changed, e.g. to run its events.
+    pub fn update(
+        &mut self,
+        time: ffi::c_int,
+        mut read_next: impl FnMut() -> Option<Box<Snapshot>>,
+    ) -> bool {
+        Self::advance(&mut self.current, &mut self.next, time, &mut read_next)
+    }
+
+    fn advance(
+        current: &mut Option<Box<Snapshot>>,
+        next: &mut Option<Box<Snapshot>>,
+        time: ffi::c_int,
+        read_next: &mut dyn FnMut() -> Option<Box<Snapshot>>,
+    ) -> bool {
+        let mut changed = false;
+        if current.is_none() {
+            match read_next() {
+                Some(snapshot) => {
+                    *current = Some(snapshot);
+                    changed = true;
+                }
+                None => return false,
+            }
+        }
+        loop {
+            if next.is_none() {
+                *next = read_next();
+            }
+            match next {
+                Some(snapshot) if time >= snapshot.server_time => {
+                    *current = next.take();
+                    changed = true;
+                }
+                _ => return changed,
+            }
+        }
+    }
+
+    /// Fraction of the time from the current to the next snapshot at `time`, `0` without a next snapshot
+    ///
+    /// See `cg.frameInterpolation` in [ioquake3's `cgame/cg_local.h`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_local.h).
+    pub fn fraction(&self, time: ffi::c_int) -> f32 {
+        match (self.current(), self.next()) {
+            (Some(current), Some(next)) if next.server_time > current.server_time => {
+                (time - current.server_time) as f32
+                    / (next.server_time - current.server_time) as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Where to render entity `number` of the current snapshot at `time`, `None` if it is not in it
+    ///
+    /// Entities with a [`TrType::TR_INTERPOLATE`](TrType::TR_INTERPOLATE) trajectory are interpolated
+    /// if they are in the next snapshot as well, the trajectories of others are evaluated at `time`.
+    ///
+    /// See `CG_CalcEntityLerpPositions` in [ioquake3's `cgame/cg_ents.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_ents.c).
+    pub fn entity_position(&self, number: ffi::c_int, time: ffi::c_int) -> Option<EntityPosition> {
+        let find = |snapshot: &Snapshot| -> Option<EntityState> {
+            snapshot
+                .entities()
+                .iter()
+                .find(|entity| entity.number == number)
+                .copied()
+        };
+        let current_snapshot = self.current()?;
+        let current = find(current_snapshot)?;
+        let interpolate = current.pos.tr_type == TrType::TR_INTERPOLATE as ffi::c_int;
+        match self.next().and_then(|next| Some((next, find(next)?))) {
+            Some((next_snapshot, next)) if interpolate => {
+                let fraction = self.fraction(time);
+                let (from_time, to_time) =
+                    (current_snapshot.server_time, next_snapshot.server_time);
+                Some(EntityPosition {
+                    origin: lerp(
+                        current.pos.evaluate(from_time),
+                        next.pos.evaluate(to_time),
+                        fraction,
+                    ),
+                    angles: lerp_angles(
+                        current.apos.evaluate(from_time),
+                        next.apos.evaluate(to_time),
+                        fraction,
+                    ),
+                })
+            }
+            _ => Some(EntityPosition {
+                origin: current.pos.evaluate(time),
+                angles: current.apos.evaluate(time),
+            }),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Progress while `cgame` loads a level
+//!
+//! The engine does not render while [`Module::init`](super::Module::init) registers the models,
+//! sounds and shaders of a level, which can take seconds. Calling
+//! [`RenderTraps::update_screen`](RenderTraps::update_screen) between the steps renders a frame
+//! right away, calling back into [`Module::draw_active_frame`](super::Module::draw_active_frame)
+//! during `init`. A [`LoadingScreen`](LoadingScreen) keeps the progress for it to draw.
+//!
+//! See `CG_LoadingString` in [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c)
+//! and `CG_DrawInformation` in [ioquake3's `cgame/cg_info.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_info.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::backend::{Mock, Recording};
+//! use quake3_native_vm::cgame::loading::LoadingScreen;
+//! use quake3_native_vm::cgame::{Imports, Syscalls};
+//!
+//! let engine = Recording::new(Mock(|_, _: &[isize]| 0));
+//! let syscalls = Syscalls::with_engine(&engine);
+//!
+//! // In `init`
+//! let loading = LoadingScreen::new(3);
+//! loading.step(&syscalls, "sounds");
+//! loading.step(&syscalls, "graphics");
+//!
+//! // In `draw_active_frame`
+//! let progress = loading.progress();
+//! assert_eq!(progress.text, "graphics");
+//! assert_eq!(progress.fraction(), 2.0 / 3.0);
+//!
+//! let calls = engine.take();
+//! assert_eq!(calls.len(), 2);
+//! assert_eq!(calls[0].num, isize::from(Imports::CG_UPDATESCREEN));
+//! ```
+
+use crate::render::RenderTraps;
+use std::sync::Mutex;
+
+/// What a [`LoadingScreen`](LoadingScreen) shows
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LoadingProgress {
+    /// What is being loaded, e.g. `"sounds"`, see `cg.infoScreenText`
+    pub text: String,
+    /// Number of steps done
+    pub done: usize,
+    /// Number of steps expected, `0` if unknown
+    pub total: usize,
+}
+
+impl LoadingProgress {
+    /// Fraction of the steps done, `0` if the number of steps is unknown
+    pub fn fraction(&self) -> f32 {
+        match self.total {
+            0 => 0.0,
+            total => self.done.min(total) as f32 / total as f32,
+        }
+    }
+}
+
+/// Loading progress, shared between `init` and the frames it renders
+///
+/// The progress is not locked while the engine renders, so drawing it from
+/// [`Module::draw_active_frame`](super::Module::draw_active_frame) does not deadlock.
+#[derive(Debug, Default)]
+pub struct LoadingScreen {
+    progress: Mutex<LoadingProgress>,
+}
+
+impl LoadingScreen {
+    /// Nothing loaded yet out of `total` steps, `0` if unknown
+    pub fn new(total: usize) -> Self {
+        Self {
+            progress: Mutex::new(LoadingProgress {
+                total,
+                ..LoadingProgress::default()
+            }),
+        }
+    }
+
+    /// Show that `text` is being loaded now and render a frame with it
+    ///
+    /// See `CG_LoadingString` in [ioquake3's `cgame/cg_main.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_main.c).
+    pub fn step<T: RenderTraps + ?Sized>(&self, traps: &T, text: impl Into<String>) {
+        {
+            let mut progress = self.progress.lock().unwrap();
+            progress.text = text.into();
+            progress.done += 1;
+        }
+        traps.update_screen();
+    }
+
+    /// Current progress, to draw it
+    pub fn progress(&self) -> LoadingProgress {
+        self.progress.lock().unwrap().clone()
+    }
+}
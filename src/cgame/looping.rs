@@ -0,0 +1,136 @@
+//! Looping sounds of entities, e.g. of movers and powerups
+//!
+//! The engine keeps two kinds of looping sounds per entity: those added with
+//! [`Syscalls::s_add_looping_sound`](Syscalls::s_add_looping_sound) only last until the next
+//! [`Syscalls::s_clear_looping_sounds`](Syscalls::s_clear_looping_sounds), so they have to be added
+//! again every frame, while "real" ones last until they are stopped.
+//! [`LoopingSounds`](LoopingSounds) collects both for a frame and submits them at once,
+//! clearing the previous frame's sounds and stopping the real ones that were not added again.
+//!
+//! See `CG_EntityEffects` in [ioquake3's `cgame/cg_ents.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_ents.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::asset::SoundHandle;
+//! use quake3_native_vm::backend::{Mock, Recording};
+//! use quake3_native_vm::cgame::looping::LoopingSounds;
+//! use quake3_native_vm::cgame::{Imports, Syscalls};
+//!
+//! let engine = Recording::new(Mock(|_, _: &[isize]| 0));
+//! let syscalls = Syscalls::with_engine(&engine);
+//! let mut sounds = LoopingSounds::new();
+//!
+//! // A door moving and a speaker
+//! sounds.add(12, [0.0; 3], [0.0, 0.0, 10.0], SoundHandle(3));
+//! sounds.add_real(40, [64.0, 0.0, 0.0], [0.0; 3], SoundHandle(4));
+//! sounds.submit(&syscalls);
+//!
+//! // The speaker is gone the next frame
+//! sounds.submit(&syscalls);
+//!
+//! let nums: Vec<isize> = engine.take().iter().map(|call| call.num).collect();
+//! assert_eq!(
+//!     nums,
+//!     [
+//!         isize::from(Imports::CG_S_CLEARLOOPINGSOUNDS),
+//!         isize::from(Imports::CG_S_ADDLOOPINGSOUND),
+//!         isize::from(Imports::CG_S_ADDREALLOOPINGSOUND),
+//!         isize::from(Imports::CG_S_CLEARLOOPINGSOUNDS),
+//!         isize::from(Imports::CG_S_STOPLOOPINGSOUND),
+//!     ]
+//! );
+//! ```
+
+use super::Syscalls;
+use crate::asset::SoundHandle;
+use crate::shared::Vec3;
+use crate::{ffi, EngineSyscalls};
+use std::collections::BTreeSet;
+
+/// Looping sound of an entity for one frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Loop {
+    entity_num: ffi::c_int,
+    origin: Vec3,
+    velocity: Vec3,
+    sfx: SoundHandle,
+}
+
+/// Looping sounds of the current frame
+///
+/// Add the sounds of all entities every frame, then [`submit`](LoopingSounds::submit) them once.
+#[derive(Clone, Debug, Default)]
+pub struct LoopingSounds {
+    frame: Vec<Loop>,
+    real: Vec<Loop>,
+    playing_real: BTreeSet<ffi::c_int>,
+}
+
+impl LoopingSounds {
+    /// No sounds yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Play `sfx` looping at entity `entity_num` in this frame, e.g. of a moving door
+    ///
+    /// See `trap_S_AddLoopingSound` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn add(&mut self, entity_num: ffi::c_int, origin: Vec3, velocity: Vec3, sfx: SoundHandle) {
+        self.frame.push(Loop {
+            entity_num,
+            origin,
+            velocity,
+            sfx,
+        });
+    }
+
+    /// Play `sfx` looping at entity `entity_num` from this frame on, e.g. of a speaker
+    ///
+    /// The engine keeps playing it while it is added every frame, and it is stopped in the first frame it is not.
+    ///
+    /// See `trap_S_AddRealLoopingSound` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    pub fn add_real(
+        &mut self,
+        entity_num: ffi::c_int,
+        origin: Vec3,
+        velocity: Vec3,
+        sfx: SoundHandle,
+    ) {
+        self.real.push(Loop {
+            entity_num,
+            origin,
+            velocity,
+            sfx,
+        });
+    }
+
+    /// Replace the looping sounds of the previous frame with those added since
+    pub fn submit<S: EngineSyscalls>(&mut self, syscalls: &Syscalls<S>) {
+        syscalls.s_clear_looping_sounds(false);
+        for sound in self.frame.drain(..) {
+            syscalls.s_add_looping_sound(sound.entity_num, sound.origin, sound.velocity, sound.sfx);
+        }
+
+        let mut playing_real = BTreeSet::new();
+        for sound in self.real.drain(..) {
+            syscalls.s_add_real_looping_sound(
+                sound.entity_num,
+                sound.origin,
+                sound.velocity,
+                sound.sfx,
+            );
+            playing_real.insert(sound.entity_num);
+        }
+        for &entity_num in self.playing_real.difference(&playing_real) {
+            syscalls.s_stop_looping_sound(entity_num);
+        }
+        self.playing_real = playing_real;
+    }
+
+    /// Stop all looping sounds, e.g. on a map restart
+    pub fn stop_all<S: EngineSyscalls>(&mut self, syscalls: &Syscalls<S>) {
+        syscalls.s_clear_looping_sounds(true);
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,223 @@
+//! Prediction of the player's own movement
+//!
+//! Snapshots from the server lag behind the player's input by the round trip time.
+//! To move without that delay, the client runs the same player movement as the server on the
+//! [`UserCmd`](UserCmd)s the server has not acknowledged yet, starting from the [`PlayerState`](PlayerState)
+//! of the latest snapshot. The movement itself is up to the mod, e.g. a port of `bg_pmove.c`,
+//! and is run by [`PredictionHooks::pmove`](PredictionHooks::pmove) with the [`bg`](crate::bg) types.
+//!
+//! Events of the predicted movement, e.g. jumps and footsteps, are passed to
+//! [`PredictionHooks::predictable_event`](PredictionHooks::predictable_event) right away instead of when the server confirms them.
+//! If the server's snapshot has a different event than was predicted, it is passed to
+//! [`PredictionHooks::changed_event`](PredictionHooks::changed_event).
+//!
+//! Don't predict during demo playback or while following another player, the snapshot's
+//! [`PlayerState`](PlayerState) is already what should be shown then.
+//!
+//! See [ioquake3's `cgame/cg_predict.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_predict.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::bg::Pmove;
+//! use quake3_native_vm::cgame::prediction::{Prediction, PredictionHooks};
+//! use quake3_native_vm::ffi;
+//! use quake3_native_vm::shared::{PlayerState, UserCmd};
+//!
+//! /// Moves a unit per forward move and millisecond, jumping with event `14` on `upmove`
+//! struct Walk {
+//!     events: Vec<ffi::c_int>,
+//! }
+//!
+//! impl PredictionHooks for Walk {
+//!     fn pmove(&mut self, pm: &mut Pmove) {
+//!         let msec = pm.input.cmd.server_time - pm.ps.command_time;
+//!         pm.ps.origin[0] += f32::from(pm.input.cmd.forwardmove) * msec as f32;
+//!         if pm.input.cmd.upmove > 0 {
+//!             let slot = pm.ps.event_sequence as usize % 2;
+//!             pm.ps.events[slot] = 14;
+//!             pm.ps.event_sequence += 1;
+//!         }
+//!         pm.ps.command_time = pm.input.cmd.server_time;
+//!     }
+//!
+//!     fn predictable_event(&mut self, _ps: &PlayerState, event: ffi::c_int, _event_parm: ffi::c_int) {
+//!         self.events.push(event);
+//!     }
+//! }
+//!
+//! let snapshot = PlayerState { command_time: 1000, ..Default::default() };
+//! let cmds = [
+//!     UserCmd { server_time: 990, forwardmove: 1, ..Default::default() },
+//!     UserCmd { server_time: 1010, forwardmove: 1, ..Default::default() },
+//!     UserCmd { server_time: 1020, forwardmove: 1, upmove: 1, ..Default::default() },
+//! ];
+//!
+//! let mut prediction = Prediction::new();
+//! let mut walk = Walk { events: Vec::new() };
+//! let predicted = prediction.run(&snapshot, cmds, Default::default(), &mut walk);
+//! // The command from before the snapshot was already applied by the server
+//! assert_eq!(predicted.origin[0], 20.0);
+//! assert_eq!(predicted.command_time, 1020);
+//! assert_eq!(walk.events, [14]);
+//!
+//! // Predicting the same jump again does not repeat its event
+//! prediction.run(&snapshot, cmds, Default::default(), &mut walk);
+//! assert_eq!(walk.events, [14]);
+//! ```
+
+use super::{Syscalls, CMD_BACKUP};
+use crate::bg::{Pmove, PmoveInput};
+use crate::shared::{PlayerState, UserCmd, MAX_PS_EVENTS};
+use crate::{ffi, EngineSyscalls};
+
+/// Number of predicted events kept to compare with those of later snapshots
+///
+/// See `MAX_PREDICTED_EVENTS` in [ioquake3's `cgame/cg_local.h`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_local.h).
+pub const MAX_PREDICTED_EVENTS: usize = 16;
+
+/// What a mod does during [`Prediction`](Prediction)
+pub trait PredictionHooks {
+    /// Move `pm.ps` by `pm.input.cmd`, the same way the server does
+    ///
+    /// See `Pmove` in [ioquake3's `game/bg_pmove.c`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_pmove.c).
+    fn pmove(&mut self, pm: &mut Pmove);
+
+    /// The predicted player `ps` got `event` with `event_parm`, e.g. to play its sound
+    ///
+    /// See `CG_EntityEvent` in [ioquake3's `cgame/cg_event.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_event.c).
+    fn predictable_event(&mut self, ps: &PlayerState, event: ffi::c_int, event_parm: ffi::c_int);
+
+    /// The snapshot `ps` has `event` where a different one was predicted
+    ///
+    /// Calls [`predictable_event`](PredictionHooks::predictable_event) by default, like the C game.
+    ///
+    /// See `CG_CheckChangedPredictableEvents` in [ioquake3's `cgame/cg_predict.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_predict.c).
+    fn changed_event(&mut self, ps: &PlayerState, event: ffi::c_int, event_parm: ffi::c_int) {
+        self.predictable_event(ps, event, event_parm)
+    }
+}
+
+/// Slot of event `sequence` in a ring buffer of `len` events
+fn slot(sequence: ffi::c_int, len: usize) -> usize {
+    // `len` is a power of two, so this works for negative sequences as well
+    (sequence & (len as ffi::c_int - 1)) as usize
+}
+
+/// State of the movement prediction, kept across frames
+///
+/// See `cg.predictedPlayerState` and `cg.predictableEvents` in [ioquake3's `cgame/cg_local.h`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_local.h).
+#[derive(Clone, Debug, Default)]
+pub struct Prediction {
+    predicted: Option<PlayerState>,
+    predictable_events: [ffi::c_int; MAX_PREDICTED_EVENTS],
+    event_sequence: ffi::c_int,
+}
+
+impl Prediction {
+    /// Nothing predicted yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Result of the last prediction, `None` before the first one
+    pub fn predicted(&self) -> Option<&PlayerState> {
+        self.predicted.as_ref()
+    }
+
+    /// Forget the previous prediction and its events, e.g. after a map restart
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Predict from `snapshot`, the player state of the latest snapshot, with the user commands of the engine
+    ///
+    /// `input` is used for every movement step, with its [`cmd`](PmoveInput::cmd) replaced.
+    ///
+    /// See `CG_PredictPlayerState` in [ioquake3's `cgame/cg_predict.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_predict.c).
+    pub fn predict<S: EngineSyscalls>(
+        &mut self,
+        syscalls: &Syscalls<S>,
+        snapshot: &PlayerState,
+        input: PmoveInput,
+        hooks: &mut impl PredictionHooks,
+    ) -> &PlayerState {
+        let current = syscalls.get_current_cmd_number();
+        let cmds = (current - CMD_BACKUP + 1..=current).filter_map(|n| syscalls.get_usercmd(n));
+        self.run(snapshot, cmds, input, hooks)
+    }
+
+    /// Like [`predict`](Prediction::predict), but with `cmds` in the order they were sent instead of those of the engine
+    ///
+    /// Commands the snapshot already includes are skipped, as are those after the latest one,
+    /// which are left over from before a map change.
+    pub fn run(
+        &mut self,
+        snapshot: &PlayerState,
+        cmds: impl IntoIterator<Item = UserCmd>,
+        input: PmoveInput,
+        hooks: &mut impl PredictionHooks,
+    ) -> &PlayerState {
+        self.check_changed_events(snapshot, hooks);
+
+        let cmds: Vec<UserCmd> = cmds.into_iter().collect();
+        let latest = cmds
+            .last()
+            .map_or(snapshot.command_time, |cmd| cmd.server_time);
+        let mut ps = *snapshot;
+        for cmd in cmds {
+            if cmd.server_time <= ps.command_time || cmd.server_time > latest {
+                continue;
+            }
+            let mut pm = Pmove::new(&mut ps, PmoveInput { cmd, ..input });
+            hooks.pmove(&mut pm);
+        }
+
+        let old = self.predicted.unwrap_or(*snapshot);
+        self.check_events(&old, &ps, hooks);
+        self.predicted.insert(ps)
+    }
+
+    /// Fire the events of `ps` that `old` did not have
+    ///
+    /// See `CG_CheckPlayerstateEvents` in [ioquake3's `cgame/cg_playerstate.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_playerstate.c).
+    fn check_events(
+        &mut self,
+        old: &PlayerState,
+        ps: &PlayerState,
+        hooks: &mut impl PredictionHooks,
+    ) {
+        let max = MAX_PS_EVENTS as ffi::c_int;
+        for i in ps.event_sequence - max..ps.event_sequence {
+            let slot_ps = slot(i, MAX_PS_EVENTS);
+            if i >= old.event_sequence
+                || (i > old.event_sequence - max && ps.events[slot_ps] != old.events[slot_ps])
+            {
+                let event = ps.events[slot_ps];
+                hooks.predictable_event(ps, event, ps.event_parms[slot_ps]);
+                self.predictable_events[slot(i, MAX_PREDICTED_EVENTS)] = event;
+                self.event_sequence += 1;
+            }
+        }
+    }
+
+    /// Fire the events of the snapshot `ps` that differ from what was predicted
+    ///
+    /// See `CG_CheckChangedPredictableEvents` in [ioquake3's `cgame/cg_predict.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_predict.c).
+    fn check_changed_events(&mut self, ps: &PlayerState, hooks: &mut impl PredictionHooks) {
+        let max = MAX_PS_EVENTS as ffi::c_int;
+        for i in ps.event_sequence - max..ps.event_sequence {
+            if i >= self.event_sequence
+                || i <= self.event_sequence - MAX_PREDICTED_EVENTS as ffi::c_int
+            {
+                continue;
+            }
+            let event = ps.events[slot(i, MAX_PS_EVENTS)];
+            let predicted = &mut self.predictable_events[slot(i, MAX_PREDICTED_EVENTS)];
+            if event != *predicted {
+                *predicted = event;
+                hooks.changed_event(ps, event, ps.event_parms[slot(i, MAX_PS_EVENTS)]);
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Server commands split into chunks
+//!
+//! The engine silently drops reliable server commands longer than
+//! [`MAX_SERVER_COMMAND_LEN`](MAX_SERVER_COMMAND_LEN) bytes, which long payloads like `scores` easily exceed.
+//! [`split`](split) turns a command and its payload into several commands that each fit,
+//! [`Assembler`](Assembler) puts the payload back together on the client.
+//!
+//! The payload is sent as a single quoted argument. Every chunk but the last is sent as
+//! `<command>+ "<part>"`, the last one as `<command> "<part>"`, so clients that know the convention
+//! only have to handle `<command>` once the payload is complete.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::chunk::{split, Assembler};
+//!
+//! let payload = "0 ".repeat(1000);
+//! let commands = split("scores", &payload).unwrap();
+//! assert_eq!(commands.len(), 2);
+//! assert!(commands[0].starts_with("scores+ \""));
+//! assert!(commands[1].starts_with("scores \""));
+//!
+//! let mut assembler = Assembler::new();
+//! assert_eq!(assembler.push("scores+", &commands[0][9..commands[0].len() - 1]), None);
+//! let (command, complete) = assembler.push("scores", &commands[1][8..commands[1].len() - 1]).unwrap();
+//! assert_eq!(command, "scores");
+//! assert_eq!(complete, payload);
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+/// Maximum length of a reliable server command
+///
+/// See `SV_SendServerCommand` in [ioquake3's `server/sv_main.c`](https://github.com/ioquake/ioq3/blob/master/code/server/sv_main.c).
+pub const MAX_SERVER_COMMAND_LEN: usize = 1022;
+
+/// Maximum number of chunks of one payload
+///
+/// The engine drops clients with more than `MAX_RELIABLE_COMMANDS` (64) unacknowledged commands,
+/// this leaves room for other commands sent in the same frame.
+///
+/// See `SV_AddServerCommand` in [ioquake3's `server/sv_main.c`](https://github.com/ioquake/ioq3/blob/master/code/server/sv_main.c).
+pub const MAX_CHUNKS: usize = 32;
+
+/// Suffix of the command name for every chunk but the last
+pub const CONTINUATION_SUFFIX: char = '+';
+
+/// Error for commands that can't be split
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChunkError {
+    /// Command name is empty or contains whitespace, quotes or the [`CONTINUATION_SUFFIX`](CONTINUATION_SUFFIX)
+    InvalidCommand,
+    /// Command name leaves no room for the payload
+    CommandTooLong {
+        /// Length of the command name
+        len: usize,
+    },
+    /// Payload contains a character that can't be sent in a quoted argument
+    InvalidChar(char),
+    /// Payload needs more than [`MAX_CHUNKS`](MAX_CHUNKS) chunks
+    TooManyChunks {
+        /// Length of the payload
+        len: usize,
+    },
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCommand => write!(f, "invalid command name"),
+            Self::CommandTooLong { len } => {
+                write!(
+                    f,
+                    "command name of length {} leaves no room for a payload",
+                    len
+                )
+            }
+            Self::InvalidChar(c) => write!(f, "can't send a payload with a {:?}", c),
+            Self::TooManyChunks { len } => write!(
+                f,
+                "payload of length {} needs more than {} chunks",
+                len, MAX_CHUNKS
+            ),
+        }
+    }
+}
+
+impl Error for ChunkError {}
+
+/// Split `payload` for `command` into server commands of at most [`MAX_SERVER_COMMAND_LEN`](MAX_SERVER_COMMAND_LEN) bytes
+///
+/// Chunks are only split between characters, not within UTF-8 sequences.
+/// Payloads that need more than [`MAX_CHUNKS`](MAX_CHUNKS) chunks are an error.
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::chunk::{split, ChunkError};
+///
+/// let payload = "0".repeat(100_000);
+/// assert_eq!(
+///     split("scores", &payload),
+///     Err(ChunkError::TooManyChunks { len: 100_000 })
+/// );
+/// ```
+pub fn split(command: &str, payload: &str) -> Result<Vec<String>, ChunkError> {
+    if command.is_empty()
+        || command
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == CONTINUATION_SUFFIX)
+    {
+        return Err(ChunkError::InvalidCommand);
+    }
+    if let Some(c) = payload.chars().find(|&c| c == '"' || c == '\0') {
+        return Err(ChunkError::InvalidChar(c));
+    }
+
+    // `<command>+ "<part>"`
+    let overhead = command.len() + CONTINUATION_SUFFIX.len_utf8() + 3;
+    let max_part = match MAX_SERVER_COMMAND_LEN.checked_sub(overhead) {
+        Some(max_part) if max_part >= 4 => max_part,
+        _ => return Err(ChunkError::CommandTooLong { len: command.len() }),
+    };
+
+    let too_many = ChunkError::TooManyChunks { len: payload.len() };
+    if payload.len() > MAX_CHUNKS * max_part {
+        return Err(too_many);
+    }
+
+    let mut commands = Vec::new();
+    let mut rest = payload;
+    while rest.len() > max_part {
+        // Splitting between characters can take one more chunk than the quick check above
+        if commands.len() + 1 == MAX_CHUNKS {
+            return Err(too_many);
+        }
+        let mut end = max_part;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (part, tail) = rest.split_at(end);
+        commands.push(format!("{}{} \"{}\"", command, CONTINUATION_SUFFIX, part));
+        rest = tail;
+    }
+    commands.push(format!("{} \"{}\"", command, rest));
+    Ok(commands)
+}
+
+/// Reassembles payloads of chunked server commands, e.g. in `CG_ServerCommand`
+#[derive(Clone, Debug, Default)]
+pub struct Assembler {
+    pending: BTreeMap<String, String>,
+}
+
+impl Assembler {
+    /// Create an assembler without pending payloads
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the `part` received as first argument of `command`
+    ///
+    /// Returns the command without [`CONTINUATION_SUFFIX`](CONTINUATION_SUFFIX) and the complete payload
+    /// once the last chunk arrived, `None` for continuation chunks.
+    /// Commands that were not split are returned right away.
+    pub fn push(&mut self, command: &str, part: &str) -> Option<(String, String)> {
+        match command.strip_suffix(CONTINUATION_SUFFIX) {
+            Some(base) => {
+                self.pending
+                    .entry(base.to_owned())
+                    .or_default()
+                    .push_str(part);
+                None
+            }
+            None => {
+                let mut payload = self.pending.remove(command).unwrap_or_default();
+                payload.push_str(part);
+                Some((command.to_owned(), payload))
+            }
+        }
+    }
+
+    /// Whether a payload for `command` is incomplete
+    pub fn is_pending(&self, command: &str) -> bool {
+        self.pending.contains_key(command)
+    }
+
+    /// Discard all incomplete payloads, e.g. when reconnecting
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
@@ -0,0 +1,200 @@
+//! RoQ video playback for `cgame` and `ui`
+//!
+//! A [`Cinematic`](Cinematic) is played by the engine and has to be run and drawn every frame
+//! by the module. It is stopped when dropped, so the engine does not keep decoding it.
+//!
+//! See [ioquake3's `client/cl_cin.c`](https://github.com/ioquake/ioq3/blob/master/code/client/cl_cin.c).
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use quake3_native_vm::cinematic::{CinStatus, Cinematic, CinematicFlags};
+//! use quake3_native_vm::ui::Syscalls;
+//!
+//! # fn example(syscalls: &Syscalls) {
+//! let intro = Cinematic::play(syscalls, "intro.roq", 0, 0, 640, 480, CinematicFlags::empty());
+//! if let Some(intro) = intro {
+//!     // Every frame
+//!     if intro.run() == Some(CinStatus::FMV_PLAY) {
+//!         intro.draw();
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! Dropping the cinematic stops it:
+//!
+//! ```rust
+//! use quake3_native_vm::backend::{Mock, Recording};
+//! use quake3_native_vm::cgame::{Imports, Syscalls};
+//! use quake3_native_vm::cinematic::{Cinematic, CinematicFlags};
+//!
+//! // Every cinematic gets handle 0
+//! let engine = Recording::new(Mock(|_, _: &[isize]| 0));
+//! let syscalls = Syscalls::with_engine(&engine);
+//! drop(Cinematic::play(&syscalls, "intro.roq", 0, 0, 640, 480, CinematicFlags::LOOP));
+//!
+//! let calls = engine.take();
+//! assert_eq!(calls[1].num, isize::from(Imports::CG_CIN_STOPCINEMATIC));
+//! ```
+
+use crate::ffi;
+use std::fmt;
+
+/// Playback status of a cinematic
+///
+/// See `e_status` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum CinStatus {
+    /// Not playing
+    FMV_IDLE = 0,
+    /// Playing
+    FMV_PLAY = 1,
+    /// Stopped, at the end or aborted
+    FMV_EOF = 2,
+    /// Unused by the engine
+    FMV_ID_BLT = 3,
+    /// Unused by the engine
+    FMV_ID_IDLE = 4,
+    /// Started over, for [`CinematicFlags::LOOP`](CinematicFlags::LOOP)
+    FMV_LOOPED = 5,
+    /// Unused by the engine
+    FMV_ID_WAIT = 6,
+}
+
+impl TryFrom<ffi::c_int> for CinStatus {
+    type Error = &'static str;
+
+    fn try_from(status: ffi::c_int) -> Result<Self, Self::Error> {
+        match status {
+            0 => Ok(Self::FMV_IDLE),
+            1 => Ok(Self::FMV_PLAY),
+            2 => Ok(Self::FMV_EOF),
+            3 => Ok(Self::FMV_ID_BLT),
+            4 => Ok(Self::FMV_ID_IDLE),
+            5 => Ok(Self::FMV_LOOPED),
+            6 => Ok(Self::FMV_ID_WAIT),
+            _ => Err("Unknown cinematic status"),
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// How to play a cinematic
+    ///
+    /// See `CIN_system` etc. in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct CinematicFlags: ffi::c_int {
+        /// Fullscreen cinematic of the engine, e.g. the intro
+        const SYSTEM = 1;
+        /// Start over at the end
+        const LOOP = 2;
+        /// Keep the last frame at the end
+        const HOLD = 4;
+        /// Without sound
+        const SILENT = 8;
+        /// Played as the video map of a shader
+        const SHADER = 16;
+    }
+}
+
+/// Cinematic traps of a module, implemented by the `Syscalls` of [`cgame`](crate::cgame::Syscalls) and [`ui`](crate::ui::Syscalls)
+///
+/// See `trap_CIN_PlayCinematic` etc. in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+pub trait CinematicTraps {
+    /// Start playing `name` from the `video/` directory at the given virtual screen coordinates,
+    /// returning its handle or a negative value on failure
+    fn cin_play_cinematic(
+        &self,
+        name: &str,
+        x: ffi::c_int,
+        y: ffi::c_int,
+        width: ffi::c_int,
+        height: ffi::c_int,
+        flags: CinematicFlags,
+    ) -> ffi::c_int;
+
+    /// Stop cinematic `handle`, returning its raw [`CinStatus`](CinStatus)
+    fn cin_stop_cinematic(&self, handle: ffi::c_int) -> ffi::c_int;
+
+    /// Advance cinematic `handle` to the current time, returning its raw [`CinStatus`](CinStatus)
+    fn cin_run_cinematic(&self, handle: ffi::c_int) -> ffi::c_int;
+
+    /// Draw the current frame of cinematic `handle`
+    fn cin_draw_cinematic(&self, handle: ffi::c_int);
+
+    /// Move and resize cinematic `handle`
+    fn cin_set_extents(
+        &self,
+        handle: ffi::c_int,
+        x: ffi::c_int,
+        y: ffi::c_int,
+        width: ffi::c_int,
+        height: ffi::c_int,
+    );
+}
+
+/// Playing cinematic, stopped on drop
+pub struct Cinematic<'a, T: CinematicTraps + ?Sized> {
+    traps: &'a T,
+    handle: ffi::c_int,
+}
+
+impl<'a, T: CinematicTraps + ?Sized> Cinematic<'a, T> {
+    /// Start playing `name` at the given virtual screen coordinates, `None` if it can't be played
+    pub fn play(
+        traps: &'a T,
+        name: &str,
+        x: ffi::c_int,
+        y: ffi::c_int,
+        width: ffi::c_int,
+        height: ffi::c_int,
+        flags: CinematicFlags,
+    ) -> Option<Self> {
+        let handle = traps.cin_play_cinematic(name, x, y, width, height, flags);
+        (handle >= 0).then_some(Self { traps, handle })
+    }
+
+    /// Raw handle for the [`CinematicTraps`](CinematicTraps)
+    pub fn handle(&self) -> ffi::c_int {
+        self.handle
+    }
+
+    /// Advance to the current time, `None` for an unknown status
+    pub fn run(&self) -> Option<CinStatus> {
+        CinStatus::try_from(self.traps.cin_run_cinematic(self.handle)).ok()
+    }
+
+    /// Draw the current frame
+    pub fn draw(&self) {
+        self.traps.cin_draw_cinematic(self.handle)
+    }
+
+    /// Move and resize to the given virtual screen coordinates
+    pub fn set_extents(&self, x: ffi::c_int, y: ffi::c_int, width: ffi::c_int, height: ffi::c_int) {
+        self.traps.cin_set_extents(self.handle, x, y, width, height)
+    }
+
+    /// Stop now instead of on drop, returning the final status
+    pub fn stop(self) -> Option<CinStatus> {
+        let status = self.traps.cin_stop_cinematic(self.handle);
+        std::mem::forget(self);
+        CinStatus::try_from(status).ok()
+    }
+}
+
+impl<T: CinematicTraps + ?Sized> Drop for Cinematic<'_, T> {
+    fn drop(&mut self) {
+        self.traps.cin_stop_cinematic(self.handle);
+    }
+}
+
+impl<T: CinematicTraps + ?Sized> fmt::Debug for Cinematic<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cinematic")
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
@@ -0,0 +1,210 @@
+//! Color codes in strings
+//!
+//! Quake 3 colors text with a `^` escape followed by a color code, e.g. `^1red^7white`.
+//! A `^` followed by another character than a letter or digit is printed as-is.
+//!
+//! See `Q_COLOR_ESCAPE` and `Q_IsColorString` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+
+use std::fmt;
+
+/// Character starting a color code
+pub const Q_COLOR_ESCAPE: char = '^';
+
+/// The eight colors of the engine's color table
+///
+/// See `g_color_table` in [ioquake3's `qcommon/q_math.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_math.c).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// `^0`
+    Black = 0,
+    /// `^1`
+    Red = 1,
+    /// `^2`
+    Green = 2,
+    /// `^3`
+    Yellow = 3,
+    /// `^4`
+    Blue = 4,
+    /// `^5`
+    Cyan = 5,
+    /// `^6`
+    Magenta = 6,
+    /// `^7`
+    White = 7,
+}
+
+impl Color {
+    /// Color for a code character, e.g. `Red` for `'1'`
+    ///
+    /// Like the engine's `ColorIndex`, every character maps to a color.
+    pub fn from_code(code: char) -> Self {
+        match (code as u32).wrapping_sub('0' as u32) & 0x07 {
+            0 => Self::Black,
+            1 => Self::Red,
+            2 => Self::Green,
+            3 => Self::Yellow,
+            4 => Self::Blue,
+            5 => Self::Cyan,
+            6 => Self::Magenta,
+            _ => Self::White,
+        }
+    }
+
+    /// Code character of this color, e.g. `'1'` for `Red`
+    pub fn code(self) -> char {
+        (b'0' + self as u8) as char
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", Q_COLOR_ESCAPE, self.code())
+    }
+}
+
+/// Whether `s` starts with a color code
+///
+/// See `Q_IsColorString` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub fn is_color_string(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next() == Some(Q_COLOR_ESCAPE)
+        && chars.next().map_or(false, |c| c.is_ascii_alphanumeric())
+}
+
+/// Borrowed string that may contain color codes
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::color::ColoredStr;
+///
+/// let name = ColoredStr::new("^1Sarge^7");
+/// assert_eq!(name.strip(), "Sarge");
+/// assert_eq!(name.visible_len(), 5);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ColoredStr<'a>(&'a str);
+
+impl<'a> ColoredStr<'a> {
+    /// Wrap a string
+    pub fn new(s: &'a str) -> Self {
+        Self(s)
+    }
+
+    /// The string including its color codes
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    /// Iterate over the visible characters, i.e. without color codes
+    pub fn visible_chars(&self) -> impl Iterator<Item = char> + 'a {
+        let s = self.0;
+        s.char_indices()
+            .scan(false, move |skip_code, (i, c)| {
+                if *skip_code {
+                    *skip_code = false;
+                    Some(None)
+                } else if is_color_string(&s[i..]) {
+                    *skip_code = true;
+                    Some(None)
+                } else {
+                    Some(Some(c))
+                }
+            })
+            .flatten()
+    }
+
+    /// The string without color codes
+    ///
+    /// Unlike the engine's `Q_CleanStr` this keeps non-printable characters.
+    pub fn strip(&self) -> String {
+        self.visible_chars().collect()
+    }
+
+    /// Number of visible characters
+    ///
+    /// See `Q_PrintStrlen` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn visible_len(&self) -> usize {
+        self.visible_chars().count()
+    }
+
+    /// Color of the last visible character, i.e. the one following text would be printed in
+    pub fn last_color(&self) -> Option<Color> {
+        let s = self.0;
+        s.char_indices()
+            .filter(|&(i, _)| is_color_string(&s[i..]))
+            .last()
+            .and_then(|(i, _)| s[i + 1..].chars().next())
+            .map(Color::from_code)
+    }
+}
+
+impl fmt::Display for ColoredStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl<'a> From<&'a str> for ColoredStr<'a> {
+    fn from(s: &'a str) -> Self {
+        Self::new(s)
+    }
+}
+
+/// Builder for strings with color codes
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::color::{Color, ColoredString};
+///
+/// let mut text = ColoredString::new();
+/// text.push(Color::Red, "Red").push(Color::Blue, " Team").push_str(" wins");
+/// assert_eq!(text.as_str(), "^1Red^4 Team wins");
+/// assert_eq!(text.as_colored_str().strip(), "Red Team wins");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ColoredString(String);
+
+impl ColoredString {
+    /// Create an empty string
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `text` in `color`
+    pub fn push(&mut self, color: Color, text: &str) -> &mut Self {
+        self.0.push(Q_COLOR_ESCAPE);
+        self.0.push(color.code());
+        self.0.push_str(text);
+        self
+    }
+
+    /// Append `text` in the current color
+    pub fn push_str(&mut self, text: &str) -> &mut Self {
+        self.0.push_str(text);
+        self
+    }
+
+    /// The string including its color codes
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Borrow as [`ColoredStr`](ColoredStr)
+    pub fn as_colored_str(&self) -> ColoredStr<'_> {
+        ColoredStr::new(&self.0)
+    }
+}
+
+impl fmt::Display for ColoredString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<ColoredString> for String {
+    fn from(s: ColoredString) -> Self {
+        s.0
+    }
+}
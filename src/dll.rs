@@ -0,0 +1,100 @@
+//! Shared library naming
+//!
+//! The engine does not load arbitrary shared libraries, but looks for a name derived from
+//! the module, the CPU architecture and the operating system, e.g. `qagamex86_64.so`.
+
+use crate::engine::Engine;
+
+/// Kind of module the engine loads
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ModuleKind {
+    /// Server-side game logic, `qagame`
+    Game,
+    /// Client-side game logic, `cgame`
+    CGame,
+    /// User interface, `ui`
+    Ui,
+}
+
+impl ModuleKind {
+    /// Base name the engine uses for this module, e.g. `qagame` for `qagame.qvm`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Game => "qagame",
+            Self::CGame => "cgame",
+            Self::Ui => "ui",
+        }
+    }
+}
+
+/// Compute the file name the engine expects for a native module
+///
+/// `arch` and `os` use the same values as Rust's `target_arch` and `target_os`,
+/// so they can be taken from [`std::env::consts`](std::env::consts) or,
+/// in a build script, from the `CARGO_CFG_TARGET_ARCH` and `CARGO_CFG_TARGET_OS` environment variables.
+///
+/// Returns `None` if `engine` does not support native modules for the target.
+///
+/// See `Sys_LoadGameDll` in [ioquake3's `sys/sys_main.c`](https://github.com/ioquake/ioq3/blob/master/code/sys/sys_main.c)
+/// and `ARCH_STRING` in [ioquake3's `qcommon/q_platform.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_platform.h).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::dll::{dll_name, ModuleKind};
+/// use quake3_native_vm::engine::Engine;
+///
+/// assert_eq!(
+///     dll_name(ModuleKind::Game, "x86_64", "linux", Engine::Ioquake3).as_deref(),
+///     Some("qagamex86_64.so")
+/// );
+/// assert_eq!(
+///     dll_name(ModuleKind::CGame, "x86", "windows", Engine::Ioquake3).as_deref(),
+///     Some("cgamex86.dll")
+/// );
+/// assert_eq!(
+///     dll_name(ModuleKind::Ui, "x86_64", "macos", Engine::Ioquake3).as_deref(),
+///     Some("uix86_64.dylib")
+/// );
+/// assert_eq!(
+///     dll_name(ModuleKind::Game, "x86", "linux", Engine::Quake3).as_deref(),
+///     Some("qagamei386.so")
+/// );
+/// ```
+pub fn dll_name(kind: ModuleKind, arch: &str, os: &str, engine: Engine) -> Option<String> {
+    let (arch, ext) = match engine {
+        Engine::Ioquake3 => (ioquake3_arch(arch, os)?, ioquake3_dll_ext(os)),
+        Engine::Quake3 => match (arch, os) {
+            ("x86", "linux") => ("i386", ".so"),
+            ("x86", "windows") => ("x86", ".dll"),
+            _ => return None,
+        },
+    };
+
+    Some(format!("{}{}{}", kind.name(), arch, ext))
+}
+
+/// See `ARCH_STRING` in [ioquake3's `qcommon/q_platform.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_platform.h).
+fn ioquake3_arch(arch: &str, os: &str) -> Option<&'static str> {
+    Some(match arch {
+        "x86" => "x86",
+        "x86_64" => "x86_64",
+        "aarch64" => "arm64",
+        "arm" if os != "windows" => "arm",
+        "powerpc" => "ppc",
+        "powerpc64" => "ppc64",
+        "sparc" | "sparc64" if os != "windows" => "sparc",
+        "mips" if os != "windows" => "mips",
+        "s390x" if os != "windows" => "s390x",
+        _ => return None,
+    })
+}
+
+/// See `DLL_EXT` in [ioquake3's `qcommon/q_platform.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_platform.h).
+fn ioquake3_dll_ext(os: &str) -> &'static str {
+    match os {
+        "windows" => ".dll",
+        "macos" => ".dylib",
+        _ => ".so",
+    }
+}
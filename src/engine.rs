@@ -0,0 +1,83 @@
+//! Engine flavors
+//!
+//! The original id Software Quake 3 engine and its forks mostly share the module ABI,
+//! but differ in details such as the shared library names they look for.
+//!
+//! [`game_module!`](crate::game_module) detects the engine at `GAME_INIT`, and the `qagame` syscalls
+//! use its trap numbers from then on, see [`qagame::Imports::number`](crate::qagame::Imports::number).
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Engine implementation hosting a module
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Engine {
+    /// [ioquake3](https://ioquake3.org/) and forks following its conventions
+    Ioquake3,
+    /// id Software's original Quake 3 1.32 point release
+    Quake3,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::Ioquake3
+    }
+}
+
+impl Engine {
+    /// All known engines
+    pub const ALL: [Engine; 2] = [Engine::Ioquake3, Engine::Quake3];
+
+    /// Guess the engine from the values of its `version` and `com_gamename` cvars
+    ///
+    /// Unknown engines are assumed to be compatible with ioquake3.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quake3_native_vm::engine::Engine;
+    ///
+    /// assert_eq!(
+    ///     Engine::detect("ioq3 1.36_GIT_f7c6c2f5-2023-05-28 linux-x86_64 Jun  1 2023", "Quake3Arena"),
+    ///     Engine::Ioquake3
+    /// );
+    /// assert_eq!(Engine::detect("Q3 1.32b linux-i386 Nov 14 2002", ""), Engine::Quake3);
+    /// ```
+    pub fn detect(version: &str, gamename: &str) -> Self {
+        // `com_gamename` was introduced by ioquake3
+        if version.starts_with("Q3 ") && gamename.is_empty() {
+            Self::Quake3
+        } else {
+            Self::Ioquake3
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Quake3,
+            _ => Self::Ioquake3,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::Ioquake3 => 0,
+            Self::Quake3 => 1,
+        }
+    }
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Engine the module is currently running in
+///
+/// This is [`Engine::default`](Engine::default) until a module's syscalls probed the engine,
+/// which [`game_module!`](crate::game_module) does at the start of each level.
+/// The `qagame` syscall wrappers use this to select the trap numbers for the engine.
+pub fn current() -> Engine {
+    Engine::from_u8(CURRENT.load(Ordering::Relaxed))
+}
+
+/// Override the engine returned by [`current`](current)
+pub fn set_current(engine: Engine) {
+    CURRENT.store(engine.to_u8(), Ordering::Relaxed);
+}
@@ -0,0 +1,215 @@
+//! Frame-driven async executor
+//!
+//! Logic spanning multiple frames, e.g. countdowns or delayed respawns, can be written as
+//! straight-line async code instead of state machines.
+//! The [`Executor`](Executor) polls all its tasks once per frame, with timers based on the level time.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::executor::Executor;
+//! use quake3_native_vm::time::LevelTime;
+//! use std::sync::{Arc, Mutex};
+//! use std::time::Duration;
+//!
+//! let executor = Executor::new();
+//! let clock = executor.clock();
+//! let messages = Arc::new(Mutex::new(Vec::new()));
+//!
+//! let countdown = Arc::clone(&messages);
+//! executor.spawn(async move {
+//!     for n in (1..=3).rev() {
+//!         countdown.lock().unwrap().push(n.to_string());
+//!         clock.sleep(Duration::from_secs(1)).await;
+//!     }
+//!     countdown.lock().unwrap().push("Fight!".to_string());
+//! });
+//!
+//! // Called from `run_frame`
+//! for level_time in (0..=3000).step_by(50) {
+//!     executor.run_frame(LevelTime(level_time));
+//! }
+//! assert_eq!(*messages.lock().unwrap(), ["3", "2", "1", "Fight!"]);
+//! assert!(executor.is_empty());
+//! ```
+
+use crate::time::LevelTime;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Executor polling its tasks once per frame
+pub struct Executor {
+    tasks: Arc<Mutex<Vec<Task>>>,
+    clock: Clock,
+}
+
+impl Executor {
+    /// Create an executor without tasks
+    pub fn new() -> Self {
+        Self {
+            tasks: Arc::new(Mutex::new(Vec::new())),
+            clock: Clock(Arc::new(AtomicI32::new(0))),
+        }
+    }
+
+    /// Clock for timers, following the level time of [`run_frame`](Executor::run_frame)
+    pub fn clock(&self) -> Clock {
+        self.clock.clone()
+    }
+
+    /// Handle to spawn tasks from within other tasks
+    pub fn spawner(&self) -> Spawner {
+        Spawner {
+            tasks: Arc::clone(&self.tasks),
+        }
+    }
+
+    /// Spawn a task, which is first polled on the next frame
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        self.spawner().spawn(task);
+    }
+
+    /// Poll all tasks once with the clock at `level_time`
+    ///
+    /// Tasks spawned while polling are first polled on the next frame.
+    pub fn run_frame(&self, level_time: LevelTime) {
+        self.clock.0.store(level_time.0, Ordering::Relaxed);
+
+        let waker = Waker::from(Arc::new(FrameWaker));
+        let mut cx = Context::from_waker(&waker);
+
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        tasks.retain_mut(|task| task.as_mut().poll(&mut cx).is_pending());
+
+        let mut spawned = self.tasks.lock().unwrap();
+        tasks.append(&mut spawned);
+        *spawned = tasks;
+    }
+
+    /// Number of unfinished tasks
+    pub fn len(&self) -> usize {
+        self.tasks.lock().unwrap().len()
+    }
+
+    /// Whether all tasks finished
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Executor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Executor")
+            .field("tasks", &self.len())
+            .field("clock", &self.clock)
+            .finish()
+    }
+}
+
+/// All tasks get polled on every frame anyway, so waking does nothing
+struct FrameWaker;
+
+impl Wake for FrameWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Handle to spawn tasks onto an [`Executor`](Executor)
+#[derive(Clone)]
+pub struct Spawner {
+    tasks: Arc<Mutex<Vec<Task>>>,
+}
+
+impl Spawner {
+    /// Spawn a task, which is first polled on the next frame
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        self.tasks.lock().unwrap().push(Box::pin(task));
+    }
+}
+
+impl fmt::Debug for Spawner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spawner").finish_non_exhaustive()
+    }
+}
+
+/// Level time as seen by the tasks of an [`Executor`](Executor)
+#[derive(Clone, Debug)]
+pub struct Clock(Arc<AtomicI32>);
+
+impl Clock {
+    /// Level time of the current frame
+    pub fn now(&self) -> LevelTime {
+        LevelTime(self.0.load(Ordering::Relaxed))
+    }
+
+    /// Wait until `deadline`
+    pub fn sleep_until(&self, deadline: LevelTime) -> Sleep {
+        Sleep {
+            clock: self.clone(),
+            deadline,
+        }
+    }
+
+    /// Wait for `duration` of level time
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        self.sleep_until(self.now() + duration)
+    }
+
+    /// Wait until the next frame
+    pub fn next_frame(&self) -> NextFrame {
+        NextFrame { yielded: false }
+    }
+}
+
+/// Future returned by [`Clock::sleep`](Clock::sleep) and [`Clock::sleep_until`](Clock::sleep_until)
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep {
+    clock: Clock,
+    deadline: LevelTime,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        if self.clock.now() >= self.deadline {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Future returned by [`Clock::next_frame`](Clock::next_frame)
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct NextFrame {
+    yielded: bool,
+}
+
+impl Future for NextFrame {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            Poll::Pending
+        }
+    }
+}
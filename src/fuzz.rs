@@ -0,0 +1,192 @@
+//! Fuzzing entry points for the module dispatchers
+//!
+//! Only available with `cfg(fuzzing)`, which is set by [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz).
+//! See the `fuzz/` directory of this crate for the actual fuzz targets.
+//!
+//! Each entry point feeds an arbitrary `(command, args)` tuple into a dispatcher
+//! with a mock module that accepts every callback.
+//! Inputs the dispatchers document to panic for, i.e. unknown commands and enum arguments, are skipped
+//! up front, so that every panic the fuzzer sees is an actual bug in the command decoding and argument
+//! conversion. Panics are not caught, since libfuzzer aborts on them anyway.
+
+use crate::time::LevelTime;
+use crate::{cgame, ffi, qagame, ui};
+
+const ARGS: usize = 12;
+
+/// Decode a `(command, args)` tuple from raw fuzzer input
+///
+/// Missing trailing bytes are treated as `0`.
+fn decode(data: &[u8]) -> (ffi::c_int, [ffi::c_int; ARGS]) {
+    let mut ints = [0; ARGS + 1];
+    for (int, chunk) in ints.iter_mut().zip(data.chunks(4)) {
+        let mut bytes = [0; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        *int = ffi::c_int::from_le_bytes(bytes);
+    }
+
+    let mut args = [0; ARGS];
+    args.copy_from_slice(&ints[1..]);
+    (ints[0], args)
+}
+
+struct MockGame;
+
+impl qagame::Module for MockGame {
+    fn dll_entry(_syscalls: qagame::Syscalls) -> Box<Self> {
+        Box::new(MockGame)
+    }
+
+    fn init(&self, _level_time: ffi::c_int, _random_seed: ffi::c_int, _restart: bool) {}
+
+    fn shutdown(&self, _restart: bool) {}
+
+    fn client_connect(
+        &self,
+        _client_number: ffi::c_int,
+        _first_time: bool,
+        _is_bot: bool,
+    ) -> Result<(), qagame::Deny> {
+        Ok(())
+    }
+
+    fn client_think(&self, _client_number: ffi::c_int) {}
+
+    fn client_userinfo_changed(&self, _client_number: ffi::c_int) {}
+
+    fn client_disconnect(&self, _client_number: ffi::c_int) {}
+
+    fn client_begin(&self, _client_number: ffi::c_int) {}
+
+    fn client_command(&self, _client_number: ffi::c_int) {}
+
+    fn run_frame(&self, _level_time: ffi::c_int) {}
+
+    fn console_command(&self) -> bool {
+        false
+    }
+
+    fn botai_start_frame(&self, _level_time: ffi::c_int) -> bool {
+        false
+    }
+}
+
+struct MockCGame;
+
+impl cgame::Module for MockCGame {
+    fn dll_entry(_syscalls: cgame::Syscalls) -> Box<Self> {
+        Box::new(MockCGame)
+    }
+
+    fn init(
+        &self,
+        _server_message_num: ffi::c_int,
+        _server_command_sequence: ffi::c_int,
+        _client_num: ffi::c_int,
+    ) {
+    }
+
+    fn shutdown(&self) {}
+
+    fn console_command(&self) -> bool {
+        false
+    }
+
+    fn draw_active_frame(
+        &self,
+        _server_time: LevelTime,
+        _stereo_view: cgame::StereoFrame,
+        _demo_playback: bool,
+    ) {
+    }
+
+    fn crosshair_player(&self) -> Option<ffi::c_int> {
+        None
+    }
+
+    fn last_attacker(&self) -> Option<ffi::c_int> {
+        None
+    }
+
+    fn key_event(&self, _key: ffi::c_int, _down: bool) {}
+
+    fn mouse_event(&self, _dx: ffi::c_int, _dy: ffi::c_int) {}
+
+    fn event_handling(&self, _event_type: ffi::c_int) {}
+}
+
+struct MockUi;
+
+impl ui::Module for MockUi {
+    fn dll_entry(_syscalls: ui::Syscalls) -> Box<Self> {
+        Box::new(MockUi)
+    }
+
+    fn init(&self, _in_game_load: bool) {}
+
+    fn shutdown(&self) {}
+
+    fn key_event(&self, _key: ffi::c_int, _down: bool) {}
+
+    fn mouse_event(&self, _dx: ffi::c_int, _dy: ffi::c_int) {}
+
+    fn refresh(&self, _realtime: ffi::c_int) {}
+
+    fn is_fullscreen(&self) -> bool {
+        false
+    }
+
+    fn set_active_menu(&self, _menu: ui::MenuCommand) {}
+
+    fn console_command(&self, _realtime: ffi::c_int) -> bool {
+        false
+    }
+
+    fn draw_connect_screen(&self, _overlay: bool) {}
+}
+
+/// Call [`qagame::dispatch`](crate::qagame::dispatch) with a mock module
+///
+/// Returns `None` without calling it if `command` is unknown.
+pub fn qagame_vm_main(command: ffi::c_int, args: [ffi::c_int; ARGS]) -> Option<ffi::intptr_t> {
+    qagame::Exports::try_from(command).ok()?;
+    Some(qagame::dispatch(&MockGame, command, args).into_raw())
+}
+
+/// Like [`qagame_vm_main`](qagame_vm_main), but with the tuple decoded from raw fuzzer input
+pub fn qagame(data: &[u8]) -> Option<ffi::intptr_t> {
+    let (command, args) = decode(data);
+    qagame_vm_main(command, args)
+}
+
+/// Call [`cgame::dispatch`](crate::cgame::dispatch) with a mock module
+///
+/// Returns `None` without calling it if `command` or the stereo frame of `CG_DRAW_ACTIVE_FRAME` is unknown.
+pub fn cgame_vm_main(command: ffi::c_int, args: [ffi::c_int; ARGS]) -> Option<ffi::intptr_t> {
+    if cgame::Exports::try_from(command).ok()? == cgame::Exports::CG_DRAW_ACTIVE_FRAME {
+        cgame::StereoFrame::try_from(args[1]).ok()?;
+    }
+    Some(cgame::dispatch(&MockCGame, command, args).into_raw())
+}
+
+/// Like [`cgame_vm_main`](cgame_vm_main), but with the tuple decoded from raw fuzzer input
+pub fn cgame(data: &[u8]) -> Option<ffi::intptr_t> {
+    let (command, args) = decode(data);
+    cgame_vm_main(command, args)
+}
+
+/// Call [`ui::dispatch`](crate::ui::dispatch) with a mock module
+///
+/// Returns `None` without calling it if `command` or the menu of `UI_SET_ACTIVE_MENU` is unknown.
+pub fn ui_vm_main(command: ffi::c_int, args: [ffi::c_int; ARGS]) -> Option<ffi::intptr_t> {
+    if ui::Exports::try_from(command).ok()? == ui::Exports::UI_SET_ACTIVE_MENU {
+        ui::MenuCommand::try_from(args[0]).ok()?;
+    }
+    Some(ui::dispatch(&MockUi, command, args).into_raw())
+}
+
+/// Like [`ui_vm_main`](ui_vm_main), but with the tuple decoded from raw fuzzer input
+pub fn ui(data: &[u8]) -> Option<ffi::intptr_t> {
+    let (command, args) = decode(data);
+    ui_vm_main(command, args)
+}
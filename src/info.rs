@@ -0,0 +1,243 @@
+//! Info strings
+//!
+//! The engine passes key-value data such as userinfo and serverinfo around as strings of the
+//! form `\key1\value1\key2\value2`.
+//!
+//! See `Info_ValueForKey` and `Info_SetValueForKey` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+
+use std::error::Error;
+use std::fmt;
+
+/// Size of info string buffers, including the terminating NUL
+///
+/// See `MAX_INFO_STRING` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const MAX_INFO_STRING: usize = 1024;
+
+/// Size of big info string buffers, e.g. for `CS_SYSTEMINFO`, including the terminating NUL
+///
+/// See `BIG_INFO_STRING` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const BIG_INFO_STRING: usize = 8192;
+
+/// Characters that are not allowed in info string keys and values
+const INVALID_CHARS: &[char] = &['\\', ';', '"'];
+
+/// Error for invalid info strings
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InfoError {
+    /// Info string would not fit into the engine's buffer
+    TooLong {
+        /// Length of the info string, excluding the terminating NUL
+        len: usize,
+        /// Size of the engine's buffer, including the terminating NUL
+        max: usize,
+    },
+    /// Key or value contains a character the engine does not allow
+    InvalidChar(char),
+    /// Info string contains a NUL byte
+    Nul,
+}
+
+impl fmt::Display for InfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong { len, max } => {
+                write!(f, "info string of length {} exceeds {}", len, max - 1)
+            }
+            Self::InvalidChar(c) => write!(f, "can't use keys or values with a '{}'", c),
+            Self::Nul => write!(f, "info string contains a NUL byte"),
+        }
+    }
+}
+
+impl Error for InfoError {}
+
+/// Info string with a buffer size of `MAX` bytes
+///
+/// Use the [`InfoString`](InfoString) and [`BigInfoString`](BigInfoString) aliases.
+///
+/// Unlike the engine, which prints a warning and ignores or truncates oversized or invalid
+/// data, all operations return an [`InfoError`](InfoError) instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::info::{InfoError, InfoString};
+///
+/// let mut userinfo: InfoString = "\\name\\Sarge\\model\\sarge".parse()?;
+/// assert_eq!(userinfo.get("NAME"), Some("Sarge"));
+///
+/// userinfo.set("handicap", "100")?;
+/// assert_eq!(userinfo.as_str(), "\\name\\Sarge\\model\\sarge\\handicap\\100");
+///
+/// assert_eq!(userinfo.set("name", "\"quoted\""), Err(InfoError::InvalidChar('"')));
+/// # Ok::<(), InfoError>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Info<const MAX: usize>(String);
+
+/// Regular info string, e.g. userinfo or `CS_SERVERINFO`
+pub type InfoString = Info<MAX_INFO_STRING>;
+
+/// Big info string, e.g. `CS_SYSTEMINFO`
+pub type BigInfoString = Info<BIG_INFO_STRING>;
+
+impl<const MAX: usize> Info<MAX> {
+    /// Create an empty info string
+    pub fn new() -> Self {
+        Self(String::new())
+    }
+
+    /// Validate an info string as received from the engine
+    ///
+    /// See `Info_Validate` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn parse(s: &str) -> Result<Self, InfoError> {
+        check_len(s.len(), MAX)?;
+        if let Some(c) = s.chars().find(|&c| c == ';' || c == '"') {
+            return Err(InfoError::InvalidChar(c));
+        }
+        if s.contains('\0') {
+            return Err(InfoError::Nul);
+        }
+        Ok(Self(s.to_owned()))
+    }
+
+    /// The info string as passed to the engine
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Iterate over all key-value pairs
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        let mut fields = self.0.strip_prefix('\\').unwrap_or(&self.0).split('\\');
+        std::iter::from_fn(move || {
+            let key = fields.next().filter(|key| !key.is_empty())?;
+            Some((key, fields.next().unwrap_or("")))
+        })
+    }
+
+    /// Value for `key`, which is compared case-insensitively like the engine does
+    ///
+    /// See `Info_ValueForKey` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    /// Set `key` to `value`, removing it if `value` is empty
+    ///
+    /// See `Info_SetValueForKey` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), InfoError> {
+        if let Some(c) = key
+            .chars()
+            .chain(value.chars())
+            .find(|c| INVALID_CHARS.contains(c))
+        {
+            return Err(InfoError::InvalidChar(c));
+        }
+        if key.contains('\0') || value.contains('\0') {
+            return Err(InfoError::Nul);
+        }
+
+        let mut info = self.clone();
+        info.remove(key);
+        if !value.is_empty() {
+            info.0.push('\\');
+            info.0.push_str(key);
+            info.0.push('\\');
+            info.0.push_str(value);
+            check_len(info.0.len(), MAX)?;
+        }
+        *self = info;
+        Ok(())
+    }
+
+    /// Remove `key`, returning whether it was present
+    ///
+    /// See `Info_RemoveKey` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn remove(&mut self, key: &str) -> bool {
+        let mut removed = false;
+        let mut info = String::with_capacity(self.0.len());
+        for (k, v) in self.iter() {
+            if k.eq_ignore_ascii_case(key) {
+                removed = true;
+            } else {
+                info.push('\\');
+                info.push_str(k);
+                info.push('\\');
+                info.push_str(v);
+            }
+        }
+        if removed {
+            self.0 = info;
+        }
+        removed
+    }
+}
+
+/// Value for `key` in the info string `info` as received from the engine, without validating it
+///
+/// Unlike [`Info::get`](Info::get), this works on any bytes, e.g. userinfo with names that are
+/// not UTF-8 or that would exceed [`MAX_INFO_STRING`](MAX_INFO_STRING) once decoded.
+///
+/// ```rust
+/// use quake3_native_vm::info;
+///
+/// let mut userinfo = b"\\name\\".to_vec();
+/// userinfo.extend([0xff; 1000]);
+/// userinfo.extend(b"\\IP\\10.1.2.3:27960");
+/// assert_eq!(info::value_for_key(&userinfo, "ip"), Some(&b"10.1.2.3:27960"[..]));
+/// assert_eq!(info::value_for_key(&userinfo, "model"), None);
+/// ```
+///
+/// See `Info_ValueForKey` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+pub fn value_for_key<'a>(info: &'a [u8], key: &str) -> Option<&'a [u8]> {
+    let mut fields = info
+        .strip_prefix(b"\\")
+        .unwrap_or(info)
+        .split(|&b| b == b'\\');
+    while let Some(k) = fields.next().filter(|k| !k.is_empty()) {
+        let v = fields.next().unwrap_or(b"");
+        if k.eq_ignore_ascii_case(key.as_bytes()) {
+            return Some(v);
+        }
+    }
+    None
+}
+
+fn check_len(len: usize, max: usize) -> Result<(), InfoError> {
+    if len >= max {
+        Err(InfoError::TooLong { len, max })
+    } else {
+        Ok(())
+    }
+}
+
+impl<const MAX: usize> std::str::FromStr for Info<MAX> {
+    type Err = InfoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl<const MAX: usize> fmt::Display for Info<MAX> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<InfoString> for BigInfoString {
+    fn from(info: InfoString) -> Self {
+        Self(info.0)
+    }
+}
+
+impl TryFrom<BigInfoString> for InfoString {
+    type Error = InfoError;
+
+    fn try_from(info: BigInfoString) -> Result<Self, Self::Error> {
+        check_len(info.0.len(), MAX_INFO_STRING)?;
+        Ok(Self(info.0))
+    }
+}
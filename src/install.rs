@@ -0,0 +1,61 @@
+//! Deploying built modules
+//!
+//! Helpers for build tooling like an `xtask` to put a built `cdylib` where the engine looks for it.
+
+use crate::dll::{dll_name, ModuleKind};
+use crate::engine::Engine;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Copy a built shared library into a mod directory under the name the engine expects
+///
+/// The destination is `<fs_homepath>/<fs_game>/<name>`, where `name` is computed with
+/// [`dll_name`](crate::dll::dll_name) for the host platform and ioquake3.
+/// The mod directory is created if it does not exist yet.
+///
+/// Returns the path of the installed module.
+///
+/// # Examples
+///
+/// ```no_run
+/// use quake3_native_vm::dll::ModuleKind;
+/// use quake3_native_vm::install;
+///
+/// # fn main() -> std::io::Result<()> {
+/// // Same as `cp target/debug/libq3hi.so ~/.q3a/rust/qagamex86_64.so`
+/// let home = std::env::var("HOME").unwrap();
+/// install::deploy(
+///     "target/debug/libq3hi.so",
+///     format!("{}/.q3a", home),
+///     "rust",
+///     ModuleKind::Game,
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn deploy(
+    artifact: impl AsRef<Path>,
+    fs_homepath: impl AsRef<Path>,
+    fs_game: impl AsRef<Path>,
+    module_kind: ModuleKind,
+) -> io::Result<PathBuf> {
+    let name = dll_name(
+        module_kind,
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        Engine::default(),
+    )
+    .ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "native modules are not supported on this platform",
+        )
+    })?;
+
+    let dir = fs_homepath.as_ref().join(fs_game);
+    std::fs::create_dir_all(&dir)?;
+
+    let destination = dir.join(name);
+    std::fs::copy(artifact, &destination)?;
+    Ok(destination)
+}
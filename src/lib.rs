@@ -12,12 +12,180 @@
 #![deny(missing_docs, unused_imports)]
 
 /// Foreign function interface
+///
+/// The C types used in the engine's module ABI.
+/// These come from [`core::ffi`](core::ffi) by default, or from the `libc` crate with the `libc` feature.
 pub mod ffi {
+    #[cfg(not(feature = "libc"))]
+    pub use core::ffi::c_int;
+    #[cfg(feature = "libc")]
     pub use libc::c_int;
+
+    /// C `intptr_t`, a signed integer with the same width as a pointer
+    #[cfg(not(feature = "libc"))]
+    #[allow(non_camel_case_types)]
+    pub type intptr_t = isize;
+    #[cfg(feature = "libc")]
     pub use libc::intptr_t;
+
+    // The engine passes `int`s through `intptr_t`, see `VM_DllSyscall`
+    const _: () = assert!(core::mem::size_of::<intptr_t>() >= core::mem::size_of::<c_int>());
+
+    /// Pass the pointer `ptr` to the engine
+    ///
+    /// `intptr_t` is as wide as a pointer on every target, i.e. 32 bit on i686.
+    pub fn ptr_to_intptr<T>(ptr: *const T) -> intptr_t {
+        ptr as intptr_t
+    }
+
+    /// Pass the C `int` `n` to the engine
+    ///
+    /// ```
+    /// use quake3_native_vm::ffi;
+    /// assert_eq!(ffi::int_to_intptr(-1), -1);
+    /// ```
+    pub fn int_to_intptr(n: c_int) -> intptr_t {
+        n as intptr_t
+    }
+
+    /// Pass the C `float` `f` to the engine, which reads its bits from the lower 32 bit
+    ///
+    /// See `PASSFLOAT` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+    ///
+    /// ```
+    /// use quake3_native_vm::ffi;
+    /// assert_eq!(ffi::float_to_intptr(1.0), 0x3f80_0000);
+    /// ```
+    pub fn float_to_intptr(f: f32) -> intptr_t {
+        int_to_intptr(f.to_bits() as c_int)
+    }
+
+    /// Pass the length or size `len` to the engine, which takes it as a C `int`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` does not fit into a C `int`, instead of the engine seeing a negative or truncated length.
+    ///
+    /// ```
+    /// use quake3_native_vm::ffi;
+    /// assert_eq!(ffi::len_to_intptr(1024), 1024);
+    /// ```
+    ///
+    /// ```should_panic
+    /// use quake3_native_vm::ffi;
+    /// ffi::len_to_intptr(usize::MAX);
+    /// ```
+    pub fn len_to_intptr(len: usize) -> intptr_t {
+        match c_int::try_from(len) {
+            Ok(n) => int_to_intptr(n),
+            Err(_) => panic!("length {} does not fit into a C int", len),
+        }
+    }
+
+    /// The C `int` returned by the engine as `intptr_t`
+    ///
+    /// The engine's syscall handlers return `int`s, so only the lower 32 bit are meaningful on 64-bit targets.
+    ///
+    /// ```
+    /// use quake3_native_vm::ffi;
+    /// assert_eq!(ffi::intptr_to_int(-1), -1);
+    /// ```
+    pub fn intptr_to_int(n: intptr_t) -> c_int {
+        n as c_int
+    }
+
+    /// Argument type of an exported `vmMain`, see [`native_vm!`](crate::native_vm)
+    ///
+    /// Engines declare the arguments of `vmMain` as `int` or, like some forks, as `intptr_t`.
+    pub trait VmMainArg: Copy {
+        /// The argument as the C `int` [`NativeVM::vm_main`](crate::NativeVM::vm_main) takes
+        fn to_int(self) -> c_int;
+    }
+
+    impl VmMainArg for c_int {
+        fn to_int(self) -> c_int {
+            self
+        }
+    }
+
+    /// Only the lower 32 bit are kept on 64-bit targets, like [`intptr_to_int`](intptr_to_int)
+    impl VmMainArg for intptr_t {
+        fn to_int(self) -> c_int {
+            intptr_to_int(self)
+        }
+    }
+
+    /// The arguments an engine passed to `vmMain` as the 12 C `int`s of [`NativeVM::vm_main`](crate::NativeVM::vm_main)
+    ///
+    /// Arguments the engine does not pass are `0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 12 `args`.
+    ///
+    /// ```
+    /// use quake3_native_vm::ffi;
+    /// let args: [ffi::intptr_t; 3] = [1, 2, -1];
+    /// assert_eq!(ffi::vm_main_args(&args), [1, 2, -1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    /// ```
+    pub fn vm_main_args<A: VmMainArg>(args: &[A]) -> [c_int; 12] {
+        assert!(
+            args.len() <= 12,
+            "vmMain takes at most 12 arguments, not {}",
+            args.len()
+        );
+        let mut ints = [0; 12];
+        for (int, &arg) in ints.iter_mut().zip(args) {
+            *int = arg.to_int();
+        }
+        ints
+    }
 }
 
+/// Assert at compile time that `$ty` has the `size` and `align` the engine expects
+///
+/// The asserted layouts are the same on 32-bit and 64-bit targets, since the engine structs contain no pointers.
+macro_rules! assert_layout {
+    ($ty:ty, size = $size:expr, align = $align:expr) => {
+        const _: () = assert!(
+            ::std::mem::size_of::<$ty>() == $size,
+            concat!("Size of ", stringify!($ty), " differs"),
+        );
+        const _: () = assert!(
+            ::std::mem::align_of::<$ty>() == $align,
+            concat!("Alignment of ", stringify!($ty), " differs"),
+        );
+    };
+}
+
+pub mod asset;
+pub mod backend;
+pub mod bg;
+pub mod cgame;
+pub mod chunk;
+pub mod cinematic;
+pub mod color;
+pub mod dll;
+pub mod engine;
+pub mod executor;
+#[cfg(fuzzing)]
+pub mod fuzz;
+pub mod info;
+pub mod install;
+pub mod metrics;
+pub mod parse;
 pub mod qagame;
+pub mod queue;
+#[cfg(feature = "bindgen")]
+pub mod raw;
+pub mod render;
+pub mod scheduler;
+pub mod shared;
+pub mod sound;
+pub mod time;
+pub mod ui;
+
+use std::any::Any;
 
 /// Engine's syscall function type
 ///
@@ -25,8 +193,227 @@ pub mod qagame;
 ///
 /// NOTE: The function is not really variadic, the actual number of arguments is an implementation detail.
 /// See `VM_DllSyscall` in [ioquake3's `qcommon/vm.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/vm.c).
+///
+/// With the `fixed-arity-syscall` feature this is a function with the trap number and
+/// always [`MAX_SYSCALL_ARGS`](MAX_SYSCALL_ARGS) arguments instead, unused ones being `0`.
+/// `VM_DllSyscall` reads exactly as many arguments. This relies on the calling convention passing
+/// integer arguments to variadic functions like to fixed-arity ones, which holds for x86, x86_64, arm
+/// and aarch64 except on Apple platforms, where variadic arguments go on the stack.
+/// The feature does not compile for other targets.
+#[cfg(not(feature = "fixed-arity-syscall"))]
 pub type Syscall = extern "C" fn(arg: ffi::intptr_t, ...) -> ffi::intptr_t;
 
+// Other calling conventions pass variadic arguments differently than fixed-arity ones,
+// e.g. on the stack instead of in registers for Apple's aarch64
+#[cfg(all(
+    feature = "fixed-arity-syscall",
+    not(any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        all(target_arch = "aarch64", not(target_vendor = "apple")),
+    ))
+))]
+compile_error!("the `fixed-arity-syscall` feature is not supported on this target, its variadic calling convention differs");
+
+/// Engine's syscall function type
+///
+/// For communication from module to the engine's syscall handler for this module, e.g. `qagame` → `SV_GameSystemCalls`.
+///
+/// The engine's function is variadic, but this is always called with the trap number and
+/// [`MAX_SYSCALL_ARGS`](MAX_SYSCALL_ARGS) arguments, which is what `VM_DllSyscall` reads.
+/// See `VM_DllSyscall` in [ioquake3's `qcommon/vm.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/vm.c).
+///
+/// Unlike a variadic function, this can be implemented in Rust, e.g. to test a module without the engine:
+///
+/// ```
+/// use quake3_native_vm::ffi::intptr_t;
+/// use quake3_native_vm::qagame::{Imports, Syscalls};
+///
+/// #[rustfmt::skip]
+/// extern "C" fn syscall(
+///     num: intptr_t,
+///     _: intptr_t, _: intptr_t, _: intptr_t, _: intptr_t, _: intptr_t,
+///     _: intptr_t, _: intptr_t, _: intptr_t, _: intptr_t, _: intptr_t,
+///     _: intptr_t, _: intptr_t, _: intptr_t, _: intptr_t, _: intptr_t,
+/// ) -> intptr_t {
+///     assert_eq!(num, Imports::G_ARGC as intptr_t);
+///     2
+/// }
+///
+/// assert_eq!(Syscalls::new(syscall).argc(), 2);
+/// ```
+#[cfg(feature = "fixed-arity-syscall")]
+pub type Syscall = extern "C" fn(
+    num: ffi::intptr_t,
+    arg0: ffi::intptr_t,
+    arg1: ffi::intptr_t,
+    arg2: ffi::intptr_t,
+    arg3: ffi::intptr_t,
+    arg4: ffi::intptr_t,
+    arg5: ffi::intptr_t,
+    arg6: ffi::intptr_t,
+    arg7: ffi::intptr_t,
+    arg8: ffi::intptr_t,
+    arg9: ffi::intptr_t,
+    arg10: ffi::intptr_t,
+    arg11: ffi::intptr_t,
+    arg12: ffi::intptr_t,
+    arg13: ffi::intptr_t,
+    arg14: ffi::intptr_t,
+) -> ffi::intptr_t;
+
+/// Maximum number of arguments of a [`Syscall`](Syscall) after the trap number
+///
+/// See `MAX_VMSYSCALL_ARGS` in [ioquake3's `qcommon/vm_local.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/vm_local.h),
+/// which includes the trap number.
+pub const MAX_SYSCALL_ARGS: usize = 15;
+
+/// Call `syscall` with trap number `num` and `args`
+///
+/// This works the same with and without the `fixed-arity-syscall` feature.
+///
+/// # Panics
+///
+/// Panics if there are more than [`MAX_SYSCALL_ARGS`](MAX_SYSCALL_ARGS) `args`.
+#[cfg(not(feature = "fixed-arity-syscall"))]
+pub fn call_syscall(syscall: Syscall, num: ffi::intptr_t, args: &[ffi::intptr_t]) -> ffi::intptr_t {
+    match *args {
+        [] => syscall(num),
+        [a] => syscall(num, a),
+        [a, b] => syscall(num, a, b),
+        [a, b, c] => syscall(num, a, b, c),
+        [a, b, c, d] => syscall(num, a, b, c, d),
+        [a, b, c, d, e] => syscall(num, a, b, c, d, e),
+        [a, b, c, d, e, f] => syscall(num, a, b, c, d, e, f),
+        [a, b, c, d, e, f, g] => syscall(num, a, b, c, d, e, f, g),
+        [a, b, c, d, e, f, g, h] => syscall(num, a, b, c, d, e, f, g, h),
+        [a, b, c, d, e, f, g, h, i] => syscall(num, a, b, c, d, e, f, g, h, i),
+        [a, b, c, d, e, f, g, h, i, j] => syscall(num, a, b, c, d, e, f, g, h, i, j),
+        [a, b, c, d, e, f, g, h, i, j, k] => syscall(num, a, b, c, d, e, f, g, h, i, j, k),
+        [a, b, c, d, e, f, g, h, i, j, k, l] => syscall(num, a, b, c, d, e, f, g, h, i, j, k, l),
+        [a, b, c, d, e, f, g, h, i, j, k, l, m] => {
+            syscall(num, a, b, c, d, e, f, g, h, i, j, k, l, m)
+        }
+        [a, b, c, d, e, f, g, h, i, j, k, l, m, n] => {
+            syscall(num, a, b, c, d, e, f, g, h, i, j, k, l, m, n)
+        }
+        [a, b, c, d, e, f, g, h, i, j, k, l, m, n, o] => {
+            syscall(num, a, b, c, d, e, f, g, h, i, j, k, l, m, n, o)
+        }
+        _ => panic!("Too many arguments for syscall {:?}", num),
+    }
+}
+
+/// Call `syscall` with trap number `num` and `args`, padded with `0` to [`MAX_SYSCALL_ARGS`](MAX_SYSCALL_ARGS)
+///
+/// # Panics
+///
+/// Panics if there are more than [`MAX_SYSCALL_ARGS`](MAX_SYSCALL_ARGS) `args`.
+#[cfg(feature = "fixed-arity-syscall")]
+pub fn call_syscall(syscall: Syscall, num: ffi::intptr_t, args: &[ffi::intptr_t]) -> ffi::intptr_t {
+    assert!(
+        args.len() <= MAX_SYSCALL_ARGS,
+        "Too many arguments for syscall {:?}",
+        num
+    );
+    let mut a = [0; MAX_SYSCALL_ARGS];
+    a[..args.len()].copy_from_slice(args);
+    syscall(
+        num, a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7], a[8], a[9], a[10], a[11], a[12],
+        a[13], a[14],
+    )
+}
+
+/// Backend of the module specific syscall wrappers, e.g. [`qagame::Syscalls`](qagame::Syscalls)
+///
+/// Inside the engine this is its [`Syscall`](Syscall). Other backends, like [`backend::Mock`](backend::Mock)
+/// and [`backend::Recording`](backend::Recording), allow testing code that makes syscalls without the engine.
+pub trait EngineSyscalls {
+    /// Call the trap with number `num` and `args`, like [`call_syscall`](call_syscall)
+    fn syscall(&self, num: ffi::intptr_t, args: &[ffi::intptr_t]) -> ffi::intptr_t;
+}
+
+impl EngineSyscalls for Syscall {
+    fn syscall(&self, num: ffi::intptr_t, args: &[ffi::intptr_t]) -> ffi::intptr_t {
+        call_syscall(*self, num, args)
+    }
+}
+
+impl<E: EngineSyscalls + ?Sized> EngineSyscalls for &E {
+    fn syscall(&self, num: ffi::intptr_t, args: &[ffi::intptr_t]) -> ffi::intptr_t {
+        (**self).syscall(num, args)
+    }
+}
+
+/// Value returned by a module's dispatcher to the engine
+///
+/// Most commands return nothing, i.e. [`ok`](VmResult::ok), some return a boolean, a number or, like
+/// `GAME_CLIENT_CONNECT`, a pointer to a message.
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::qagame::Deny;
+/// use quake3_native_vm::VmResult;
+///
+/// assert_eq!(VmResult::ok().into_raw(), 0);
+/// assert_eq!(VmResult::bool(true).into_raw(), 1);
+/// assert_eq!(VmResult::int(-1).into_raw(), -1);
+/// assert!(VmResult::from(Deny::new("Banned")).is_deny());
+/// ```
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VmResult(ffi::intptr_t);
+
+impl VmResult {
+    /// Nothing to return, `0`
+    pub const fn ok() -> Self {
+        Self(0)
+    }
+
+    /// `1` for `true`, `0` for `false`, e.g. whether a console command was handled
+    pub const fn bool(b: bool) -> Self {
+        Self(b as ffi::intptr_t)
+    }
+
+    /// The number `n`, e.g. a client number
+    pub fn int(n: ffi::c_int) -> Self {
+        Self(ffi::int_to_intptr(n))
+    }
+
+    /// Reject a connecting client with the NUL-terminated `message`
+    ///
+    /// The message must stay valid until the engine copied it, i.e. it has to outlive the dispatcher,
+    /// which is why modules reject clients with a [`Deny`](qagame::Deny) instead.
+    ///
+    /// See `ClientConnect` in [ioquake3's `game/g_client.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_client.c).
+    pub(crate) fn deny<T>(message: *const T) -> Self {
+        Self(ffi::ptr_to_intptr(message))
+    }
+
+    /// Value as returned by a `vmMain`, e.g. of another module that is proxied
+    pub const fn from_raw(value: ffi::intptr_t) -> Self {
+        Self(value)
+    }
+
+    /// Value for the engine
+    pub const fn into_raw(self) -> ffi::intptr_t {
+        self.0
+    }
+
+    /// Whether this is a deny message, i.e. not `0`, for `GAME_CLIENT_CONNECT`
+    pub const fn is_deny(self) -> bool {
+        self.0 != 0
+    }
+}
+
+impl From<VmResult> for ffi::intptr_t {
+    fn from(result: VmResult) -> Self {
+        result.into_raw()
+    }
+}
+
 /// Raw FFI interface for shared library modules
 ///
 /// To use an implementation of this, it needs to be wrapped into a shared library with [`native_vm!`](native_vm).
@@ -97,9 +484,33 @@ pub type VmMain = extern "C" fn(
 /// Name of the exported [`VmMain`](VmMain) function
 pub const VMMAIN_EXPORT_NAME: &[u8] = b"vmMain\0";
 
+/// Handler for panics of a module, passed as `on_panic` to [`native_vm!`](native_vm)
+///
+/// Receives the panic payload and the command being dispatched, `None` for `dllEntry`.
+pub type PanicHandler = fn(payload: &(dyn Any + Send), command: Option<ffi::c_int>);
+
+/// Message of a panic `payload`, e.g. for a [`PanicHandler`](PanicHandler)
+///
+/// ```
+/// use quake3_native_vm::panic_message;
+///
+/// let payload = std::panic::catch_unwind(|| panic!("Out of {}", "ammo")).unwrap_err();
+/// assert_eq!(panic_message(&*payload), "Out of ammo");
+/// ```
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
 /// Create required `extern "C" fn`s to load a [`impl NativeVM`](NativeVM) as shared library
 ///
 /// Can only be used once per Rust lib. Each module (`qagame` etc.) needs its own shared library.
+/// The macro can be invoked in any module, and `$ty` can be a path like `game::MyGame`.
 ///
 /// # Examples
 ///
@@ -152,7 +563,7 @@ pub const VMMAIN_EXPORT_NAME: &[u8] = b"vmMain\0";
 ///        match command {
 ///            GAME_INIT => {
 ///                let msg = CString::new("Hello, World!").unwrap();
-///                (self.syscall)(G_ERROR, msg.as_ptr());
+///                call_syscall(self.syscall, G_ERROR, &[ffi::ptr_to_intptr(msg.as_ptr())]);
 ///                unreachable!()
 ///            }
 ///            GAME_SHUTDOWN => {
@@ -178,45 +589,261 @@ pub const VMMAIN_EXPORT_NAME: &[u8] = b"vmMain\0";
 /// ioq3ded +set fs_game rust +set vm_game 0 +map q3dm6
 /// ```
 ///
+/// The copying step can also be done from Rust with [`install::deploy`](install::deploy).
+///
+/// # Export names
+///
+/// The functions are exported as `dllEntry` and `vmMain` by default. Engines that look them up under
+/// other names can be served by passing the names to export each function under, every name being an alias:
+///
+/// ```rust
+/// # use quake3_native_vm::*;
+/// # struct HelloQuake3;
+/// # impl NativeVM for HelloQuake3 {
+/// #     fn dll_entry(_syscall: Syscall) -> Box<Self> { Box::new(HelloQuake3) }
+/// #     fn vm_main(&self, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int,
+/// #                _: ffi::c_int, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int,
+/// #                _: ffi::c_int, _: ffi::c_int, _: ffi::c_int) -> ffi::intptr_t { 0 }
+/// # }
+/// # fn main() {
+/// native_vm!(HelloQuake3, dll_entry = ["dllEntry"], vm_main = ["vmMain", "VM_Main"]);
+/// # }
+/// ```
+///
+/// Nothing but these names is exported from the shared library, since Rust only exports the
+/// `#[no_mangle]` and `#[export_name]` functions of a `cdylib`. Those of dependencies are exported as well though.
+///
+/// # `vmMain` arguments
+///
+/// ioquake3 and Quake 3 call `vmMain` with the command and 12 `int` arguments. Other engines
+/// declare fewer arguments or pass them as `intptr_t`, which `vm_main_args = [type; count]` exports instead,
+/// with `type` being `int` or `intptr_t`:
+///
+/// ```rust
+/// # use quake3_native_vm::*;
+/// # struct HelloQuake3;
+/// # impl NativeVM for HelloQuake3 {
+/// #     fn dll_entry(_syscall: Syscall) -> Box<Self> { Box::new(HelloQuake3) }
+/// #     fn vm_main(&self, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int,
+/// #                _: ffi::c_int, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int, _: ffi::c_int,
+/// #                _: ffi::c_int, _: ffi::c_int, _: ffi::c_int) -> ffi::intptr_t { 0 }
+/// # }
+/// # fn main() {
+/// native_vm!(HelloQuake3, vm_main_args = [intptr_t; 7]);
+/// # }
+/// ```
+///
+/// [`NativeVM::vm_main`](NativeVM::vm_main) still gets 12 `int`s, converted by [`ffi::vm_main_args`](ffi::vm_main_args).
+/// The option goes after `on_panic` and before the export names.
+///
+/// # Panic handler
+///
+/// Unwinding into the engine is undefined behaviour, so a panic that reaches `dllEntry` or `vmMain`
+/// is caught and the process is aborted with [`std::process::abort`](std::process::abort). The engine gets no chance
+/// to shut down, so modules should handle fatal errors with their engine's error trap instead,
+/// e.g. like [`game_module!`](game_module) does with `G_ERROR`.
+///
+/// To report a panic first, e.g. to crash telemetry,
+/// pass a [`PanicHandler`](PanicHandler) as `native_vm!(HelloQuake3, on_panic = report_panic)`.
+/// It is called with the panic payload and the command, before aborting.
+/// The export names follow it, as in `native_vm!(HelloQuake3, on_panic = report_panic, dll_entry = [...], vm_main = [...])`.
+///
 /// See `Sys_LoadGameDll` in [ioquake3's `sys/sys_main.c`](https://github.com/ioquake/ioq3/blob/master/code/sys/sys_main.c).
 #[macro_export]
 macro_rules! native_vm {
-    ($ty:ident) => {
-        use std::sync::{Arc, RwLock};
-
-        static _VM_IMPL: once_cell::sync::Lazy<Arc<RwLock<Option<Box<dyn $crate::NativeVM>>>>> =
-            once_cell::sync::Lazy::new(|| Arc::new(RwLock::new(None)));
-
-        #[doc(hidden)]
-        #[no_mangle]
-        #[allow(non_snake_case)]
-        pub extern "C" fn dllEntry(syscall: $crate::Syscall) {
-            let mut VM_IMPL = _VM_IMPL.write().unwrap();
-            *VM_IMPL = Some($ty::dll_entry(syscall));
-        }
+    (@on_panic) => {
+        ::std::option::Option::None
+    };
+    (@on_panic $on_panic:path) => {
+        ::std::option::Option::Some($on_panic as $crate::PanicHandler)
+    };
+    (@arg int) => {
+        $crate::ffi::c_int
+    };
+    (@arg intptr_t) => {
+        $crate::ffi::intptr_t
+    };
+    // Names for the `vmMain` parameters of an arity, passed on to the rest of the invocation
+    (@arity 0; $($rest:tt)*) => { $crate::native_vm!($($rest)* []); };
+    (@arity 1; $($rest:tt)*) => { $crate::native_vm!($($rest)* [arg0]); };
+    (@arity 2; $($rest:tt)*) => { $crate::native_vm!($($rest)* [arg0 arg1]); };
+    (@arity 3; $($rest:tt)*) => { $crate::native_vm!($($rest)* [arg0 arg1 arg2]); };
+    (@arity 4; $($rest:tt)*) => { $crate::native_vm!($($rest)* [arg0 arg1 arg2 arg3]); };
+    (@arity 5; $($rest:tt)*) => { $crate::native_vm!($($rest)* [arg0 arg1 arg2 arg3 arg4]); };
+    (@arity 6; $($rest:tt)*) => {
+        $crate::native_vm!($($rest)* [arg0 arg1 arg2 arg3 arg4 arg5]);
+    };
+    (@arity 7; $($rest:tt)*) => {
+        $crate::native_vm!($($rest)* [arg0 arg1 arg2 arg3 arg4 arg5 arg6]);
+    };
+    (@arity 8; $($rest:tt)*) => {
+        $crate::native_vm!($($rest)* [arg0 arg1 arg2 arg3 arg4 arg5 arg6 arg7]);
+    };
+    (@arity 9; $($rest:tt)*) => {
+        $crate::native_vm!($($rest)* [arg0 arg1 arg2 arg3 arg4 arg5 arg6 arg7 arg8]);
+    };
+    (@arity 10; $($rest:tt)*) => {
+        $crate::native_vm!($($rest)* [arg0 arg1 arg2 arg3 arg4 arg5 arg6 arg7 arg8 arg9]);
+    };
+    (@arity 11; $($rest:tt)*) => {
+        $crate::native_vm!($($rest)* [arg0 arg1 arg2 arg3 arg4 arg5 arg6 arg7 arg8 arg9 arg10]);
+    };
+    (@arity 12; $($rest:tt)*) => {
+        $crate::native_vm!(
+            $($rest)* [arg0 arg1 arg2 arg3 arg4 arg5 arg6 arg7 arg8 arg9 arg10 arg11]
+        );
+    };
+    (@vm_main_export $name:literal, $arg:ident, [$($args:ident)*]) => {
+        const _: () = {
+            #[doc(hidden)]
+            #[export_name = $name]
+            pub extern "C" fn export(
+                command: $crate::ffi::c_int,
+                $($args: $crate::native_vm!(@arg $arg),)*
+            ) -> $crate::ffi::intptr_t {
+                let [arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8, arg9, arg10, arg11] =
+                    $crate::ffi::vm_main_args::<$crate::native_vm!(@arg $arg)>(&[$($args),*]);
+                vm_main(
+                    command, arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8, arg9, arg10, arg11,
+                )
+            }
+        };
+    };
+    ($ty:ty $(, on_panic = $on_panic:path)? $(,)?) => {
+        $crate::native_vm!(
+            $ty,
+            $(on_panic = $on_panic,)?
+            vm_main_args = [int; 12],
+            dll_entry = ["dllEntry"],
+            vm_main = ["vmMain"],
+        );
+    };
+    ($ty:ty $(, on_panic = $on_panic:path)?, vm_main_args = [$arg:ident; $arity:tt] $(,)?) => {
+        $crate::native_vm!(
+            $ty,
+            $(on_panic = $on_panic,)?
+            vm_main_args = [$arg; $arity],
+            dll_entry = ["dllEntry"],
+            vm_main = ["vmMain"],
+        );
+    };
+    (
+        $ty:ty,
+        $(on_panic = $on_panic:path,)?
+        dll_entry = [$($dll_entry:literal),+ $(,)?],
+        vm_main = [$($vm_main:literal),+ $(,)?] $(,)?
+    ) => {
+        $crate::native_vm!(
+            $ty,
+            $(on_panic = $on_panic,)?
+            vm_main_args = [int; 12],
+            dll_entry = [$($dll_entry),+],
+            vm_main = [$($vm_main),+],
+        );
+    };
+    (
+        $ty:ty,
+        $(on_panic = $on_panic:path,)?
+        vm_main_args = [$arg:ident; $arity:tt],
+        dll_entry = [$($dll_entry:literal),+ $(,)?],
+        vm_main = [$($vm_main:literal),+ $(,)?] $(,)?
+    ) => {
+        // Anonymous const, so nothing but the exported symbols leaks into the caller's module
+        const _: () = {
+            const ON_PANIC: ::std::option::Option<$crate::PanicHandler> =
+                $crate::native_vm!(@on_panic $($on_panic)?);
 
-        #[doc(hidden)]
-        #[no_mangle]
-        #[allow(non_snake_case)]
-        pub extern "C" fn vmMain(
-            command: $crate::ffi::c_int,
-            arg0: $crate::ffi::c_int,
-            arg1: $crate::ffi::c_int,
-            arg2: $crate::ffi::c_int,
-            arg3: $crate::ffi::c_int,
-            arg4: $crate::ffi::c_int,
-            arg5: $crate::ffi::c_int,
-            arg6: $crate::ffi::c_int,
-            arg7: $crate::ffi::c_int,
-            arg8: $crate::ffi::c_int,
-            arg9: $crate::ffi::c_int,
-            arg10: $crate::ffi::c_int,
-            arg11: $crate::ffi::c_int,
-        ) -> $crate::ffi::intptr_t {
-            let data = _VM_IMPL.read().unwrap();
-            data.as_ref().unwrap().vm_main(
-                command, arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8, arg9, arg10, arg11,
-            )
-        }
+            static VM_IMPL: $crate::__private::once_cell::sync::Lazy<
+                ::std::sync::RwLock<::std::option::Option<::std::boxed::Box<dyn $crate::NativeVM>>>,
+            > = $crate::__private::once_cell::sync::Lazy::new(|| {
+                ::std::sync::RwLock::new(::std::option::Option::None)
+            });
+
+            fn dll_entry(syscall: $crate::Syscall) {
+                $crate::__private::guard(ON_PANIC, ::std::option::Option::None, || {
+                    let mut vm_impl = VM_IMPL.write().unwrap();
+                    *vm_impl =
+                        ::std::option::Option::Some(<$ty as $crate::NativeVM>::dll_entry(syscall));
+                })
+            }
+
+            #[allow(clippy::too_many_arguments)]
+            fn vm_main(
+                command: $crate::ffi::c_int,
+                arg0: $crate::ffi::c_int,
+                arg1: $crate::ffi::c_int,
+                arg2: $crate::ffi::c_int,
+                arg3: $crate::ffi::c_int,
+                arg4: $crate::ffi::c_int,
+                arg5: $crate::ffi::c_int,
+                arg6: $crate::ffi::c_int,
+                arg7: $crate::ffi::c_int,
+                arg8: $crate::ffi::c_int,
+                arg9: $crate::ffi::c_int,
+                arg10: $crate::ffi::c_int,
+                arg11: $crate::ffi::c_int,
+            ) -> $crate::ffi::intptr_t {
+                $crate::__private::guard(ON_PANIC, ::std::option::Option::Some(command), || {
+                    let vm_impl = VM_IMPL.read().unwrap();
+                    $crate::NativeVM::vm_main(
+                        &**vm_impl.as_ref().unwrap(),
+                        command,
+                        arg0,
+                        arg1,
+                        arg2,
+                        arg3,
+                        arg4,
+                        arg5,
+                        arg6,
+                        arg7,
+                        arg8,
+                        arg9,
+                        arg10,
+                        arg11,
+                    )
+                })
+            }
+
+            // One anonymous const per name, so every export gets its own function
+            $(
+                const _: () = {
+                    #[doc(hidden)]
+                    #[export_name = $dll_entry]
+                    pub extern "C" fn export(syscall: $crate::Syscall) {
+                        dll_entry(syscall)
+                    }
+                };
+            )+
+
+            $(
+                $crate::native_vm!(@arity $arity; @vm_main_export $vm_main, $arg,);
+            )+
+        };
     };
 }
+
+/// Not public API, used by the macros
+#[doc(hidden)]
+pub mod __private {
+    pub use once_cell;
+
+    use crate::{ffi, PanicHandler};
+    use std::panic::{self, AssertUnwindSafe};
+
+    /// Run `f`, passing a panic to `on_panic` and then aborting instead of unwinding into the engine
+    pub fn guard<T>(
+        on_panic: Option<PanicHandler>,
+        command: Option<ffi::c_int>,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(value) => value,
+            Err(payload) => {
+                if let Some(on_panic) = on_panic {
+                    on_panic(&*payload, command);
+                }
+                std::process::abort()
+            }
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Counters for what the module costs per frame
+//!
+//! [`qagame::dispatch`](crate::qagame::dispatch) counts every command from the engine and times every frame,
+//! [`qagame::Syscalls`](crate::qagame::Syscalls) counts every trap into the engine.
+//! Both record into the [`global`](global) metrics.
+//!
+//! With `game_module!(MyGame, builtins)`, server operators can print these with the `metrics_dump` console command,
+//! or write them as CSV to a file in the mod directory with `metrics_dump <file>`.
+//!
+//! Counting only touches atomic counters indexed by the number of the command or trap, so it takes
+//! no lock on the engine thread.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::metrics::Metrics;
+//! use quake3_native_vm::qagame::{Exports, Imports};
+//! use std::time::Duration;
+//!
+//! let metrics = Metrics::new();
+//! metrics.count(Exports::GAME_RUN_FRAME);
+//! metrics.count(Imports::G_PRINT);
+//! metrics.count(Imports::G_PRINT);
+//! metrics.record_frame(Duration::from_millis(2));
+//!
+//! assert_eq!(metrics.commands(), [("GAME_RUN_FRAME", 1)]);
+//! assert_eq!(metrics.syscalls(), [("G_PRINT", 2)]);
+//! assert_eq!(metrics.frames().max, Duration::from_millis(2));
+//! assert!(metrics.to_csv().contains("syscall,G_PRINT,2\n"));
+//! ```
+
+use crate::{cgame, qagame, ui};
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Console command to print or export the [`global`](global) metrics
+pub const DUMP_COMMAND: &str = "metrics_dump";
+
+/// Number of counters for the commands of each module, above the highest `Exports` value
+const COMMANDS: usize = 16;
+
+/// Number of counters for the traps of each module, above the highest `Imports` value
+const SYSCALLS: usize = 128;
+
+/// Durations of `GAME_RUN_FRAME`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Number of frames
+    pub count: u64,
+    /// Sum of all frame durations
+    pub total: Duration,
+    /// Longest frame
+    pub max: Duration,
+    /// Most recent frame
+    pub last: Duration,
+}
+
+impl FrameStats {
+    /// Mean frame duration, zero without frames
+    pub fn average(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            count => self.total / count.min(u32::MAX as u64) as u32,
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Command from the engine or trap into the engine that [`Metrics`](Metrics) counts
+///
+/// Implemented for the `Exports` and `Imports` of [`qagame`](crate::qagame), [`cgame`](crate::cgame) and [`ui`](crate::ui).
+pub trait Counted: Copy + sealed::Sealed {
+    /// Name of the C enum value, e.g. `"G_PRINT"`
+    fn name(self) -> &'static str;
+
+    #[doc(hidden)]
+    fn counter(self, metrics: &Metrics) -> &AtomicU64;
+}
+
+// Only to initialize the counters, every use is a new atomic
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+
+/// Dispatch counts, syscall counts and frame durations
+pub struct Metrics {
+    game_commands: [AtomicU64; COMMANDS],
+    game_syscalls: [AtomicU64; SYSCALLS],
+    cgame_commands: [AtomicU64; COMMANDS],
+    cgame_syscalls: [AtomicU64; SYSCALLS],
+    ui_commands: [AtomicU64; COMMANDS],
+    ui_syscalls: [AtomicU64; SYSCALLS],
+    frame_count: AtomicU64,
+    frame_total_ns: AtomicU64,
+    frame_max_ns: AtomicU64,
+    frame_last_ns: AtomicU64,
+}
+
+/// Implement [`Counted`](Counted) for C enums with the counters in `$field`, which must have room for all values
+macro_rules! counted {
+    ($($ty:ty => $field:ident[$len:ident],)+) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl Counted for $ty {
+                fn name(self) -> &'static str {
+                    <$ty>::name(&self)
+                }
+
+                fn counter(self, metrics: &Metrics) -> &AtomicU64 {
+                    &metrics.$field[self as usize]
+                }
+            }
+
+            const _: () = {
+                let mut index = 0;
+                while index < <$ty>::ALL.len() {
+                    assert!((<$ty>::ALL[index] as usize) < $len);
+                    index += 1;
+                }
+            };
+        )+
+    };
+}
+
+counted! {
+    qagame::Exports => game_commands[COMMANDS],
+    qagame::Imports => game_syscalls[SYSCALLS],
+    cgame::Exports => cgame_commands[COMMANDS],
+    cgame::Imports => cgame_syscalls[SYSCALLS],
+    ui::Exports => ui_commands[COMMANDS],
+    ui::Imports => ui_syscalls[SYSCALLS],
+}
+
+impl Metrics {
+    /// Create metrics with all counters at zero
+    pub const fn new() -> Self {
+        Self {
+            game_commands: [ZERO; COMMANDS],
+            game_syscalls: [ZERO; SYSCALLS],
+            cgame_commands: [ZERO; COMMANDS],
+            cgame_syscalls: [ZERO; SYSCALLS],
+            ui_commands: [ZERO; COMMANDS],
+            ui_syscalls: [ZERO; SYSCALLS],
+            frame_count: ZERO,
+            frame_total_ns: ZERO,
+            frame_max_ns: ZERO,
+            frame_last_ns: ZERO,
+        }
+    }
+
+    /// Count a command from the engine, e.g. `GAME_RUN_FRAME`, or a trap into the engine, e.g. `G_PRINT`
+    #[inline]
+    pub fn count<T: Counted>(&self, value: T) {
+        value.counter(self).fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record how long a frame took
+    pub fn record_frame(&self, duration: Duration) {
+        let nanos = duration.as_nanos().min(u64::MAX as u128) as u64;
+        self.frame_count.fetch_add(1, Ordering::Relaxed);
+        self.frame_total_ns.fetch_add(nanos, Ordering::Relaxed);
+        self.frame_max_ns.fetch_max(nanos, Ordering::Relaxed);
+        self.frame_last_ns.store(nanos, Ordering::Relaxed);
+    }
+
+    /// Counts of commands from the engine, by name
+    pub fn commands(&self) -> Vec<(&'static str, u64)> {
+        let mut counts = Vec::new();
+        self.collect(&qagame::Exports::ALL, &mut counts);
+        self.collect(&cgame::Exports::ALL, &mut counts);
+        self.collect(&ui::Exports::ALL, &mut counts);
+        counts.sort_unstable();
+        counts
+    }
+
+    /// Counts of traps into the engine, by name
+    pub fn syscalls(&self) -> Vec<(&'static str, u64)> {
+        let mut counts = Vec::new();
+        self.collect(&qagame::Imports::ALL, &mut counts);
+        self.collect(&cgame::Imports::ALL, &mut counts);
+        self.collect(&ui::Imports::ALL, &mut counts);
+        counts.sort_unstable();
+        counts
+    }
+
+    /// Add the names and counts of `values` that were counted at all to `counts`
+    fn collect<T: Counted>(&self, values: &[T], counts: &mut Vec<(&'static str, u64)>) {
+        counts.extend(values.iter().filter_map(|&value| {
+            match value.counter(self).load(Ordering::Relaxed) {
+                0 => None,
+                count => Some((value.name(), count)),
+            }
+        }));
+    }
+
+    /// Frame durations
+    pub fn frames(&self) -> FrameStats {
+        FrameStats {
+            count: self.frame_count.load(Ordering::Relaxed),
+            total: Duration::from_nanos(self.frame_total_ns.load(Ordering::Relaxed)),
+            max: Duration::from_nanos(self.frame_max_ns.load(Ordering::Relaxed)),
+            last: Duration::from_nanos(self.frame_last_ns.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Set all counters back to zero
+    pub fn reset(&self) {
+        let counters = [
+            &self.game_commands[..],
+            &self.game_syscalls[..],
+            &self.cgame_commands[..],
+            &self.cgame_syscalls[..],
+            &self.ui_commands[..],
+            &self.ui_syscalls[..],
+        ];
+        for counter in counters.into_iter().flatten().chain([
+            &self.frame_count,
+            &self.frame_total_ns,
+            &self.frame_max_ns,
+            &self.frame_last_ns,
+        ]) {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Human readable summary for qconsole
+    pub fn report(&self) -> String {
+        let frames = self.frames();
+        let mut report = format!(
+            "frames: {}, average {:.3}ms, max {:.3}ms, last {:.3}ms\n",
+            frames.count,
+            millis(frames.average()),
+            millis(frames.max),
+            millis(frames.last),
+        );
+        report.push_str("commands:\n");
+        for (name, count) in self.commands() {
+            writeln!(report, "  {:<32} {}", name, count).unwrap();
+        }
+        report.push_str("syscalls:\n");
+        for (name, count) in self.syscalls() {
+            writeln!(report, "  {:<32} {}", name, count).unwrap();
+        }
+        report
+    }
+
+    /// All counters as CSV with the columns `kind,name,value`, durations in microseconds
+    pub fn to_csv(&self) -> String {
+        let frames = self.frames();
+        let mut csv = String::from("kind,name,value\n");
+        for (name, count) in self.commands() {
+            writeln!(csv, "command,{},{}", name, count).unwrap();
+        }
+        for (name, count) in self.syscalls() {
+            writeln!(csv, "syscall,{},{}", name, count).unwrap();
+        }
+        writeln!(csv, "frame,count,{}", frames.count).unwrap();
+        writeln!(csv, "frame,total_us,{}", frames.total.as_micros()).unwrap();
+        writeln!(csv, "frame,average_us,{}", frames.average().as_micros()).unwrap();
+        writeln!(csv, "frame,max_us,{}", frames.max.as_micros()).unwrap();
+        writeln!(csv, "frame,last_us,{}", frames.last.as_micros()).unwrap();
+        csv
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics")
+            .field("frames", &self.frames())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Milliseconds of `duration` with fractions, as printed in reports and watchdog warnings
+pub(crate) fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+static GLOBAL: Metrics = Metrics::new();
+
+/// Metrics recorded by the dispatchers and syscall wrappers of this crate
+pub fn global() -> &'static Metrics {
+    &GLOBAL
+}
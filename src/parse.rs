@@ -0,0 +1,257 @@
+//! Tokenizer for the engine's script files
+//!
+//! Quake 3 uses the same simple syntax for most of its text files, e.g. `scripts/*.arena`,
+//! shaders or the entity string of a map: tokens are separated by whitespace,
+//! `//` and `/* */` start comments, and double quotes group a token with whitespace, without escapes.
+//!
+//! See `COM_ParseExt` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::parse::Tokenizer;
+//!
+//! let mut tokens = Tokenizer::new(
+//!     r#"{
+//!     // Comment
+//!     map "q3dm1" /* another
+//!     comment */ longname "Arena Gate"
+//!     bots
+//!     }"#,
+//! );
+//! assert_eq!(tokens.parse().as_deref(), Some("{"));
+//! assert_eq!(tokens.parse().as_deref(), Some("map"));
+//! assert_eq!(tokens.token_line(), 3);
+//! assert_eq!(tokens.parse_ext(false).as_deref(), Some("q3dm1"));
+//! assert_eq!(tokens.parse_ext(false).as_deref(), Some("longname"));
+//! assert_eq!(tokens.parse_ext(false).as_deref(), Some("Arena Gate"));
+//! assert_eq!(tokens.token_line(), 4);
+//!
+//! // No value on the same line
+//! assert_eq!(tokens.parse().as_deref(), Some("bots"));
+//! assert_eq!(tokens.parse_ext(false), None);
+//!
+//! assert_eq!(tokens.parse().as_deref(), Some("}"));
+//! assert_eq!(tokens.parse(), None);
+//! ```
+
+/// Maximum length of a token, including the terminating NUL
+///
+/// Longer tokens are truncated like in the engine.
+///
+/// See `MAX_TOKEN_CHARS` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const MAX_TOKEN_CHARS: usize = 1024;
+
+/// Tokens of an engine script, with line numbers
+///
+/// Also an [`Iterator`](Iterator) of the tokens, like repeatedly calling [`parse`](Tokenizer::parse).
+#[derive(Clone, Debug)]
+pub struct Tokenizer<'a> {
+    rest: &'a str,
+    line: usize,
+    token_line: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Start tokenizing `text` at line 1
+    ///
+    /// See `COM_BeginParseSession` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            rest: text,
+            line: 1,
+            token_line: 1,
+        }
+    }
+
+    /// Current line, starting at 1
+    ///
+    /// See `COM_GetCurrentParseLine` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Line of the last token, e.g. for error messages
+    pub fn token_line(&self) -> usize {
+        self.token_line
+    }
+
+    /// Text after the last token
+    pub fn rest(&self) -> &'a str {
+        self.rest
+    }
+
+    fn advance(&mut self, len: usize) {
+        let (skipped, rest) = self.rest.split_at(len);
+        self.line += skipped.matches('\n').count();
+        self.rest = rest;
+    }
+
+    /// Skip whitespace and comments, returning `false` after a line break unless `allow_line_breaks`
+    fn skip(&mut self, allow_line_breaks: bool) -> bool {
+        let mut line_break = false;
+        loop {
+            let whitespace = self.rest.find(|c: char| c > ' ').unwrap_or(self.rest.len());
+            line_break |= self.rest[..whitespace].contains('\n');
+            self.advance(whitespace);
+            if line_break && !allow_line_breaks {
+                return false;
+            }
+
+            if self.rest.starts_with("//") {
+                let end = self.rest.find('\n').unwrap_or(self.rest.len());
+                self.advance(end);
+            } else if self.rest.starts_with("/*") {
+                let end = self.rest[2..]
+                    .find("*/")
+                    .map_or(self.rest.len(), |end| end + 4);
+                self.advance(end);
+            } else {
+                return true;
+            }
+        }
+    }
+
+    /// Next token, `None` at the end of the text
+    ///
+    /// See `COM_Parse` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn parse(&mut self) -> Option<String> {
+        self.parse_ext(true)
+    }
+
+    /// Next token, `None` at the end of the text
+    ///
+    /// Unless `allow_line_breaks`, this also returns `None` if the next token is on another line,
+    /// e.g. for values that have to follow their key on the same line.
+    ///
+    /// See `COM_ParseExt` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn parse_ext(&mut self, allow_line_breaks: bool) -> Option<String> {
+        if !self.skip(allow_line_breaks) || self.rest.is_empty() {
+            return None;
+        }
+        self.token_line = self.line;
+
+        let rest = self.rest;
+        let token = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            self.advance((end + 2).min(rest.len()));
+            &quoted[..end]
+        } else {
+            let end = rest.find(|c: char| c <= ' ').unwrap_or(rest.len());
+            self.advance(end);
+            &rest[..end]
+        };
+        Some(truncate(token).to_owned())
+    }
+
+    /// Whether the next token, possibly on another line, is quoted
+    pub(crate) fn peek_quoted(&mut self) -> bool {
+        self.skip(true) && self.rest.starts_with('"')
+    }
+
+    /// Skip the rest of the current line
+    ///
+    /// See `SkipRestOfLine` in [ioquake3's `qcommon/q_shared.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.c).
+    pub fn skip_rest_of_line(&mut self) {
+        let end = self.rest.find('\n').map_or(self.rest.len(), |end| end + 1);
+        self.advance(end);
+    }
+}
+
+impl Iterator for Tokenizer<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parse()
+    }
+}
+
+/// Truncate `token` to [`MAX_TOKEN_CHARS`](MAX_TOKEN_CHARS) - 1 bytes, at a character boundary
+fn truncate(token: &str) -> &str {
+    let mut end = token.len().min(MAX_TOKEN_CHARS - 1);
+    while !token.is_char_boundary(end) {
+        end -= 1;
+    }
+    &token[..end]
+}
+
+/// Error for an invalid entity string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpawnVarsError {
+    /// Line of the offending token
+    pub line: usize,
+    /// What went wrong
+    pub message: &'static str,
+}
+
+impl std::fmt::Display for SpawnVarsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for SpawnVarsError {}
+
+/// Key-value pairs of one entity of a map, e.g. `classname` and `origin`, in the order of the entity string
+pub type SpawnVars = Vec<(String, String)>;
+
+/// Parse the entities of a map's entity string, e.g. from a `.ent` file
+///
+/// The engine hands the entities of the current map to the game token by token instead,
+/// with `trap_GetEntityToken`.
+///
+/// See `G_ParseSpawnVars` in [ioquake3's `game/g_spawn.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_spawn.c).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::parse::parse_spawn_vars;
+///
+/// let entities = parse_spawn_vars(
+///     r#"
+///     { "classname" "worldspawn" "message" "Arena Gate" }
+///     { "classname" "item_quad" "origin" "0 0 64" }
+///     "#,
+/// )
+/// .unwrap();
+/// assert_eq!(entities.len(), 2);
+/// assert_eq!(entities[1][1], ("origin".to_owned(), "0 0 64".to_owned()));
+///
+/// assert_eq!(parse_spawn_vars("{ \"classname\"").unwrap_err().line, 1);
+/// ```
+pub fn parse_spawn_vars(text: &str) -> Result<Vec<SpawnVars>, SpawnVarsError> {
+    let mut tokens = Tokenizer::new(text);
+    let error = |tokens: &Tokenizer<'_>, message| SpawnVarsError {
+        line: tokens.token_line(),
+        message,
+    };
+
+    let mut entities = Vec::new();
+    while let Some(token) = tokens.parse() {
+        if token != "{" {
+            return Err(error(
+                &tokens,
+                "found a token other than { at the start of an entity",
+            ));
+        }
+
+        let mut vars = SpawnVars::new();
+        loop {
+            let key = tokens
+                .parse()
+                .ok_or_else(|| error(&tokens, "unexpected end of entities"))?;
+            if key == "}" {
+                break;
+            }
+            let value = tokens
+                .parse()
+                .ok_or_else(|| error(&tokens, "unexpected end of entities"))?;
+            if value == "}" {
+                return Err(error(&tokens, "closing brace without data"));
+            }
+            vars.push((key, value));
+        }
+        entities.push(vars);
+    }
+    Ok(entities)
+}
@@ -10,13 +10,51 @@
 //! Unlike the other modules, it does not have to be present on the game client,
 //! i.e. players do not have to download it.
 
-use crate::{ffi, Syscall};
+pub mod admin;
+#[cfg(feature = "base")]
+pub mod base;
+pub mod bot;
+pub mod clients;
+pub mod command;
+#[cfg(feature = "serde")]
+pub mod config;
+pub mod crash;
+pub mod entity;
+pub mod fallible;
+pub mod filter;
+pub mod flood;
+pub mod fs;
+pub mod name;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "serde")]
+pub mod persist;
+pub mod query;
+pub mod reliable;
+#[cfg(feature = "rhai")]
+pub mod script;
+#[cfg(feature = "serde")]
+pub mod store;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watchdog;
+
+use crate::chunk::{self, ChunkError};
+use crate::color::Color;
+use crate::engine::{self, Engine};
+use crate::info::MAX_INFO_STRING;
+use crate::metrics;
+use crate::shared::{CvarFlags, UserCmd, Vec3, VmCvar};
+use crate::{ffi, EngineSyscalls, Syscall, VmResult};
 use std::ffi::CString;
+use std::sync::Mutex;
+use std::time::Instant;
 
 /// System traps provided by the engine
 ///
 /// See `gameImport_t` in [ioquake3's `game/g_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/g_public.h).
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 // TODO: Should these be shortened and renamed, e.g. `Print` and `Error` instead of `G_PRINT` and `G_ERROR`?
 #[allow(non_camel_case_types)]
 pub enum Imports {
@@ -24,6 +62,46 @@ pub enum Imports {
     G_PRINT = 0,
     /// Shutdown the game with an error message
     G_ERROR = 1,
+    /// Register a cvar and copy it into module memory
+    G_CVAR_REGISTER = 3,
+    /// Update the copy of a cvar in module memory
+    G_CVAR_UPDATE = 4,
+    /// Set the value of a cvar
+    G_CVAR_SET = 5,
+    /// Get the value of a cvar as string
+    G_CVAR_VARIABLE_STRING_BUFFER = 7,
+    /// Get the number of arguments of the current command
+    G_ARGC = 8,
+    /// Get an argument of the current command
+    G_ARGV = 9,
+    /// Open a file in the mod directory
+    G_FS_FOPEN_FILE = 10,
+    /// Read from a file
+    G_FS_READ = 11,
+    /// Write to a file
+    G_FS_WRITE = 12,
+    /// Close a file
+    G_FS_FCLOSE_FILE = 13,
+    /// Add text to the engine's command buffer
+    G_SEND_CONSOLE_COMMAND = 14,
+    /// Tell the engine where the game entities and clients are
+    G_LOCATE_GAME_DATA = 15,
+    /// Send a reliable command to clients
+    G_SEND_SERVER_COMMAND = 17,
+    /// Set a configstring, which is sent to all clients
+    G_SET_CONFIGSTRING = 18,
+    /// Get the userinfo of a client
+    G_GET_USERINFO = 20,
+    /// Find the entities touching a box
+    G_ENTITIES_IN_BOX = 32,
+    /// Reserve a client slot for a bot
+    G_BOT_ALLOCATE_CLIENT = 34,
+    /// Release the client slot of a bot
+    G_BOT_FREE_CLIENT = 35,
+    /// Get the latest input of a client
+    G_GET_USERCMD = 36,
+    /// List files in a directory of the virtual filesystem
+    G_FS_GETFILELIST = 38,
 }
 
 impl From<Imports> for isize {
@@ -31,6 +109,132 @@ impl From<Imports> for isize {
         match import {
             Imports::G_PRINT => 0,
             Imports::G_ERROR => 1,
+            Imports::G_CVAR_REGISTER => 3,
+            Imports::G_CVAR_UPDATE => 4,
+            Imports::G_CVAR_SET => 5,
+            Imports::G_CVAR_VARIABLE_STRING_BUFFER => 7,
+            Imports::G_ARGC => 8,
+            Imports::G_ARGV => 9,
+            Imports::G_FS_FOPEN_FILE => 10,
+            Imports::G_FS_READ => 11,
+            Imports::G_FS_WRITE => 12,
+            Imports::G_FS_FCLOSE_FILE => 13,
+            Imports::G_SEND_CONSOLE_COMMAND => 14,
+            Imports::G_LOCATE_GAME_DATA => 15,
+            Imports::G_SEND_SERVER_COMMAND => 17,
+            Imports::G_SET_CONFIGSTRING => 18,
+            Imports::G_GET_USERINFO => 20,
+            Imports::G_ENTITIES_IN_BOX => 32,
+            Imports::G_BOT_ALLOCATE_CLIENT => 34,
+            Imports::G_BOT_FREE_CLIENT => 35,
+            Imports::G_GET_USERCMD => 36,
+            Imports::G_FS_GETFILELIST => 38,
+        }
+    }
+}
+
+impl Imports {
+    /// All wrapped traps, in ascending order
+    pub const ALL: [Imports; 22] = [
+        Imports::G_PRINT,
+        Imports::G_ERROR,
+        Imports::G_CVAR_REGISTER,
+        Imports::G_CVAR_UPDATE,
+        Imports::G_CVAR_SET,
+        Imports::G_CVAR_VARIABLE_STRING_BUFFER,
+        Imports::G_ARGC,
+        Imports::G_ARGV,
+        Imports::G_FS_FOPEN_FILE,
+        Imports::G_FS_READ,
+        Imports::G_FS_WRITE,
+        Imports::G_FS_FCLOSE_FILE,
+        Imports::G_SEND_CONSOLE_COMMAND,
+        Imports::G_LOCATE_GAME_DATA,
+        Imports::G_SEND_SERVER_COMMAND,
+        Imports::G_SET_CONFIGSTRING,
+        Imports::G_GET_USERINFO,
+        Imports::G_ENTITIES_IN_BOX,
+        Imports::G_BOT_ALLOCATE_CLIENT,
+        Imports::G_BOT_FREE_CLIENT,
+        Imports::G_GET_USERCMD,
+        Imports::G_FS_GETFILELIST,
+    ];
+
+    /// Name of the `gameImport_t` value, e.g. `"G_PRINT"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Imports::G_PRINT => "G_PRINT",
+            Imports::G_ERROR => "G_ERROR",
+            Imports::G_CVAR_REGISTER => "G_CVAR_REGISTER",
+            Imports::G_CVAR_UPDATE => "G_CVAR_UPDATE",
+            Imports::G_CVAR_SET => "G_CVAR_SET",
+            Imports::G_CVAR_VARIABLE_STRING_BUFFER => "G_CVAR_VARIABLE_STRING_BUFFER",
+            Imports::G_ARGC => "G_ARGC",
+            Imports::G_ARGV => "G_ARGV",
+            Imports::G_FS_FOPEN_FILE => "G_FS_FOPEN_FILE",
+            Imports::G_FS_READ => "G_FS_READ",
+            Imports::G_FS_WRITE => "G_FS_WRITE",
+            Imports::G_FS_FCLOSE_FILE => "G_FS_FCLOSE_FILE",
+            Imports::G_SEND_CONSOLE_COMMAND => "G_SEND_CONSOLE_COMMAND",
+            Imports::G_LOCATE_GAME_DATA => "G_LOCATE_GAME_DATA",
+            Imports::G_SEND_SERVER_COMMAND => "G_SEND_SERVER_COMMAND",
+            Imports::G_SET_CONFIGSTRING => "G_SET_CONFIGSTRING",
+            Imports::G_GET_USERINFO => "G_GET_USERINFO",
+            Imports::G_ENTITIES_IN_BOX => "G_ENTITIES_IN_BOX",
+            Imports::G_BOT_ALLOCATE_CLIENT => "G_BOT_ALLOCATE_CLIENT",
+            Imports::G_BOT_FREE_CLIENT => "G_BOT_FREE_CLIENT",
+            Imports::G_GET_USERCMD => "G_GET_USERCMD",
+            Imports::G_FS_GETFILELIST => "G_FS_GETFILELIST",
+        }
+    }
+
+    /// Trap number of this import for `engine`
+    ///
+    /// `isize::from` gives ioquake3's numbering, the original engine has its own table.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quake3_native_vm::engine::Engine;
+    /// use quake3_native_vm::qagame::Imports;
+    ///
+    /// assert_eq!(Imports::G_GET_USERCMD.number(Engine::Ioquake3), 36);
+    /// assert_eq!(Imports::G_GET_USERCMD.number(Engine::Quake3), 36);
+    /// ```
+    pub fn number(self, engine: Engine) -> ffi::intptr_t {
+        match engine {
+            Engine::Ioquake3 => self.into(),
+            Engine::Quake3 => self.quake3_number(),
+        }
+    }
+
+    /// Trap number in Quake 3 1.32, which ioquake3 kept so far
+    ///
+    /// See `gameImport_t` in [Quake III Arena's `game/g_public.h`](https://github.com/id-Software/Quake-III-Arena/blob/master/code/game/g_public.h).
+    fn quake3_number(self) -> ffi::intptr_t {
+        match self {
+            Self::G_PRINT => 0,
+            Self::G_ERROR => 1,
+            Self::G_CVAR_REGISTER => 3,
+            Self::G_CVAR_UPDATE => 4,
+            Self::G_CVAR_SET => 5,
+            Self::G_CVAR_VARIABLE_STRING_BUFFER => 7,
+            Self::G_ARGC => 8,
+            Self::G_ARGV => 9,
+            Self::G_FS_FOPEN_FILE => 10,
+            Self::G_FS_READ => 11,
+            Self::G_FS_WRITE => 12,
+            Self::G_FS_FCLOSE_FILE => 13,
+            Self::G_SEND_CONSOLE_COMMAND => 14,
+            Self::G_LOCATE_GAME_DATA => 15,
+            Self::G_SEND_SERVER_COMMAND => 17,
+            Self::G_SET_CONFIGSTRING => 18,
+            Self::G_GET_USERINFO => 20,
+            Self::G_ENTITIES_IN_BOX => 32,
+            Self::G_BOT_ALLOCATE_CLIENT => 34,
+            Self::G_BOT_FREE_CLIENT => 35,
+            Self::G_GET_USERCMD => 36,
+            Self::G_FS_GETFILELIST => 38,
         }
     }
 }
@@ -39,7 +243,7 @@ impl From<Imports> for isize {
 ///
 /// See `gameExport_t` in [ioquake3's `game/g_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/g_public.h).
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 // TODO: Should these be shortened and renamed, e.g. `Init` and `Shutdown` instead of `GAME_INIT` and `GAME_SHUTDOWN`?
 #[allow(non_camel_case_types)]
 pub enum Exports {
@@ -74,28 +278,730 @@ impl std::convert::TryFrom<ffi::c_int> for Exports {
         match cmd {
             0 => Ok(Self::GAME_INIT),
             1 => Ok(Self::GAME_SHUTDOWN),
+            2 => Ok(Self::GAME_CLIENT_CONNECT),
+            3 => Ok(Self::GAME_CLIENT_BEGIN),
+            4 => Ok(Self::GAME_CLIENT_USERINFO_CHANGED),
+            5 => Ok(Self::GAME_CLIENT_DISCONNECT),
+            6 => Ok(Self::GAME_CLIENT_COMMAND),
+            7 => Ok(Self::GAME_CLIENT_THINK),
+            8 => Ok(Self::GAME_RUN_FRAME),
+            9 => Ok(Self::GAME_CONSOLE_COMMAND),
+            10 => Ok(Self::BOTAI_START_FRAME),
             _ => Err("Unknown command"),
         }
     }
 }
 
+impl Exports {
+    /// All functions, in ascending order
+    pub const ALL: [Exports; 11] = [
+        Exports::GAME_INIT,
+        Exports::GAME_SHUTDOWN,
+        Exports::GAME_CLIENT_CONNECT,
+        Exports::GAME_CLIENT_BEGIN,
+        Exports::GAME_CLIENT_USERINFO_CHANGED,
+        Exports::GAME_CLIENT_DISCONNECT,
+        Exports::GAME_CLIENT_COMMAND,
+        Exports::GAME_CLIENT_THINK,
+        Exports::GAME_RUN_FRAME,
+        Exports::GAME_CONSOLE_COMMAND,
+        Exports::BOTAI_START_FRAME,
+    ];
+
+    /// Name of the `gameExport_t` value, e.g. `"GAME_INIT"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Exports::GAME_INIT => "GAME_INIT",
+            Exports::GAME_SHUTDOWN => "GAME_SHUTDOWN",
+            Exports::GAME_CLIENT_CONNECT => "GAME_CLIENT_CONNECT",
+            Exports::GAME_CLIENT_BEGIN => "GAME_CLIENT_BEGIN",
+            Exports::GAME_CLIENT_USERINFO_CHANGED => "GAME_CLIENT_USERINFO_CHANGED",
+            Exports::GAME_CLIENT_DISCONNECT => "GAME_CLIENT_DISCONNECT",
+            Exports::GAME_CLIENT_COMMAND => "GAME_CLIENT_COMMAND",
+            Exports::GAME_CLIENT_THINK => "GAME_CLIENT_THINK",
+            Exports::GAME_RUN_FRAME => "GAME_RUN_FRAME",
+            Exports::GAME_CONSOLE_COMMAND => "GAME_CONSOLE_COMMAND",
+            Exports::BOTAI_START_FRAME => "BOTAI_START_FRAME",
+        }
+    }
+}
+
+/// Size of the buffer for [`Syscalls::fs_get_file_list`](Syscalls::fs_get_file_list)
+pub const MAX_FILE_LIST: usize = 8192;
+
+/// Maximum length of cvar values
+///
+/// See `MAX_CVAR_VALUE_STRING` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const MAX_CVAR_VALUE_STRING: usize = 256;
+
+/// Maximum length of strings passed between engine and module
+///
+/// See `MAX_STRING_CHARS` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const MAX_STRING_CHARS: usize = 1024;
+
+/// When to execute text added to the engine's command buffer
+///
+/// See `cbufExec_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum CbufExec {
+    /// Don't return until completed
+    EXEC_NOW = 0,
+    /// Insert at current position, but don't run yet
+    EXEC_INSERT = 1,
+    /// Add to end of the command buffer
+    EXEC_APPEND = 2,
+}
+
+/// How to open a file
+///
+/// See `fsMode_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum FsMode {
+    /// Open for reading
+    FS_READ = 0,
+    /// Create or truncate for writing
+    FS_WRITE = 1,
+    /// Create or append for writing
+    FS_APPEND = 2,
+    /// Like [`FS_APPEND`](FsMode::FS_APPEND), but flush after every write
+    FS_APPEND_SYNC = 3,
+}
+
+/// Recipient of a server command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// All connected clients
+    All,
+    /// A single client
+    Client(ffi::c_int),
+}
+
+impl From<Target> for ffi::c_int {
+    fn from(target: Target) -> Self {
+        match target {
+            Target::All => -1,
+            Target::Client(client_number) => client_number,
+        }
+    }
+}
+
+/// Buffer of the last [`Deny`](Deny) message, which the engine reads after the dispatcher returned
+static DENY_MESSAGE: Mutex<[u8; MAX_STRING_CHARS]> = Mutex::new([0; MAX_STRING_CHARS]);
+
+/// Message to reject a connecting client with, returned from [`Module::client_connect`](Module::client_connect)
+///
+/// The engine reads the message after `vmMain` returned, so it can't be a temporary string.
+/// Every message is copied into the same static buffer instead, which is neither leaked nor
+/// reallocated per rejection. The engine is done with it before the next client connects.
+/// Messages are truncated to [`MAX_STRING_CHARS`](MAX_STRING_CHARS) - 1 bytes, and NUL bytes are dropped.
+///
+/// See `ClientConnect` in [ioquake3's `game/g_client.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_client.c).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::ffi;
+/// use quake3_native_vm::qagame::Deny;
+///
+/// # fn client_connect(banned: bool) -> Result<(), Deny> {
+/// // In `Module::client_connect`
+/// if banned {
+///     return Err(Deny::new("Banned"));
+/// }
+/// Ok(())
+/// # }
+///
+/// let banned = client_connect(true).unwrap_err();
+/// assert_ne!(banned.into_raw(), 0);
+/// assert_eq!(Deny::current(), "Banned");
+///
+/// // Every rejection reuses the buffer
+/// for i in 0..1000 {
+///     assert_eq!(Deny::new(format!("Server is full ({})", i)), banned);
+/// }
+/// assert_eq!(Deny::current(), "Server is full (999)");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Deny(VmResult);
+
+impl Deny {
+    /// Reject the client with `message`, replacing the message of the previous `Deny`
+    pub fn new<T: AsRef<[u8]>>(message: T) -> Self {
+        let mut buffer = DENY_MESSAGE.lock().unwrap();
+        let mut len = 0;
+        for &b in message.as_ref().iter().filter(|&&b| b != 0) {
+            if len == buffer.len() - 1 {
+                break;
+            }
+            buffer[len] = b;
+            len += 1;
+        }
+        buffer[len] = 0;
+        Self(VmResult::deny(buffer.as_ptr()))
+    }
+
+    /// Message of the last `Deny`, e.g. for tests
+    pub fn current() -> String {
+        buffer_to_string(&*DENY_MESSAGE.lock().unwrap())
+    }
+
+    /// Value for the engine
+    pub fn into_raw(self) -> ffi::intptr_t {
+        self.0.into_raw()
+    }
+}
+
+impl From<Deny> for VmResult {
+    fn from(deny: Deny) -> Self {
+        deny.0
+    }
+}
+
+impl From<Deny> for ffi::intptr_t {
+    fn from(deny: Deny) -> Self {
+        deny.into_raw()
+    }
+}
+
+/// Bytes of a NUL-terminated buffer filled by the engine, without the NUL
+pub(crate) fn buffer_bytes(buffer: &[u8]) -> &[u8] {
+    let len = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    &buffer[..len]
+}
+
+/// Convert a NUL-terminated buffer filled by the engine
+///
+/// Invalid UTF-8, e.g. from Latin-1 player names, is replaced with `U+FFFD`.
+pub(crate) fn buffer_to_string(buffer: &[u8]) -> String {
+    String::from_utf8_lossy(buffer_bytes(buffer)).into_owned()
+}
+
+/// Convert `text` for the engine, dropping NUL bytes instead of failing
+///
+/// The engine would stop reading at the first NUL anyway, and panicking in e.g.
+/// [`Syscalls::error`](Syscalls::error) would hide the original error.
+pub(crate) fn to_cstring<T: Into<Vec<u8>>>(text: T) -> CString {
+    let mut bytes = text.into();
+    bytes.retain(|&b| b != 0);
+    CString::new(bytes).expect("NUL bytes were removed")
+}
+
+/// Make `text` safe to put between quotes of a command
+///
+/// The engine's tokenizer has no escapes, so double quotes are replaced with single quotes.
+fn quote(text: &str) -> String {
+    text.replace('\0', "").replace('"', "'")
+}
+
+/// Join command arguments with spaces, truncated to [`MAX_STRING_CHARS`](MAX_STRING_CHARS) - 1 bytes
+///
+/// Quotes around arguments are not restored, like in the engine,
+/// e.g. the arguments of `say "hello  world" again` become `hello  world again`.
+///
+/// See `ConcatArgs` in [ioquake3's `game/g_cmds.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_cmds.c).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::qagame::concat_args;
+///
+/// assert_eq!(concat_args(["hello  world", "again"]), "hello  world again");
+/// assert_eq!(concat_args(Vec::<String>::new()), "");
+/// ```
+pub fn concat_args<I, S>(args: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut text = String::new();
+    for (i, arg) in args.into_iter().enumerate() {
+        if i > 0 {
+            text.push(' ');
+        }
+        text.push_str(arg.as_ref());
+    }
+
+    let mut len = text.len().min(MAX_STRING_CHARS - 1);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    text.truncate(len);
+    text
+}
+
+/// Replace line breaks so `text` can't add lines to chat
+fn single_line(text: &str) -> String {
+    text.replace(['\n', '\r'], " ")
+}
+
 /// `qagame` specific wrapper around generic [`Syscall`](Syscall)
 ///
+/// This is a `Copy` handle to the engine, so every part of a mod can keep its own.
+/// Traps must only be called from the engine's thread, while it is running the module.
+///
 /// See [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
-pub struct Syscalls {
-    syscall: Syscall,
+///
+/// The engine is any [`EngineSyscalls`](EngineSyscalls) backend, the default being the engine's [`Syscall`](Syscall).
+#[derive(Clone, Copy)]
+pub struct Syscalls<S = Syscall> {
+    engine: S,
 }
 
 impl Syscalls {
     /// See `dllEntry` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
     pub fn new(syscall: Syscall) -> Self {
-        Self { syscall }
+        Self::with_engine(syscall)
+    }
+}
+
+impl<S: EngineSyscalls> Syscalls<S> {
+    /// Wrapper around another backend than the engine's [`Syscall`](Syscall), e.g. a [`backend::Mock`](crate::backend::Mock)
+    pub fn with_engine(engine: S) -> Self {
+        Self { engine }
+    }
+
+    /// Call the trap for `import` with the engine's numbering, see [`engine::current`](crate::engine::current)
+    fn call(&self, import: Imports, args: &[ffi::intptr_t]) -> ffi::intptr_t {
+        metrics::global().count(import);
+        self.engine.syscall(import.number(engine::current()), args)
     }
 
+    /// Shutdown the game with an error message
+    ///
+    /// The engine does not return from this, it `longjmp`s out of the module or exits.
+    /// Like for all string arguments, NUL bytes in `text` are dropped instead of panicking.
+    ///
     /// See `trap_Error` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
-    pub fn error<T: Into<Vec<u8>>>(&self, text: T) {
-        let msg = CString::new(text).unwrap();
-        (self.syscall)(Imports::G_ERROR.into(), msg.as_ptr());
+    pub fn error<T: Into<Vec<u8>>>(&self, text: T) -> ! {
+        let msg = to_cstring(text);
+        self.call(Imports::G_ERROR, &[ffi::ptr_to_intptr(msg.as_ptr())]);
+        unreachable!("G_ERROR returned")
+    }
+
+    /// Print a message to qconsole
+    ///
+    /// See `trap_Print` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn print<T: Into<Vec<u8>>>(&self, text: T) {
+        let msg = to_cstring(text);
+        self.call(Imports::G_PRINT, &[ffi::ptr_to_intptr(msg.as_ptr())]);
+    }
+
+    /// Register the cvar `var_name` with `default_value` and `flags`, e.g. [`CvarFlags::ARCHIVE`](CvarFlags::ARCHIVE)
+    ///
+    /// The returned copy is only refreshed by [`cvar_update`](Syscalls::cvar_update).
+    ///
+    /// See `trap_Cvar_Register` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn cvar_register<T: Into<Vec<u8>>, U: Into<Vec<u8>>>(
+        &self,
+        var_name: T,
+        default_value: U,
+        flags: CvarFlags,
+    ) -> VmCvar {
+        let var_name = to_cstring(var_name);
+        let default_value = to_cstring(default_value);
+        let mut cvar = VmCvar::default();
+        self.call(
+            Imports::G_CVAR_REGISTER,
+            &[
+                ffi::ptr_to_intptr(&mut cvar as *mut VmCvar),
+                ffi::ptr_to_intptr(var_name.as_ptr()),
+                ffi::ptr_to_intptr(default_value.as_ptr()),
+                ffi::int_to_intptr(flags.bits()),
+            ],
+        );
+        cvar
+    }
+
+    /// Refresh `cvar` from the engine, returning whether its value changed since the last update
+    ///
+    /// Call this every frame for the cvars the game reacts to, like `G_UpdateCvars` in the C game.
+    ///
+    /// See `trap_Cvar_Update` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn cvar_update(&self, cvar: &mut VmCvar) -> bool {
+        let modification_count = cvar.modification_count();
+        self.call(
+            Imports::G_CVAR_UPDATE,
+            &[ffi::ptr_to_intptr(cvar as *mut VmCvar)],
+        );
+        cvar.modification_count() != modification_count
+    }
+
+    /// See `trap_Cvar_Set` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn cvar_set<T: Into<Vec<u8>>, U: Into<Vec<u8>>>(&self, var_name: T, value: U) {
+        let var_name = to_cstring(var_name);
+        let value = to_cstring(value);
+        self.call(
+            Imports::G_CVAR_SET,
+            &[
+                ffi::ptr_to_intptr(var_name.as_ptr()),
+                ffi::ptr_to_intptr(value.as_ptr()),
+            ],
+        );
+    }
+
+    /// See `trap_Cvar_VariableStringBuffer` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn cvar_variable_string_buffer<T: Into<Vec<u8>>>(&self, var_name: T) -> String {
+        String::from_utf8_lossy(&self.cvar_variable_bytes(var_name)).into_owned()
+    }
+
+    /// Value of the cvar `var_name` as set, for values that are not valid UTF-8
+    pub fn cvar_variable_bytes<T: Into<Vec<u8>>>(&self, var_name: T) -> Vec<u8> {
+        let var_name = to_cstring(var_name);
+        let mut buffer = vec![0u8; MAX_CVAR_VALUE_STRING];
+        self.call(
+            Imports::G_CVAR_VARIABLE_STRING_BUFFER,
+            &[
+                ffi::ptr_to_intptr(var_name.as_ptr()),
+                ffi::ptr_to_intptr(buffer.as_mut_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+            ],
+        );
+        buffer_bytes(&buffer).to_vec()
+    }
+
+    /// Number of arguments of the current client or console command, including the command name
+    ///
+    /// See `trap_Argc` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn argc(&self) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::G_ARGC, &[]))
+    }
+
+    /// Argument `n` of the current client or console command, `0` being the command name
+    ///
+    /// See `trap_Argv` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn argv(&self, n: ffi::c_int) -> String {
+        String::from_utf8_lossy(&self.argv_bytes(n)).into_owned()
+    }
+
+    /// Argument `n` as sent, for arguments that are not valid UTF-8
+    pub fn argv_bytes(&self, n: ffi::c_int) -> Vec<u8> {
+        let mut buffer = vec![0u8; MAX_STRING_CHARS];
+        self.call(
+            Imports::G_ARGV,
+            &[
+                ffi::int_to_intptr(n),
+                ffi::ptr_to_intptr(buffer.as_mut_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+            ],
+        );
+        buffer_bytes(&buffer).to_vec()
+    }
+
+    /// Arguments of the current command from `start` on, joined with [`concat_args`](concat_args)
+    pub fn args_from(&self, start: ffi::c_int) -> String {
+        concat_args((start..self.argc()).map(|n| self.argv(n)))
+    }
+
+    /// All arguments of the current command without the command name, e.g. the text of `say`
+    ///
+    /// See `trap_Args` in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c),
+    /// which the game lacks.
+    pub fn args_raw(&self) -> String {
+        self.args_from(1)
+    }
+
+    /// Open the file `qpath` in the mod directory, returning its handle and length
+    ///
+    /// The handle is `0` if the file could not be opened.
+    /// The length is only meaningful for [`FsMode::FS_READ`](FsMode::FS_READ), and negative if the file does not exist.
+    /// See [`fs::File`](fs::File) for a safer wrapper.
+    ///
+    /// See `trap_FS_FOpenFile` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn fs_fopen_file<T: Into<Vec<u8>>>(
+        &self,
+        qpath: T,
+        mode: FsMode,
+    ) -> (ffi::c_int, ffi::c_int) {
+        let qpath = to_cstring(qpath);
+        let mut f: ffi::c_int = 0;
+        let len = self.call(
+            Imports::G_FS_FOPEN_FILE,
+            &[
+                ffi::ptr_to_intptr(qpath.as_ptr()),
+                ffi::ptr_to_intptr(&mut f as *mut ffi::c_int),
+                ffi::int_to_intptr(mode as ffi::c_int),
+            ],
+        );
+        (f, ffi::intptr_to_int(len))
+    }
+
+    /// Fill `buffer` from the file with handle `f`
+    ///
+    /// See `trap_FS_Read` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn fs_read(&self, buffer: &mut [u8], f: ffi::c_int) {
+        self.call(
+            Imports::G_FS_READ,
+            &[
+                ffi::ptr_to_intptr(buffer.as_mut_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+                ffi::int_to_intptr(f),
+            ],
+        );
+    }
+
+    /// Write all of `buffer` to the file with handle `f`
+    ///
+    /// See `trap_FS_Write` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn fs_write(&self, buffer: &[u8], f: ffi::c_int) {
+        self.call(
+            Imports::G_FS_WRITE,
+            &[
+                ffi::ptr_to_intptr(buffer.as_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+                ffi::int_to_intptr(f),
+            ],
+        );
+    }
+
+    /// Close the file with handle `f`
+    ///
+    /// See `trap_FS_FCloseFile` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn fs_fclose_file(&self, f: ffi::c_int) {
+        self.call(Imports::G_FS_FCLOSE_FILE, &[ffi::int_to_intptr(f)]);
+    }
+
+    /// Names of the files in `path` with `extension`, e.g. `fs_get_file_list("scripts", ".arena")`
+    ///
+    /// Names are relative to `path`, and only as many as fit into [`MAX_FILE_LIST`](MAX_FILE_LIST) bytes are returned.
+    ///
+    /// See `trap_FS_GetFileList` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn fs_get_file_list<T: Into<Vec<u8>>, U: Into<Vec<u8>>>(
+        &self,
+        path: T,
+        extension: U,
+    ) -> Vec<String> {
+        let path = to_cstring(path);
+        let extension = to_cstring(extension);
+        let mut buffer = vec![0u8; MAX_FILE_LIST];
+        let count = self.call(
+            Imports::G_FS_GETFILELIST,
+            &[
+                ffi::ptr_to_intptr(path.as_ptr()),
+                ffi::ptr_to_intptr(extension.as_ptr()),
+                ffi::ptr_to_intptr(buffer.as_mut_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+            ],
+        );
+        buffer
+            .split(|&b| b == 0)
+            .take(usize::try_from(count).unwrap_or(0))
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .collect()
+    }
+
+    /// Add `text` to the engine's command buffer, like typing it into the server console
+    ///
+    /// See `trap_SendConsoleCommand` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn send_console_command<T: Into<Vec<u8>>>(&self, exec_when: CbufExec, text: T) {
+        let text = to_cstring(text);
+        self.call(
+            Imports::G_SEND_CONSOLE_COMMAND,
+            &[
+                ffi::int_to_intptr(exec_when as ffi::c_int),
+                ffi::ptr_to_intptr(text.as_ptr()),
+            ],
+        );
+    }
+
+    /// Tell the engine where the game entities and clients are
+    ///
+    /// Entities of type `E` have to start with a [`SharedEntity`](entity::SharedEntity),
+    /// clients of type `C` with a [`PlayerState`](crate::shared::PlayerState).
+    /// Only the first `num_entities` of `gentities` are in use.
+    ///
+    /// The engine keeps using both slices until the game is shut down or this is called again,
+    /// so they must neither be moved nor dropped before that, e.g. by allocating them once in a `Vec`.
+    ///
+    /// See `trap_LocateGameData` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn locate_game_data<E, C>(
+        &self,
+        gentities: &mut [E],
+        num_entities: ffi::c_int,
+        clients: &mut [C],
+    ) {
+        assert!(usize::try_from(num_entities).map_or(false, |n| n <= gentities.len()));
+        self.call(
+            Imports::G_LOCATE_GAME_DATA,
+            &[
+                ffi::ptr_to_intptr(gentities.as_mut_ptr()),
+                ffi::int_to_intptr(num_entities),
+                ffi::len_to_intptr(std::mem::size_of::<E>()),
+                ffi::ptr_to_intptr(clients.as_mut_ptr()),
+                ffi::len_to_intptr(std::mem::size_of::<C>()),
+            ],
+        );
+    }
+
+    /// Send a reliable command, e.g. `print "Hello\n"`, to `target`
+    ///
+    /// See `trap_SendServerCommand` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn send_server_command<T: Into<Vec<u8>>>(&self, target: Target, text: T) {
+        let text = to_cstring(text);
+        self.call(
+            Imports::G_SEND_SERVER_COMMAND,
+            &[
+                ffi::int_to_intptr(ffi::c_int::from(target)),
+                ffi::ptr_to_intptr(text.as_ptr()),
+            ],
+        );
+    }
+
+    /// Send `payload` for `command` to `target`, split into as many server commands as needed
+    ///
+    /// Unlike [`send_server_command`](Syscalls::send_server_command), this returns an error instead of the
+    /// engine dropping oversized commands, and for payloads of more than [`chunk::MAX_CHUNKS`](crate::chunk::MAX_CHUNKS)
+    /// chunks, which would overflow the client. Nothing is sent on errors.
+    /// See [`chunk`](crate::chunk) for how clients reassemble the payload.
+    pub fn send_server_command_chunked(
+        &self,
+        target: Target,
+        command: &str,
+        payload: &str,
+    ) -> Result<(), ChunkError> {
+        for text in chunk::split(command, payload)? {
+            self.send_server_command(target, text);
+        }
+        Ok(())
+    }
+
+    /// Set configstring `num` to `value`
+    ///
+    /// See `trap_SetConfigstring` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn set_configstring<T: Into<Vec<u8>>>(&self, num: ffi::c_int, value: T) {
+        let value = to_cstring(value);
+        self.call(
+            Imports::G_SET_CONFIGSTRING,
+            &[ffi::int_to_intptr(num), ffi::ptr_to_intptr(value.as_ptr())],
+        );
+    }
+
+    /// Userinfo of `client_number`, e.g. `\name\Sarge\ip\127.0.0.1:27960`
+    ///
+    /// Parse it with [`InfoString`](crate::info::InfoString).
+    ///
+    /// See `trap_GetUserinfo` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn get_userinfo(&self, client_number: ffi::c_int) -> String {
+        String::from_utf8_lossy(&self.get_userinfo_bytes(client_number)).into_owned()
+    }
+
+    /// Userinfo of `client_number` as sent, e.g. for player names in another encoding than UTF-8
+    pub fn get_userinfo_bytes(&self, client_number: ffi::c_int) -> Vec<u8> {
+        let mut buffer = vec![0u8; MAX_INFO_STRING];
+        self.call(
+            Imports::G_GET_USERINFO,
+            &[
+                ffi::int_to_intptr(client_number),
+                ffi::ptr_to_intptr(buffer.as_mut_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+            ],
+        );
+        buffer_bytes(&buffer).to_vec()
+    }
+
+    /// Numbers of the linked entities whose bounds touch the box from `mins` to `maxs`
+    ///
+    /// See [`query::entities_within`](query::entities_within) for a radius search.
+    ///
+    /// See `trap_EntitiesInBox` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn entities_in_box(&self, mins: Vec3, maxs: Vec3) -> Vec<ffi::c_int> {
+        let mut list: Vec<ffi::c_int> = vec![0; entity::MAX_GENTITIES];
+        let count = self.call(
+            Imports::G_ENTITIES_IN_BOX,
+            &[
+                ffi::ptr_to_intptr(mins.as_ptr()),
+                ffi::ptr_to_intptr(maxs.as_ptr()),
+                ffi::ptr_to_intptr(list.as_mut_ptr()),
+                ffi::len_to_intptr(list.len()),
+            ],
+        );
+        list.truncate(usize::try_from(count).unwrap_or(0));
+        list
+    }
+
+    /// Latest input of `client_number`
+    ///
+    /// See `trap_GetUsercmd` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn get_usercmd(&self, client_number: ffi::c_int) -> UserCmd {
+        let mut cmd = UserCmd::default();
+        self.call(
+            Imports::G_GET_USERCMD,
+            &[
+                ffi::int_to_intptr(client_number),
+                ffi::ptr_to_intptr(&mut cmd as *mut UserCmd),
+            ],
+        );
+        cmd
+    }
+
+    /// Reserve a client slot for a bot, `None` if the server is full
+    ///
+    /// The bot still has to connect with [`Module::client_connect`](Module::client_connect),
+    /// see [`bot::BotClients`](bot::BotClients) to keep track of the slots.
+    ///
+    /// See `trap_BotAllocateClient` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn bot_allocate_client(&self) -> Option<ffi::c_int> {
+        let client_number = ffi::intptr_to_int(self.call(Imports::G_BOT_ALLOCATE_CLIENT, &[]));
+        (client_number >= 0).then_some(client_number)
+    }
+
+    /// Release the client slot of the bot `client_number`
+    ///
+    /// See `trap_BotFreeClient` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn bot_free_client(&self, client_number: ffi::c_int) {
+        self.call(
+            Imports::G_BOT_FREE_CLIENT,
+            &[ffi::int_to_intptr(client_number)],
+        );
+    }
+
+    /// Print `text` to the console of `target`
+    ///
+    /// Like `trap_SendServerCommand(clientNum, "print \"...\"")` in the C game.
+    pub fn console_print(&self, target: Target, text: &str) {
+        self.send_server_command(target, format!("print \"{}\"", quote(text)));
+    }
+
+    /// Print `text` in the center of the screen of `target`
+    ///
+    /// Like `trap_SendServerCommand(clientNum, "cp \"...\"")` in the C game.
+    pub fn center_print(&self, target: Target, text: &str) {
+        self.send_server_command(target, format!("cp \"{}\"", quote(text)));
+    }
+
+    /// Show a chat message from `from` to `target`
+    ///
+    /// `text` is on a single line, in green like player chat.
+    ///
+    /// See `G_SayTo` in [ioquake3's `game/g_cmds.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_cmds.c).
+    pub fn chat(&self, target: Target, from: &str, text: &str) {
+        self.send_server_command(
+            target,
+            format!(
+                "chat \"{}{}: {}{}\"",
+                quote(&single_line(from)),
+                Color::White,
+                Color::Green,
+                quote(&single_line(text))
+            ),
+        );
+    }
+
+    /// Detect the engine from its cvars and make it the [`engine::current`](crate::engine::current) one
+    ///
+    /// The cvars are read with the numbering of the previous engine, all known engines agree on `G_CVAR_VARIABLE_STRING_BUFFER`.
+    ///
+    /// [`game_module!`](crate::game_module) does this on every `GAME_INIT`.
+    pub fn probe_engine(&self) -> Engine {
+        let engine = Engine::detect(
+            &self.cvar_variable_string_buffer("version"),
+            &self.cvar_variable_string_buffer("com_gamename"),
+        );
+        engine::set_current(engine);
+        engine
     }
 }
 
@@ -112,13 +1018,15 @@ pub trait Module: 'static + Sync + Send {
     /// See `G_ShutdownGame` in [ioquake3's `game/g_main.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_main.c).
     fn shutdown(&self, restart: bool);
 
+    /// Return `Ok(())` to accept the client, or a [`Deny`](Deny) to reject it with a message.
+    ///
     /// See `ClientConnect` in [ioquake3's `game/g_client.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_client.c).
     fn client_connect(
         &self,
         client_number: ffi::c_int,
         first_time: bool,
         is_bot: bool,
-    ) -> ffi::intptr_t;
+    ) -> Result<(), Deny>;
 
     /// See `ClientThink` in [ioquake3's `game/g_active.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_active.c).
     fn client_think(&self, client_number: ffi::c_int);
@@ -145,97 +1053,257 @@ pub trait Module: 'static + Sync + Send {
     fn botai_start_frame(&self, level_time: ffi::c_int) -> bool;
 }
 
+/// Call of a [`Module`](Module) function with the arguments of an engine command
+type Handler = fn(&dyn Module, &[ffi::c_int; 12]) -> VmResult;
+
+/// Handlers of all [`Exports`](Exports), indexed by command number
+const HANDLERS: [(Exports, Handler); 11] = [
+    (Exports::GAME_INIT, |module, args| {
+        module.init(args[0], args[1], args[2] != 0);
+        VmResult::ok()
+    }),
+    (Exports::GAME_SHUTDOWN, |module, args| {
+        module.shutdown(args[0] != 0);
+        VmResult::ok()
+    }),
+    (Exports::GAME_CLIENT_CONNECT, |module, args| {
+        match module.client_connect(args[0], args[1] != 0, args[2] != 0) {
+            Ok(()) => VmResult::ok(),
+            Err(deny) => deny.into(),
+        }
+    }),
+    (Exports::GAME_CLIENT_BEGIN, |module, args| {
+        module.client_begin(args[0]);
+        VmResult::ok()
+    }),
+    (Exports::GAME_CLIENT_USERINFO_CHANGED, |module, args| {
+        module.client_userinfo_changed(args[0]);
+        VmResult::ok()
+    }),
+    (Exports::GAME_CLIENT_DISCONNECT, |module, args| {
+        module.client_disconnect(args[0]);
+        VmResult::ok()
+    }),
+    (Exports::GAME_CLIENT_COMMAND, |module, args| {
+        module.client_command(args[0]);
+        VmResult::ok()
+    }),
+    (Exports::GAME_CLIENT_THINK, client_think),
+    (Exports::GAME_RUN_FRAME, run_frame),
+    (Exports::GAME_CONSOLE_COMMAND, |module, _| {
+        VmResult::bool(module.console_command())
+    }),
+    (Exports::BOTAI_START_FRAME, |module, args| {
+        VmResult::bool(module.botai_start_frame(args[0]))
+    }),
+];
+
+// A handler in the wrong slot would call the wrong `Module` function
+const _: () = {
+    let mut index = 0;
+    while index < HANDLERS.len() {
+        assert!(HANDLERS[index].0 as usize == index);
+        index += 1;
+    }
+};
+
+/// `GAME_CLIENT_THINK`, sent for every client in every frame
+#[inline]
+fn client_think(module: &dyn Module, args: &[ffi::c_int; 12]) -> VmResult {
+    module.client_think(args[0]);
+    VmResult::ok()
+}
+
+/// `GAME_RUN_FRAME`, timed for [`metrics::global`](crate::metrics::global)
+#[inline]
+fn run_frame(module: &dyn Module, args: &[ffi::c_int; 12]) -> VmResult {
+    let start = Instant::now();
+    module.run_frame(args[0]);
+    metrics::global().record_frame(start.elapsed());
+    VmResult::ok()
+}
+
+/// Decode an engine command and its arguments and call the matching [`Module`](Module) function
+///
+/// `GAME_CLIENT_THINK` and `GAME_RUN_FRAME`, which make up most calls, are handled directly,
+/// all other commands are looked up in a table by their number.
+///
+/// # Panics
+///
+/// Panics if `command` is not a known [`Exports`](Exports) value.
+///
+/// See `vmMain` in [ioquake3's `game/g_main.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_main.c).
+#[inline]
+pub fn dispatch(module: &dyn Module, command: ffi::c_int, args: [ffi::c_int; 12]) -> VmResult {
+    const CLIENT_THINK: ffi::c_int = Exports::GAME_CLIENT_THINK as ffi::c_int;
+    const RUN_FRAME: ffi::c_int = Exports::GAME_RUN_FRAME as ffi::c_int;
+
+    match command {
+        CLIENT_THINK => {
+            metrics::global().count(Exports::GAME_CLIENT_THINK);
+            client_think(module, &args)
+        }
+        RUN_FRAME => {
+            metrics::global().count(Exports::GAME_RUN_FRAME);
+            run_frame(module, &args)
+        }
+        _ => {
+            let (command, handler) = match usize::try_from(command)
+                .ok()
+                .and_then(|index| HANDLERS.get(index))
+            {
+                Some(&entry) => entry,
+                None => panic!("Unknown game command {:?}", command),
+            };
+            metrics::global().count(command);
+            handler(module, &args)
+        }
+    }
+}
+
+/// [`dispatch`](dispatch) a command, warning about it if it takes longer than the [`watchdog::global`](watchdog::global) budget
+///
+/// This is what [`game_module!`](crate::game_module) uses for `vmMain`.
+/// Commands are only timed while the watchdog is on.
+#[inline]
+pub fn dispatch_watched<S: EngineSyscalls>(
+    syscalls: &Syscalls<S>,
+    module: &dyn Module,
+    command: ffi::c_int,
+    args: [ffi::c_int; 12],
+) -> VmResult {
+    let watchdog = watchdog::global();
+    if !watchdog.is_enabled() {
+        return dispatch(module, command, args);
+    }
+    let start = Instant::now();
+    let result = dispatch(module, command, args);
+    if let Ok(command) = Exports::try_from(command) {
+        watchdog.check(syscalls, command, start.elapsed());
+    }
+    result
+}
+
 /// Create a [NativeVM](::NativeVM) impl for the id Quake 3 `qagame` module
+///
+/// With `game_module!(MyGame, builtins)`, the console commands in [`command::builtins`](crate::qagame::command::builtins),
+/// e.g. `metrics_dump`, are handled before [`Module::console_command`](crate::qagame::Module::console_command).
+/// They are left out by default, so that they can't shadow commands of the module with the same name.
+///
+/// Every command is timed for the [`watchdog::global`](crate::qagame::watchdog::global) watchdog while it is on,
+/// see [`dispatch_watched`](crate::qagame::dispatch_watched).
+///
+/// Panics never unwind into the engine, the game is shut down with `G_ERROR` instead, see [`crash::guard`](crate::qagame::crash::guard).
+///
+/// With `game_module!(MyGame, crash_report)`, panics are reported with [`crash::install`](crate::qagame::crash::install).
+///
+/// With `game_module!(MyGame, on_panic = report_panic)`, panics are passed to the [`PanicHandler`](crate::PanicHandler)
+/// `report_panic` before the game is shut down, and before the crash report is written.
+///
+/// Export names can be given like for [`native_vm!`](crate::native_vm), e.g.
+/// `game_module!(MyGame, builtins, crash_report, dll_entry = ["dllEntry"], vm_main = ["vmMain", "VM_Main"])`.
+/// The options have to be in this order, i.e. `builtins`, `crash_report`, `on_panic` and then the export names.
 #[macro_export]
 macro_rules! game_module {
-    ($ty:ident) => {
-        struct ModuleWrapper {
-            module: Box<dyn $crate::qagame::Module>,
-        }
-
-        use $crate::NativeVM;
-        impl $crate::NativeVM for ModuleWrapper {
-            fn dll_entry(syscall: $crate::Syscall) -> Box<Self> {
-                Box::new(ModuleWrapper {
-                    module: $ty::dll_entry($crate::qagame::Syscalls::new(syscall)),
-                })
+    (@wrapper $ty:ty, $builtins:literal, $crash_report:literal, [$($on_panic:path)?] $(, $($exports:tt)+)?) => {
+        // Anonymous const, so the wrapper can't clash with names in the caller's module
+        const _: () = {
+            struct ModuleWrapper {
+                syscalls: $crate::qagame::Syscalls,
+                commands: $crate::qagame::command::Commands,
+                module: ::std::boxed::Box<dyn $crate::qagame::Module>,
             }
 
-            fn vm_main(
-                &self,
-                command: $crate::ffi::c_int,
-                arg0: $crate::ffi::c_int,
-                arg1: $crate::ffi::c_int,
-                arg2: $crate::ffi::c_int,
-                _arg3: $crate::ffi::c_int,
-                _arg4: $crate::ffi::c_int,
-                _arg5: $crate::ffi::c_int,
-                _arg6: $crate::ffi::c_int,
-                _arg7: $crate::ffi::c_int,
-                _arg8: $crate::ffi::c_int,
-                _arg9: $crate::ffi::c_int,
-                _arg10: $crate::ffi::c_int,
-                _arg11: $crate::ffi::c_int,
-            ) -> $crate::ffi::intptr_t {
-                use std::convert::TryFrom;
-
-                match $crate::qagame::Exports::try_from(command) {
-                    Ok($crate::qagame::Exports::GAME_INIT) => {
-                        self.module.init(arg0, arg1, arg2 != 0);
-                        0
-                    }
-                    Ok($crate::qagame::Exports::GAME_SHUTDOWN) => {
-                        self.module.shutdown(arg0 != 0);
-                        0
-                    }
-                    Ok($crate::qagame::Exports::GAME_CLIENT_CONNECT) => {
-                        self.module.client_connect(arg0, arg1 != 0, arg2 != 0)
-                    }
-                    Ok($crate::qagame::Exports::GAME_CLIENT_THINK) => {
-                        self.module.client_think(arg0);
-                        0
-                    }
-                    Ok($crate::qagame::Exports::GAME_CLIENT_USERINFO_CHANGED) => {
-                        self.module.client_userinfo_changed(arg0);
-                        0
-                    }
-                    Ok($crate::qagame::Exports::GAME_CLIENT_DISCONNECT) => {
-                        self.module.client_disconnect(arg0);
-                        0
-                    }
-                    Ok($crate::qagame::Exports::GAME_CLIENT_BEGIN) => {
-                        self.module.client_begin(arg0);
-                        0
-                    }
-                    Ok($crate::qagame::Exports::GAME_CLIENT_COMMAND) => {
-                        self.module.client_command(arg0);
-                        0
-                    }
-                    Ok($crate::qagame::Exports::GAME_RUN_FRAME) => {
-                        self.module.run_frame(arg0);
-                        0
-                    }
-                    Ok($crate::qagame::Exports::GAME_CONSOLE_COMMAND) => {
-                        if self.module.console_command() {
-                            1
-                        } else {
-                            0
-                        }
+            const ON_PANIC: ::std::option::Option<$crate::PanicHandler> =
+                $crate::native_vm!(@on_panic $($on_panic)?);
+
+            impl $crate::NativeVM for ModuleWrapper {
+                fn dll_entry(syscall: $crate::Syscall) -> ::std::boxed::Box<Self> {
+                    let syscalls = $crate::qagame::Syscalls::new(syscall);
+                    if $crash_report {
+                        $crate::qagame::crash::install();
                     }
-                    Ok($crate::qagame::Exports::BOTAI_START_FRAME) => {
-                        if self.module.botai_start_frame(arg0) {
-                            1
+                    let module = $crate::qagame::crash::guard(
+                        &syscalls,
+                        ON_PANIC,
+                        ::std::option::Option::None,
+                        || <$ty as $crate::qagame::Module>::dll_entry(syscalls),
+                    );
+                    ::std::boxed::Box::new(ModuleWrapper {
+                        syscalls,
+                        commands: if $builtins {
+                            $crate::qagame::command::builtins()
                         } else {
-                            0
-                        }
-                    }
-                    Ok(command) => todo!("Game command {:?} not implemented", command),
+                            $crate::qagame::command::Commands::new()
+                        },
+                        module,
+                    })
+                }
+
+                fn vm_main(
+                    &self,
+                    command: $crate::ffi::c_int,
+                    arg0: $crate::ffi::c_int,
+                    arg1: $crate::ffi::c_int,
+                    arg2: $crate::ffi::c_int,
+                    arg3: $crate::ffi::c_int,
+                    arg4: $crate::ffi::c_int,
+                    arg5: $crate::ffi::c_int,
+                    arg6: $crate::ffi::c_int,
+                    arg7: $crate::ffi::c_int,
+                    arg8: $crate::ffi::c_int,
+                    arg9: $crate::ffi::c_int,
+                    arg10: $crate::ffi::c_int,
+                    arg11: $crate::ffi::c_int,
+                ) -> $crate::ffi::intptr_t {
+                    $crate::qagame::crash::guard(
+                        &self.syscalls,
+                        ON_PANIC,
+                        ::std::option::Option::Some(command),
+                        || {
+                            if command == $crate::qagame::Exports::GAME_INIT as $crate::ffi::c_int {
+                                self.syscalls.probe_engine();
+                            }
+                            if $builtins
+                                && command == $crate::qagame::Exports::GAME_CONSOLE_COMMAND as $crate::ffi::c_int
+                                && self.commands.dispatch_current(&self.syscalls)
+                            {
+                                return 1;
+                            }
 
-                    Err(command) => panic!("Unknown game command {:?}", command),
+                            $crate::qagame::dispatch_watched(
+                                &self.syscalls,
+                                &*self.module,
+                                command,
+                                [
+                                    arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8, arg9, arg10, arg11,
+                                ],
+                            )
+                            .into_raw()
+                        },
+                    )
                 }
             }
-        }
 
-        $crate::native_vm!(ModuleWrapper);
+            $crate::native_vm!(ModuleWrapper $(, $($exports)+)?);
+        };
+    };
+    (@options $ty:ty, $builtins:literal, crash_report, on_panic = $on_panic:path $(, $($exports:tt)+)?) => {
+        $crate::game_module!(@wrapper $ty, $builtins, true, [$on_panic] $(, $($exports)+)?);
+    };
+    (@options $ty:ty, $builtins:literal, crash_report $(, $($exports:tt)+)?) => {
+        $crate::game_module!(@wrapper $ty, $builtins, true, [] $(, $($exports)+)?);
+    };
+    (@options $ty:ty, $builtins:literal, on_panic = $on_panic:path $(, $($exports:tt)+)?) => {
+        $crate::game_module!(@wrapper $ty, $builtins, false, [$on_panic] $(, $($exports)+)?);
+    };
+    (@options $ty:ty, $builtins:literal $(, $($exports:tt)+)?) => {
+        $crate::game_module!(@wrapper $ty, $builtins, false, [] $(, $($exports)+)?);
+    };
+    ($ty:ty, builtins $(, $($options:tt)+)?) => {
+        $crate::game_module!(@options $ty, true $(, $($options)+)?);
+    };
+    ($ty:ty $(, $($options:tt)+)?) => {
+        $crate::game_module!(@options $ty, false $(, $($options)+)?);
     };
 }
@@ -0,0 +1,472 @@
+//! Admin commands with per-client permission levels
+//!
+//! Every client has a [`Level`](Level), `0` unless [`Permissions`](Permissions) grant it a higher one,
+//! either by the IP address the server put into its userinfo or after it ran `login <password>`.
+//! Commands are registered on an [`Admin`](Admin) with the level they require, and are run from
+//! the clients' commands or the server console, which has every level.
+//!
+//! Levels are never granted by userinfo keys the client sets itself, e.g. ioquake3's `cl_guid`:
+//! anyone who learns such a value can send it as well. Passwords are sent in clear text like `rcon`,
+//! so they are only as safe as the network between client and server.
+//!
+//! [`register_builtins`](Admin::register_builtins) adds the commands most servers need:
+//!
+//! * `login <password>`, available to every client
+//! * `admin_kick <client>`
+//! * `mute <client>` and `unmute <client>`, see [`Admin::is_muted`](Admin::is_muted)
+//! * `admin_map <name>`
+//!
+//! Kicking and changing the map are prefixed because the engine runs its own `kick` and `map`
+//! commands before the module gets to see them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::admin::Permissions;
+//!
+//! let mut permissions = Permissions::default();
+//! permissions.ips.insert("192.168.1.5".to_owned(), 1);
+//! permissions.passwords.insert("correct horse battery staple".to_owned(), 3);
+//!
+//! assert_eq!(permissions.level("\\name\\Visor\\ip\\192.168.1.5:27960"), 1);
+//! assert_eq!(permissions.level("\\name\\Grunt\\ip\\10.0.0.1:27960"), 0);
+//! // Client-set keys grant nothing
+//! assert_eq!(permissions.level("\\name\\Sarge\\cl_guid\\8E2A0D3F9C6B41D7A5E3B2C1D0F9E8A7"), 0);
+//! assert_eq!(permissions.login("correct horse battery staple"), Some(3));
+//! assert_eq!(permissions.login("hunter2"), None);
+//! ```
+//!
+//! Wired into a module:
+//!
+//! ```rust,no_run
+//! use quake3_native_vm::ffi;
+//! use quake3_native_vm::qagame::admin::{Admin, Invocation, Permissions};
+//! use quake3_native_vm::qagame::{CbufExec, Syscalls};
+//!
+//! # fn example(syscalls: &Syscalls, client_number: ffi::c_int) {
+//! // In `Module::dll_entry`
+//! let mut admin = Admin::new(Permissions::default());
+//! admin.register_builtins(1);
+//! admin.register("restart", 2, |call: &Invocation| {
+//!     call.reply("Restarting\n");
+//!     call.syscalls.send_console_command(CbufExec::EXEC_APPEND, "map_restart 0\n");
+//! });
+//!
+//! // In `Module::client_connect` and `Module::client_userinfo_changed`
+//! admin.client_userinfo_changed(syscalls, client_number);
+//!
+//! // In `Module::client_command`
+//! if !admin.client_command(syscalls, client_number) {
+//!     // Not an admin command, e.g. `say`, which should check `admin.is_muted(client_number)`
+//! }
+//!
+//! // In `Module::console_command`
+//! admin.console_command(syscalls);
+//!
+//! // In `Module::client_disconnect`
+//! admin.client_disconnect(client_number);
+//! # }
+//! ```
+
+use super::command::Commands;
+use super::filter::parse_client_addr;
+use super::{CbufExec, Syscalls, Target};
+use crate::info::InfoString;
+use crate::shared::MAX_CLIENTS;
+use crate::{ffi, EngineSyscalls, Syscall};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Permission level of a client, higher levels may do more
+pub type Level = u32;
+
+/// Level of the server console, which may run every command
+pub const CONSOLE_LEVEL: Level = Level::MAX;
+
+/// Who runs an admin command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Caller {
+    /// Server console or `rcon`
+    Console,
+    /// A client with this number
+    Client(ffi::c_int),
+}
+
+/// Levels granted to clients, by IP address or password
+///
+/// With the `serde` feature, this can be read from a [`config`](super::config) file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Permissions {
+    /// Levels by IP address without port, e.g. `192.168.1.5` or `::1`
+    ///
+    /// The engine sets the `ip` userinfo key itself, so clients can't fake it.
+    pub ips: BTreeMap<String, Level>,
+    /// Levels by the password of the `login` command
+    pub passwords: BTreeMap<String, Level>,
+}
+
+impl Permissions {
+    /// Level granted to a client with `userinfo` by its IP address, `0` if none
+    ///
+    /// See `SV_UserinfoChanged` in [ioquake3's `server/sv_client.c`](https://github.com/ioquake/ioq3/blob/master/code/server/sv_client.c).
+    pub fn level(&self, userinfo: &str) -> Level {
+        let Ok(userinfo) = InfoString::parse(userinfo) else {
+            return 0;
+        };
+        userinfo
+            .get("ip")
+            .and_then(parse_client_addr)
+            .and_then(|addr| self.ips.get(&addr.to_string()).copied())
+            .unwrap_or(0)
+    }
+
+    /// Level granted by `password`, `None` if it is wrong
+    pub fn login(&self, password: &str) -> Option<Level> {
+        if password.is_empty() {
+            return None;
+        }
+        self.passwords.get(password).copied()
+    }
+}
+
+/// Context of an admin command handler
+#[derive(Clone)]
+pub struct Invocation<S = Syscall> {
+    /// Traps, e.g. for [`Syscalls::argv`](super::Syscalls::argv) with the command name as `argv(0)`
+    pub syscalls: Syscalls<S>,
+    /// Who runs the command
+    pub caller: Caller,
+    /// Level of the caller, at least the one required for the command
+    pub level: Level,
+    /// Levels and mutes of all clients
+    pub state: Arc<AdminState>,
+}
+
+impl<S: EngineSyscalls> Invocation<S> {
+    /// Print `text` to the console of the caller
+    pub fn reply(&self, text: &str) {
+        match self.caller {
+            Caller::Console => self.syscalls.print(text),
+            Caller::Client(client_number) => self
+                .syscalls
+                .console_print(Target::Client(client_number), text),
+        }
+    }
+
+    /// Whether the caller may use commands on `client_number`, i.e. has a higher level than it
+    ///
+    /// Clients may also target themselves.
+    pub fn may_target(&self, client_number: ffi::c_int) -> bool {
+        self.caller == Caller::Client(client_number) || self.level > self.state.level(client_number)
+    }
+}
+
+impl<S> fmt::Debug for Invocation<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Invocation")
+            .field("caller", &self.caller)
+            .field("level", &self.level)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Levels and mutes of the clients, shared with the command handlers
+#[derive(Debug)]
+pub struct AdminState {
+    levels: Mutex<Vec<Level>>,
+    /// Levels from `login`, kept across userinfo changes
+    logins: Mutex<Vec<Level>>,
+    muted: Mutex<Vec<bool>>,
+}
+
+impl AdminState {
+    fn new() -> Self {
+        Self {
+            levels: Mutex::new(vec![0; MAX_CLIENTS]),
+            logins: Mutex::new(vec![0; MAX_CLIENTS]),
+            muted: Mutex::new(vec![false; MAX_CLIENTS]),
+        }
+    }
+
+    /// Level of `client_number`, `0` if it has none or is not a valid client number
+    pub fn level(&self, client_number: ffi::c_int) -> Level {
+        index(client_number).map_or(0, |i| self.levels.lock().unwrap()[i])
+    }
+
+    /// Whether `client_number` must not chat
+    pub fn is_muted(&self, client_number: ffi::c_int) -> bool {
+        index(client_number).map_or(false, |i| self.muted.lock().unwrap()[i])
+    }
+
+    /// Mute or unmute `client_number`, returning whether it was muted before
+    pub fn set_muted(&self, client_number: ffi::c_int, muted: bool) -> bool {
+        index(client_number).map_or(false, |i| {
+            std::mem::replace(&mut self.muted.lock().unwrap()[i], muted)
+        })
+    }
+
+    /// Set the level of `client_number` from its IP address, or its login if that is higher
+    fn set_level(&self, client_number: ffi::c_int, level: Level) {
+        if let Some(i) = index(client_number) {
+            let login = self.logins.lock().unwrap()[i];
+            self.levels.lock().unwrap()[i] = level.max(login);
+        }
+    }
+
+    fn set_login(&self, client_number: ffi::c_int, level: Level) {
+        if let Some(i) = index(client_number) {
+            self.logins.lock().unwrap()[i] = level;
+            let mut levels = self.levels.lock().unwrap();
+            levels[i] = levels[i].max(level);
+        }
+    }
+
+    fn reset(&self, client_number: ffi::c_int) {
+        if let Some(i) = index(client_number) {
+            self.levels.lock().unwrap()[i] = 0;
+            self.logins.lock().unwrap()[i] = 0;
+            self.muted.lock().unwrap()[i] = false;
+        }
+    }
+}
+
+fn index(client_number: ffi::c_int) -> Option<usize> {
+    usize::try_from(client_number)
+        .ok()
+        .filter(|&i| i < MAX_CLIENTS)
+}
+
+/// Admin commands by name, with the level each requires
+pub struct Admin<S = Syscall> {
+    commands: Commands<Invocation<S>>,
+    required: BTreeMap<String, Level>,
+    permissions: Arc<Mutex<Permissions>>,
+    state: Arc<AdminState>,
+}
+
+impl<S: EngineSyscalls + Copy + 'static> Admin<S> {
+    /// No commands yet, with `permissions` for the clients
+    pub fn new(permissions: Permissions) -> Self {
+        Self {
+            commands: Commands::new(),
+            required: BTreeMap::new(),
+            permissions: Arc::new(Mutex::new(permissions)),
+            state: Arc::new(AdminState::new()),
+        }
+    }
+
+    /// Run `handler` for the command `name` if the caller has at least `level`, replacing any previous handler
+    pub fn register(
+        &mut self,
+        name: &str,
+        level: Level,
+        handler: impl Fn(&Invocation<S>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.commands.register(name, handler);
+        self.required.insert(name.to_ascii_lowercase(), level);
+        self
+    }
+
+    /// Change the level required for `name`, returning whether there is such a command
+    pub fn require(&mut self, name: &str, level: Level) -> bool {
+        match self.required.get_mut(&name.to_ascii_lowercase()) {
+            Some(required) => {
+                *required = level;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Level required for `name`, `None` if there is no such command
+    pub fn required_level(&self, name: &str) -> Option<Level> {
+        self.required.get(&name.to_ascii_lowercase()).copied()
+    }
+
+    /// Add the `admin_kick`, `mute`, `unmute` and `admin_map` commands, requiring `level`, and `login` for everyone
+    pub fn register_builtins(&mut self, level: Level) -> &mut Self {
+        let permissions = Arc::clone(&self.permissions);
+        self.register("login", 0, move |call: &Invocation<S>| {
+            login(call, &permissions.lock().unwrap())
+        });
+        self.register("admin_kick", level, |call: &Invocation<S>| {
+            if let Some(client_number) = target_client(call, "admin_kick") {
+                call.syscalls.send_console_command(
+                    CbufExec::EXEC_APPEND,
+                    format!("clientkick {}\n", client_number),
+                );
+            }
+        });
+        self.register("mute", level, |call: &Invocation<S>| {
+            if let Some(client_number) = target_client(call, "mute") {
+                call.state.set_muted(client_number, true);
+                call.reply(&format!("Muted client {}.\n", client_number));
+            }
+        });
+        self.register("unmute", level, |call: &Invocation<S>| {
+            if let Some(client_number) = target_client(call, "unmute") {
+                call.state.set_muted(client_number, false);
+                call.reply(&format!("Unmuted client {}.\n", client_number));
+            }
+        });
+        self.register("admin_map", level, map);
+        self
+    }
+
+    /// Levels and mutes of the clients
+    pub fn state(&self) -> &AdminState {
+        &self.state
+    }
+
+    /// Level of `client_number`, as of its last [`client_userinfo_changed`](Admin::client_userinfo_changed) or `login`
+    pub fn level(&self, client_number: ffi::c_int) -> Level {
+        self.state.level(client_number)
+    }
+
+    /// Whether `client_number` was muted with the `mute` command, so its chat has to be ignored
+    pub fn is_muted(&self, client_number: ffi::c_int) -> bool {
+        self.state.is_muted(client_number)
+    }
+
+    /// Levels granted to clients
+    pub fn permissions(&self) -> Permissions {
+        self.permissions.lock().unwrap().clone()
+    }
+
+    /// Replace the levels granted to clients, taking effect on their next userinfo change
+    pub fn set_permissions(&self, permissions: Permissions) {
+        *self.permissions.lock().unwrap() = permissions;
+    }
+
+    /// Update the level of `client_number` from its userinfo
+    ///
+    /// Call this from [`Module::client_connect`](super::Module::client_connect)
+    /// and [`Module::client_userinfo_changed`](super::Module::client_userinfo_changed).
+    pub fn client_userinfo_changed(&self, syscalls: &Syscalls<S>, client_number: ffi::c_int) {
+        let level = self
+            .permissions
+            .lock()
+            .unwrap()
+            .level(&syscalls.get_userinfo(client_number));
+        self.state.set_level(client_number, level);
+    }
+
+    /// Forget the level, login and mute of `client_number`, so they don't carry over to the next client in its slot
+    ///
+    /// Call this from [`Module::client_disconnect`](super::Module::client_disconnect).
+    pub fn client_disconnect(&self, client_number: ffi::c_int) {
+        self.state.reset(client_number);
+    }
+
+    /// Run the admin command of `client_number`, returning whether it was one
+    ///
+    /// Clients without the required level are told so.
+    ///
+    /// Call this from [`Module::client_command`](super::Module::client_command).
+    pub fn client_command(&self, syscalls: &Syscalls<S>, client_number: ffi::c_int) -> bool {
+        let name = syscalls.argv(0);
+        let Some(required) = self.required_level(&name) else {
+            return false;
+        };
+        let call = Invocation {
+            syscalls: *syscalls,
+            caller: Caller::Client(client_number),
+            level: self.level(client_number),
+            state: Arc::clone(&self.state),
+        };
+        if call.level < required {
+            call.reply(&format!("You are not allowed to use {}.\n", name));
+            return true;
+        }
+        self.commands.dispatch(&name, &call)
+    }
+
+    /// Run the admin command of the server console, returning whether it was one
+    ///
+    /// Call this from [`Module::console_command`](super::Module::console_command).
+    pub fn console_command(&self, syscalls: &Syscalls<S>) -> bool {
+        let call = Invocation {
+            syscalls: *syscalls,
+            caller: Caller::Console,
+            level: CONSOLE_LEVEL,
+            state: Arc::clone(&self.state),
+        };
+        self.commands.dispatch(&syscalls.argv(0), &call)
+    }
+}
+
+impl<S> fmt::Debug for Admin<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Admin")
+            .field("required", &self.required)
+            .field("permissions", &*self.permissions.lock().unwrap())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Client number from the first argument of `name` that the caller may target
+fn target_client<S: EngineSyscalls>(call: &Invocation<S>, name: &str) -> Option<ffi::c_int> {
+    if call.syscalls.argc() < 2 {
+        call.reply(&format!("Usage: {} <client>\n", name));
+        return None;
+    }
+    let arg = call.syscalls.argv(1);
+    let Some(client_number) = arg.parse().ok().filter(|&n| index(n).is_some()) else {
+        call.reply(&format!("Bad client number: {}\n", arg));
+        return None;
+    };
+    if !call.may_target(client_number) {
+        call.reply(&format!(
+            "You can't {} client {}.\n",
+            name.trim_start_matches("admin_"),
+            client_number
+        ));
+        return None;
+    }
+    Some(client_number)
+}
+
+/// Grant the level of the password given as argument to the calling client
+fn login<S: EngineSyscalls>(call: &Invocation<S>, permissions: &Permissions) {
+    let Caller::Client(client_number) = call.caller else {
+        call.reply("The console has every level.\n");
+        return;
+    };
+    if call.syscalls.argc() != 2 {
+        call.reply("Usage: login <password>\n");
+        return;
+    }
+    match permissions.login(&call.syscalls.argv(1)) {
+        Some(level) => {
+            call.state.set_login(client_number, level);
+            call.reply(&format!(
+                "Logged in with level {}.\n",
+                call.state.level(client_number)
+            ));
+        }
+        None => call.reply("Wrong password.\n"),
+    }
+}
+
+/// Change to the map given as argument
+fn map<S: EngineSyscalls>(call: &Invocation<S>) {
+    if call.syscalls.argc() < 2 {
+        call.reply("Usage: admin_map <name>\n");
+        return;
+    }
+    let name = call.syscalls.argv(1);
+    // Anything else could add commands to the engine's command buffer
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+    if !valid {
+        call.reply(&format!("Bad map name: {}\n", name));
+        return;
+    }
+    call.syscalls
+        .send_console_command(CbufExec::EXEC_APPEND, format!("map {}\n", name));
+}
@@ -0,0 +1,187 @@
+//! Minimal game to build upon
+//!
+//! Requires the `base` feature.
+//!
+//! A [`Module`](super::Module) has to do a few things before the engine lets clients play,
+//! e.g. tell it where the entities are, or clients crash in `ClientBegin`.
+//! [`BaseGame`](BaseGame) does just that and nothing more: clients connect and spectate an empty level.
+//!
+//! Mods can keep a `BaseGame` and delegate to it, overriding only what they need.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use quake3_native_vm::ffi;
+//! use quake3_native_vm::qagame::base::BaseGame;
+//! use quake3_native_vm::qagame::{Deny, Module, Syscalls};
+//!
+//! struct MyGame {
+//!     base: BaseGame,
+//! }
+//!
+//! impl Module for MyGame {
+//!     fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+//!         Box::new(MyGame {
+//!             base: BaseGame::new(syscalls),
+//!         })
+//!     }
+//!
+//!     fn init(&self, level_time: ffi::c_int, random_seed: ffi::c_int, restart: bool) {
+//!         self.base.init(level_time, random_seed, restart);
+//!         self.base.syscalls().print("MyGame ready\n");
+//!     }
+//!
+//!     // Everything else goes to `self.base`
+//! #   fn shutdown(&self, restart: bool) { self.base.shutdown(restart) }
+//! #   fn client_connect(&self, n: ffi::c_int, first_time: bool, is_bot: bool) -> Result<(), Deny> { self.base.client_connect(n, first_time, is_bot) }
+//! #   fn client_think(&self, n: ffi::c_int) { self.base.client_think(n) }
+//! #   fn client_userinfo_changed(&self, n: ffi::c_int) { self.base.client_userinfo_changed(n) }
+//! #   fn client_disconnect(&self, n: ffi::c_int) { self.base.client_disconnect(n) }
+//! #   fn client_begin(&self, n: ffi::c_int) { self.base.client_begin(n) }
+//! #   fn client_command(&self, n: ffi::c_int) { self.base.client_command(n) }
+//! #   fn run_frame(&self, level_time: ffi::c_int) { self.base.run_frame(level_time) }
+//! #   fn console_command(&self) -> bool { self.base.console_command() }
+//! #   fn botai_start_frame(&self, level_time: ffi::c_int) -> bool { self.base.botai_start_frame(level_time) }
+//! }
+//! ```
+
+use super::entity::{SharedEntity, MAX_GENTITIES};
+use super::{Deny, Module, Syscalls};
+use crate::bg::{PmType, CS_GAME_VERSION, CS_LEVEL_START_TIME, GAME_VERSION};
+use crate::ffi;
+use crate::shared::{PlayerState, MAX_CLIENTS};
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+
+/// Entities and clients shared with the engine
+///
+/// Allocated once, since the engine keeps pointers to them.
+#[derive(Debug)]
+pub struct Level {
+    /// All entities, the first [`MAX_CLIENTS`](crate::shared::MAX_CLIENTS) are the clients
+    pub entities: Vec<SharedEntity>,
+    /// All clients
+    pub clients: Vec<PlayerState>,
+    /// Time of the current frame, in milliseconds
+    pub time: ffi::c_int,
+    /// Time the level started at
+    pub start_time: ffi::c_int,
+}
+
+/// [`Module`](super::Module) that keeps a server running with connected clients, but has no gameplay
+pub struct BaseGame {
+    syscalls: Syscalls,
+    level: Mutex<Level>,
+}
+
+impl BaseGame {
+    /// Create the game, to be initialized by the engine with [`init`](BaseGame::init)
+    pub fn new(syscalls: Syscalls) -> Self {
+        Self {
+            syscalls,
+            level: Mutex::new(Level {
+                entities: vec![SharedEntity::default(); MAX_GENTITIES],
+                clients: vec![PlayerState::default(); MAX_CLIENTS],
+                time: 0,
+                start_time: 0,
+            }),
+        }
+    }
+
+    /// Syscalls of the engine
+    pub fn syscalls(&self) -> &Syscalls {
+        &self.syscalls
+    }
+
+    /// Lock the entities and clients shared with the engine
+    pub fn level(&self) -> MutexGuard<'_, Level> {
+        self.level.lock().unwrap()
+    }
+}
+
+impl Module for BaseGame {
+    fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+        Box::new(Self::new(syscalls))
+    }
+
+    fn init(&self, level_time: ffi::c_int, _random_seed: ffi::c_int, _restart: bool) {
+        let mut level = self.level();
+        let level = &mut *level;
+        level.time = level_time;
+        level.start_time = level_time;
+        level.entities.fill(SharedEntity::default());
+        level.clients.fill(PlayerState::default());
+
+        self.syscalls
+            .set_configstring(CS_GAME_VERSION, GAME_VERSION);
+        self.syscalls
+            .set_configstring(CS_LEVEL_START_TIME, level_time.to_string());
+        // Entities up to the clients are reserved, like `level.num_entities` in the C game
+        self.syscalls.locate_game_data(
+            &mut level.entities,
+            MAX_CLIENTS as ffi::c_int,
+            &mut level.clients,
+        );
+    }
+
+    fn shutdown(&self, _restart: bool) {}
+
+    fn client_connect(
+        &self,
+        _client_number: ffi::c_int,
+        _first_time: bool,
+        _is_bot: bool,
+    ) -> Result<(), Deny> {
+        // Accept everyone
+        Ok(())
+    }
+
+    fn client_think(&self, client_number: ffi::c_int) {
+        let cmd = self.syscalls.get_usercmd(client_number);
+        // Acknowledge the command, or the client keeps predicting from an old state
+        self.level().clients[client_number as usize].command_time = cmd.server_time;
+    }
+
+    fn client_userinfo_changed(&self, _client_number: ffi::c_int) {}
+
+    fn client_disconnect(&self, client_number: ffi::c_int) {
+        let mut level = self.level();
+        level.entities[client_number as usize] = SharedEntity::default();
+        level.clients[client_number as usize] = PlayerState::default();
+    }
+
+    fn client_begin(&self, client_number: ffi::c_int) {
+        let mut level = self.level();
+        let time = level.time;
+
+        let ps = &mut level.clients[client_number as usize];
+        *ps = PlayerState::default();
+        ps.command_time = time;
+        ps.client_num = client_number;
+        ps.pm_type = PmType::PM_SPECTATOR as ffi::c_int;
+
+        let entity = &mut level.entities[client_number as usize];
+        *entity = SharedEntity::default();
+        entity.s.number = client_number;
+    }
+
+    fn client_command(&self, _client_number: ffi::c_int) {}
+
+    fn run_frame(&self, level_time: ffi::c_int) {
+        self.level().time = level_time;
+    }
+
+    fn console_command(&self) -> bool {
+        false
+    }
+
+    fn botai_start_frame(&self, _level_time: ffi::c_int) -> bool {
+        false
+    }
+}
+
+impl fmt::Debug for BaseGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BaseGame").finish_non_exhaustive()
+    }
+}
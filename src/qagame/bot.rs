@@ -0,0 +1,545 @@
+//! Bot and arena definitions, and the client slots of bots
+//!
+//! The game finds its bots in `scripts/bots.txt` and `scripts/*.bot`, and its maps in
+//! `scripts/arenas.txt` and `scripts/*.arena`. Both are lists of info blocks like
+//!
+//! ```text
+//! {
+//! name        Sarge
+//! funname     ^1Sarge
+//! model       sarge
+//! aifile      bots/sarge_c.c
+//! }
+//! ```
+//!
+//! The `aifile` is a bot character file with the characteristics of the bot per skill level.
+//!
+//! See `G_LoadBots` and `G_LoadArenas` in [ioquake3's `game/g_bot.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_bot.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::bot::{parse_infos, ArenaInfo, BotInfo};
+//!
+//! let bots = parse_infos(
+//!     r#"
+//!     // Sarge
+//!     {
+//!     name    Sarge
+//!     model   sarge
+//!     aifile  bots/sarge_c.c
+//!     }
+//!     "#,
+//! )
+//! .unwrap();
+//! let sarge = BotInfo::from_info(bots[0].clone()).unwrap();
+//! assert_eq!(sarge.name, "Sarge");
+//! assert_eq!(sarge.aifile.as_deref(), Some("bots/sarge_c.c"));
+//!
+//! let arenas = parse_infos(r#"{ map "q3dm1" bots "sarge doom" fraglimit "15" type "single ffa" }"#).unwrap();
+//! let arena = ArenaInfo::from_info(arenas[0].clone()).unwrap();
+//! assert_eq!(arena.map, "q3dm1");
+//! assert_eq!(arena.bots, ["sarge", "doom"]);
+//! assert_eq!(arena.fraglimit, Some(15));
+//! assert!(arena.supports("ffa"));
+//! ```
+//!
+//! Bots play in client slots like players, see [`BotClients`](BotClients).
+
+use super::{fs, Syscalls};
+use crate::parse::Tokenizer;
+use crate::shared::UserCmd;
+use crate::{ffi, EngineSyscalls};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Mutex;
+
+/// Key-value pairs of one info block, with lowercase keys
+pub type Info = BTreeMap<String, String>;
+
+/// Error for invalid bot, arena or bot character files
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// File ended within a block
+    UnexpectedEnd,
+    /// Found another token than expected
+    Expected {
+        /// What was expected, e.g. `{`
+        expected: &'static str,
+        /// What was found instead
+        found: String,
+    },
+    /// Required key is missing from an info block
+    MissingKey(&'static str),
+    /// Value is not a valid number
+    InvalidNumber(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of file"),
+            Self::Expected { expected, found } => {
+                write!(f, "expected {} but found {:?}", expected, found)
+            }
+            Self::MissingKey(key) => write!(f, "missing key {:?}", key),
+            Self::InvalidNumber(value) => write!(f, "invalid number {:?}", value),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+fn expect(tokens: &mut Tokenizer<'_>, expected: &'static str) -> Result<(), ParseError> {
+    match tokens.parse_ext(true) {
+        Some(token) if token == expected => Ok(()),
+        Some(found) => Err(ParseError::Expected { expected, found }),
+        None => Err(ParseError::UnexpectedEnd),
+    }
+}
+
+/// Parse all info blocks of a `.bot` or `.arena` file
+///
+/// Every key is followed by its value on the same line, which is `<NULL>` if missing, like in the engine.
+///
+/// See `G_ParseInfos` in [ioquake3's `game/g_bot.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_bot.c).
+pub fn parse_infos(text: &str) -> Result<Vec<Info>, ParseError> {
+    let mut tokens = Tokenizer::new(text);
+    let mut infos = Vec::new();
+    while let Some(token) = tokens.parse_ext(true) {
+        if token != "{" {
+            return Err(ParseError::Expected {
+                expected: "{",
+                found: token,
+            });
+        }
+
+        let mut info = Info::new();
+        loop {
+            let key = tokens.parse_ext(true).ok_or(ParseError::UnexpectedEnd)?;
+            if key == "}" {
+                break;
+            }
+            let value = tokens
+                .parse_ext(false)
+                .unwrap_or_else(|| "<NULL>".to_owned());
+            info.insert(key.to_ascii_lowercase(), value);
+        }
+        infos.push(info);
+    }
+    Ok(infos)
+}
+
+fn parse_number<T: std::str::FromStr>(info: &Info, key: &str) -> Result<Option<T>, ParseError> {
+    info.get(key)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| ParseError::InvalidNumber(value.clone()))
+        })
+        .transpose()
+}
+
+/// Bot from a `.bot` file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BotInfo {
+    /// Name to add the bot with, e.g. `addbot sarge`
+    pub name: String,
+    /// Name shown to players, with color codes
+    pub funname: Option<String>,
+    /// Player model and skin, e.g. `sarge/red`
+    pub model: Option<String>,
+    /// Bot character file
+    pub aifile: Option<String>,
+    /// All keys of the info block
+    pub info: Info,
+}
+
+impl BotInfo {
+    /// Bot from its info block, which needs at least a `name`
+    pub fn from_info(info: Info) -> Result<Self, ParseError> {
+        Ok(Self {
+            name: info
+                .get("name")
+                .cloned()
+                .ok_or(ParseError::MissingKey("name"))?,
+            funname: info.get("funname").cloned(),
+            model: info.get("model").cloned(),
+            aifile: info.get("aifile").cloned(),
+            info,
+        })
+    }
+}
+
+/// Map from an `.arena` file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArenaInfo {
+    /// Map name, e.g. `q3dm1`
+    pub map: String,
+    /// Name shown to players
+    pub longname: Option<String>,
+    /// Bots for single player, by name
+    pub bots: Vec<String>,
+    /// Frag limit for single player
+    pub fraglimit: Option<i32>,
+    /// Time limit for single player, in minutes
+    pub timelimit: Option<i32>,
+    /// Game types the map supports, e.g. `ffa` or `ctf`
+    pub types: Vec<String>,
+    /// All keys of the info block
+    pub info: Info,
+}
+
+impl ArenaInfo {
+    /// Arena from its info block, which needs at least a `map`
+    pub fn from_info(info: Info) -> Result<Self, ParseError> {
+        let words = |key| {
+            info.get(key)
+                .map(|value: &String| value.split_whitespace().map(str::to_owned).collect())
+                .unwrap_or_default()
+        };
+        Ok(Self {
+            map: info
+                .get("map")
+                .cloned()
+                .ok_or(ParseError::MissingKey("map"))?,
+            longname: info.get("longname").cloned(),
+            bots: words("bots"),
+            fraglimit: parse_number(&info, "fraglimit")?,
+            timelimit: parse_number(&info, "timelimit")?,
+            types: words("type"),
+            info,
+        })
+    }
+
+    /// Whether the map supports the game type `game_type`, e.g. `ffa`
+    pub fn supports(&self, game_type: &str) -> bool {
+        self.types.iter().any(|t| t.eq_ignore_ascii_case(game_type))
+    }
+}
+
+/// Value of a bot characteristic
+#[derive(Clone, Debug, PartialEq)]
+pub enum Characteristic {
+    /// Whole number, e.g. a weapon number
+    Integer(i32),
+    /// Number with a fraction, usually between `0` and `1`, e.g. `CHARACTERISTIC_AIM_ACCURACY`
+    Float(f32),
+    /// Quoted string, e.g. `CHARACTERISTIC_NAME`
+    String(String),
+}
+
+/// Characteristics of one skill level, by name or number
+pub type Characteristics = BTreeMap<String, Characteristic>;
+
+/// Bot character file, e.g. `bots/sarge_c.c`
+///
+/// Characteristics are identified by the token in the file, i.e. their name like `CHARACTERISTIC_NAME`,
+/// or their number if the file was already preprocessed. Preprocessor lines like `#include "chars.h"` are skipped.
+///
+/// See `BotLoadCharacterFromFile` in [ioquake3's `botlib/be_ai_char.c`](https://github.com/ioquake/ioq3/blob/master/code/botlib/be_ai_char.c).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::qagame::bot::{BotCharacter, Characteristic};
+///
+/// let sarge = BotCharacter::parse(
+///     r#"
+///     #include "chars.h"
+///     skill 1
+///     {
+///         CHARACTERISTIC_NAME "Sarge"
+///         CHARACTERISTIC_AIM_ACCURACY 0.2
+///         CHARACTERISTIC_WEAPONJUMPING 1
+///     }
+///     skill 4 { CHARACTERISTIC_AIM_ACCURACY 0.7 }
+///     "#,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(sarge.skills().len(), 2);
+/// assert_eq!(
+///     sarge.get(2.0, "CHARACTERISTIC_AIM_ACCURACY"),
+///     Some(&Characteristic::Float(0.2))
+/// );
+/// assert_eq!(
+///     sarge.get(5.0, "CHARACTERISTIC_AIM_ACCURACY"),
+///     Some(&Characteristic::Float(0.7))
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BotCharacter {
+    skills: Vec<(f32, Characteristics)>,
+}
+
+impl BotCharacter {
+    /// Parse the `skill <level> { <characteristic> <value> ... }` blocks of a bot character file
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut tokens = Tokenizer::new(text);
+        let mut skills = Vec::new();
+        while let Some(token) = tokens.parse_ext(true) {
+            if token.starts_with('#') {
+                tokens.skip_rest_of_line();
+                continue;
+            }
+            if token != "skill" {
+                return Err(ParseError::Expected {
+                    expected: "skill",
+                    found: token,
+                });
+            }
+
+            let level = tokens.parse_ext(true).ok_or(ParseError::UnexpectedEnd)?;
+            let level = level
+                .parse::<f32>()
+                .map_err(|_| ParseError::InvalidNumber(level))?;
+            expect(&mut tokens, "{")?;
+            let mut characteristics = Characteristics::new();
+            loop {
+                let key = tokens.parse_ext(true).ok_or(ParseError::UnexpectedEnd)?;
+                if key == "}" {
+                    break;
+                }
+                let value = Self::parse_value(&mut tokens)?;
+                characteristics.insert(key, value);
+            }
+            skills.push((level, characteristics));
+        }
+        skills.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Ok(Self { skills })
+    }
+
+    fn parse_value(tokens: &mut Tokenizer<'_>) -> Result<Characteristic, ParseError> {
+        if tokens.peek_quoted() {
+            return tokens
+                .parse_ext(true)
+                .map(Characteristic::String)
+                .ok_or(ParseError::UnexpectedEnd);
+        }
+        let value = tokens.parse_ext(true).ok_or(ParseError::UnexpectedEnd)?;
+        if let Ok(integer) = value.parse() {
+            Ok(Characteristic::Integer(integer))
+        } else if let Ok(float) = value.parse() {
+            Ok(Characteristic::Float(float))
+        } else {
+            Err(ParseError::InvalidNumber(value))
+        }
+    }
+
+    /// Skill levels and their characteristics, by ascending level
+    pub fn skills(&self) -> &[(f32, Characteristics)] {
+        &self.skills
+    }
+
+    /// Characteristics for `skill`, i.e. of the highest level not above it, or of the lowest level
+    pub fn characteristics(&self, skill: f32) -> Option<&Characteristics> {
+        self.skills
+            .iter()
+            .rev()
+            .find(|(level, _)| *level <= skill)
+            .or_else(|| self.skills.first())
+            .map(|(_, characteristics)| characteristics)
+    }
+
+    /// Characteristic `key` for `skill`, falling back to lower levels that define it
+    pub fn get(&self, skill: f32, key: &str) -> Option<&Characteristic> {
+        self.characteristics(skill)?.get(key).or_else(|| {
+            self.skills
+                .iter()
+                .rev()
+                .filter(|(level, _)| *level <= skill)
+                .find_map(|(_, characteristics)| characteristics.get(key))
+        })
+    }
+}
+
+fn read_to_string<S: EngineSyscalls>(syscalls: &Syscalls<S>, path: &str) -> io::Result<String> {
+    Ok(String::from_utf8_lossy(&fs::read(syscalls, path)?).into_owned())
+}
+
+fn invalid_data(path: &str, error: ParseError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, error))
+}
+
+fn load_infos<S: EngineSyscalls>(
+    syscalls: &Syscalls<S>,
+    file: &str,
+    extension: &str,
+) -> io::Result<Vec<Info>> {
+    let mut paths = vec![file.to_owned()];
+    paths.extend(
+        syscalls
+            .fs_get_file_list("scripts", extension)
+            .into_iter()
+            .map(|name| format!("scripts/{}", name)),
+    );
+
+    let mut infos = Vec::new();
+    for path in paths {
+        let text = match read_to_string(syscalls, &path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        infos.extend(parse_infos(&text).map_err(|e| invalid_data(&path, e))?);
+    }
+    Ok(infos)
+}
+
+/// Load all bots from `scripts/bots.txt` and `scripts/*.bot`
+pub fn load_bots<S: EngineSyscalls>(syscalls: &Syscalls<S>) -> io::Result<Vec<BotInfo>> {
+    load_infos(syscalls, "scripts/bots.txt", ".bot")?
+        .into_iter()
+        .map(|info| BotInfo::from_info(info).map_err(|e| invalid_data("bot", e)))
+        .collect()
+}
+
+/// Load all arenas from `scripts/arenas.txt` and `scripts/*.arena`
+pub fn load_arenas<S: EngineSyscalls>(syscalls: &Syscalls<S>) -> io::Result<Vec<ArenaInfo>> {
+    load_infos(syscalls, "scripts/arenas.txt", ".arena")?
+        .into_iter()
+        .map(|info| ArenaInfo::from_info(info).map_err(|e| invalid_data("arena", e)))
+        .collect()
+}
+
+/// Load the bot character file `path`, e.g. the [`BotInfo::aifile`](BotInfo::aifile) `bots/sarge_c.c`
+///
+/// Paths are relative to `botfiles/` like in the engine's bot library.
+pub fn load_character<S: EngineSyscalls>(
+    syscalls: &Syscalls<S>,
+    path: &str,
+) -> io::Result<BotCharacter> {
+    let path = format!("botfiles/{}", path);
+    BotCharacter::parse(&read_to_string(syscalls, &path)?).map_err(|e| invalid_data(&path, e))
+}
+
+/// Client slots reserved for bots
+///
+/// Every slot from [`allocate`](BotClients::allocate) is released again when its bot disconnects,
+/// which the engine frees itself, or with [`free`](BotClients::free) if the bot can't connect.
+///
+/// Bots stay connected across `map_restart` and map changes, where the engine shuts the module down,
+/// loads it again and calls [`Module::client_connect`](super::Module::client_connect) with `is_bot` for them.
+/// [`client_connect`](BotClients::client_connect) registers their slots again, so nothing has to be released
+/// in [`Module::shutdown`](super::Module::shutdown).
+///
+/// See `G_AddBot` in [ioquake3's `game/g_bot.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_bot.c).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::backend::{Mock, Recording};
+/// use quake3_native_vm::qagame::bot::BotClients;
+/// use quake3_native_vm::qagame::{Imports, Syscalls};
+///
+/// // The engine has slot 5 free
+/// let engine = Recording::new(Mock(|_, _: &[isize]| 5));
+/// let syscalls = Syscalls::with_engine(&engine);
+///
+/// let bots = BotClients::new();
+/// let client_number = bots.allocate(&syscalls).unwrap();
+/// assert!(bots.is_bot(client_number));
+///
+/// // In `Module::client_connect` of the next module instance, after a map change
+/// let bots = BotClients::new();
+/// assert!(bots.client_connect(client_number, true));
+/// assert!(!bots.client_connect(0, false));
+/// assert_eq!(bots.clients(), [client_number]);
+///
+/// // The bot couldn't connect after all
+/// assert!(bots.free(&syscalls, client_number));
+/// assert!(bots.is_empty());
+///
+/// let calls = engine.take();
+/// assert_eq!(calls[0].num, isize::from(Imports::G_BOT_ALLOCATE_CLIENT));
+/// assert_eq!(calls[1].num, isize::from(Imports::G_BOT_FREE_CLIENT));
+/// assert_eq!(calls[1].args, [5]);
+/// ```
+#[derive(Debug, Default)]
+pub struct BotClients {
+    clients: Mutex<BTreeSet<ffi::c_int>>,
+}
+
+impl BotClients {
+    /// No slots reserved yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserve a client slot for a bot, `None` if the server is full
+    ///
+    /// [`free`](BotClients::free) the slot if the bot can't connect.
+    pub fn allocate<S: EngineSyscalls>(&self, syscalls: &Syscalls<S>) -> Option<ffi::c_int> {
+        let client_number = syscalls.bot_allocate_client()?;
+        self.clients.lock().unwrap().insert(client_number);
+        Some(client_number)
+    }
+
+    /// Release the slot of the bot `client_number`, returning whether it was one
+    ///
+    /// Slots of players are left alone.
+    pub fn free<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        client_number: ffi::c_int,
+    ) -> bool {
+        let freed = self.clients.lock().unwrap().remove(&client_number);
+        if freed {
+            syscalls.bot_free_client(client_number);
+        }
+        freed
+    }
+
+    /// Register the slot of `client_number` if it is a bot, returning `is_bot`
+    ///
+    /// Call this from [`Module::client_connect`](super::Module::client_connect), so that bots which stayed
+    /// connected while the module was loaded again, e.g. on a map change, are tracked.
+    pub fn client_connect(&self, client_number: ffi::c_int, is_bot: bool) -> bool {
+        if is_bot {
+            self.clients.lock().unwrap().insert(client_number);
+        }
+        is_bot
+    }
+
+    /// Forget the slot of `client_number`, which the engine frees itself when a bot disconnects
+    ///
+    /// Call this from [`Module::client_disconnect`](super::Module::client_disconnect).
+    pub fn client_disconnect(&self, client_number: ffi::c_int) -> bool {
+        self.clients.lock().unwrap().remove(&client_number)
+    }
+
+    /// Whether `client_number` is the slot of a bot
+    pub fn is_bot(&self, client_number: ffi::c_int) -> bool {
+        self.clients.lock().unwrap().contains(&client_number)
+    }
+
+    /// Client numbers of all bots, ascending
+    pub fn clients(&self) -> Vec<ffi::c_int> {
+        self.clients.lock().unwrap().iter().copied().collect()
+    }
+
+    /// Number of bots
+    pub fn len(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /// Whether there are no bots
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Latest input of the bot `client_number`, as set by the bot AI, `None` if it is not a bot
+    ///
+    /// See `ClientThink` in [ioquake3's `game/g_active.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_active.c).
+    pub fn usercmd<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        client_number: ffi::c_int,
+    ) -> Option<UserCmd> {
+        self.is_bot(client_number)
+            .then(|| syscalls.get_usercmd(client_number))
+    }
+}
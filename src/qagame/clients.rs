@@ -0,0 +1,143 @@
+//! Per-client data with the lifetime of the client's connection
+//!
+//! Mods keep data for each connected client, e.g. its team or score. Keeping it in a `HashMap`
+//! by client number makes it easy to forget removing it on disconnect, so that the next client in the
+//! slot inherits it. A [`ClientStore`](ClientStore) has one slot per client number instead, filled in
+//! [`Module::client_connect`](super::Module::client_connect) and emptied in
+//! [`Module::client_disconnect`](super::Module::client_disconnect) and on shutdown.
+//!
+//! Slots are locked separately, so callbacks for different clients don't wait on each other.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::clients::ClientStore;
+//!
+//! #[derive(Debug, Default, PartialEq)]
+//! struct Player {
+//!     kills: u32,
+//! }
+//!
+//! let players = ClientStore::new();
+//!
+//! // In `Module::client_connect`
+//! players.connect(3, Player::default());
+//!
+//! // In any other callback
+//! players.with(3, |player| player.kills += 1);
+//! assert_eq!(players.with(3, |player| player.kills), Some(1));
+//! assert_eq!(players.with(4, |player| player.kills), None);
+//!
+//! // In `Module::client_disconnect`
+//! assert_eq!(players.disconnect(3), Some(Player { kills: 1 }));
+//! assert!(!players.is_connected(3));
+//! ```
+
+use crate::ffi;
+use crate::shared::MAX_CLIENTS;
+use std::fmt;
+use std::sync::Mutex;
+
+/// Data of type `T` for every connected client, by client number
+pub struct ClientStore<T> {
+    slots: Vec<Mutex<Option<T>>>,
+}
+
+impl<T> ClientStore<T> {
+    /// No clients connected yet
+    pub fn new() -> Self {
+        Self {
+            slots: (0..MAX_CLIENTS).map(|_| Mutex::new(None)).collect(),
+        }
+    }
+
+    fn slot(&self, client_number: ffi::c_int) -> &Mutex<Option<T>> {
+        match usize::try_from(client_number) {
+            Ok(index) if index < MAX_CLIENTS => &self.slots[index],
+            _ => panic!("Invalid client number {}", client_number),
+        }
+    }
+
+    /// Keep `data` for `client_number`, returning the data of a previous client in the slot, if any
+    ///
+    /// Call this from [`Module::client_connect`](super::Module::client_connect).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `client_number` is not a valid client, i.e. below [`MAX_CLIENTS`](crate::shared::MAX_CLIENTS),
+    /// like all other methods taking a client number.
+    pub fn connect(&self, client_number: ffi::c_int, data: T) -> Option<T> {
+        self.slot(client_number).lock().unwrap().replace(data)
+    }
+
+    /// Remove the data of `client_number`, returning it
+    ///
+    /// Call this from [`Module::client_disconnect`](super::Module::client_disconnect).
+    pub fn disconnect(&self, client_number: ffi::c_int) -> Option<T> {
+        self.slot(client_number).lock().unwrap().take()
+    }
+
+    /// Whether there is data for `client_number`
+    pub fn is_connected(&self, client_number: ffi::c_int) -> bool {
+        self.slot(client_number).lock().unwrap().is_some()
+    }
+
+    /// Run `f` with the data of `client_number`, `None` if there is none
+    ///
+    /// The slot is locked while `f` runs, so `f` must not access the same client of this store again.
+    pub fn with<R>(&self, client_number: ffi::c_int, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.slot(client_number).lock().unwrap().as_mut().map(f)
+    }
+
+    /// Copy of the data of `client_number`, `None` if there is none
+    pub fn get(&self, client_number: ffi::c_int) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.slot(client_number).lock().unwrap().clone()
+    }
+
+    /// Numbers of the connected clients, ascending
+    pub fn connected(&self) -> Vec<ffi::c_int> {
+        (0..MAX_CLIENTS as ffi::c_int)
+            .filter(|&client_number| self.is_connected(client_number))
+            .collect()
+    }
+
+    /// Run `f` with the data of every connected client, by ascending client number
+    pub fn for_each(&self, mut f: impl FnMut(ffi::c_int, &mut T)) {
+        for (client_number, slot) in (0..).zip(&self.slots) {
+            if let Some(data) = slot.lock().unwrap().as_mut() {
+                f(client_number, data);
+            }
+        }
+    }
+
+    /// Remove the data of all clients, returning how many there were
+    ///
+    /// Call this from [`Module::shutdown`](super::Module::shutdown), after saving what has to survive it.
+    pub fn clear(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.lock().unwrap().take().is_some())
+            .count()
+    }
+}
+
+impl<T> Default for ClientStore<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ClientStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (client_number, slot) in self.slots.iter().enumerate() {
+            if let Some(data) = slot.lock().unwrap().as_ref() {
+                map.entry(&client_number, data);
+            }
+        }
+        map.finish()
+    }
+}
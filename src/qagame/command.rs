@@ -0,0 +1,139 @@
+//! Routing console commands to handlers by name
+//!
+//! The engine passes every server console command it does not know itself to
+//! [`Module::console_command`](super::Module::console_command), which has to look at
+//! [`Syscalls::argv(0)`](super::Syscalls::argv) to find out which one it got.
+//! [`Commands`](Commands) does that lookup, case-insensitively like the engine.
+//!
+//! `game_module!(MyGame, builtins)` handles the [`builtins`](builtins) before calling the module.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::command::Commands;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! let mut commands = Commands::new();
+//! commands.register("say_hello", |count: &AtomicUsize| {
+//!     count.fetch_add(1, Ordering::Relaxed);
+//! });
+//!
+//! let count = AtomicUsize::new(0);
+//! assert!(commands.dispatch("Say_Hello", &count));
+//! assert!(!commands.dispatch("map_restart", &count));
+//! assert_eq!(count.load(Ordering::Relaxed), 1);
+//! ```
+
+use super::fs::File;
+use super::watchdog;
+use super::{FsMode, Syscalls};
+use crate::{metrics, EngineSyscalls};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+
+type Handler<C> = Box<dyn Fn(&C) + Send + Sync>;
+
+/// Console command handlers by name, with a context `C`, usually [`Syscalls`](super::Syscalls)
+pub struct Commands<C = Syscalls> {
+    handlers: BTreeMap<String, Handler<C>>,
+}
+
+impl<C> Commands<C> {
+    /// Create a router without commands
+    pub fn new() -> Self {
+        Self {
+            handlers: BTreeMap::new(),
+        }
+    }
+
+    /// Run `handler` for the command `name`, replacing any previous handler
+    pub fn register(
+        &mut self,
+        name: &str,
+        handler: impl Fn(&C) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.handlers
+            .insert(name.to_ascii_lowercase(), Box::new(handler));
+        self
+    }
+
+    /// Remove the handler for `name`, returning whether there was one
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.handlers.remove(&name.to_ascii_lowercase()).is_some()
+    }
+
+    /// Whether there is a handler for `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(&name.to_ascii_lowercase())
+    }
+
+    /// Names of all commands, lowercase and sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    /// Run the handler for `name`, returning whether there was one
+    pub fn dispatch(&self, name: &str, context: &C) -> bool {
+        match self.handlers.get(&name.to_ascii_lowercase()) {
+            Some(handler) => {
+                handler(context);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<S: EngineSyscalls> Commands<Syscalls<S>> {
+    /// Run the handler for the current console command, returning whether there was one
+    ///
+    /// Call this from [`Module::console_command`](super::Module::console_command).
+    pub fn dispatch_current(&self, syscalls: &Syscalls<S>) -> bool {
+        self.dispatch(&syscalls.argv(0), syscalls)
+    }
+}
+
+impl<C> Default for Commands<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> fmt::Debug for Commands<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
+
+/// Commands of a [`game_module!`](crate::game_module) with the `builtins` option
+///
+/// They are handled before the module's own console commands, so a module command with the same name is never run.
+///
+/// * [`metrics_dump`](metrics_dump)
+/// * [`watchdog`](super::watchdog::watchdog_command)
+pub fn builtins<S: EngineSyscalls + 'static>() -> Commands<Syscalls<S>> {
+    let mut commands = Commands::new();
+    commands.register(metrics::DUMP_COMMAND, metrics_dump);
+    commands.register(watchdog::COMMAND, watchdog::watchdog_command);
+    commands
+}
+
+/// Print the [`metrics::global`](crate::metrics::global) report, or write it as CSV to the file given as argument
+pub fn metrics_dump<S: EngineSyscalls>(syscalls: &Syscalls<S>) {
+    let metrics = metrics::global();
+    if syscalls.argc() < 2 {
+        for line in metrics.report().lines() {
+            syscalls.print(format!("{}\n", line));
+        }
+        return;
+    }
+
+    let path = syscalls.argv(1);
+    let written = File::open(syscalls, &path, FsMode::FS_WRITE)
+        .and_then(|mut file| file.write_all(metrics.to_csv().as_bytes()));
+    match written {
+        Ok(()) => syscalls.print(format!("Wrote metrics to {}\n", path)),
+        Err(e) => syscalls.print(format!("Could not write metrics: {}\n", e)),
+    }
+}
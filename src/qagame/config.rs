@@ -0,0 +1,209 @@
+//! Configuration files in the mod directory
+//!
+//! Requires the `serde` feature, and the `toml` feature for TOML files.
+//!
+//! Cvars are the engine's way to configure a mod, but every setting needs its own cvar and all of
+//! them are strings. A config file is read into any [serde](https://serde.rs/) type at once instead,
+//! in the format of its extension, i.e. `.json` or `.toml`.
+//! With `#[serde(default)]` on the type, settings missing from the file keep their defaults.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use quake3_native_vm::qagame::config;
+//! use quake3_native_vm::qagame::Syscalls;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! #[serde(default)]
+//! struct Settings {
+//!     motd: String,
+//!     max_rockets: u32,
+//! }
+//!
+//! impl Default for Settings {
+//!     fn default() -> Self {
+//!         Self {
+//!             motd: "Welcome!".to_owned(),
+//!             max_rockets: 10,
+//!         }
+//!     }
+//! }
+//!
+//! # fn example(syscalls: &Syscalls) {
+//! // In `Module::init`, errors are printed to the console
+//! let settings: Settings = config::load(syscalls, "rustmod.json");
+//! # }
+//! ```
+
+use super::fs::File;
+use super::{FsMode, Syscalls};
+use crate::EngineSyscalls;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+
+/// Format of a config file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// [JSON](https://www.json.org/)
+    Json,
+    /// [TOML](https://toml.io/), requires the `toml` feature
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Format of the file `path` by its extension, `None` if it is not supported
+    ///
+    /// ```rust
+    /// use quake3_native_vm::qagame::config::ConfigFormat;
+    ///
+    /// assert_eq!(ConfigFormat::from_path("rustmod.JSON"), Some(ConfigFormat::Json));
+    /// assert_eq!(ConfigFormat::from_path("rustmod.cfg"), None);
+    /// ```
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            #[cfg(feature = "toml")]
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Error while reading a config file
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Extension of the file is not a supported [`ConfigFormat`](ConfigFormat)
+    UnknownFormat(String),
+    /// Could not read the file
+    Io(io::Error),
+    /// Could not deserialize the JSON
+    Json(serde_json::Error),
+    /// Could not deserialize the TOML
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat(path) => write!(f, "unknown format of config {}", path),
+            Self::Io(e) => write!(f, "config file: {}", e),
+            Self::Json(e) => write!(f, "config JSON: {}", e),
+            #[cfg(feature = "toml")]
+            Self::Toml(e) => write!(f, "config TOML: {}", e),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::UnknownFormat(_) => None,
+            Self::Io(e) => Some(e),
+            Self::Json(e) => Some(e),
+            #[cfg(feature = "toml")]
+            Self::Toml(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for ConfigError {
+    fn from(e: toml::de::Error) -> Self {
+        Self::Toml(e)
+    }
+}
+
+/// Deserialize the config `text` in `format`
+///
+/// ```rust
+/// use quake3_native_vm::qagame::config::{self, ConfigFormat};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Default, PartialEq, Deserialize)]
+/// #[serde(default)]
+/// struct Settings {
+///     motd: String,
+///     max_rockets: u32,
+/// }
+///
+/// let settings: Settings = config::parse(r#"{ "max_rockets": 5 }"#, ConfigFormat::Json).unwrap();
+/// assert_eq!(settings, Settings { motd: String::new(), max_rockets: 5 });
+///
+/// assert!(config::parse::<Settings>(r#"{ "max_rockets": -1 }"#, ConfigFormat::Json).is_err());
+/// ```
+pub fn parse<T: DeserializeOwned>(text: &str, format: ConfigFormat) -> Result<T, ConfigError> {
+    match format {
+        ConfigFormat::Json => Ok(serde_json::from_str(text)?),
+        #[cfg(feature = "toml")]
+        ConfigFormat::Toml => Ok(toml::from_str(text)?),
+    }
+}
+
+/// Read the config file `path`, relative to the mod directory, `None` if it does not exist
+///
+/// The format is taken from the extension, see [`ConfigFormat::from_path`](ConfigFormat::from_path).
+pub fn read<T: DeserializeOwned, S: EngineSyscalls>(
+    syscalls: &Syscalls<S>,
+    path: &str,
+) -> Result<Option<T>, ConfigError> {
+    let format =
+        ConfigFormat::from_path(path).ok_or_else(|| ConfigError::UnknownFormat(path.to_owned()))?;
+    let mut text = String::new();
+    match File::open(syscalls, path, FsMode::FS_READ) {
+        Ok(mut file) => file.read_to_string(&mut text)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    parse(&text, format).map(Some)
+}
+
+/// Read the config file `path` like [`read`](read), or the defaults if it does not exist or is invalid
+///
+/// Errors are printed to the console, so that server operators see what is wrong with their config.
+///
+/// ```rust
+/// use quake3_native_vm::backend::{Mock, Recording};
+/// use quake3_native_vm::qagame::{config, Imports, Syscalls};
+///
+/// // No files exist
+/// let engine = Recording::new(Mock(|_, _: &[isize]| 0));
+/// let syscalls = Syscalls::with_engine(&engine);
+///
+/// let max_clients: u32 = config::load(&syscalls, "rustmod.json");
+/// assert_eq!(max_clients, 0);
+/// assert_eq!(engine.take().len(), 1);
+///
+/// let max_clients: u32 = config::load(&syscalls, "rustmod.cfg");
+/// assert_eq!(max_clients, 0);
+/// assert_eq!(engine.take()[0].num, isize::from(Imports::G_PRINT));
+/// ```
+pub fn load<T: DeserializeOwned + Default, S: EngineSyscalls>(
+    syscalls: &Syscalls<S>,
+    path: &str,
+) -> T {
+    match read(syscalls, path) {
+        Ok(config) => config.unwrap_or_default(),
+        Err(e) => {
+            syscalls.print(format!("Could not load {}, using defaults: {}\n", path, e));
+            T::default()
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Crash reports for panics
+//!
+//! A panic must not unwind into the engine, so [`guard`](guard) catches it at `dllEntry` and `vmMain`
+//! and shuts the game down with `G_ERROR`, like the C game does for fatal errors.
+//! With the panic hook of [`install`](install), [`guard`](guard) then also:
+//!
+//! 1. appends the panic message and a full backtrace to [`CRASH_LOG`](CRASH_LOG) in the mod directory
+//! 2. prints the message and the first [`PRINTED_FRAMES`](PRINTED_FRAMES) frames of the backtrace to qconsole
+//!
+//! The hook only captures the message and backtrace, the report is written once unwinding has
+//! finished, so that `G_ERROR` never jumps out of the panic machinery.
+//!
+//! Use `game_module!(MyGame, crash_report)` to install it when the module is loaded.
+//!
+//! Panics on other threads than the engine thread go to the previous hook,
+//! since syscalls must not be made from there, see [`queue`](crate::queue).
+
+use super::fs::File;
+use super::{Exports, FsMode, Syscalls};
+use crate::{ffi, panic_message, EngineSyscalls, PanicHandler};
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe, PanicInfo};
+use std::sync::Once;
+
+/// File in the mod directory that crash reports are appended to
+pub const CRASH_LOG: &str = "crashlog.txt";
+
+/// Number of backtrace frames printed to qconsole, the log has all of them
+pub const PRINTED_FRAMES: usize = 12;
+
+thread_local! {
+    /// Whether [`install`](install) was called on this thread
+    static ENGINE_THREAD: Cell<bool> = Cell::new(false);
+    /// Panic captured by the hook, until [`guard`](guard) reports it
+    static CAPTURED: RefCell<Option<Crash>> = RefCell::new(None);
+}
+
+static HOOK: Once = Once::new();
+
+/// Message and backtrace of a panic
+#[derive(Clone, Debug)]
+pub struct Crash {
+    /// Panic message with its location, e.g. `panicked at 'oops', src/lib.rs:1:2`
+    pub message: String,
+    /// Formatted backtrace
+    pub backtrace: String,
+}
+
+impl Crash {
+    /// Capture the backtrace of the panic `info` from within a panic hook
+    pub fn capture(info: &PanicInfo<'_>) -> Self {
+        Self {
+            message: format!(
+                "panicked at '{}', {}",
+                panic_message(info.payload()),
+                info.location()
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| "unknown location".to_owned()),
+            ),
+            backtrace: Backtrace::force_capture().to_string(),
+        }
+    }
+}
+
+/// Capture crash reports for panics on the current thread, which has to be the engine thread
+///
+/// The panic hook is only set once per process, so that loading the module again after a
+/// `G_ERROR` or map change keeps the same hook.
+pub fn install() {
+    ENGINE_THREAD.with(|engine_thread| engine_thread.set(true));
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !ENGINE_THREAD.with(Cell::get) {
+                return previous(info);
+            }
+            let crash = Crash::capture(info);
+            CAPTURED.with(|captured| *captured.borrow_mut() = Some(crash));
+        }));
+    });
+}
+
+/// Write the crash report for a panic and shut the game down
+///
+/// This must not be called from a panic hook, since `G_ERROR` does not return.
+pub fn report<S: EngineSyscalls>(syscalls: &Syscalls<S>, crash: &Crash) -> ! {
+    let logged = File::open(syscalls, CRASH_LOG, FsMode::FS_APPEND_SYNC)
+        .and_then(|mut log| write!(log, "{}\n{}\n", crash.message, crash.backtrace));
+
+    syscalls.print(format!("^1{}\n", crash.message));
+    for frame in frames(&crash.backtrace).take(PRINTED_FRAMES) {
+        syscalls.print(format!("{}\n", frame));
+    }
+    match logged {
+        Ok(()) => syscalls.print(format!("Full backtrace in {}\n", CRASH_LOG)),
+        Err(e) => syscalls.print(format!("Could not write {}: {}\n", CRASH_LOG, e)),
+    }
+
+    syscalls.error(crash.message.as_str())
+}
+
+/// Run `f`, shutting the game down with `G_ERROR` if it panics
+///
+/// The panic is first passed to `on_panic`, if any, and then reported like [`report`](report)
+/// if [`install`](install)ed. `command` is the one being dispatched, `None` for `dllEntry`.
+///
+/// [`game_module!`](crate::game_module) runs the module with this.
+pub fn guard<T, S: EngineSyscalls>(
+    syscalls: &Syscalls<S>,
+    on_panic: Option<PanicHandler>,
+    command: Option<ffi::c_int>,
+    f: impl FnOnce() -> T,
+) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(payload) => {
+            if let Some(on_panic) = on_panic {
+                on_panic(&*payload, command);
+            }
+            if let Some(crash) = CAPTURED.with(|captured| captured.borrow_mut().take()) {
+                report(syscalls, &crash)
+            }
+            let command = match command.map(Exports::try_from) {
+                Some(Ok(command)) => command.name().to_owned(),
+                Some(Err(_)) => format!("command {}", command.unwrap_or_default()),
+                None => "dllEntry".to_owned(),
+            };
+            syscalls.error(format!(
+                "panicked at '{}' in {}",
+                panic_message(&*payload),
+                command
+            ))
+        }
+    }
+}
+
+/// Frames of a formatted backtrace, without those of the panic machinery itself
+fn frames(backtrace: &str) -> impl Iterator<Item = String> + '_ {
+    const INTERNAL: [&str; 6] = [
+        "std::backtrace",
+        "std::panicking",
+        "core::panicking",
+        "rust_begin_unwind",
+        "quake3_native_vm::qagame::crash",
+        "std::rt::lang_start",
+    ];
+
+    let mut frames: Vec<String> = Vec::new();
+    for line in backtrace.lines() {
+        let is_frame_start = line
+            .trim_start()
+            .split_once(": ")
+            .map_or(false, |(number, _)| number.parse::<usize>().is_ok());
+        match frames.last_mut() {
+            Some(frame) if !is_frame_start => {
+                frame.push('\n');
+                frame.push_str(line);
+            }
+            _ => frames.push(line.to_owned()),
+        }
+    }
+
+    frames.into_iter().filter(|frame| {
+        let name = frame.lines().next().unwrap_or_default();
+        !INTERNAL.iter().any(|internal| name.contains(internal))
+    })
+}
@@ -0,0 +1,247 @@
+//! Game entities
+//!
+//! The engine only knows about the [`SharedEntity`](SharedEntity) part at the start of each game entity.
+//! Everything else, e.g. classnames, health or think functions, is private to the game.
+
+use crate::ffi;
+use crate::shared::{
+    next_event, snap_vector, EntityState, PlayerState, QBoolean, Trajectory, Vec3,
+};
+
+/// Maximum number of entities, i.e. clients plus everything else
+///
+/// See `MAX_GENTITIES` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const MAX_GENTITIES: usize = 1 << GENTITYNUM_BITS;
+
+/// See `GENTITYNUM_BITS` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const GENTITYNUM_BITS: usize = 10;
+
+/// First [`EntityState::e_type`](crate::shared::EntityState::e_type) of temporary event entities
+///
+/// A temporary entity for event `ev` has type `ET_EVENTS + ev`.
+///
+/// See `entityType_t` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+pub const ET_EVENTS: ffi::c_int = 13;
+
+/// How long events are kept in the entity state, in milliseconds
+///
+/// See `EVENT_VALID_MSEC` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+pub const EVENT_VALID_MSEC: ffi::c_int = 300;
+
+/// Part of an entity that is only used by the server
+///
+/// See `entityShared_t` in [ioquake3's `game/g_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/g_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct EntityShared {
+    /// Leftover copy of the entity state, ignored by the engine
+    pub unused: EntityState,
+    /// Whether the entity is linked into the world
+    pub linked: QBoolean,
+    pub linkcount: ffi::c_int,
+    pub sv_flags: ffi::c_int,
+    pub single_client: ffi::c_int,
+    pub bmodel: QBoolean,
+    pub mins: Vec3,
+    pub maxs: Vec3,
+    pub contents: ffi::c_int,
+    pub absmin: Vec3,
+    pub absmax: Vec3,
+    pub current_origin: Vec3,
+    pub current_angles: Vec3,
+    pub owner_num: ffi::c_int,
+}
+
+assert_layout!(EntityShared, size = 308, align = 4);
+
+/// Part of an entity that is shared with the engine
+///
+/// Game entities must start with this.
+///
+/// See `sharedEntity_t` in [ioquake3's `game/g_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/g_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SharedEntity {
+    /// Communicated by the server to clients
+    pub s: EntityState,
+    /// Only used by the server
+    pub r: EntityShared,
+}
+
+assert_layout!(SharedEntity, size = 516, align = 4);
+
+impl SharedEntity {
+    /// Set the entity to stay at `origin`
+    ///
+    /// See `G_SetOrigin` in [ioquake3's `game/g_utils.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_utils.c).
+    pub fn set_origin(&mut self, origin: Vec3) {
+        self.s.pos = Trajectory::stationary(origin);
+        self.r.current_origin = origin;
+    }
+
+    /// Turn a freshly spawned entity into a temporary entity for `event` at `origin`
+    ///
+    /// The entity still needs to be linked, and should be freed once [`EntityEvents::expire`](EntityEvents::expire)
+    /// says so.
+    ///
+    /// See `G_TempEntity` in [ioquake3's `game/g_utils.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_utils.c).
+    pub fn temp_event(
+        &mut self,
+        events: &mut EntityEvents,
+        origin: Vec3,
+        event: ffi::c_int,
+        level_time: ffi::c_int,
+    ) {
+        self.s.e_type = ET_EVENTS + event;
+        self.set_origin(snap_vector(origin));
+        events.event_time = level_time;
+        events.free_after_event = true;
+    }
+}
+
+/// What to do with an entity whose event expired
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventExpiry {
+    /// Nothing, the entity had no expired event
+    Keep,
+    /// Free the entity
+    Free,
+    /// Unlink the entity from the world
+    Unlink,
+}
+
+/// Event bookkeeping of a game entity
+///
+/// Events are sent to clients as part of the [`EntityState`](crate::shared::EntityState),
+/// and have to be cleared once all clients had a chance to see them.
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::qagame::entity::{EntityEvents, EventExpiry, SharedEntity};
+///
+/// let mut ent = SharedEntity::default();
+/// let mut events = EntityEvents::default();
+///
+/// assert!(events.add_event(&mut ent.s, None, 42, 0, 1000));
+/// assert_eq!(events.expire(&mut ent.s, None, 1200), EventExpiry::Keep);
+/// assert_ne!(ent.s.event, 0);
+/// assert_eq!(events.expire(&mut ent.s, None, 1400), EventExpiry::Keep);
+/// assert_eq!(ent.s.event, 0);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EntityEvents {
+    /// Level time of the last event
+    pub event_time: ffi::c_int,
+    /// Free the entity once its event expired, e.g. for temporary entities
+    pub free_after_event: bool,
+    /// Unlink the entity once its event expired
+    pub unlink_after_event: bool,
+}
+
+impl EntityEvents {
+    /// Add `event` with `event_parm` to an entity, returning `false` for the invalid event `0`
+    ///
+    /// Entities of clients pass their `client` player state, which gets the event as its external event
+    /// instead of `state`.
+    ///
+    /// See `G_AddEvent` in [ioquake3's `game/g_utils.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_utils.c).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quake3_native_vm::qagame::entity::{EntityEvents, SharedEntity};
+    /// use quake3_native_vm::shared::{PlayerState, EV_EVENT_BITS};
+    ///
+    /// let mut ent = SharedEntity::default();
+    /// let mut ps = PlayerState::default();
+    /// let mut events = EntityEvents::default();
+    ///
+    /// assert!(events.add_event(&mut ent.s, Some(&mut ps), 42, 7, 1000));
+    /// let first = ps.external_event;
+    /// assert!(events.add_event(&mut ent.s, Some(&mut ps), 42, 7, 1050));
+    /// assert_ne!(ps.external_event, first);
+    /// assert_eq!(ps.external_event & !EV_EVENT_BITS, 42);
+    /// assert_eq!((ps.external_event_parm, ps.external_event_time), (7, 1050));
+    /// assert_eq!(ent.s.event, 0);
+    ///
+    /// assert!(!events.add_event(&mut ent.s, Some(&mut ps), 0, 0, 1100));
+    /// assert_eq!(events.event_time, 1050);
+    /// ```
+    pub fn add_event(
+        &mut self,
+        state: &mut EntityState,
+        client: Option<&mut PlayerState>,
+        event: ffi::c_int,
+        event_parm: ffi::c_int,
+        level_time: ffi::c_int,
+    ) -> bool {
+        if event == 0 {
+            return false;
+        }
+
+        match client {
+            Some(ps) => {
+                ps.external_event = next_event(ps.external_event, event);
+                ps.external_event_parm = event_parm;
+                ps.external_event_time = level_time;
+            }
+            None => {
+                state.event = next_event(state.event, event);
+                state.event_parm = event_parm;
+            }
+        }
+        self.event_time = level_time;
+        true
+    }
+
+    /// Clear the event of `state` if it is older than [`EVENT_VALID_MSEC`](EVENT_VALID_MSEC)
+    ///
+    /// Call this for every entity on each frame. Entities of clients pass their `client` player state,
+    /// whose external event from [`add_event`](EntityEvents::add_event) is cleared the same way.
+    ///
+    /// See `G_RunFrame` in [ioquake3's `game/g_main.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_main.c).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quake3_native_vm::qagame::entity::{EntityEvents, EventExpiry, SharedEntity};
+    /// use quake3_native_vm::shared::PlayerState;
+    ///
+    /// let mut ent = SharedEntity::default();
+    /// let mut ps = PlayerState::default();
+    /// let mut events = EntityEvents::default();
+    ///
+    /// assert!(events.add_event(&mut ent.s, Some(&mut ps), 42, 7, 1000));
+    /// assert_eq!(events.expire(&mut ent.s, Some(&mut ps), 1300), EventExpiry::Keep);
+    /// assert_ne!(ps.external_event, 0);
+    /// assert_eq!(events.expire(&mut ent.s, Some(&mut ps), 1301), EventExpiry::Keep);
+    /// assert_eq!(ps.external_event, 0);
+    /// ```
+    pub fn expire(
+        &mut self,
+        state: &mut EntityState,
+        client: Option<&mut PlayerState>,
+        level_time: ffi::c_int,
+    ) -> EventExpiry {
+        if let Some(ps) = client {
+            if level_time - ps.external_event_time > EVENT_VALID_MSEC {
+                ps.external_event = 0;
+            }
+        }
+        if level_time - self.event_time <= EVENT_VALID_MSEC {
+            return EventExpiry::Keep;
+        }
+
+        state.event = 0;
+        if self.free_after_event {
+            EventExpiry::Free
+        } else if self.unlink_after_event {
+            self.unlink_after_event = false;
+            EventExpiry::Unlink
+        } else {
+            EventExpiry::Keep
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! [`Module`](super::Module) functions that can fail
+//!
+//! Implementing [`FallibleModule`](FallibleModule) instead of [`Module`](super::Module) allows using `?`
+//! throughout the game code. Wrapped in [`Fallible`](Fallible), errors are turned into a `G_ERROR` that shuts
+//! down the game, or into a warning on qconsole, depending on the [`ErrorPolicy`](ErrorPolicy) for the command.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::fallible::{ErrorPolicy, Fallible, FallibleModule, Result};
+//! use quake3_native_vm::qagame::{Deny, Exports, Syscalls};
+//! use quake3_native_vm::{ffi, game_module};
+//!
+//! struct Game {
+//!     syscalls: Syscalls,
+//! }
+//!
+//! impl FallibleModule for Game {
+//!     fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+//!         Box::new(Game { syscalls })
+//!     }
+//!     fn error_policy(&self, command: Exports) -> ErrorPolicy {
+//!         match command {
+//!             Exports::GAME_INIT => ErrorPolicy::Error,
+//!             _ => ErrorPolicy::Warn,
+//!         }
+//!     }
+//!     fn init(&self, _level_time: ffi::c_int, _random_seed: ffi::c_int, _restart: bool) -> Result<()> {
+//!         let fraglimit: i32 = self.syscalls.cvar_variable_string_buffer("fraglimit").parse()?;
+//!         self.syscalls.print(format!("fraglimit is {}\n", fraglimit));
+//!         Ok(())
+//!     }
+//!     fn shutdown(&self, _restart: bool) -> Result<()> {
+//!         Ok(())
+//!     }
+//!     fn client_connect(
+//!         &self,
+//!         _client_number: ffi::c_int,
+//!         _first_time: bool,
+//!         _is_bot: bool,
+//!     ) -> Result<std::result::Result<(), Deny>> {
+//!         Ok(Ok(()))
+//!     }
+//!     fn client_think(&self, _client_number: ffi::c_int) -> Result<()> {
+//!         Ok(())
+//!     }
+//!     fn client_userinfo_changed(&self, _client_number: ffi::c_int) -> Result<()> {
+//!         Ok(())
+//!     }
+//!     fn client_disconnect(&self, _client_number: ffi::c_int) -> Result<()> {
+//!         Ok(())
+//!     }
+//!     fn client_begin(&self, _client_number: ffi::c_int) -> Result<()> {
+//!         Ok(())
+//!     }
+//!     fn client_command(&self, _client_number: ffi::c_int) -> Result<()> {
+//!         Ok(())
+//!     }
+//!     fn run_frame(&self, _level_time: ffi::c_int) -> Result<()> {
+//!         Ok(())
+//!     }
+//!     fn console_command(&self) -> Result<bool> {
+//!         Ok(false)
+//!     }
+//!     fn botai_start_frame(&self, _level_time: ffi::c_int) -> Result<bool> {
+//!         Ok(false)
+//!     }
+//! }
+//!
+//! game_module!(Fallible<Game>);
+//! ```
+
+use super::{Deny, Exports, Module, Syscalls};
+use crate::ffi;
+
+/// Error returned by [`FallibleModule`](FallibleModule) functions
+///
+/// Any error type converts into this with `?`.
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Result of [`FallibleModule`](FallibleModule) functions
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// What to do when a [`FallibleModule`](FallibleModule) function returns an error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Shut down the game with `G_ERROR`
+    Error,
+    /// Print a warning and carry on as if the function returned `0` or `false`
+    Warn,
+}
+
+/// Message for an `error` returned while handling `command`, including its sources
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::qagame::fallible::message;
+/// use quake3_native_vm::qagame::Exports;
+///
+/// let error = "15x".parse::<i32>().unwrap_err();
+/// assert_eq!(
+///     message(Exports::GAME_INIT, &error),
+///     "GAME_INIT failed: invalid digit found in string\n"
+/// );
+/// ```
+pub fn message(command: Exports, error: &dyn std::error::Error) -> String {
+    let mut message = format!("{} failed: {}", command.name(), error);
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(&format!(": {}", error));
+        source = error.source();
+    }
+    message.push('\n');
+    message
+}
+
+/// Like [`Module`](super::Module), but every function returns a [`Result`](Result)
+///
+/// Wrap it in [`Fallible`](Fallible) to use it with [`game_module!`](crate::game_module).
+pub trait FallibleModule: 'static + Sync + Send {
+    /// See [`Module::dll_entry`](super::Module::dll_entry)
+    fn dll_entry(syscalls: Syscalls) -> Box<Self>
+    where
+        Self: Sized;
+
+    /// How to handle errors returned for `command`, [`ErrorPolicy::Error`](ErrorPolicy::Error) by default
+    fn error_policy(&self, _command: Exports) -> ErrorPolicy {
+        ErrorPolicy::Error
+    }
+
+    /// See [`Module::init`](super::Module::init)
+    fn init(&self, level_time: ffi::c_int, random_seed: ffi::c_int, restart: bool) -> Result<()>;
+
+    /// See [`Module::shutdown`](super::Module::shutdown)
+    fn shutdown(&self, restart: bool) -> Result<()>;
+
+    /// See [`Module::client_connect`](super::Module::client_connect), `Ok(Err(deny))` rejects the client
+    ///
+    /// With [`ErrorPolicy::Warn`](ErrorPolicy::Warn), an error lets the client connect.
+    fn client_connect(
+        &self,
+        client_number: ffi::c_int,
+        first_time: bool,
+        is_bot: bool,
+    ) -> Result<std::result::Result<(), Deny>>;
+
+    /// See [`Module::client_think`](super::Module::client_think)
+    fn client_think(&self, client_number: ffi::c_int) -> Result<()>;
+
+    /// See [`Module::client_userinfo_changed`](super::Module::client_userinfo_changed)
+    fn client_userinfo_changed(&self, client_number: ffi::c_int) -> Result<()>;
+
+    /// See [`Module::client_disconnect`](super::Module::client_disconnect)
+    fn client_disconnect(&self, client_number: ffi::c_int) -> Result<()>;
+
+    /// See [`Module::client_begin`](super::Module::client_begin)
+    fn client_begin(&self, client_number: ffi::c_int) -> Result<()>;
+
+    /// See [`Module::client_command`](super::Module::client_command)
+    fn client_command(&self, client_number: ffi::c_int) -> Result<()>;
+
+    /// See [`Module::run_frame`](super::Module::run_frame)
+    fn run_frame(&self, level_time: ffi::c_int) -> Result<()>;
+
+    /// See [`Module::console_command`](super::Module::console_command)
+    fn console_command(&self) -> Result<bool>;
+
+    /// See [`Module::botai_start_frame`](super::Module::botai_start_frame)
+    fn botai_start_frame(&self, level_time: ffi::c_int) -> Result<bool>;
+}
+
+/// [`Module`](super::Module) for a [`FallibleModule`](FallibleModule), handling its errors
+pub struct Fallible<M> {
+    syscalls: Syscalls,
+    module: Box<M>,
+}
+
+impl<M: FallibleModule> Fallible<M> {
+    /// The wrapped module
+    pub fn module(&self) -> &M {
+        &self.module
+    }
+
+    fn handle<T: Default>(&self, command: Exports, result: Result<T>) -> T {
+        match result {
+            Ok(value) => value,
+            Err(error) => {
+                let text = message(command, &*error);
+                match self.module.error_policy(command) {
+                    ErrorPolicy::Error => self.syscalls.error(text),
+                    ErrorPolicy::Warn => {
+                        self.syscalls.print(format!("WARNING: {}", text));
+                        T::default()
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<M: FallibleModule> Module for Fallible<M> {
+    fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+        Box::new(Self {
+            module: M::dll_entry(syscalls),
+            syscalls,
+        })
+    }
+
+    fn init(&self, level_time: ffi::c_int, random_seed: ffi::c_int, restart: bool) {
+        let result = self.module.init(level_time, random_seed, restart);
+        self.handle(Exports::GAME_INIT, result)
+    }
+
+    fn shutdown(&self, restart: bool) {
+        let result = self.module.shutdown(restart);
+        self.handle(Exports::GAME_SHUTDOWN, result)
+    }
+
+    fn client_connect(
+        &self,
+        client_number: ffi::c_int,
+        first_time: bool,
+        is_bot: bool,
+    ) -> std::result::Result<(), Deny> {
+        let result = self
+            .module
+            .client_connect(client_number, first_time, is_bot);
+        self.handle(Exports::GAME_CLIENT_CONNECT, result.map(Some))
+            .unwrap_or(Ok(()))
+    }
+
+    fn client_think(&self, client_number: ffi::c_int) {
+        let result = self.module.client_think(client_number);
+        self.handle(Exports::GAME_CLIENT_THINK, result)
+    }
+
+    fn client_userinfo_changed(&self, client_number: ffi::c_int) {
+        let result = self.module.client_userinfo_changed(client_number);
+        self.handle(Exports::GAME_CLIENT_USERINFO_CHANGED, result)
+    }
+
+    fn client_disconnect(&self, client_number: ffi::c_int) {
+        let result = self.module.client_disconnect(client_number);
+        self.handle(Exports::GAME_CLIENT_DISCONNECT, result)
+    }
+
+    fn client_begin(&self, client_number: ffi::c_int) {
+        let result = self.module.client_begin(client_number);
+        self.handle(Exports::GAME_CLIENT_BEGIN, result)
+    }
+
+    fn client_command(&self, client_number: ffi::c_int) {
+        let result = self.module.client_command(client_number);
+        self.handle(Exports::GAME_CLIENT_COMMAND, result)
+    }
+
+    fn run_frame(&self, level_time: ffi::c_int) {
+        let result = self.module.run_frame(level_time);
+        self.handle(Exports::GAME_RUN_FRAME, result)
+    }
+
+    fn console_command(&self) -> bool {
+        let result = self.module.console_command();
+        self.handle(Exports::GAME_CONSOLE_COMMAND, result)
+    }
+
+    fn botai_start_frame(&self, level_time: ffi::c_int) -> bool {
+        let result = self.module.botai_start_frame(level_time);
+        self.handle(Exports::BOTAI_START_FRAME, result)
+    }
+}
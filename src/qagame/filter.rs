@@ -0,0 +1,462 @@
+//! Banning clients by IP address
+//!
+//! [`Filters`](Filters) is a list of address ranges, e.g. `192.168.0.0/16`, kept in a cvar or in a file of the mod directory.
+//! Like the C game, it also understands the older `192.168.*.*` form, and can either ban the clients matching
+//! any filter or only allow those, see [`FilterMode`](FilterMode).
+//!
+//! [`register`](Filters::register) adds the console commands of the C game:
+//!
+//! * `addip <filter>`
+//! * `removeip <filter>`
+//! * `listip`
+//!
+//! See `G_FilterPacket` in [ioquake3's `game/g_svcmds.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_svcmds.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::filter::{Filter, FilterMode, Filters};
+//!
+//! let filters = Filters::new(FilterMode::Ban);
+//! filters.add("10.0.0.0/8".parse::<Filter>()?);
+//! filters.add("192.168.*.*".parse::<Filter>()?);
+//!
+//! assert!(filters.is_filtered("10.1.2.3".parse().unwrap()));
+//! assert!(!filters.is_filtered("172.16.0.1".parse().unwrap()));
+//! assert!(filters.is_filtered_userinfo("\\name\\Sarge\\ip\\192.168.1.5:27960"));
+//! assert!(!filters.is_filtered_userinfo("\\name\\Sarge\\ip\\localhost"));
+//! assert_eq!(filters.to_string(), "10.0.0.0/8 192.168.0.0/16");
+//! # Ok::<(), quake3_native_vm::qagame::filter::FilterError>(())
+//! ```
+
+use super::command::Commands;
+use super::fs::File;
+use super::{FsMode, Syscalls};
+use crate::info;
+use crate::{ffi, EngineSyscalls};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Message for clients rejected by [`Filters::client_connect`](Filters::client_connect), same as the C game's
+pub const BANNED_MESSAGE: &str = "You are banned from this server.";
+
+/// Error parsing a [`Filter`](Filter)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FilterError {
+    /// Not an IP address, CIDR range or wildcard address
+    InvalidAddress(String),
+    /// Prefix length longer than the address
+    InvalidPrefix(String),
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAddress(s) => write!(f, "bad filter address: {}", s),
+            Self::InvalidPrefix(s) => write!(f, "bad filter prefix length: {}", s),
+        }
+    }
+}
+
+impl Error for FilterError {}
+
+/// Range of IP addresses
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Filter {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Filter {
+    /// Range of the addresses sharing the first `prefix_len` bits with `network`
+    pub fn new(network: IpAddr, prefix_len: u8) -> Result<Self, FilterError> {
+        if prefix_len > max_prefix_len(network) {
+            return Err(FilterError::InvalidPrefix(prefix_len.to_string()));
+        }
+        Ok(Self {
+            network: mask(network, prefix_len),
+            prefix_len,
+        })
+    }
+
+    /// First address of the range
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    /// Number of leading bits that have to match
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Whether `addr` is in this range
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        addr.is_ipv4() == self.network.is_ipv4() && mask(addr, self.prefix_len) == self.network
+    }
+}
+
+fn max_prefix_len(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn mask(addr: IpAddr, prefix_len: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(addr) => {
+            let bits = u32::from(addr)
+                .checked_shr(32 - u32::from(prefix_len))
+                .unwrap_or(0)
+                .checked_shl(32 - u32::from(prefix_len))
+                .unwrap_or(0);
+            IpAddr::V4(bits.into())
+        }
+        IpAddr::V6(addr) => {
+            let bits = u128::from(addr)
+                .checked_shr(128 - u32::from(prefix_len))
+                .unwrap_or(0)
+                .checked_shl(128 - u32::from(prefix_len))
+                .unwrap_or(0);
+            IpAddr::V6(bits.into())
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    /// Parse `addr/prefix_len`, a single address or an IPv4 address with trailing `*` or missing octets
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || FilterError::InvalidAddress(s.to_owned());
+
+        if let Some((addr, prefix_len)) = s.split_once('/') {
+            let addr = addr.parse().map_err(|_| invalid())?;
+            let prefix_len = prefix_len
+                .parse()
+                .map_err(|_| FilterError::InvalidPrefix(prefix_len.to_owned()))?;
+            return Self::new(addr, prefix_len);
+        }
+        if let Ok(addr) = s.parse::<IpAddr>() {
+            return Self::new(addr, max_prefix_len(addr));
+        }
+
+        // `G_ParseFilter` style, e.g. `192.168.*.*` or `192.168`
+        let mut octets = [0u8; 4];
+        let mut prefix_len = 0;
+        for (i, part) in s.split('.').enumerate() {
+            if i >= 4 || part.is_empty() {
+                return Err(invalid());
+            }
+            if part == "*" {
+                continue;
+            }
+            if prefix_len != 8 * i as u8 {
+                // Wildcards can only be at the end
+                return Err(invalid());
+            }
+            octets[i] = part.parse().map_err(|_| invalid())?;
+            prefix_len += 8;
+        }
+        Self::new(IpAddr::V4(Ipv4Addr::from(octets)), prefix_len)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// Whether [`Filters`](Filters) ban or allow matching clients
+///
+/// See the `g_filterBan` cvar in [ioquake3's `game/g_svcmds.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_svcmds.c).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FilterMode {
+    /// Reject clients matching any filter
+    #[default]
+    Ban,
+    /// Reject clients matching no filter
+    Allow,
+}
+
+/// Where [`Filters`](Filters) are loaded from and saved to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Space separated in the cvar with this name, like `g_banIPs` of the C game
+    Cvar(String),
+    /// Space or line separated in the file with this path, relative to the mod directory
+    File(String),
+}
+
+/// List of IP address filters
+pub struct Filters {
+    mode: Mutex<FilterMode>,
+    filters: Mutex<Vec<Filter>>,
+    source: Mutex<Option<Source>>,
+}
+
+impl Filters {
+    /// Create an empty list
+    pub fn new(mode: FilterMode) -> Self {
+        Self {
+            mode: Mutex::new(mode),
+            filters: Mutex::new(Vec::new()),
+            source: Mutex::new(None),
+        }
+    }
+
+    /// Whether matching clients are banned or allowed
+    pub fn mode(&self) -> FilterMode {
+        *self.mode.lock().unwrap()
+    }
+
+    /// Change whether matching clients are banned or allowed
+    pub fn set_mode(&self, mode: FilterMode) {
+        *self.mode.lock().unwrap() = mode;
+    }
+
+    /// Add `filter`, returning whether it was not in the list yet
+    pub fn add(&self, filter: Filter) -> bool {
+        let mut filters = self.filters.lock().unwrap();
+        if filters.contains(&filter) {
+            return false;
+        }
+        filters.push(filter);
+        true
+    }
+
+    /// Remove `filter`, returning whether it was in the list
+    pub fn remove(&self, filter: Filter) -> bool {
+        let mut filters = self.filters.lock().unwrap();
+        let len = filters.len();
+        filters.retain(|f| *f != filter);
+        filters.len() != len
+    }
+
+    /// All filters, in the order they were added
+    pub fn filters(&self) -> Vec<Filter> {
+        self.filters.lock().unwrap().clone()
+    }
+
+    /// Whether a client from `addr` has to be rejected
+    pub fn is_filtered(&self, addr: IpAddr) -> bool {
+        let matches = self
+            .filters
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|f| f.contains(addr));
+        match self.mode() {
+            FilterMode::Ban => matches,
+            FilterMode::Allow => !matches,
+        }
+    }
+
+    /// Whether a client with `userinfo` has to be rejected
+    ///
+    /// `userinfo` is taken as sent, see [`Syscalls::get_userinfo_bytes`](super::Syscalls::get_userinfo_bytes),
+    /// so that names which are not UTF-8 can't hide the `ip` key.
+    /// Local clients, i.e. with `localhost` in the `ip` key, are never rejected.
+    /// Clients without a valid address are always rejected, no matter the [`FilterMode`](FilterMode).
+    ///
+    /// ```rust
+    /// use quake3_native_vm::qagame::filter::{FilterMode, Filters};
+    ///
+    /// let filters = Filters::new(FilterMode::Ban);
+    /// filters.add("10.0.0.0/8".parse()?);
+    ///
+    /// // Decoded as UTF-8, every byte of the name would become 3 and not fit `MAX_INFO_STRING` anymore
+    /// let mut userinfo = b"\\name\\".to_vec();
+    /// userinfo.extend([0xff; 900]);
+    /// userinfo.extend(b"\\ip\\10.1.2.3:27960");
+    /// assert!(filters.is_filtered_userinfo(&userinfo));
+    ///
+    /// assert!(filters.is_filtered_userinfo("\\name\\Sarge"));
+    /// assert!(filters.is_filtered_userinfo("\\name\\Sarge\\ip\\10.1"));
+    /// assert!(!filters.is_filtered_userinfo("\\name\\Sarge\\ip\\172.16.0.1:27960"));
+    ///
+    /// filters.set_mode(FilterMode::Allow);
+    /// assert!(filters.is_filtered_userinfo("\\name\\Sarge\\ip\\10.1"));
+    /// assert!(!filters.is_filtered_userinfo(&userinfo));
+    /// # Ok::<(), quake3_native_vm::qagame::filter::FilterError>(())
+    /// ```
+    pub fn is_filtered_userinfo(&self, userinfo: impl AsRef<[u8]>) -> bool {
+        let ip = match info::value_for_key(userinfo.as_ref(), "ip") {
+            Some(ip) => ip,
+            None => return true,
+        };
+        if ip == b"localhost" {
+            return false;
+        }
+        match std::str::from_utf8(ip).ok().and_then(parse_client_addr) {
+            Some(addr) => self.is_filtered(addr),
+            None => true,
+        }
+    }
+
+    /// Whether the connecting `client_number` is allowed
+    ///
+    /// Call this from [`Module::client_connect`](super::Module::client_connect)
+    /// and deny the connection with [`BANNED_MESSAGE`](BANNED_MESSAGE) otherwise.
+    /// Bots have no address and are always allowed.
+    pub fn client_connect<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        client_number: ffi::c_int,
+        is_bot: bool,
+    ) -> bool {
+        is_bot || !self.is_filtered_userinfo(syscalls.get_userinfo_bytes(client_number))
+    }
+
+    /// Replace all filters with those in `source`, and save changes there from now on
+    ///
+    /// Invalid filters are skipped with a warning.
+    pub fn load<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        source: Source,
+    ) -> io::Result<()> {
+        let text = match &source {
+            Source::Cvar(name) => syscalls.cvar_variable_string_buffer(name.as_str()),
+            Source::File(path) => {
+                let mut text = String::new();
+                match File::open(syscalls, path, FsMode::FS_READ) {
+                    Ok(mut file) => {
+                        file.read_to_string(&mut text)?;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+                text
+            }
+        };
+
+        let mut filters = Vec::new();
+        for part in text.split_whitespace() {
+            match part.parse::<Filter>() {
+                Ok(filter) if !filters.contains(&filter) => filters.push(filter),
+                Ok(_) => {}
+                Err(e) => syscalls.print(format!("Skipping IP filter: {}\n", e)),
+            }
+        }
+        *self.filters.lock().unwrap() = filters;
+        *self.source.lock().unwrap() = Some(source);
+        Ok(())
+    }
+
+    /// Write all filters to the source they were [`load`](Filters::load)ed from, if any
+    ///
+    /// See `UpdateIPBans` in [ioquake3's `game/g_svcmds.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_svcmds.c).
+    pub fn save<S: EngineSyscalls>(&self, syscalls: &Syscalls<S>) -> io::Result<()> {
+        let source = self.source.lock().unwrap().clone();
+        match source {
+            Some(Source::Cvar(name)) => syscalls.cvar_set(name, self.to_string()),
+            Some(Source::File(path)) => {
+                let mut file = File::open(syscalls, &path, FsMode::FS_WRITE)?;
+                for filter in self.filters() {
+                    writeln!(file, "{}", filter)?;
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Add the `addip`, `removeip` and `listip` console commands
+    pub fn register<S: EngineSyscalls + 'static>(
+        self: &Arc<Self>,
+        commands: &mut Commands<Syscalls<S>>,
+    ) {
+        let filters = Arc::clone(self);
+        commands.register("addip", move |syscalls: &Syscalls<S>| {
+            filters.change(syscalls, "addip", |filters, filter| {
+                if filters.add(filter) {
+                    format!("Added {}.\n", filter)
+                } else {
+                    format!("{} is already in the list.\n", filter)
+                }
+            })
+        });
+        let filters = Arc::clone(self);
+        commands.register("removeip", move |syscalls: &Syscalls<S>| {
+            filters.change(syscalls, "removeip", |filters, filter| {
+                if filters.remove(filter) {
+                    format!("Removed {}.\n", filter)
+                } else {
+                    format!("Didn't find {}.\n", filter)
+                }
+            })
+        });
+        let filters = Arc::clone(self);
+        commands.register("listip", move |syscalls: &Syscalls<S>| {
+            for filter in filters.filters() {
+                syscalls.print(format!("{}\n", filter));
+            }
+        });
+    }
+
+    fn change<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        name: &str,
+        f: impl FnOnce(&Self, Filter) -> String,
+    ) {
+        if syscalls.argc() < 2 {
+            syscalls.print(format!("Usage: {} <ip-mask>\n", name));
+            return;
+        }
+        match syscalls.argv(1).parse() {
+            Ok(filter) => {
+                syscalls.print(f(self, filter));
+                if let Err(e) = self.save(syscalls) {
+                    syscalls.print(format!("Could not save IP filters: {}\n", e));
+                }
+            }
+            Err(e) => syscalls.print(format!("{}\n", e)),
+        }
+    }
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self::new(FilterMode::default())
+    }
+}
+
+impl fmt::Display for Filters {
+    /// Space separated filters, like the `g_banIPs` cvar
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, filter) in self.filters.lock().unwrap().iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", filter)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Filters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filters")
+            .field("mode", &self.mode())
+            .field("filters", &self.filters())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Address of a client from the `ip` userinfo key, e.g. `1.2.3.4:27960` or `[::1]:27960`
+pub(crate) fn parse_client_addr(ip: &str) -> Option<IpAddr> {
+    if let Some(rest) = ip.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    match ip.rsplit_once(':') {
+        Some((addr, _port)) if !addr.contains(':') => addr.parse().ok(),
+        _ => ip.parse().ok(),
+    }
+}
@@ -0,0 +1,152 @@
+//! Flood protection for client commands
+//!
+//! Clients can send commands like `say` or `callvote` as fast as their connection allows.
+//! A [`FloodGuard`](FloodGuard) gives every client a bucket of commands that refills over level time,
+//! so [`Module::client_command`](super::Module::client_command) can ignore clients that exceed it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::flood::FloodGuard;
+//! use quake3_native_vm::time::LevelTime;
+//! use std::time::Duration;
+//!
+//! let guard = FloodGuard::new(2, Duration::from_secs(1));
+//! assert!(guard.allow(0, LevelTime(1000)));
+//! assert!(guard.allow(0, LevelTime(1100)));
+//! assert!(!guard.allow(0, LevelTime(1200)));
+//!
+//! // Other clients have their own bucket
+//! assert!(guard.allow(1, LevelTime(1200)));
+//!
+//! // One command per second refills
+//! assert!(guard.allow(0, LevelTime(2000)));
+//! assert!(!guard.allow(0, LevelTime(2100)));
+//! ```
+
+use super::{Syscalls, Target};
+use crate::shared::MAX_CLIENTS;
+use crate::time::LevelTime;
+use crate::{ffi, EngineSyscalls};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Message printed to clients whose command was ignored by [`FloodGuard::check`](FloodGuard::check)
+pub const FLOOD_MESSAGE: &str = "Flood protection: command ignored\n";
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: u32,
+    refilled: LevelTime,
+}
+
+/// Per-client token bucket of commands, keyed on level time
+#[derive(Debug)]
+pub struct FloodGuard {
+    burst: u32,
+    interval: Duration,
+    buckets: Mutex<Vec<Option<Bucket>>>,
+}
+
+impl FloodGuard {
+    /// Allow up to `burst` commands at once, and one more every `interval`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `burst` is `0` or `interval` is zero.
+    pub fn new(burst: u32, interval: Duration) -> Self {
+        assert!(burst > 0, "FloodGuard burst must not be 0");
+        assert!(!interval.is_zero(), "FloodGuard interval must not be zero");
+        Self {
+            burst,
+            interval,
+            buckets: Mutex::new(vec![None; MAX_CLIENTS]),
+        }
+    }
+
+    /// Number of commands a client can send at once
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Time for one more command to become available
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Take a command from the bucket of `client_number` at level time `now`, returning whether there was one
+    ///
+    /// A level time before the last command, e.g. after a `map_restart`, refills the bucket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `client_number` is not a valid client, i.e. below [`MAX_CLIENTS`](crate::shared::MAX_CLIENTS).
+    pub fn allow(&self, client_number: ffi::c_int, now: LevelTime) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets[index(client_number)].get_or_insert(Bucket {
+            tokens: self.burst,
+            refilled: now,
+        });
+
+        if now < bucket.refilled {
+            *bucket = Bucket {
+                tokens: self.burst,
+                refilled: now,
+            };
+        }
+        let elapsed = now - bucket.refilled;
+        let refills = elapsed.as_nanos() / self.interval.as_nanos();
+        if refills > 0 {
+            let refills = u32::try_from(refills).unwrap_or(u32::MAX);
+            bucket.tokens = bucket.tokens.saturating_add(refills).min(self.burst);
+            bucket.refilled = if bucket.tokens == self.burst {
+                now
+            } else {
+                bucket.refilled + self.interval * refills
+            };
+        }
+
+        if bucket.tokens == 0 {
+            return false;
+        }
+        bucket.tokens -= 1;
+        true
+    }
+
+    /// Like [`allow`](FloodGuard::allow), but print [`FLOOD_MESSAGE`](FLOOD_MESSAGE) to the client if not
+    pub fn check<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        client_number: ffi::c_int,
+        now: LevelTime,
+    ) -> bool {
+        let allowed = self.allow(client_number, now);
+        if !allowed {
+            syscalls.console_print(Target::Client(client_number), FLOOD_MESSAGE);
+        }
+        allowed
+    }
+
+    /// Forget the bucket of `client_number`, e.g. in [`Module::client_disconnect`](super::Module::client_disconnect)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `client_number` is not a valid client, i.e. below [`MAX_CLIENTS`](crate::shared::MAX_CLIENTS).
+    pub fn reset(&self, client_number: ffi::c_int) {
+        self.buckets.lock().unwrap()[index(client_number)] = None;
+    }
+}
+
+impl Default for FloodGuard {
+    /// One command per second, like the engine's `sv_floodProtect`
+    fn default() -> Self {
+        Self::new(1, Duration::from_secs(1))
+    }
+}
+
+fn index(client_number: ffi::c_int) -> usize {
+    match usize::try_from(client_number) {
+        Ok(index) if index < MAX_CLIENTS => index,
+        _ => panic!("Invalid client number {}", client_number),
+    }
+}
@@ -0,0 +1,136 @@
+//! Files in the mod directory
+//!
+//! Modules should go through the engine's virtual filesystem instead of [`std::fs`](std::fs),
+//! so that paths are confined to the mod directory and `pk3` archives are searched as well.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use quake3_native_vm::qagame::fs::File;
+//! use quake3_native_vm::qagame::{FsMode, Syscalls};
+//! use std::io::{Read, Write};
+//!
+//! # fn example(syscalls: &Syscalls) -> std::io::Result<()> {
+//! let mut motd = String::new();
+//! File::open(syscalls, "motd.txt", FsMode::FS_READ)?.read_to_string(&mut motd)?;
+//!
+//! let mut log = File::open(syscalls, "visitors.log", FsMode::FS_APPEND)?;
+//! writeln!(log, "{}", motd.trim())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Files can be tested without the engine with another [`EngineSyscalls`](crate::EngineSyscalls) backend:
+//!
+//! ```rust
+//! use quake3_native_vm::backend::Mock;
+//! use quake3_native_vm::qagame::fs::File;
+//! use quake3_native_vm::qagame::{FsMode, Syscalls};
+//! use std::io::ErrorKind;
+//!
+//! // No file handle, i.e. the file does not exist
+//! let syscalls = Syscalls::with_engine(Mock(|_, _: &[isize]| 0));
+//! let err = File::open(&syscalls, "motd.txt", FsMode::FS_READ).unwrap_err();
+//! assert_eq!(err.kind(), ErrorKind::NotFound);
+//! ```
+
+use super::{FsMode, Syscalls};
+use crate::{ffi, EngineSyscalls, Syscall};
+use std::io::Read;
+use std::{fmt, io};
+
+/// Read the whole file `qpath`, like [`std::fs::read`](std::fs::read) but through the engine
+pub fn read<S: EngineSyscalls>(syscalls: &Syscalls<S>, qpath: &str) -> io::Result<Vec<u8>> {
+    let mut file = File::open(syscalls, qpath, FsMode::FS_READ)?;
+    let mut bytes = Vec::with_capacity(file.remaining);
+    file.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read the whole file `qpath` as UTF-8, like [`std::fs::read_to_string`](std::fs::read_to_string) but through the engine
+pub fn read_to_string<S: EngineSyscalls>(
+    syscalls: &Syscalls<S>,
+    qpath: &str,
+) -> io::Result<String> {
+    String::from_utf8(read(syscalls, qpath)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Open file of the engine's virtual filesystem, closed on drop
+///
+/// See `fileHandle_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub struct File<'a, S: EngineSyscalls = Syscall> {
+    syscalls: &'a Syscalls<S>,
+    handle: ffi::c_int,
+    /// Bytes left to read, the engine does not report short reads
+    remaining: usize,
+}
+
+impl<'a, S: EngineSyscalls> File<'a, S> {
+    /// Open `qpath` relative to the mod directory
+    ///
+    /// See `trap_FS_FOpenFile` in [ioquake3's `game/g_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_syscalls.c).
+    pub fn open(syscalls: &'a Syscalls<S>, qpath: &str, mode: FsMode) -> io::Result<Self> {
+        let qpath = qpath.replace('\0', "");
+        let (handle, len) = syscalls.fs_fopen_file(qpath.as_str(), mode);
+        if handle == 0 {
+            let kind = match mode {
+                FsMode::FS_READ => io::ErrorKind::NotFound,
+                _ => io::ErrorKind::Other,
+            };
+            return Err(io::Error::new(kind, format!("Could not open {}", qpath)));
+        }
+
+        Ok(Self {
+            syscalls,
+            handle,
+            remaining: match mode {
+                FsMode::FS_READ => usize::try_from(len).unwrap_or(0),
+                _ => 0,
+            },
+        })
+    }
+
+    /// Raw handle for the `fs_*` functions of [`Syscalls`](super::Syscalls)
+    pub fn handle(&self) -> ffi::c_int {
+        self.handle
+    }
+}
+
+impl<S: EngineSyscalls> io::Read for File<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.remaining);
+        if len > 0 {
+            self.syscalls.fs_read(&mut buf[..len], self.handle);
+            self.remaining -= len;
+        }
+        Ok(len)
+    }
+}
+
+impl<S: EngineSyscalls> io::Write for File<'_, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(ffi::c_int::MAX as usize);
+        self.syscalls.fs_write(&buf[..len], self.handle);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: EngineSyscalls> Drop for File<'_, S> {
+    fn drop(&mut self) {
+        self.syscalls.fs_fclose_file(self.handle);
+    }
+}
+
+impl<S: EngineSyscalls> fmt::Debug for File<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("File")
+            .field("handle", &self.handle)
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
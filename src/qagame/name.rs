@@ -0,0 +1,142 @@
+//! Player names
+//!
+//! Players can pick almost arbitrary names in their `name` userinfo key.
+//! The game cleans them up before showing them to other players.
+
+use crate::color::{Color, ColoredStr, Q_COLOR_ESCAPE};
+
+/// Maximum length of a player name, including the terminating NUL
+///
+/// See `MAX_NETNAME` in [ioquake3's `game/g_local.h`](https://github.com/ioquake/ioq3/blob/master/code/game/g_local.h).
+pub const MAX_NETNAME: usize = 36;
+
+/// Name used instead of empty or invisible names
+pub const UNNAMED_PLAYER: &str = "UnnamedPlayer";
+
+/// Sanitized player name
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::qagame::name::CleanName;
+///
+/// let name = CleanName::new("  ^1Sarge^0    the\tgreat");
+/// assert_eq!(name.display(), "^1Sarge   thegreat");
+/// assert_eq!(name.clean(), "Sarge   thegreat");
+///
+/// assert_eq!(CleanName::new("^3").display(), "UnnamedPlayer");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CleanName {
+    display: String,
+    clean: String,
+}
+
+impl CleanName {
+    /// Clean up a name as requested by a player
+    ///
+    /// Like the engine, this
+    ///
+    /// * discards leading spaces and more than three consecutive spaces
+    /// * discards black color codes, which would make the name hard to see
+    /// * limits the name to [`MAX_NETNAME`](MAX_NETNAME) bytes
+    /// * replaces empty names with [`UNNAMED_PLAYER`](UNNAMED_PLAYER)
+    ///
+    /// Additionally control characters are discarded.
+    ///
+    /// See `ClientCleanName` in [ioquake3's `game/g_client.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_client.c).
+    pub fn new(name: &str) -> Self {
+        let mut display = String::with_capacity(MAX_NETNAME);
+        let mut colorless_len = 0;
+        let mut spaces = 0;
+
+        for c in name
+            .trim_start_matches(' ')
+            .chars()
+            .filter(|c| !c.is_control())
+        {
+            if display.len() + c.len_utf8() >= MAX_NETNAME {
+                break;
+            }
+
+            if c == ' ' {
+                if spaces > 2 {
+                    continue;
+                }
+                spaces += 1;
+            } else if display.ends_with(Q_COLOR_ESCAPE) && c.is_ascii_alphanumeric() {
+                colorless_len -= 1;
+                if Color::from_code(c) == Color::Black {
+                    display.pop();
+                    continue;
+                }
+            } else {
+                spaces = 0;
+                colorless_len += 1;
+            }
+
+            display.push(c);
+        }
+
+        if colorless_len <= 0 {
+            display = UNNAMED_PLAYER.to_owned();
+        }
+
+        Self::from_display(display)
+    }
+
+    fn from_display(display: String) -> Self {
+        let clean = ColoredStr::new(&display).strip();
+        Self { display, clean }
+    }
+
+    /// Name with color codes, for showing to players
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+
+    /// Name without color codes, for comparing names
+    pub fn clean(&self) -> &str {
+        &self.clean
+    }
+
+    /// Whether both names look the same to players, ignoring colors and case
+    pub fn is_same(&self, other: &CleanName) -> bool {
+        self.clean.eq_ignore_ascii_case(&other.clean)
+    }
+
+    /// Make this name distinct from the `existing` names of other clients
+    ///
+    /// Appends a number, e.g. `Sarge (2)`, if another client already uses the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quake3_native_vm::qagame::name::CleanName;
+    ///
+    /// let existing = [CleanName::new("^1Sarge"), CleanName::new("Sarge (2)")];
+    /// let name = CleanName::new("sarge").deduplicate(&existing);
+    /// assert_eq!(name.display(), "sarge (3)");
+    /// ```
+    pub fn deduplicate<'a>(self, existing: impl IntoIterator<Item = &'a CleanName>) -> Self {
+        let existing: Vec<&CleanName> = existing.into_iter().collect();
+        if !existing.iter().any(|other| self.is_same(other)) {
+            return self;
+        }
+
+        (2..)
+            .map(|n| {
+                let suffix = format!(" ({})", n);
+                let mut display = self.display.clone();
+                while display.len() + suffix.len() >= MAX_NETNAME
+                    || display.ends_with(Q_COLOR_ESCAPE)
+                {
+                    display.pop();
+                }
+                display.push_str(&suffix);
+                Self::from_display(display)
+            })
+            .find(|candidate| !existing.iter().any(|other| candidate.is_same(other)))
+            .unwrap()
+    }
+}
@@ -0,0 +1,137 @@
+//! Analyzing entities on all cores in [`Module::run_frame`](super::Module::run_frame)
+//!
+//! Requires the `rayon` feature.
+//!
+//! Much of a frame only reads entities, e.g. which players see each other or which target a bot picks,
+//! and can be split across threads with [rayon](https://docs.rs/rayon). Syscalls and changes to
+//! the entities still have to happen on the engine thread, so workers return them as closures for a
+//! [`SyscallQueue`](crate::queue::SyscallQueue) instead, see [`schedule`](schedule).
+//!
+//! Results are in the order of the entity numbers no matter which thread produced them,
+//! so frames stay deterministic, e.g. for the lockstep tests of `quake3-native-vm-mock` and demos.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::entity::SharedEntity;
+//! use quake3_native_vm::qagame::parallel;
+//! use quake3_native_vm::qagame::query::GameEntity;
+//! use quake3_native_vm::queue::SyscallQueue;
+//! use std::sync::Mutex;
+//!
+//! #[derive(Default)]
+//! struct Entity {
+//!     shared: SharedEntity,
+//!     in_use: bool,
+//!     health: i32,
+//! }
+//!
+//! impl GameEntity for Entity {
+//!     fn shared(&self) -> &SharedEntity {
+//!         &self.shared
+//!     }
+//!     fn classname(&self) -> &str {
+//!         "player"
+//!     }
+//!     fn in_use(&self) -> bool {
+//!         self.in_use
+//!     }
+//! }
+//!
+//! /// What the closures run with on the engine thread, usually the module with its `Syscalls`
+//! struct Game {
+//!     entities: Mutex<Vec<Entity>>,
+//! }
+//!
+//! let game = Game {
+//!     entities: Mutex::new((0..64).map(|n| Entity { in_use: true, health: n, ..Entity::default() }).collect()),
+//! };
+//! let queue = SyscallQueue::new();
+//!
+//! // In `Module::run_frame`, read-only on all cores
+//! let scheduled = parallel::schedule(&game.entities.lock().unwrap(), &queue.sender(), |number, entity| {
+//!     (entity.health < 10).then(|| move |game: &Game| game.entities.lock().unwrap()[number as usize].health = 100)
+//! });
+//! assert_eq!(scheduled, 10);
+//!
+//! // Then on the engine thread
+//! queue.drain(&game);
+//! assert!(game.entities.lock().unwrap().iter().all(|entity| entity.health >= 10));
+//! ```
+
+use super::query::GameEntity;
+use crate::ffi;
+use crate::queue::SyscallSender;
+use rayon::prelude::*;
+
+/// Number and entity of the entities in use, split across rayon's threads
+///
+/// This is the parallel version of [`query::entities`](super::query::entities).
+pub fn par_entities<E: GameEntity + Sync>(
+    entities: &[E],
+) -> impl ParallelIterator<Item = (ffi::c_int, &E)> {
+    entities
+        .par_iter()
+        .enumerate()
+        .filter(|(_, entity)| entity.in_use())
+        .map(|(number, entity)| (number as ffi::c_int, entity))
+}
+
+/// Results of `analyze` for every entity in use, computed on rayon's threads
+///
+/// `None` results are dropped. The others are in the order of the entity numbers.
+///
+/// `analyze` must not make syscalls, as it does not run on the engine thread.
+///
+/// ```rust
+/// # use quake3_native_vm::qagame::entity::SharedEntity;
+/// # use quake3_native_vm::qagame::query::GameEntity;
+/// use quake3_native_vm::qagame::parallel;
+///
+/// # #[derive(Default)]
+/// # struct Entity { shared: SharedEntity, in_use: bool }
+/// # impl GameEntity for Entity {
+/// #     fn shared(&self) -> &SharedEntity { &self.shared }
+/// #     fn classname(&self) -> &str { "player" }
+/// #     fn in_use(&self) -> bool { self.in_use }
+/// # }
+/// let mut level: Vec<Entity> = (0..1024).map(|_| Entity { in_use: true, ..Entity::default() }).collect();
+/// level[3].shared.r.current_origin = [0.0, 0.0, 100.0];
+/// level[700].shared.r.current_origin = [0.0, 0.0, 200.0];
+/// level[700].in_use = false;
+///
+/// let airborne = parallel::analyze(&level, |_, entity| {
+///     let height = entity.shared().r.current_origin[2];
+///     (height > 0.0).then_some(height)
+/// });
+/// assert_eq!(airborne, [(3, 100.0)]);
+/// ```
+pub fn analyze<E, T, F>(entities: &[E], analyze: F) -> Vec<(ffi::c_int, T)>
+where
+    E: GameEntity + Sync,
+    T: Send,
+    F: Fn(ffi::c_int, &E) -> Option<T> + Sync + Send,
+{
+    par_entities(entities)
+        .filter_map(|(number, entity)| Some((number, analyze(number, entity)?)))
+        .collect()
+}
+
+/// Send the closures `analyze` returns for the entities in use to run on the engine thread,
+/// returning how many were sent
+///
+/// `analyze` runs on rayon's threads, while the closures run with the `S` of a
+/// [`SyscallQueue::drain`](crate::queue::SyscallQueue::drain), in the order of the entity numbers.
+pub fn schedule<E, S, J, F>(entities: &[E], sender: &SyscallSender<S>, analyze: F) -> usize
+where
+    E: GameEntity + Sync,
+    J: FnOnce(&S) + Send + 'static,
+    F: Fn(ffi::c_int, &E) -> Option<J> + Sync + Send,
+{
+    let jobs = self::analyze(entities, analyze);
+    let count = jobs.len();
+    for (_, job) in jobs {
+        sender.send(job);
+    }
+    count
+}
@@ -0,0 +1,348 @@
+//! Keeping module state across `map_restart`
+//!
+//! Requires the `serde` feature.
+//!
+//! The engine shuts the module down and initializes it again on every `map_restart`, e.g. between warmup and match,
+//! so all state in Rust memory is lost.
+//! The C game keeps its session data in cvars for this, see `G_WriteSessionData` in
+//! [ioquake3's `game/g_session.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_session.c).
+//! [`PersistentState`](PersistentState) does the same for any [serde](https://serde.rs/) type,
+//! as JSON in a cvar or in a file of the mod directory.
+//! [`Session`](Session) keeps data per client, and also survives map changes.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use quake3_native_vm::qagame::persist::PersistentState;
+//! use quake3_native_vm::qagame::Syscalls;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Default, Serialize, Deserialize)]
+//! struct Warmup {
+//!     rounds_played: u32,
+//! }
+//!
+//! # fn example(syscalls: &Syscalls, restart: bool) {
+//! let persistent = PersistentState::cvar("g_rust_warmup");
+//!
+//! // In `Module::init`
+//! let mut warmup: Warmup = persistent.init(syscalls, restart).unwrap_or_default();
+//! warmup.rounds_played += 1;
+//!
+//! // In `Module::shutdown`
+//! if let Err(e) = persistent.shutdown(syscalls, restart, &warmup) {
+//!     syscalls.print(format!("Could not keep warmup state: {}\n", e));
+//! }
+//! # }
+//! ```
+
+use super::fs::File;
+use super::{FsMode, Syscalls, MAX_CVAR_VALUE_STRING};
+use crate::{ffi, EngineSyscalls};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+/// Error while saving or restoring a [`PersistentState`](PersistentState) or a [`Store`](super::store::Store)
+#[derive(Debug)]
+pub enum PersistError {
+    /// Serialized state would not fit into a cvar
+    TooLong {
+        /// Length of the serialized state
+        len: usize,
+        /// Size of the engine's cvar buffer, including the terminating NUL
+        max: usize,
+    },
+    /// Could not read or write the file
+    Io(io::Error),
+    /// Could not serialize or deserialize the state
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong { len, max } => {
+                write!(f, "state of length {} exceeds {}", len, max - 1)
+            }
+            Self::Io(e) => write!(f, "state file: {}", e),
+            Self::Json(e) => write!(f, "state JSON: {}", e),
+        }
+    }
+}
+
+impl Error for PersistError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::TooLong { .. } => None,
+            Self::Io(e) => Some(e),
+            Self::Json(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for PersistError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PersistError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// Where a [`PersistentState`](PersistentState) is kept
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Storage {
+    /// In the cvar with this name, limited to [`MAX_CVAR_VALUE_STRING`](super::MAX_CVAR_VALUE_STRING)
+    Cvar(String),
+    /// In the file with this path, relative to the mod directory
+    File(String),
+}
+
+/// State of type `T` that survives a `map_restart`, but not a map change or server restart
+#[derive(Debug)]
+pub struct PersistentState<T> {
+    storage: Storage,
+    state: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> PersistentState<T> {
+    /// Keep the state in `storage`
+    pub fn new(storage: Storage) -> Self {
+        Self {
+            storage,
+            state: PhantomData,
+        }
+    }
+
+    /// Keep the state in the cvar `name`
+    pub fn cvar(name: &str) -> Self {
+        Self::new(Storage::Cvar(name.to_owned()))
+    }
+
+    /// Keep the state in the file `path`, relative to the mod directory
+    pub fn file(path: &str) -> Self {
+        Self::new(Storage::File(path.to_owned()))
+    }
+
+    /// Where the state is kept
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    /// Save `state`, to be restored by [`init`](PersistentState::init), if the game is shut down for a `restart`
+    ///
+    /// Call this from [`Module::shutdown`](super::Module::shutdown).
+    pub fn shutdown<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        restart: bool,
+        state: &T,
+    ) -> Result<(), PersistError> {
+        if restart {
+            self.save(syscalls, state)
+        } else {
+            self.clear(syscalls)
+        }
+    }
+
+    /// Restore the state saved by [`shutdown`](PersistentState::shutdown) if the game is initialized after a `restart`
+    ///
+    /// Returns `None` without a `restart`, if nothing was saved or if the saved state is invalid.
+    /// The saved state is cleared either way, so it is only restored once.
+    ///
+    /// Call this from [`Module::init`](super::Module::init).
+    pub fn init<S: EngineSyscalls>(&self, syscalls: &Syscalls<S>, restart: bool) -> Option<T> {
+        let state = if restart {
+            self.load(syscalls).ok().flatten()
+        } else {
+            None
+        };
+        // Nothing to report if there was no state to begin with
+        let _ = self.clear(syscalls);
+        state
+    }
+
+    /// Save `state` now
+    pub fn save<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        state: &T,
+    ) -> Result<(), PersistError> {
+        let json = serde_json::to_string(state)?;
+        match &self.storage {
+            Storage::Cvar(name) => {
+                if json.len() >= MAX_CVAR_VALUE_STRING {
+                    return Err(PersistError::TooLong {
+                        len: json.len(),
+                        max: MAX_CVAR_VALUE_STRING,
+                    });
+                }
+                // JSON escapes control characters, so there can't be a NUL
+                syscalls.cvar_set(name.as_str(), json);
+            }
+            Storage::File(path) => {
+                File::open(syscalls, path, FsMode::FS_WRITE)?.write_all(json.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the saved state now, `None` if nothing was saved
+    pub fn load<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+    ) -> Result<Option<T>, PersistError> {
+        let json = match &self.storage {
+            Storage::Cvar(name) => syscalls.cvar_variable_string_buffer(name.as_str()),
+            Storage::File(path) => {
+                let mut json = String::new();
+                match File::open(syscalls, path, FsMode::FS_READ) {
+                    Ok(mut file) => file.read_to_string(&mut json)?,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+                    Err(e) => return Err(e.into()),
+                };
+                json
+            }
+        };
+        if json.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    /// Remove the saved state
+    pub fn clear<S: EngineSyscalls>(&self, syscalls: &Syscalls<S>) -> Result<(), PersistError> {
+        match &self.storage {
+            Storage::Cvar(name) => syscalls.cvar_set(name.as_str(), ""),
+            // The engine can't delete files, an empty file counts as nothing saved
+            Storage::File(path) => drop(File::open(syscalls, path, FsMode::FS_WRITE)?),
+        }
+        Ok(())
+    }
+}
+
+/// Per-client data of type `T` that survives map changes and `map_restart`, e.g. the team
+///
+/// Stored as JSON in the `session<N>` cvar of each client, like the C game does.
+///
+/// See `G_WriteClientSessionData` and `G_ReadSessionData` in
+/// [ioquake3's `game/g_session.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_session.c).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use quake3_native_vm::qagame::persist::Session;
+/// use quake3_native_vm::qagame::Syscalls;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct ClientSession {
+///     team: u8,
+///     wins: u32,
+/// }
+///
+/// # fn example(syscalls: &Syscalls, client_number: i32, first_time: bool) {
+/// let sessions = Session::new();
+///
+/// // In `Module::client_connect`
+/// let session: ClientSession = sessions
+///     .connect(syscalls, client_number, first_time)
+///     .unwrap_or_default();
+///
+/// // In `Module::shutdown`, for every connected client
+/// if let Err(e) = sessions.write(syscalls, client_number, &session) {
+///     syscalls.print(format!("Could not write session of client {}: {}\n", client_number, e));
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Session<T> {
+    prefix: String,
+    state: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> Session<T> {
+    /// Store sessions in the `session<N>` cvars of the C game
+    pub fn new() -> Self {
+        Self::with_prefix("session")
+    }
+
+    /// Store sessions in `<prefix><N>` cvars, e.g. to not clash with the C game's format
+    pub fn with_prefix(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+            state: PhantomData,
+        }
+    }
+
+    /// Name of the cvar for the session of `client_number`
+    pub fn cvar_name(&self, client_number: ffi::c_int) -> String {
+        format!("{}{}", self.prefix, client_number)
+    }
+
+    fn cvar(&self, client_number: ffi::c_int) -> PersistentState<T> {
+        PersistentState::cvar(&self.cvar_name(client_number))
+    }
+
+    /// Write the session of `client_number`
+    ///
+    /// Call this from [`Module::shutdown`](super::Module::shutdown) for every connected client.
+    pub fn write<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        client_number: ffi::c_int,
+        session: &T,
+    ) -> Result<(), PersistError> {
+        self.cvar(client_number).save(syscalls, session)
+    }
+
+    /// Read the session of `client_number`, `None` if there is none
+    pub fn read<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        client_number: ffi::c_int,
+    ) -> Result<Option<T>, PersistError> {
+        self.cvar(client_number).load(syscalls)
+    }
+
+    /// Read the session of a connecting client, unless it connects for the `first_time`
+    ///
+    /// Returns `None` for new clients, without a session or with an invalid one.
+    ///
+    /// Call this from [`Module::client_connect`](super::Module::client_connect).
+    pub fn connect<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        client_number: ffi::c_int,
+        first_time: bool,
+    ) -> Option<T> {
+        if first_time {
+            // A previous client in this slot might have left a session behind
+            let _ = self.clear(syscalls, client_number);
+            return None;
+        }
+        self.read(syscalls, client_number).ok().flatten()
+    }
+
+    /// Remove the session of `client_number`
+    pub fn clear<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        client_number: ffi::c_int,
+    ) -> Result<(), PersistError> {
+        self.cvar(client_number).clear(syscalls)
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Default for Session<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,185 @@
+//! Finding entities by classname or position
+//!
+//! The game keeps its entities in one array, usually of a type starting with a [`SharedEntity`](super::entity::SharedEntity).
+//! Implementing [`GameEntity`](GameEntity) for that type allows searching them like `G_Find` and `findradius` do in the C game.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::entity::SharedEntity;
+//! use quake3_native_vm::qagame::query::{entities, EntityIteratorExt, GameEntity};
+//!
+//! #[derive(Default)]
+//! struct Entity {
+//!     shared: SharedEntity,
+//!     classname: &'static str,
+//!     in_use: bool,
+//! }
+//!
+//! impl GameEntity for Entity {
+//!     fn shared(&self) -> &SharedEntity {
+//!         &self.shared
+//!     }
+//!     fn classname(&self) -> &str {
+//!         self.classname
+//!     }
+//!     fn in_use(&self) -> bool {
+//!         self.in_use
+//!     }
+//! }
+//!
+//! let mut level: Vec<Entity> = (0..4).map(|_| Entity::default()).collect();
+//! level[1] = Entity { classname: "item_quad", in_use: true, ..Default::default() };
+//! level[2] = Entity { classname: "item_quad", in_use: false, ..Default::default() };
+//! level[3] = Entity { classname: "item_armor_body", in_use: true, ..Default::default() };
+//! // Bounds are set by the engine when linking the entity
+//! level[3].shared.r.absmin = [100.0, -10.0, -10.0];
+//! level[3].shared.r.absmax = [120.0, 10.0, 10.0];
+//!
+//! assert_eq!(entities(&level).with_classname("ITEM_QUAD").count(), 1);
+//! assert_eq!(entities(&level).within([0.0, 0.0, 0.0], 50.0).count(), 1);
+//! assert_eq!(entities(&level).within([0.0, 0.0, 0.0], 150.0).count(), 2);
+//! ```
+
+use super::entity::SharedEntity;
+use super::Syscalls;
+use crate::shared::Vec3;
+use crate::EngineSyscalls;
+
+/// Game entity with a classname
+pub trait GameEntity {
+    /// Part of the entity shared with the engine
+    fn shared(&self) -> &SharedEntity;
+
+    /// Classname, e.g. `item_quad`
+    fn classname(&self) -> &str;
+
+    /// Whether the entity slot is in use, see `gentity_t.inuse`
+    fn in_use(&self) -> bool;
+}
+
+/// Entities in use, in the order of their numbers
+///
+/// See `G_Find` in [ioquake3's `game/g_utils.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_utils.c).
+pub fn entities<E: GameEntity>(entities: &[E]) -> Entities<'_, E> {
+    Entities {
+        iter: entities.iter(),
+    }
+}
+
+/// Iterator of the entities in use, see [`entities`](entities)
+#[derive(Clone, Debug)]
+pub struct Entities<'a, E> {
+    iter: std::slice::Iter<'a, E>,
+}
+
+impl<'a, E: GameEntity> Iterator for Entities<'a, E> {
+    type Item = &'a E;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.by_ref().find(|entity| entity.in_use())
+    }
+}
+
+/// Distance from `origin` to the closest point of the bounds `absmin` to `absmax`, `0` if within
+pub fn distance_to_bounds(origin: Vec3, absmin: Vec3, absmax: Vec3) -> f32 {
+    let mut squared = 0.0;
+    for i in 0..3 {
+        let d = if origin[i] < absmin[i] {
+            absmin[i] - origin[i]
+        } else if origin[i] > absmax[i] {
+            origin[i] - absmax[i]
+        } else {
+            0.0
+        };
+        squared += d * d;
+    }
+    f32::sqrt(squared)
+}
+
+fn is_within<E: GameEntity>(entity: &E, origin: Vec3, radius: f32) -> bool {
+    let r = &entity.shared().r;
+    distance_to_bounds(origin, r.absmin, r.absmax) < radius
+}
+
+/// Adapters for iterators of [`GameEntity`](GameEntity)s
+pub trait EntityIteratorExt<'a, E: GameEntity + 'a>: Iterator<Item = &'a E> + Sized {
+    /// Only entities with `classname`, ignoring case
+    fn with_classname(self, classname: &'a str) -> WithClassname<'a, Self> {
+        WithClassname {
+            iter: self,
+            classname,
+        }
+    }
+
+    /// Only entities whose bounds are closer than `radius` to `origin`
+    ///
+    /// This looks at every entity, see [`entities_within`](entities_within) to let the engine narrow them down first.
+    ///
+    /// See `G_RadiusDamage` in [ioquake3's `game/g_combat.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_combat.c).
+    fn within(self, origin: Vec3, radius: f32) -> Within<Self> {
+        Within {
+            iter: self,
+            origin,
+            radius,
+        }
+    }
+}
+
+impl<'a, E: GameEntity + 'a, I: Iterator<Item = &'a E>> EntityIteratorExt<'a, E> for I {}
+
+/// Iterator of the entities with a classname, see [`EntityIteratorExt::with_classname`](EntityIteratorExt::with_classname)
+#[derive(Clone, Debug)]
+pub struct WithClassname<'a, I> {
+    iter: I,
+    classname: &'a str,
+}
+
+impl<'a, E: GameEntity + 'a, I: Iterator<Item = &'a E>> Iterator for WithClassname<'a, I> {
+    type Item = &'a E;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let classname = self.classname;
+        self.iter
+            .find(|entity| entity.classname().eq_ignore_ascii_case(classname))
+    }
+}
+
+/// Iterator of the entities within a radius, see [`EntityIteratorExt::within`](EntityIteratorExt::within)
+#[derive(Clone, Debug)]
+pub struct Within<I> {
+    iter: I,
+    origin: Vec3,
+    radius: f32,
+}
+
+impl<'a, E: GameEntity + 'a, I: Iterator<Item = &'a E>> Iterator for Within<I> {
+    type Item = &'a E;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (origin, radius) = (self.origin, self.radius);
+        self.iter.find(|entity| is_within(*entity, origin, radius))
+    }
+}
+
+/// Linked entities in use whose bounds are closer than `radius` to `origin`
+///
+/// The engine finds the entities in the box around the sphere with [`Syscalls::entities_in_box`](Syscalls::entities_in_box),
+/// which are then filtered by distance.
+///
+/// See `G_RadiusDamage` in [ioquake3's `game/g_combat.c`](https://github.com/ioquake/ioq3/blob/master/code/game/g_combat.c).
+pub fn entities_within<'a, E: GameEntity, S: EngineSyscalls>(
+    syscalls: &Syscalls<S>,
+    entities: &'a [E],
+    origin: Vec3,
+    radius: f32,
+) -> impl Iterator<Item = &'a E> {
+    let mins = origin.map(|x| x - radius);
+    let maxs = origin.map(|x| x + radius);
+    syscalls
+        .entities_in_box(mins, maxs)
+        .into_iter()
+        .filter_map(move |number| entities.get(usize::try_from(number).ok()?))
+        .filter(|entity| entity.in_use())
+        .within(origin, radius)
+}
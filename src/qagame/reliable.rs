@@ -0,0 +1,175 @@
+//! Rate limiting reliable server commands
+//!
+//! The engine keeps a limited number of unacknowledged reliable commands per client and drops the client
+//! with "Server command overflow" when a mod sends too many at once, e.g. scores for every player on every event.
+//! A [`CommandQueue`](CommandQueue) buffers the commands instead and sends a bounded number of them
+//! to each client every frame, in the order they were queued.
+//!
+//! This spreads bursts over several frames, but does not prevent overflows: the module can not see which
+//! commands a client acknowledged, so a client that stops acknowledging still overflows eventually.
+//!
+//! See `SV_AddServerCommand` in [ioquake3's `server/sv_main.c`](https://github.com/ioquake/ioq3/blob/master/code/server/sv_main.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::reliable::CommandQueue;
+//! use quake3_native_vm::qagame::Target;
+//!
+//! let queue = CommandQueue::new(2);
+//! for i in 0..3 {
+//!     queue.push(Target::Client(0), format!("print \"{}\\n\"", i));
+//! }
+//! queue.push(Target::All, "cp \"Fight!\"");
+//!
+//! // Once per frame, e.g. in `run_frame` with `queue.flush(&syscalls)`
+//! let mut sent = Vec::new();
+//! assert_eq!(queue.drain(|target, text| sent.push((target, text))), 2);
+//! assert_eq!(
+//!     sent,
+//!     [
+//!         (Target::Client(0), "print \"0\\n\"".to_owned()),
+//!         (Target::Client(0), "print \"1\\n\"".to_owned()),
+//!     ]
+//! );
+//!
+//! // The broadcast counts against the limit of client 0 as well, and stays behind its last command
+//! sent.clear();
+//! assert_eq!(queue.drain(|target, text| sent.push((target, text))), 2);
+//! assert_eq!(
+//!     sent,
+//!     [
+//!         (Target::Client(0), "print \"2\\n\"".to_owned()),
+//!         (Target::All, "cp \"Fight!\"".to_owned()),
+//!     ]
+//! );
+//! assert!(queue.is_empty());
+//! ```
+
+use super::{Syscalls, Target};
+use crate::{ffi, EngineSyscalls};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+
+/// Number of commands sent per client and frame by [`CommandQueue::default`](CommandQueue::default)
+pub const DEFAULT_PER_FRAME: usize = 4;
+
+/// Outgoing server commands, in the order they were queued
+#[derive(Debug)]
+pub struct CommandQueue {
+    per_frame: usize,
+    queue: Mutex<VecDeque<(ffi::c_int, String)>>,
+}
+
+impl CommandQueue {
+    /// Create an empty queue that sends up to `per_frame` commands per client and frame
+    ///
+    /// Broadcasts to [`Target::All`](Target::All) count against the limit of every client.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `per_frame` is `0`.
+    pub fn new(per_frame: usize) -> Self {
+        assert!(per_frame > 0, "CommandQueue per_frame must not be 0");
+        Self {
+            per_frame,
+            queue: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Number of commands sent per client and frame
+    pub fn per_frame(&self) -> usize {
+        self.per_frame
+    }
+
+    /// Enqueue the server command `text` for `target`
+    pub fn push<T: Into<String>>(&self, target: Target, text: T) {
+        self.queue
+            .lock()
+            .unwrap()
+            .push_back((ffi::c_int::from(target), text.into()));
+    }
+
+    /// Number of commands waiting for `target`, not counting broadcasts for a client
+    pub fn len(&self, target: Target) -> usize {
+        let target = ffi::c_int::from(target);
+        self.queue
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(t, _)| *t == target)
+            .count()
+    }
+
+    /// Whether no commands are waiting for any recipient
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().unwrap().is_empty()
+    }
+
+    /// Discard the commands waiting for `target`, e.g. in [`Module::client_disconnect`](super::Module::client_disconnect)
+    pub fn clear(&self, target: Target) {
+        let target = ffi::c_int::from(target);
+        self.queue.lock().unwrap().retain(|(t, _)| *t != target);
+    }
+
+    /// Pass the commands due this frame to `send` in queue order, returning how many
+    ///
+    /// Every client gets up to [`per_frame`](CommandQueue::per_frame) commands including broadcasts.
+    /// Commands never overtake earlier ones for the same client, so a broadcast that does not fit
+    /// holds back everything queued after it.
+    pub fn drain(&self, mut send: impl FnMut(Target, String)) -> usize {
+        let mut due = Vec::new();
+        {
+            let mut queue = self.queue.lock().unwrap();
+            let mut sent: BTreeMap<ffi::c_int, usize> = BTreeMap::new();
+            let mut broadcasts = 0;
+            let mut kept = VecDeque::new();
+            while let Some((target, text)) = queue.pop_front() {
+                let fits = match target {
+                    -1 => sent.values().max().copied().unwrap_or(0) + broadcasts < self.per_frame,
+                    client_number => {
+                        sent.get(&client_number).copied().unwrap_or(0) + broadcasts < self.per_frame
+                    }
+                };
+                if !fits {
+                    kept.push_back((target, text));
+                    if target == -1 {
+                        break;
+                    }
+                    continue;
+                }
+
+                match target {
+                    -1 => broadcasts += 1,
+                    client_number => *sent.entry(client_number).or_default() += 1,
+                }
+                due.push((target, text));
+            }
+            kept.append(&mut queue);
+            *queue = kept;
+        }
+
+        let count = due.len();
+        for (target, text) in due {
+            let target = match target {
+                -1 => Target::All,
+                client_number => Target::Client(client_number),
+            };
+            send(target, text);
+        }
+        count
+    }
+
+    /// Send the commands due this frame with [`Syscalls::send_server_command`](Syscalls::send_server_command)
+    ///
+    /// Call this once per frame, e.g. in [`Module::run_frame`](super::Module::run_frame).
+    pub fn flush<S: EngineSyscalls>(&self, syscalls: &Syscalls<S>) -> usize {
+        self.drain(|target, text| syscalls.send_server_command(target, text))
+    }
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_PER_FRAME)
+    }
+}
@@ -0,0 +1,235 @@
+//! Game logic written in [Rhai](https://rhai.rs/)
+//!
+//! Requires the `rhai` feature.
+//!
+//! [`ScriptedModule`](ScriptedModule) implements [`Module`](super::Module) by calling functions of
+//! a Rhai script with the same names as the `Module` functions, e.g. `fn init(level_time, random_seed, restart)`.
+//! Functions missing from the script are skipped.
+//! This lets server admins customize behavior without recompiling the module.
+//!
+//! The script is loaded on each `GAME_INIT` from the path in the `g_script` cvar,
+//! relative to the mod directory, and defaults to `game.rhai`. It is read through the engine's filesystem,
+//! so it can also be in a `pk3` archive.
+//!
+//! Scripts can use these functions to call into the engine:
+//!
+//! * `print(text)`
+//! * `cvar_get(name)` and `cvar_set(name, value)`
+//! * `argc()` and `argv(n)`
+//! * `send_console_command(text)`
+//! * `send_server_command(client_number, text)`, with `-1` for all clients
+//!
+//! `client_connect` can return a string to deny the connection with that message.
+//! `console_command` and `botai_start_frame` should return a `bool`.
+//!
+//! # Examples
+//!
+//! ```rhai
+//! fn init(level_time, random_seed, restart) {
+//!     print(`Hello from Rhai at ${level_time}\n`);
+//! }
+//!
+//! fn client_connect(client_number, first_time, is_bot) {
+//!     if !is_bot && cvar_get("g_botsonly") == "1" {
+//!         return "Only bots allowed";
+//!     }
+//! }
+//!
+//! fn client_begin(client_number) {
+//!     send_server_command(-1, `print "Client ${client_number} entered the game\n"`);
+//! }
+//! ```
+
+use super::{fs, CbufExec, Deny, Module, Syscalls, Target};
+use crate::{ffi, EngineSyscalls, Syscall};
+use rhai::{Dynamic, Engine, FuncArgs, Scope, AST, INT};
+use std::sync::Mutex;
+
+/// Cvar with the path of the script
+pub const SCRIPT_CVAR: &str = "g_script";
+
+/// Default path of the script
+pub const DEFAULT_SCRIPT: &str = "game.rhai";
+
+struct Script {
+    ast: AST,
+    scope: Scope<'static>,
+}
+
+/// [`Module`](super::Module) forwarding all callbacks to a Rhai script
+pub struct ScriptedModule<S = Syscall> {
+    syscalls: Syscalls<S>,
+    engine: Engine,
+    script: Mutex<Option<Script>>,
+}
+
+impl<S: EngineSyscalls + Copy + Send + Sync + 'static> ScriptedModule<S> {
+    /// Module without a script yet, which is loaded on [`Module::init`](super::Module::init)
+    pub fn new(syscalls: Syscalls<S>) -> Self {
+        Self {
+            engine: Self::engine(&syscalls),
+            syscalls,
+            script: Mutex::new(None),
+        }
+    }
+
+    fn engine(syscalls: &Syscalls<S>) -> Engine {
+        let mut engine = Engine::new();
+
+        let s = *syscalls;
+        engine.on_print(move |text| s.print(text));
+        let s = *syscalls;
+        engine.on_debug(move |text, _source, pos| s.print(format!("{} {}\n", pos, text)));
+
+        let s = *syscalls;
+        engine.register_fn("cvar_get", move |name: &str| {
+            s.cvar_variable_string_buffer(name)
+        });
+        let s = *syscalls;
+        engine.register_fn("cvar_set", move |name: &str, value: &str| {
+            s.cvar_set(name, value)
+        });
+        let s = *syscalls;
+        engine.register_fn("argc", move || s.argc() as INT);
+        let s = *syscalls;
+        engine.register_fn("argv", move |n: INT| s.argv(n as ffi::c_int));
+        let s = *syscalls;
+        engine.register_fn("send_console_command", move |text: &str| {
+            s.send_console_command(CbufExec::EXEC_APPEND, text)
+        });
+        let s = *syscalls;
+        engine.register_fn(
+            "send_server_command",
+            move |client_number: INT, text: &str| {
+                let target = match client_number {
+                    -1 => Target::All,
+                    n => Target::Client(n as ffi::c_int),
+                };
+                s.send_server_command(target, text)
+            },
+        );
+
+        engine
+    }
+
+    fn load(&self) {
+        let mut path = self.syscalls.cvar_variable_string_buffer(SCRIPT_CVAR);
+        if path.is_empty() {
+            path = DEFAULT_SCRIPT.to_owned();
+        }
+
+        let script = fs::read_to_string(&self.syscalls, &path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| self.engine.compile(source).map_err(|e| e.to_string()))
+            .and_then(|ast| {
+                let mut scope = Scope::new();
+                self.engine
+                    .run_ast_with_scope(&mut scope, &ast)
+                    .map_err(|e| e.to_string())?;
+                Ok(Script { ast, scope })
+            });
+
+        match script {
+            Ok(script) => *self.script.lock().unwrap() = Some(script),
+            Err(e) => {
+                *self.script.lock().unwrap() = None;
+                self.syscalls
+                    .print(format!("Could not load script {}: {}\n", path, e));
+            }
+        }
+    }
+
+    /// Call the script function `name` if it exists
+    fn call(&self, name: &str, args: impl FuncArgs) -> Option<Dynamic> {
+        let mut guard = self.script.lock().unwrap();
+        let script = guard.as_mut()?;
+
+        let mut arg_values = Vec::new();
+        args.parse(&mut arg_values);
+        if !script
+            .ast
+            .iter_functions()
+            .any(|f| f.name == name && f.params.len() == arg_values.len())
+        {
+            return None;
+        }
+
+        match self
+            .engine
+            .call_fn::<Dynamic>(&mut script.scope, &script.ast, name, arg_values)
+        {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.syscalls
+                    .print(format!("Script error in {}: {}\n", name, e));
+                None
+            }
+        }
+    }
+
+    fn call_bool(&self, name: &str, args: impl FuncArgs) -> bool {
+        self.call(name, args)
+            .and_then(|value| value.as_bool().ok())
+            .unwrap_or(false)
+    }
+}
+
+impl Module for ScriptedModule {
+    fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+        Box::new(Self::new(syscalls))
+    }
+
+    fn init(&self, level_time: ffi::c_int, random_seed: ffi::c_int, restart: bool) {
+        self.load();
+        self.call("init", (level_time as INT, random_seed as INT, restart));
+    }
+
+    fn shutdown(&self, restart: bool) {
+        self.call("shutdown", (restart,));
+    }
+
+    fn client_connect(
+        &self,
+        client_number: ffi::c_int,
+        first_time: bool,
+        is_bot: bool,
+    ) -> Result<(), Deny> {
+        let result = self.call("client_connect", (client_number as INT, first_time, is_bot));
+        match result.and_then(|value| value.into_string().ok()) {
+            Some(message) => Err(Deny::new(message)),
+            None => Ok(()),
+        }
+    }
+
+    fn client_think(&self, client_number: ffi::c_int) {
+        self.call("client_think", (client_number as INT,));
+    }
+
+    fn client_userinfo_changed(&self, client_number: ffi::c_int) {
+        self.call("client_userinfo_changed", (client_number as INT,));
+    }
+
+    fn client_disconnect(&self, client_number: ffi::c_int) {
+        self.call("client_disconnect", (client_number as INT,));
+    }
+
+    fn client_begin(&self, client_number: ffi::c_int) {
+        self.call("client_begin", (client_number as INT,));
+    }
+
+    fn client_command(&self, client_number: ffi::c_int) {
+        self.call("client_command", (client_number as INT,));
+    }
+
+    fn run_frame(&self, level_time: ffi::c_int) {
+        self.call("run_frame", (level_time as INT,));
+    }
+
+    fn console_command(&self) -> bool {
+        self.call_bool("console_command", ())
+    }
+
+    fn botai_start_frame(&self, level_time: ffi::c_int) -> bool {
+        self.call_bool("botai_start_frame", (level_time as INT,))
+    }
+}
@@ -0,0 +1,190 @@
+//! Key-value store in a file of the mod directory, e.g. for player stats
+//!
+//! Requires the `serde` feature.
+//!
+//! Unlike [`PersistentState`](super::persist::PersistentState), which only survives a `map_restart`,
+//! and [`Session`](super::persist::Session), which also survives map changes,
+//! a [`Store`](Store) is kept across server restarts. It is loaded once in
+//! [`Module::init`](super::Module::init), changed in memory and written back as a single JSON
+//! snapshot in [`Module::shutdown`](super::Module::shutdown), all through the engine's filesystem.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use quake3_native_vm::qagame::store::Store;
+//! use quake3_native_vm::qagame::Syscalls;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Default, Serialize, Deserialize)]
+//! struct Stats {
+//!     kills: u32,
+//!     deaths: u32,
+//! }
+//!
+//! # fn example(syscalls: &Syscalls, guid: &str) {
+//! // In `Module::init`
+//! let mut stats = Store::<Stats>::open(syscalls, "stats.json").unwrap_or_else(|e| {
+//!     syscalls.print(format!("Could not load stats: {}\n", e));
+//!     Store::new("stats.json")
+//! });
+//!
+//! // In `Module::client_command` etc.
+//! stats.update(guid, |stats| stats.kills += 1);
+//!
+//! // In `Module::shutdown`
+//! if let Err(e) = stats.flush(syscalls) {
+//!     syscalls.print(format!("Could not save stats: {}\n", e));
+//! }
+//! # }
+//! ```
+
+use super::fs::File;
+use super::persist::PersistError;
+use super::{FsMode, Syscalls};
+use crate::EngineSyscalls;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{btree_map, BTreeMap};
+use std::io::{self, Read, Write};
+
+/// Values of type `T` by string keys, e.g. player stats by GUID, kept in a file
+///
+/// Keys are sorted, so the file changes as little as possible between flushes.
+#[derive(Clone, Debug)]
+pub struct Store<T> {
+    path: String,
+    entries: BTreeMap<String, T>,
+    dirty: bool,
+}
+
+impl<T: Serialize + DeserializeOwned> Store<T> {
+    /// Empty store to be kept in the file `path`, relative to the mod directory
+    ///
+    /// The file is not read, so the first [`flush`](Store::flush) replaces it.
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            entries: BTreeMap::new(),
+            dirty: false,
+        }
+    }
+
+    /// Store kept in the file `path`, with the entries it has so far
+    ///
+    /// The store is empty if the file does not exist yet.
+    ///
+    /// ```rust
+    /// use quake3_native_vm::backend::Mock;
+    /// use quake3_native_vm::qagame::store::Store;
+    /// use quake3_native_vm::qagame::Syscalls;
+    ///
+    /// // No files exist
+    /// let syscalls = Syscalls::with_engine(Mock(|_, _: &[isize]| 0));
+    ///
+    /// let mut wins = Store::<u32>::open(&syscalls, "wins.json").unwrap();
+    /// assert!(wins.is_empty());
+    ///
+    /// wins.update("7f2a", |wins| *wins += 1);
+    /// wins.update("7f2a", |wins| *wins += 1);
+    /// assert_eq!(wins.get("7f2a"), Some(&2));
+    /// assert!(wins.is_dirty());
+    /// ```
+    pub fn open<S: EngineSyscalls>(
+        syscalls: &Syscalls<S>,
+        path: &str,
+    ) -> Result<Self, PersistError> {
+        let mut store = Self::new(path);
+        store.load(syscalls)?;
+        Ok(store)
+    }
+
+    /// Path of the file, relative to the mod directory
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Replace all entries with those in the file now, discarding changes that were not flushed
+    pub fn load<S: EngineSyscalls>(&mut self, syscalls: &Syscalls<S>) -> Result<(), PersistError> {
+        let mut json = String::new();
+        match File::open(syscalls, &self.path, FsMode::FS_READ) {
+            Ok(mut file) => file.read_to_string(&mut json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+        // The engine can't delete files, an empty file counts as an empty store
+        self.entries = if json.trim().is_empty() {
+            BTreeMap::new()
+        } else {
+            serde_json::from_str(&json)?
+        };
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Write all entries to the file if they changed since they were loaded or last flushed
+    ///
+    /// Call this from [`Module::shutdown`](super::Module::shutdown), or periodically so that less
+    /// is lost if the server crashes.
+    pub fn flush<S: EngineSyscalls>(&mut self, syscalls: &Syscalls<S>) -> Result<(), PersistError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let json = serde_json::to_vec_pretty(&self.entries)?;
+        File::open(syscalls, &self.path, FsMode::FS_WRITE)?.write_all(&json)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Whether there are changes to [`flush`](Store::flush)
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Value of `key`, if any
+    pub fn get(&self, key: &str) -> Option<&T> {
+        self.entries.get(key)
+    }
+
+    /// Set the value of `key`, returning the previous one
+    pub fn insert(&mut self, key: &str, value: T) -> Option<T> {
+        self.dirty = true;
+        self.entries.insert(key.to_owned(), value)
+    }
+
+    /// Remove the value of `key`, returning it
+    pub fn remove(&mut self, key: &str) -> Option<T> {
+        let value = self.entries.remove(key);
+        self.dirty |= value.is_some();
+        value
+    }
+
+    /// Change the value of `key` with `f`, starting from the default if there is none yet
+    pub fn update<F: FnOnce(&mut T)>(&mut self, key: &str, f: F)
+    where
+        T: Default,
+    {
+        self.dirty = true;
+        f(self.entries.entry(key.to_owned()).or_default());
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.dirty |= !self.entries.is_empty();
+        self.entries.clear();
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All entries, sorted by key
+    pub fn iter(&self) -> btree_map::Iter<'_, String, T> {
+        self.entries.iter()
+    }
+}
@@ -0,0 +1,415 @@
+//! Game logic compiled to [WebAssembly](https://webassembly.org/)
+//!
+//! Requires the `wasm` feature.
+//!
+//! [`WasmModule`](WasmModule) implements [`Module`](super::Module) by running a WebAssembly module
+//! with the same `vmMain`/syscall contract as a QVM, similar to ioquake3's QVM interpreter but for modern toolchains.
+//! The WebAssembly module is sandboxed, so it can only reach the engine through the syscalls listed below
+//! and can not access memory of the engine or the native module.
+//!
+//! The WebAssembly module is loaded on each `GAME_INIT` from the path in the `g_wasm` cvar,
+//! relative to the mod directory, and defaults to `qagame.wasm`. It is read through the engine's filesystem,
+//! so it can also be in a `pk3` archive.
+//!
+//! Each call into the WebAssembly module gets [`FUEL_PER_CALL`](FUEL_PER_CALL) fuel. A module running out of fuel,
+//! e.g. in an endless loop, traps and aborts the game with an error instead of hanging the server.
+//!
+//! It has to export:
+//!
+//! * `memory`, its linear memory
+//! * `vmMain(command: i32, arg0: i32, …, arg11: i32) -> i32`, see [`Exports`](super::Exports)
+//!
+//! It can import `env.syscall(num: i32, arg0: i32, …, arg9: i32) -> i32`, with `num` from [`Imports`](super::Imports)
+//! in ioquake3's numbering. Pointer arguments are offsets into `memory` and translated by the host.
+//! Only these traps are supported, others abort the game with an error:
+//!
+//! * `G_PRINT` and `G_ERROR`
+//! * `G_CVAR_SET` and `G_CVAR_VARIABLE_STRING_BUFFER`
+//! * `G_ARGC` and `G_ARGV`
+//! * `G_SEND_CONSOLE_COMMAND` and `G_SEND_SERVER_COMMAND`
+//! * `G_SET_CONFIGSTRING`
+//! * `G_GET_USERINFO`
+//!
+//! `GAME_CLIENT_CONNECT` can return an offset of a string in `memory` to deny the connection with that message.
+//!
+//! Syscalls that make the engine call back into the module, e.g. `G_SEND_CONSOLE_COMMAND` with `EXEC_NOW`,
+//! abort the game with an error since the WebAssembly module is still busy.
+//!
+//! # Examples
+//!
+//! A WebAssembly module in C, compiled with `clang --target=wasm32 -nostdlib -Wl,--no-entry -Wl,--export=vmMain`:
+//!
+//! ```c
+//! int syscall(int num, int a0, int a1, int a2, int a3, int a4, int a5, int a6, int a7, int a8, int a9);
+//!
+//! int vmMain(int command, int arg0, int arg1, int arg2, int arg3, int arg4, int arg5,
+//!            int arg6, int arg7, int arg8, int arg9, int arg10, int arg11) {
+//!     if (command == 0) {
+//!         syscall(0, (int)"Hello from WebAssembly\n", 0, 0, 0, 0, 0, 0, 0, 0, 0);
+//!     }
+//!     return 0;
+//! }
+//! ```
+
+use super::{fs, Deny, Exports, Imports, Module, Syscalls};
+use crate::ffi;
+use std::ffi::CString;
+use std::sync::Mutex;
+use wasmi::core::Trap;
+use wasmi::errors::FuelError;
+use wasmi::{Caller, Config, Engine, Extern, Linker, Memory, Store, TypedFunc};
+
+/// Cvar with the path of the WebAssembly module
+pub const WASM_CVAR: &str = "g_wasm";
+
+/// Default path of the WebAssembly module
+pub const DEFAULT_WASM: &str = "qagame.wasm";
+
+/// Fuel for each call into the WebAssembly module, roughly the number of instructions it may execute
+pub const FUEL_PER_CALL: u64 = 100_000_000;
+
+/// Parameters of `vmMain`, the command and 12 arguments
+type VmMainParams = (
+    i32,
+    i32,
+    i32,
+    i32,
+    i32,
+    i32,
+    i32,
+    i32,
+    i32,
+    i32,
+    i32,
+    i32,
+    i32,
+);
+
+/// Kind of a syscall argument, to translate it between WebAssembly and the engine
+#[derive(Clone, Copy)]
+enum Arg {
+    /// Passed as is
+    Int,
+    /// Offset of a NUL-terminated string in `memory`
+    Str,
+    /// Offset of an output buffer in `memory`, with the index of the argument holding its length
+    Buf(usize),
+}
+
+/// Import and argument kinds of trap `num`, in ioquake3's numbering
+fn signature(num: i32) -> Option<(Imports, &'static [Arg])> {
+    use Arg::*;
+
+    match num {
+        0 => Some((Imports::G_PRINT, &[Str])),
+        1 => Some((Imports::G_ERROR, &[Str])),
+        5 => Some((Imports::G_CVAR_SET, &[Str, Str])),
+        7 => Some((Imports::G_CVAR_VARIABLE_STRING_BUFFER, &[Str, Buf(2), Int])),
+        8 => Some((Imports::G_ARGC, &[])),
+        9 => Some((Imports::G_ARGV, &[Int, Buf(2), Int])),
+        14 => Some((Imports::G_SEND_CONSOLE_COMMAND, &[Int, Str])),
+        17 => Some((Imports::G_SEND_SERVER_COMMAND, &[Int, Str])),
+        18 => Some((Imports::G_SET_CONFIGSTRING, &[Int, Str])),
+        20 => Some((Imports::G_GET_USERINFO, &[Int, Buf(2), Int])),
+        _ => None,
+    }
+}
+
+/// Host state of the WebAssembly store
+struct Host {
+    syscalls: Syscalls,
+    /// Message of a `G_ERROR` from the WebAssembly module, raised once it unwound
+    error: Option<String>,
+}
+
+struct Instance {
+    store: Store<Host>,
+    memory: Memory,
+    vm_main: TypedFunc<VmMainParams, i32>,
+}
+
+/// Read the NUL-terminated string at `offset` of `memory`
+fn read_string(memory: &[u8], offset: i32) -> Result<CString, Trap> {
+    let bytes = usize::try_from(offset)
+        .ok()
+        .and_then(|offset| memory.get(offset..))
+        .ok_or_else(|| Trap::new(format!("String offset {} out of bounds", offset)))?;
+    let len = bytes
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| Trap::new(format!("String at {} is not terminated", offset)))?;
+    Ok(CString::new(&bytes[..len]).unwrap())
+}
+
+fn syscall(mut caller: Caller<'_, Host>, num: i32, args: [i32; 10]) -> Result<i32, Trap> {
+    let (import, kinds) =
+        signature(num).ok_or_else(|| Trap::new(format!("Unsupported syscall {}", num)))?;
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Trap::new("Missing memory export"))?;
+
+    if let Imports::G_ERROR = import {
+        let text = read_string(memory.data(&caller), args[0])?;
+        caller.data_mut().error = Some(text.to_string_lossy().into_owned());
+        return Err(Trap::new("G_ERROR"));
+    }
+
+    // Keep strings and buffers alive until the engine returned
+    let mut strings = Vec::new();
+    let mut buffers = Vec::new();
+    let mut native_args = Vec::with_capacity(kinds.len());
+    for (i, kind) in kinds.iter().enumerate() {
+        let arg = match *kind {
+            Arg::Int => ffi::int_to_intptr(args[i]),
+            Arg::Str => {
+                let string = read_string(memory.data(&caller), args[i])?;
+                let ptr = ffi::ptr_to_intptr(string.as_ptr());
+                strings.push(string);
+                ptr
+            }
+            Arg::Buf(len) => {
+                let (offset, len) = match (usize::try_from(args[i]), usize::try_from(args[len])) {
+                    (Ok(offset), Ok(len))
+                        if offset.saturating_add(len) <= memory.data(&caller).len() =>
+                    {
+                        (offset, len)
+                    }
+                    _ => {
+                        return Err(Trap::new(format!(
+                            "Buffer offset {} out of bounds",
+                            args[i]
+                        )))
+                    }
+                };
+                let mut buffer = vec![0u8; len];
+                let ptr = ffi::ptr_to_intptr(buffer.as_mut_ptr());
+                buffers.push((offset, buffer));
+                ptr
+            }
+        };
+        native_args.push(arg);
+    }
+
+    let result = caller.data().syscalls.call(import, &native_args);
+
+    for (offset, buffer) in buffers {
+        memory
+            .write(&mut caller, offset, &buffer)
+            .map_err(|e| Trap::new(e.to_string()))?;
+    }
+    Ok(ffi::intptr_to_int(result))
+}
+
+/// Top up the fuel of `store` to [`FUEL_PER_CALL`](FUEL_PER_CALL)
+fn refuel(store: &mut Store<Host>) -> Result<(), FuelError> {
+    let remaining = store.consume_fuel(0)?;
+    store.add_fuel(FUEL_PER_CALL.saturating_sub(remaining))
+}
+
+/// [`Module`](super::Module) forwarding all callbacks to a sandboxed WebAssembly module
+pub struct WasmModule {
+    syscalls: Syscalls,
+    engine: Engine,
+    instance: Mutex<Option<Instance>>,
+}
+
+impl WasmModule {
+    fn instantiate(&self, wasm: &[u8]) -> Result<Instance, wasmi::Error> {
+        let module = wasmi::Module::new(&self.engine, wasm)?;
+        let mut store = Store::new(
+            &self.engine,
+            Host {
+                syscalls: self.syscalls,
+                error: None,
+            },
+        );
+
+        let mut linker = Linker::new(&self.engine);
+        linker.func_wrap(
+            "env",
+            "syscall",
+            |caller: Caller<'_, Host>,
+             num: i32,
+             a0: i32,
+             a1: i32,
+             a2: i32,
+             a3: i32,
+             a4: i32,
+             a5: i32,
+             a6: i32,
+             a7: i32,
+             a8: i32,
+             a9: i32| {
+                syscall(caller, num, [a0, a1, a2, a3, a4, a5, a6, a7, a8, a9])
+            },
+        )?;
+        refuel(&mut store)?;
+        let instance = linker.instantiate(&mut store, &module)?.start(&mut store)?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| wasmi::Error::from(Trap::new("Missing memory export")))?;
+        let vm_main = instance.get_typed_func::<VmMainParams, i32>(&store, "vmMain")?;
+        Ok(Instance {
+            store,
+            memory,
+            vm_main,
+        })
+    }
+
+    fn load(&self) {
+        let mut path = self.syscalls.cvar_variable_string_buffer(WASM_CVAR);
+        if path.is_empty() {
+            path = DEFAULT_WASM.to_owned();
+        }
+
+        let instance = fs::read(&self.syscalls, &path)
+            .map_err(|e| e.to_string())
+            .and_then(|wasm| self.instantiate(&wasm).map_err(|e| e.to_string()));
+
+        match instance {
+            Ok(instance) => *self.instance.lock().unwrap() = Some(instance),
+            Err(e) => self
+                .syscalls
+                .error(format!("Could not load WebAssembly module {}: {}", path, e)),
+        }
+    }
+
+    /// Call `vmMain` of the WebAssembly module, and the translated result if it returned
+    ///
+    /// `translate` runs while the WebAssembly memory is still accessible.
+    fn vm_main<R>(
+        &self,
+        command: Exports,
+        args: &[ffi::c_int],
+        translate: impl FnOnce(&[u8], i32) -> R,
+    ) -> Option<R> {
+        let mut guard = match self.instance.try_lock() {
+            Ok(guard) => guard,
+            Err(_) => self.syscalls.error(format!(
+                "{:?} while the WebAssembly module is busy",
+                command
+            )),
+        };
+        let instance = guard.as_mut()?;
+
+        let mut a = [0; 12];
+        a[..args.len()].copy_from_slice(args);
+        let params = (
+            command as i32,
+            a[0],
+            a[1],
+            a[2],
+            a[3],
+            a[4],
+            a[5],
+            a[6],
+            a[7],
+            a[8],
+            a[9],
+            a[10],
+            a[11],
+        );
+
+        let result = refuel(&mut instance.store)
+            .map_err(|e| Trap::new(e.to_string()))
+            .and_then(|()| instance.vm_main.call(&mut instance.store, params));
+        match result {
+            Ok(result) => Some(translate(instance.memory.data(&instance.store), result)),
+            Err(trap) => {
+                let message = instance
+                    .store
+                    .data_mut()
+                    .error
+                    .take()
+                    .unwrap_or_else(|| format!("WebAssembly module trapped: {}", trap));
+                *guard = None;
+                drop(guard);
+                self.syscalls.error(message)
+            }
+        }
+    }
+
+    fn call(&self, command: Exports, args: &[ffi::c_int]) -> i32 {
+        self.vm_main(command, args, |_, result| result).unwrap_or(0)
+    }
+}
+
+impl Module for WasmModule {
+    fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+        Box::new(Self {
+            syscalls,
+            engine: Engine::new(Config::default().consume_fuel(true)),
+            instance: Mutex::new(None),
+        })
+    }
+
+    fn init(&self, level_time: ffi::c_int, random_seed: ffi::c_int, restart: bool) {
+        self.load();
+        self.call(
+            Exports::GAME_INIT,
+            &[level_time, random_seed, restart as ffi::c_int],
+        );
+    }
+
+    fn shutdown(&self, restart: bool) {
+        self.call(Exports::GAME_SHUTDOWN, &[restart as ffi::c_int]);
+    }
+
+    fn client_connect(
+        &self,
+        client_number: ffi::c_int,
+        first_time: bool,
+        is_bot: bool,
+    ) -> Result<(), Deny> {
+        let message = self.vm_main(
+            Exports::GAME_CLIENT_CONNECT,
+            &[
+                client_number,
+                first_time as ffi::c_int,
+                is_bot as ffi::c_int,
+            ],
+            |memory, result| match result {
+                0 => None,
+                offset => Some(read_string(memory, offset).unwrap_or_default()),
+            },
+        );
+        match message.flatten() {
+            Some(message) => Err(Deny::new(message.as_bytes())),
+            None => Ok(()),
+        }
+    }
+
+    fn client_think(&self, client_number: ffi::c_int) {
+        self.call(Exports::GAME_CLIENT_THINK, &[client_number]);
+    }
+
+    fn client_userinfo_changed(&self, client_number: ffi::c_int) {
+        self.call(Exports::GAME_CLIENT_USERINFO_CHANGED, &[client_number]);
+    }
+
+    fn client_disconnect(&self, client_number: ffi::c_int) {
+        self.call(Exports::GAME_CLIENT_DISCONNECT, &[client_number]);
+    }
+
+    fn client_begin(&self, client_number: ffi::c_int) {
+        self.call(Exports::GAME_CLIENT_BEGIN, &[client_number]);
+    }
+
+    fn client_command(&self, client_number: ffi::c_int) {
+        self.call(Exports::GAME_CLIENT_COMMAND, &[client_number]);
+    }
+
+    fn run_frame(&self, level_time: ffi::c_int) {
+        self.call(Exports::GAME_RUN_FRAME, &[level_time]);
+    }
+
+    fn console_command(&self) -> bool {
+        self.call(Exports::GAME_CONSOLE_COMMAND, &[]) != 0
+    }
+
+    fn botai_start_frame(&self, level_time: ffi::c_int) -> bool {
+        self.call(Exports::BOTAI_START_FRAME, &[level_time]) != 0
+    }
+}
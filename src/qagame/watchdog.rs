@@ -0,0 +1,149 @@
+//! Warnings for engine commands that take too long
+//!
+//! A slow `GAME_RUN_FRAME` or `GAME_CLIENT_THINK` makes the server miss its `sv_fps` frames.
+//! [`game_module!`](crate::game_module) times every command from the engine and, once a budget is set,
+//! prints a warning with the command name and duration through `G_PRINT` for every command over budget.
+//!
+//! The watchdog is off by default. With `game_module!(MyGame, builtins)`, server operators can turn it on
+//! with the `watchdog <milliseconds>` console command, e.g. `watchdog 5`, and off again with `watchdog 0`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::qagame::watchdog::Watchdog;
+//! use quake3_native_vm::qagame::Exports;
+//! use std::time::Duration;
+//!
+//! let watchdog = Watchdog::new();
+//! assert_eq!(watchdog.warning(Exports::GAME_RUN_FRAME, Duration::from_secs(1)), None);
+//!
+//! watchdog.set_budget(Some(Duration::from_millis(5)));
+//! assert_eq!(watchdog.warning(Exports::GAME_RUN_FRAME, Duration::from_millis(2)), None);
+//! assert_eq!(
+//!     watchdog.warning(Exports::GAME_RUN_FRAME, Duration::from_millis(12)).as_deref(),
+//!     Some("WARNING: GAME_RUN_FRAME took 12.000ms, budget is 5.000ms\n")
+//! );
+//! ```
+
+use super::{Exports, Syscalls};
+use crate::metrics::millis;
+use crate::EngineSyscalls;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Console command to show or set the budget of the [`global`](global) watchdog
+pub const COMMAND: &str = "watchdog";
+
+/// Budget for how long a single command from the engine may take
+pub struct Watchdog {
+    /// Budget in nanoseconds, `0` if turned off
+    budget_ns: AtomicU64,
+}
+
+impl Watchdog {
+    /// Create a watchdog without a budget, i.e. turned off
+    pub const fn new() -> Self {
+        Self {
+            budget_ns: AtomicU64::new(0),
+        }
+    }
+
+    /// Current budget, `None` if turned off
+    pub fn budget(&self) -> Option<Duration> {
+        match self.budget_ns.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// Whether there is a budget, i.e. commands have to be timed
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.budget_ns.load(Ordering::Relaxed) != 0
+    }
+
+    /// Warn about commands taking longer than `budget`, or turn the watchdog off with `None`
+    ///
+    /// Budgets are rounded up to whole nanoseconds.
+    pub fn set_budget(&self, budget: Option<Duration>) {
+        let nanos = budget.map_or(0, |budget| {
+            budget.as_nanos().clamp(1, u64::MAX as u128) as u64
+        });
+        self.budget_ns.store(nanos, Ordering::Relaxed);
+    }
+
+    /// Warning for qconsole if `command` took longer than the budget
+    pub fn warning(&self, command: Exports, elapsed: Duration) -> Option<String> {
+        let budget = self.budget()?;
+        if elapsed <= budget {
+            return None;
+        }
+        Some(format!(
+            "WARNING: {} took {:.3}ms, budget is {:.3}ms\n",
+            command.name(),
+            millis(elapsed),
+            millis(budget)
+        ))
+    }
+
+    /// Print the [`warning`](Watchdog::warning), if any, to qconsole
+    pub fn check<S: EngineSyscalls>(
+        &self,
+        syscalls: &Syscalls<S>,
+        command: Exports,
+        elapsed: Duration,
+    ) {
+        if let Some(warning) = self.warning(command, elapsed) {
+            syscalls.print(warning);
+        }
+    }
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("budget", &self.budget())
+            .finish()
+    }
+}
+
+static GLOBAL: Watchdog = Watchdog::new();
+
+/// Watchdog used by [`game_module!`](crate::game_module)
+pub fn global() -> &'static Watchdog {
+    &GLOBAL
+}
+
+/// Print the budget of the [`global`](global) watchdog, or set it to the milliseconds given as argument
+///
+/// A budget of `0` turns the watchdog off.
+pub fn watchdog_command<S: EngineSyscalls>(syscalls: &Syscalls<S>) {
+    let watchdog = global();
+    if syscalls.argc() < 2 {
+        match watchdog.budget() {
+            Some(budget) => syscalls.print(format!("watchdog budget is {:.3}ms\n", millis(budget))),
+            None => syscalls.print("watchdog is off\n"),
+        }
+        return;
+    }
+
+    let arg = syscalls.argv(1);
+    match arg.parse::<f64>() {
+        Ok(ms) if ms == 0.0 => {
+            watchdog.set_budget(None);
+            syscalls.print("watchdog is off\n");
+        }
+        Ok(ms) if ms.is_finite() && ms > 0.0 => {
+            watchdog.set_budget(Some(Duration::from_secs_f64(ms / 1000.0)));
+            syscalls.print(format!("watchdog budget is {:.3}ms\n", ms));
+        }
+        _ => syscalls.print(format!("usage: {} [milliseconds]\n", COMMAND)),
+    }
+}
@@ -0,0 +1,119 @@
+//! Marshalling syscalls from worker threads to the engine thread
+//!
+//! The engine is not thread-safe, so syscalls must only be made from the thread that calls into the module.
+//! Worker threads can instead send closures through a [`SyscallSender`](SyscallSender),
+//! which the module runs on the engine thread with [`SyscallQueue::drain`](SyscallQueue::drain),
+//! e.g. in [`qagame::Module::run_frame`](crate::qagame::Module::run_frame).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::queue::SyscallQueue;
+//!
+//! # struct Syscalls;
+//! # impl Syscalls { fn print(&self, _: &str) {} }
+//! # let syscalls = Syscalls;
+//! let queue = SyscallQueue::new();
+//! let sender = queue.sender();
+//!
+//! std::thread::spawn(move || {
+//!     let answer = 6 * 7;
+//!     sender.send(move |syscalls: &Syscalls| syscalls.print(&format!("answer: {}\n", answer)));
+//! })
+//! .join()
+//! .unwrap();
+//!
+//! // On the engine thread, e.g. once per frame
+//! assert_eq!(queue.drain(&syscalls), 1);
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+type Job<S> = Box<dyn FnOnce(&S) + Send>;
+
+type Jobs<S> = Arc<Mutex<VecDeque<Job<S>>>>;
+
+/// Queue of closures to run with the syscalls `S` on the engine thread
+pub struct SyscallQueue<S> {
+    jobs: Jobs<S>,
+}
+
+impl<S> SyscallQueue<S> {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        Self {
+            jobs: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Create a handle for other threads to enqueue closures
+    pub fn sender(&self) -> SyscallSender<S> {
+        SyscallSender {
+            jobs: Arc::clone(&self.jobs),
+        }
+    }
+
+    /// Run all closures enqueued so far, in order, returning how many ran
+    ///
+    /// Closures enqueued while draining are run on the next call.
+    pub fn drain(&self, syscalls: &S) -> usize {
+        let jobs = std::mem::take(&mut *self.jobs.lock().unwrap());
+        let count = jobs.len();
+        for job in jobs {
+            job(syscalls);
+        }
+        count
+    }
+
+    /// Number of closures waiting to run
+    pub fn len(&self) -> usize {
+        self.jobs.lock().unwrap().len()
+    }
+
+    /// Whether no closures are waiting to run
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<S> Default for SyscallQueue<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> fmt::Debug for SyscallQueue<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyscallQueue")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Handle to enqueue closures into a [`SyscallQueue`](SyscallQueue) from any thread
+pub struct SyscallSender<S> {
+    jobs: Jobs<S>,
+}
+
+impl<S> SyscallSender<S> {
+    /// Enqueue `job` to be run on the engine thread
+    pub fn send(&self, job: impl FnOnce(&S) + Send + 'static) {
+        self.jobs.lock().unwrap().push_back(Box::new(job));
+    }
+}
+
+impl<S> Clone for SyscallSender<S> {
+    fn clone(&self) -> Self {
+        Self {
+            jobs: Arc::clone(&self.jobs),
+        }
+    }
+}
+
+impl<S> fmt::Debug for SyscallSender<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyscallSender").finish_non_exhaustive()
+    }
+}
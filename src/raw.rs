@@ -0,0 +1,248 @@
+//! Raw bindings of the engine headers
+//!
+//! Requires the `bindgen` feature and libclang.
+//!
+//! Generated by [bindgen](https://rust-lang.github.io/rust-bindgen/) from the excerpts of ioquake3's headers
+//! in `include/ioq3/` of this crate, with C names and types.
+//! Prefer the hand-written wrappers elsewhere in this crate, which are checked against these at compile time:
+//! a struct with a different size or alignment, or an enum with a different value, fails the build.
+
+#![allow(
+    missing_docs,
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    clippy::all
+)]
+
+include!(concat!(env!("OUT_DIR"), "/raw.rs"));
+
+/// Assert that `$wrapper` has the same size and alignment as `$raw`
+macro_rules! check_layout {
+    ($($raw:ty => $wrapper:ty),* $(,)?) => {
+        $(
+            const _: () = assert!(
+                std::mem::size_of::<$raw>() == std::mem::size_of::<$wrapper>()
+                    && std::mem::align_of::<$raw>() == std::mem::align_of::<$wrapper>(),
+                concat!("Layout of ", stringify!($wrapper), " differs from ", stringify!($raw)),
+            );
+        )*
+    };
+}
+
+/// Assert that the variants of `$wrapper` have the values of the `$raw` enum constants of the same name
+macro_rules! check_enum {
+    ($raw:ty => $wrapper:path { $($variant:ident),* $(,)? }) => {
+        $(
+            const _: () = assert!(
+                <$wrapper>::$variant as $raw == $variant,
+                concat!("Value of ", stringify!($wrapper), "::", stringify!($variant), " differs"),
+            );
+        )*
+    };
+}
+
+/// Assert that the `$wrapper` constants have the values of the `$raw` constants
+macro_rules! check_const {
+    ($($raw:ident => $wrapper:path),* $(,)?) => {
+        $(
+            const _: () = assert!(
+                $raw as usize == $wrapper as usize,
+                concat!("Value of ", stringify!($wrapper), " differs"),
+            );
+        )*
+    };
+}
+
+/// Assert that the `$wrapper` flags have the values of the `$raw` constants
+macro_rules! check_flags {
+    ($($raw:ident => $wrapper:expr),* $(,)?) => {
+        $(
+            const _: () = assert!(
+                $raw as crate::ffi::c_int == $wrapper.bits(),
+                concat!("Value of ", stringify!($wrapper), " differs"),
+            );
+        )*
+    };
+}
+
+check_layout! {
+    qboolean => crate::shared::QBoolean,
+    vec3_t => crate::shared::Vec3,
+    cplane_t => crate::shared::Plane,
+    trace_t => crate::shared::Trace,
+    playerState_t => crate::shared::PlayerState,
+    usercmd_t => crate::shared::UserCmd,
+    trajectory_t => crate::shared::Trajectory,
+    entityState_t => crate::shared::EntityState,
+    entityShared_t => crate::qagame::entity::EntityShared,
+    sharedEntity_t => crate::qagame::entity::SharedEntity,
+    vmCvar_t => crate::shared::VmCvar,
+    snapshot_t => crate::cgame::Snapshot,
+    uiClientState_t => crate::ui::UiClientState,
+    qhandle_t => crate::asset::ShaderHandle,
+    sfxHandle_t => crate::asset::SoundHandle,
+    glyphInfo_t => crate::asset::GlyphInfo,
+    fontInfo_t => crate::asset::FontInfo,
+    orientation_t => crate::shared::Orientation,
+    refEntity_t => crate::render::RefEntity,
+}
+
+check_const! {
+    MAX_QPATH => crate::shared::MAX_QPATH,
+    MAX_STRING_CHARS => crate::qagame::MAX_STRING_CHARS,
+    MAX_INFO_STRING => crate::info::MAX_INFO_STRING,
+    MAX_CVAR_VALUE_STRING => crate::qagame::MAX_CVAR_VALUE_STRING,
+    GENTITYNUM_BITS => crate::qagame::entity::GENTITYNUM_BITS,
+    MAX_GENTITIES => crate::qagame::entity::MAX_GENTITIES,
+    MAX_CLIENTS => crate::shared::MAX_CLIENTS,
+    CS_SERVERINFO => crate::shared::CS_SERVERINFO,
+    CS_SYSTEMINFO => crate::shared::CS_SYSTEMINFO,
+    GLYPHS_PER_FONT => crate::asset::GLYPHS_PER_FONT,
+    MAX_MAP_AREA_BYTES => crate::shared::MAX_MAP_AREA_BYTES,
+    SNAPFLAG_RATE_DELAYED => crate::shared::SNAPFLAG_RATE_DELAYED,
+    SNAPFLAG_NOT_ACTIVE => crate::shared::SNAPFLAG_NOT_ACTIVE,
+    SNAPFLAG_SERVERCOUNT => crate::shared::SNAPFLAG_SERVERCOUNT,
+    CMD_BACKUP => crate::cgame::CMD_BACKUP,
+    MAX_ENTITIES_IN_SNAPSHOT => crate::cgame::MAX_ENTITIES_IN_SNAPSHOT,
+    CVAR_ARCHIVE => crate::shared::CVAR_ARCHIVE,
+    CVAR_USERINFO => crate::shared::CVAR_USERINFO,
+    CVAR_SERVERINFO => crate::shared::CVAR_SERVERINFO,
+    CVAR_SYSTEMINFO => crate::shared::CVAR_SYSTEMINFO,
+    CVAR_INIT => crate::shared::CVAR_INIT,
+    CVAR_LATCH => crate::shared::CVAR_LATCH,
+    CVAR_ROM => crate::shared::CVAR_ROM,
+    CVAR_TEMP => crate::shared::CVAR_TEMP,
+    CVAR_CHEAT => crate::shared::CVAR_CHEAT,
+    CVAR_NORESTART => crate::shared::CVAR_NORESTART,
+    MAX_STATS => crate::shared::MAX_STATS,
+    MAX_PERSISTANT => crate::shared::MAX_PERSISTANT,
+    MAX_POWERUPS => crate::shared::MAX_POWERUPS,
+    MAX_WEAPONS => crate::shared::MAX_WEAPONS,
+    MAX_PS_EVENTS => crate::shared::MAX_PS_EVENTS,
+    DEFAULT_GRAVITY => crate::bg::DEFAULT_GRAVITY,
+    CS_GAME_VERSION => crate::bg::CS_GAME_VERSION,
+    CS_LEVEL_START_TIME => crate::bg::CS_LEVEL_START_TIME,
+    PW_NUM_POWERUPS => crate::bg::PW_NUM_POWERUPS,
+    UI_API_VERSION => crate::ui::UI_API_VERSION,
+}
+
+check_enum!(e_status => crate::cinematic::CinStatus {
+    FMV_IDLE, FMV_PLAY, FMV_EOF, FMV_ID_BLT, FMV_ID_IDLE, FMV_LOOPED, FMV_ID_WAIT,
+});
+check_enum!(connstate_t => crate::shared::ConnState {
+    CA_UNINITIALIZED, CA_DISCONNECTED, CA_AUTHORIZING, CA_CONNECTING, CA_CHALLENGING,
+    CA_CONNECTED, CA_LOADING, CA_PRIMED, CA_ACTIVE, CA_CINEMATIC,
+});
+check_enum!(refEntityType_t => crate::render::RefEntityType {
+    RT_MODEL, RT_POLY, RT_SPRITE, RT_BEAM, RT_RAIL_CORE, RT_RAIL_RINGS, RT_LIGHTNING,
+    RT_PORTALSURFACE,
+});
+check_enum!(stereoFrame_t => crate::cgame::StereoFrame {
+    STEREO_CENTER, STEREO_LEFT, STEREO_RIGHT,
+});
+check_enum!(cbufExec_t => crate::qagame::CbufExec { EXEC_NOW, EXEC_INSERT, EXEC_APPEND });
+check_enum!(fsMode_t => crate::qagame::FsMode { FS_READ, FS_WRITE, FS_APPEND, FS_APPEND_SYNC });
+check_enum!(trType_t => crate::shared::TrType {
+    TR_STATIONARY, TR_INTERPOLATE, TR_LINEAR, TR_LINEAR_STOP, TR_SINE, TR_GRAVITY,
+});
+check_enum!(pmtype_t => crate::bg::PmType {
+    PM_NORMAL, PM_NOCLIP, PM_SPECTATOR, PM_DEAD, PM_FREEZE, PM_INTERMISSION, PM_SPINTERMISSION,
+});
+check_enum!(statIndex_t => crate::bg::StatIndex {
+    STAT_HEALTH, STAT_HOLDABLE_ITEM, STAT_WEAPONS, STAT_ARMOR, STAT_DEAD_YAW, STAT_CLIENTS_READY,
+    STAT_MAX_HEALTH,
+});
+check_enum!(powerup_t => crate::bg::Powerup {
+    PW_NONE, PW_QUAD, PW_BATTLESUIT, PW_HASTE, PW_INVIS, PW_REGEN, PW_FLIGHT, PW_REDFLAG,
+    PW_BLUEFLAG, PW_NEUTRALFLAG, PW_SCOUT, PW_GUARD, PW_DOUBLER, PW_AMMOREGEN, PW_INVULNERABILITY,
+});
+check_enum!(soundChannel_t => crate::bg::SoundChannel {
+    CHAN_AUTO, CHAN_LOCAL, CHAN_WEAPON, CHAN_VOICE, CHAN_ITEM, CHAN_BODY, CHAN_LOCAL_SOUND,
+    CHAN_ANNOUNCER,
+});
+check_enum!(gameImport_t => crate::qagame::Imports {
+    G_PRINT, G_ERROR, G_CVAR_REGISTER, G_CVAR_UPDATE, G_CVAR_SET, G_CVAR_VARIABLE_STRING_BUFFER,
+    G_ARGC, G_ARGV, G_FS_FOPEN_FILE, G_FS_READ, G_FS_WRITE, G_FS_FCLOSE_FILE,
+    G_SEND_CONSOLE_COMMAND, G_LOCATE_GAME_DATA, G_SEND_SERVER_COMMAND, G_SET_CONFIGSTRING,
+    G_GET_USERINFO, G_ENTITIES_IN_BOX, G_BOT_ALLOCATE_CLIENT, G_BOT_FREE_CLIENT, G_GET_USERCMD,
+    G_FS_GETFILELIST,
+});
+check_enum!(gameExport_t => crate::qagame::Exports {
+    GAME_INIT, GAME_SHUTDOWN, GAME_CLIENT_CONNECT, GAME_CLIENT_BEGIN, GAME_CLIENT_USERINFO_CHANGED,
+    GAME_CLIENT_DISCONNECT, GAME_CLIENT_COMMAND, GAME_CLIENT_THINK, GAME_RUN_FRAME,
+    GAME_CONSOLE_COMMAND, BOTAI_START_FRAME,
+});
+check_enum!(cgameImport_t => crate::cgame::Imports {
+    CG_PRINT, CG_ERROR, CG_ARGC, CG_ARGV, CG_UPDATESCREEN, CG_S_STARTLOCALSOUND,
+    CG_S_CLEARLOOPINGSOUNDS, CG_S_ADDLOOPINGSOUND, CG_S_REGISTERSOUND, CG_S_STARTBACKGROUNDTRACK,
+    CG_R_REGISTERMODEL, CG_R_REGISTERSHADER, CG_R_LERPTAG, CG_GETCURRENTSNAPSHOTNUMBER,
+    CG_GETSNAPSHOT, CG_GETCURRENTCMDNUMBER, CG_GETUSERCMD, CG_R_REGISTERSHADERNOMIP,
+    CG_R_REGISTERFONT, CG_KEY_ISDOWN, CG_KEY_GETCATCHER, CG_KEY_SETCATCHER, CG_KEY_GETKEY,
+    CG_S_STOPBACKGROUNDTRACK, CG_CIN_PLAYCINEMATIC, CG_CIN_STOPCINEMATIC, CG_CIN_RUNCINEMATIC,
+    CG_CIN_DRAWCINEMATIC, CG_CIN_SETEXTENTS, CG_S_ADDREALLOOPINGSOUND, CG_S_STOPLOOPINGSOUND,
+});
+check_enum!(cgameExport_t => crate::cgame::Exports {
+    CG_INIT, CG_SHUTDOWN, CG_CONSOLE_COMMAND, CG_DRAW_ACTIVE_FRAME, CG_CROSSHAIR_PLAYER,
+    CG_LAST_ATTACKER, CG_KEY_EVENT, CG_MOUSE_EVENT, CG_EVENT_HANDLING,
+});
+check_enum!(uiImport_t => crate::ui::Imports {
+    UI_ERROR, UI_PRINT, UI_MILLISECONDS, UI_ARGC, UI_ARGV, UI_CMD_EXECUTETEXT, UI_R_REGISTERMODEL,
+    UI_R_REGISTERSHADERNOMIP, UI_UPDATESCREEN, UI_CM_LERPTAG, UI_S_REGISTERSOUND,
+    UI_S_STARTLOCALSOUND, UI_KEY_GETCATCHER, UI_KEY_SETCATCHER, UI_GETCLIPBOARDDATA,
+    UI_GETCLIENTSTATE, UI_GETCONFIGSTRING, UI_MEMORY_REMAINING, UI_R_REGISTERFONT,
+    UI_S_STOPBACKGROUNDTRACK, UI_S_STARTBACKGROUNDTRACK, UI_CIN_PLAYCINEMATIC, UI_CIN_STOPCINEMATIC,
+    UI_CIN_RUNCINEMATIC, UI_CIN_DRAWCINEMATIC, UI_CIN_SETEXTENTS,
+});
+check_enum!(uiExport_t => crate::ui::Exports {
+    UI_GETAPIVERSION, UI_INIT, UI_SHUTDOWN, UI_KEY_EVENT, UI_MOUSE_EVENT, UI_REFRESH,
+    UI_IS_FULLSCREEN, UI_SET_ACTIVE_MENU, UI_CONSOLE_COMMAND, UI_DRAW_CONNECT_SCREEN,
+    UI_HASUNIQUECDKEY,
+});
+check_enum!(uiMenuCommand_t => crate::ui::MenuCommand {
+    UIMENU_NONE, UIMENU_MAIN, UIMENU_INGAME, UIMENU_NEED_CD, UIMENU_BAD_CD_KEY, UIMENU_TEAM,
+    UIMENU_POSTGAME,
+});
+
+check_flags! {
+    CONTENTS_SOLID => crate::shared::Contents::SOLID,
+    CONTENTS_LAVA => crate::shared::Contents::LAVA,
+    CONTENTS_SLIME => crate::shared::Contents::SLIME,
+    CONTENTS_WATER => crate::shared::Contents::WATER,
+    CONTENTS_FOG => crate::shared::Contents::FOG,
+    CONTENTS_NOTTEAM1 => crate::shared::Contents::NOTTEAM1,
+    CONTENTS_NOTTEAM2 => crate::shared::Contents::NOTTEAM2,
+    CONTENTS_NOBOTCLIP => crate::shared::Contents::NOBOTCLIP,
+    CONTENTS_AREAPORTAL => crate::shared::Contents::AREAPORTAL,
+    CONTENTS_PLAYERCLIP => crate::shared::Contents::PLAYERCLIP,
+    CONTENTS_MONSTERCLIP => crate::shared::Contents::MONSTERCLIP,
+    CONTENTS_TELEPORTER => crate::shared::Contents::TELEPORTER,
+    CONTENTS_JUMPPAD => crate::shared::Contents::JUMPPAD,
+    CONTENTS_CLUSTERPORTAL => crate::shared::Contents::CLUSTERPORTAL,
+    CONTENTS_DONOTENTER => crate::shared::Contents::DONOTENTER,
+    CONTENTS_BOTCLIP => crate::shared::Contents::BOTCLIP,
+    CONTENTS_MOVER => crate::shared::Contents::MOVER,
+    CONTENTS_ORIGIN => crate::shared::Contents::ORIGIN,
+    CONTENTS_BODY => crate::shared::Contents::BODY,
+    CONTENTS_CORPSE => crate::shared::Contents::CORPSE,
+    CONTENTS_DETAIL => crate::shared::Contents::DETAIL,
+    CONTENTS_STRUCTURAL => crate::shared::Contents::STRUCTURAL,
+    CONTENTS_TRANSLUCENT => crate::shared::Contents::TRANSLUCENT,
+    CONTENTS_TRIGGER => crate::shared::Contents::TRIGGER,
+    CONTENTS_NODROP => crate::shared::Contents::NODROP,
+    MASK_SOLID => crate::bg::TraceMask::SOLID,
+    MASK_PLAYERSOLID => crate::bg::TraceMask::PLAYERSOLID,
+    MASK_DEADSOLID => crate::bg::TraceMask::DEADSOLID,
+    MASK_WATER => crate::bg::TraceMask::WATER,
+    MASK_OPAQUE => crate::bg::TraceMask::OPAQUE,
+    MASK_SHOT => crate::bg::TraceMask::SHOT,
+    CIN_system => crate::cinematic::CinematicFlags::SYSTEM,
+    CIN_loop => crate::cinematic::CinematicFlags::LOOP,
+    CIN_hold => crate::cinematic::CinematicFlags::HOLD,
+    CIN_silent => crate::cinematic::CinematicFlags::SILENT,
+    CIN_shader => crate::cinematic::CinematicFlags::SHADER,
+    KEYCATCH_CONSOLE => crate::shared::KeyCatcher::CONSOLE,
+    KEYCATCH_UI => crate::shared::KeyCatcher::UI,
+    KEYCATCH_MESSAGE => crate::shared::KeyCatcher::MESSAGE,
+    KEYCATCH_CGAME => crate::shared::KeyCatcher::CGAME,
+}
@@ -0,0 +1,267 @@
+//! Entities for the renderer of `cgame` and `ui`
+//!
+//! Models like weapons and flags are attached to tags of other models, e.g. `tag_weapon` of a
+//! player's torso. A tag moves with the animation frames of its model, so its [`Orientation`](Orientation)
+//! is interpolated with [`RenderTraps::r_lerp_tag`](RenderTraps::r_lerp_tag) every frame and then
+//! combined with the position and rotation of the parent [`RefEntity`](RefEntity).
+//!
+//! See `CG_PositionEntityOnTag` in [ioquake3's `cgame/cg_ents.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_ents.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::render::{position_on_tag, RefEntity};
+//! use quake3_native_vm::shared::{Orientation, AXIS_DEFAULT};
+//!
+//! // Torso turned to the left, i.e. facing +y
+//! let torso = RefEntity {
+//!     origin: [100.0, 0.0, 0.0],
+//!     axis: [[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+//!     ..RefEntity::default()
+//! };
+//! // Hand 10 units in front of the torso
+//! let tag_weapon = Orientation {
+//!     origin: [10.0, 0.0, 0.0],
+//!     axis: AXIS_DEFAULT,
+//! };
+//!
+//! let mut gun = RefEntity::default();
+//! position_on_tag(&mut gun, &torso, &tag_weapon);
+//! assert_eq!(gun.origin, [100.0, 10.0, 0.0]);
+//! assert_eq!(gun.axis, torso.axis);
+//! ```
+
+use crate::asset::{ModelHandle, ShaderHandle};
+use crate::ffi;
+use crate::shared::{Orientation, QBoolean, Vec3};
+
+/// Kinds of [`RefEntity`](RefEntity) for [`RefEntity::re_type`](RefEntity::re_type)
+///
+/// See `refEntityType_t` in [ioquake3's `renderercommon/tr_types.h`](https://github.com/ioquake/ioq3/blob/master/code/renderercommon/tr_types.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum RefEntityType {
+    /// Model, e.g. a player or an item
+    RT_MODEL = 0,
+    /// Unused by the renderer
+    RT_POLY = 1,
+    /// Camera-facing quad, e.g. an explosion
+    RT_SPRITE = 2,
+    /// Beam, e.g. of a grapple
+    RT_BEAM = 3,
+    /// Core of a railgun trail
+    RT_RAIL_CORE = 4,
+    /// Rings of a railgun trail
+    RT_RAIL_RINGS = 5,
+    /// Lightning gun beam
+    RT_LIGHTNING = 6,
+    /// Surface of a portal, e.g. a mirror
+    RT_PORTALSURFACE = 7,
+}
+
+impl TryFrom<ffi::c_int> for RefEntityType {
+    type Error = &'static str;
+
+    fn try_from(re_type: ffi::c_int) -> Result<Self, Self::Error> {
+        match re_type {
+            0 => Ok(Self::RT_MODEL),
+            1 => Ok(Self::RT_POLY),
+            2 => Ok(Self::RT_SPRITE),
+            3 => Ok(Self::RT_BEAM),
+            4 => Ok(Self::RT_RAIL_CORE),
+            5 => Ok(Self::RT_RAIL_RINGS),
+            6 => Ok(Self::RT_LIGHTNING),
+            7 => Ok(Self::RT_PORTALSURFACE),
+            _ => Err("Unknown ref entity type"),
+        }
+    }
+}
+
+/// Entity to render, e.g. a model of a player
+///
+/// See `refEntity_t` in [ioquake3's `renderercommon/tr_types.h`](https://github.com/ioquake/ioq3/blob/master/code/renderercommon/tr_types.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RefEntity {
+    /// Raw [`RefEntityType`](RefEntityType)
+    pub re_type: ffi::c_int,
+    /// `RF_*` bits, e.g. `RF_THIRD_PERSON`
+    pub renderfx: ffi::c_int,
+    /// Model to render, for [`RefEntityType::RT_MODEL`](RefEntityType::RT_MODEL)
+    pub h_model: ModelHandle,
+    /// Position the light is taken from, for `RF_LIGHTING_ORIGIN`
+    pub lighting_origin: Vec3,
+    /// Height of the plane the shadow is projected on, for `RF_SHADOW_PLANE`
+    pub shadow_plane: f32,
+    /// Rotation, scaled unless [`non_normalized_axes`](RefEntity::non_normalized_axes)
+    pub axis: [Vec3; 3],
+    /// Whether [`axis`](RefEntity::axis) is scaled and has to be normalized for lighting
+    pub non_normalized_axes: QBoolean,
+    /// Position of the current frame
+    pub origin: Vec3,
+    /// Current animation frame
+    pub frame: ffi::c_int,
+    /// Position of the previous frame
+    pub oldorigin: Vec3,
+    /// Previous animation frame
+    pub oldframe: ffi::c_int,
+    /// Fraction of the [`oldframe`](RefEntity::oldframe) to render, `0` for the [`frame`](RefEntity::frame) only
+    pub backlerp: f32,
+    /// Skin of the model file to use
+    pub skin_num: ffi::c_int,
+    /// Skin replacing those of the model, `0` for none
+    pub custom_skin: ffi::c_int,
+    /// Shader replacing those of the model, `0` for none
+    pub custom_shader: ShaderHandle,
+    /// Color for shaders with `rgbGen entity`
+    pub shader_rgba: [u8; 4],
+    /// Texture coordinates for shaders with `tcGen entity`
+    pub shader_tex_coord: [f32; 2],
+    /// Time for shader animations, subtracted from the current time
+    pub shader_time: f32,
+    /// Size of a [`RefEntityType::RT_SPRITE`](RefEntityType::RT_SPRITE)
+    pub radius: f32,
+    /// Rotation of a [`RefEntityType::RT_SPRITE`](RefEntityType::RT_SPRITE) in degrees
+    pub rotation: f32,
+}
+
+assert_layout!(RefEntity, size = 140, align = 4);
+
+/// Render traps of a module, implemented by the `Syscalls` of [`cgame`](crate::cgame::Syscalls) and [`ui`](crate::ui::Syscalls)
+///
+/// See `trap_R_LerpTag` etc. in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+pub trait RenderTraps {
+    /// Orientation of the tag `tag_name` of `model`, interpolated from `start_frame` to `end_frame` by `fraction`,
+    /// `None` if the model has no such tag
+    fn r_lerp_tag(
+        &self,
+        model: ModelHandle,
+        start_frame: ffi::c_int,
+        end_frame: ffi::c_int,
+        fraction: f32,
+        tag_name: &str,
+    ) -> Option<Orientation>;
+
+    /// Render a frame right away, calling back into the module to draw it
+    ///
+    /// E.g. to show progress while loading, see [`cgame::loading`](crate::cgame::loading).
+    fn update_screen(&self);
+}
+
+/// Product of the rotations `a` and `b`
+///
+/// See `MatrixMultiply` in [ioquake3's `qcommon/q_math.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_math.c).
+fn matrix_multiply(a: &[Vec3; 3], b: &[Vec3; 3]) -> [Vec3; 3] {
+    let mut out = [[0.0; 3]; 3];
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = a[i][0] * b[0][j] + a[i][1] * b[1][j] + a[i][2] * b[2][j];
+        }
+    }
+    out
+}
+
+/// Origin of `tag` of `parent` in world coordinates
+fn tag_origin(parent: &RefEntity, tag: &Orientation) -> Vec3 {
+    let mut origin = parent.origin;
+    for (offset, axis) in tag.origin.iter().zip(&parent.axis) {
+        for (origin, axis) in origin.iter_mut().zip(axis) {
+            *origin += offset * axis;
+        }
+    }
+    origin
+}
+
+/// Move `entity` to `tag` of `parent` and rotate it like the tag
+///
+/// The [`backlerp`](RefEntity::backlerp) of `parent` is copied, so both animate in step.
+///
+/// See `CG_PositionEntityOnTag` in [ioquake3's `cgame/cg_ents.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_ents.c).
+pub fn position_on_tag(entity: &mut RefEntity, parent: &RefEntity, tag: &Orientation) {
+    entity.origin = tag_origin(parent, tag);
+    entity.axis = matrix_multiply(&tag.axis, &parent.axis);
+    entity.backlerp = parent.backlerp;
+}
+
+/// Move `entity` to `tag` of `parent`, keeping its own rotation relative to the tag
+///
+/// E.g. for the barrel of a machinegun, which spins relative to the gun.
+///
+/// See `CG_PositionRotatedEntityOnTag` in [ioquake3's `cgame/cg_ents.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_ents.c).
+pub fn position_rotated_on_tag(entity: &mut RefEntity, parent: &RefEntity, tag: &Orientation) {
+    entity.origin = tag_origin(parent, tag);
+    let rotated = matrix_multiply(&entity.axis, &tag.axis);
+    entity.axis = matrix_multiply(&rotated, &parent.axis);
+}
+
+/// Interpolate the tag `tag_name` of `parent`'s current frames
+fn lerp_parent_tag<T: RenderTraps + ?Sized>(
+    traps: &T,
+    parent: &RefEntity,
+    tag_name: &str,
+) -> Option<Orientation> {
+    traps.r_lerp_tag(
+        parent.h_model,
+        parent.oldframe,
+        parent.frame,
+        1.0 - parent.backlerp,
+        tag_name,
+    )
+}
+
+/// [`position_on_tag`](position_on_tag) with the tag `tag_name` of `parent`'s model, e.g. `"tag_weapon"`
+///
+/// Returns `false` and leaves `entity` as it is if the model has no such tag.
+///
+/// ```rust
+/// use quake3_native_vm::backend::{Mock, Recording};
+/// use quake3_native_vm::cgame::Syscalls;
+/// use quake3_native_vm::ffi;
+/// use quake3_native_vm::render::{attach_to_tag, RefEntity};
+///
+/// // No model has tags
+/// let engine = Recording::new(Mock(|_, _: &[isize]| 0));
+/// let syscalls = Syscalls::with_engine(&engine);
+///
+/// let torso = RefEntity { frame: 3, oldframe: 2, backlerp: 0.25, ..RefEntity::default() };
+/// let mut gun = RefEntity::default();
+/// assert!(!attach_to_tag(&syscalls, &mut gun, &torso, "tag_weapon"));
+/// assert_eq!(gun, RefEntity::default());
+///
+/// // Interpolated from the old frame to the current one
+/// let calls = engine.take();
+/// assert_eq!(calls[0].args[2..5], [2, 3, ffi::float_to_intptr(0.75)]);
+/// ```
+pub fn attach_to_tag<T: RenderTraps + ?Sized>(
+    traps: &T,
+    entity: &mut RefEntity,
+    parent: &RefEntity,
+    tag_name: &str,
+) -> bool {
+    match lerp_parent_tag(traps, parent, tag_name) {
+        Some(tag) => {
+            position_on_tag(entity, parent, &tag);
+            true
+        }
+        None => false,
+    }
+}
+
+/// [`position_rotated_on_tag`](position_rotated_on_tag) with the tag `tag_name` of `parent`'s model, e.g. `"tag_barrel"`
+///
+/// Returns `false` and leaves `entity` as it is if the model has no such tag.
+pub fn attach_rotated_to_tag<T: RenderTraps + ?Sized>(
+    traps: &T,
+    entity: &mut RefEntity,
+    parent: &RefEntity,
+    tag_name: &str,
+) -> bool {
+    match lerp_parent_tag(traps, parent, tag_name) {
+        Some(tag) => {
+            position_rotated_on_tag(entity, parent, &tag);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,210 @@
+//! Deferred callbacks keyed on level time
+//!
+//! The C game schedules entity logic with `nextthink` timestamps.
+//! The [`Scheduler`](Scheduler) does the same for logic that is not tied to an entity,
+//! e.g. announcements or periodic cleanup.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::scheduler::Scheduler;
+//! use quake3_native_vm::time::LevelTime;
+//! use std::sync::Mutex;
+//! use std::time::Duration;
+//!
+//! let log = Mutex::new(Vec::new());
+//! let scheduler = Scheduler::new();
+//!
+//! scheduler.after(Duration::from_millis(100), |log: &Mutex<Vec<_>>| {
+//!     log.lock().unwrap().push("once")
+//! });
+//! let id = scheduler.every(Duration::from_millis(50), |log: &Mutex<Vec<_>>| {
+//!     log.lock().unwrap().push("tick")
+//! });
+//!
+//! // Called from `run_frame`
+//! scheduler.run(LevelTime(50), &log);
+//! scheduler.run(LevelTime(100), &log);
+//! scheduler.cancel(id);
+//! scheduler.run(LevelTime(150), &log);
+//!
+//! assert_eq!(*log.lock().unwrap(), ["tick", "once", "tick"]);
+//! ```
+
+use crate::time::LevelTime;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Identifier of a scheduled callback, to [`cancel`](Scheduler::cancel) it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(u64);
+
+type Callback<C> = Box<dyn FnMut(&C) + Send>;
+
+struct Entry<C> {
+    callback: Callback<C>,
+    interval: Option<Duration>,
+}
+
+struct State<C> {
+    entries: BTreeMap<(LevelTime, TaskId), Entry<C>>,
+    /// Callback currently being run
+    running: Option<TaskId>,
+    /// Repeating callbacks cancelled while they were running
+    cancelled: HashSet<TaskId>,
+    next_id: u64,
+    now: LevelTime,
+}
+
+/// Callbacks to run at a given level time, with a context `C`
+pub struct Scheduler<C = ()> {
+    state: Mutex<State<C>>,
+}
+
+impl<C> Scheduler<C> {
+    /// Create a scheduler without callbacks
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                entries: BTreeMap::new(),
+                running: None,
+                cancelled: HashSet::new(),
+                next_id: 0,
+                now: LevelTime::default(),
+            }),
+        }
+    }
+
+    fn insert(&self, time: LevelTime, interval: Option<Duration>, callback: Callback<C>) -> TaskId {
+        let mut state = self.state.lock().unwrap();
+        let id = TaskId(state.next_id);
+        state.next_id += 1;
+        state
+            .entries
+            .insert((time, id), Entry { callback, interval });
+        id
+    }
+
+    /// Run `callback` at the absolute level `time`
+    pub fn at(&self, time: LevelTime, callback: impl FnMut(&C) + Send + 'static) -> TaskId {
+        self.insert(time, None, Box::new(callback))
+    }
+
+    /// Run `callback` once `delay` passed since the last [`run`](Scheduler::run)
+    pub fn after(&self, delay: Duration, callback: impl FnMut(&C) + Send + 'static) -> TaskId {
+        let time = self.now() + delay;
+        self.insert(time, None, Box::new(callback))
+    }
+
+    /// Run `callback` every `interval`, starting one `interval` after the last [`run`](Scheduler::run)
+    ///
+    /// The callback runs at most once per [`run`](Scheduler::run), missed intervals are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is shorter than a millisecond.
+    pub fn every(&self, interval: Duration, callback: impl FnMut(&C) + Send + 'static) -> TaskId {
+        assert!(
+            interval >= Duration::from_millis(1),
+            "Interval must be at least 1ms"
+        );
+        let time = self.now() + interval;
+        self.insert(time, Some(interval), Box::new(callback))
+    }
+
+    /// Remove a callback, returning whether it was still scheduled
+    pub fn cancel(&self, id: TaskId) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let key = state.entries.keys().find(|(_, i)| *i == id).copied();
+        match key {
+            Some(key) => {
+                state.entries.remove(&key);
+                true
+            }
+            None if state.running == Some(id) => state.cancelled.insert(id),
+            None => false,
+        }
+    }
+
+    /// Level time of the last [`run`](Scheduler::run)
+    pub fn now(&self) -> LevelTime {
+        self.state.lock().unwrap().now
+    }
+
+    /// Run all callbacks due at `now`, in order of their scheduled time, returning how many ran
+    ///
+    /// Callbacks scheduled by the callbacks are not run before the next call,
+    /// even if they are already due.
+    pub fn run(&self, now: LevelTime, context: &C) -> usize {
+        let limit = {
+            let mut state = self.state.lock().unwrap();
+            state.now = now;
+            state.cancelled.clear();
+            state.next_id
+        };
+
+        let mut count = 0;
+        loop {
+            let due = {
+                let mut state = self.state.lock().unwrap();
+                let key = state
+                    .entries
+                    .keys()
+                    .take_while(|(time, _)| *time <= now)
+                    .find(|(_, id)| id.0 < limit)
+                    .copied();
+                let due = key.and_then(|key| state.entries.remove_entry(&key));
+                state.running = due.as_ref().map(|((_, id), _)| *id);
+                due
+            };
+            let ((time, id), mut entry) = match due {
+                Some(due) => due,
+                None => break,
+            };
+
+            (entry.callback)(context);
+            count += 1;
+
+            let mut state = self.state.lock().unwrap();
+            state.running = None;
+            if let Some(interval) = entry.interval {
+                if !state.cancelled.remove(&id) {
+                    let mut next = time + interval;
+                    if next <= now {
+                        next = now + interval;
+                    }
+                    state.entries.insert((next, id), entry);
+                }
+            }
+        }
+        count
+    }
+
+    /// Number of scheduled callbacks
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Whether no callbacks are scheduled
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<C> Default for Scheduler<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> fmt::Debug for Scheduler<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("Scheduler")
+            .field("callbacks", &state.entries.len())
+            .field("now", &state.now)
+            .finish()
+    }
+}
@@ -0,0 +1,830 @@
+//! Types shared between the engine and all modules
+//!
+//! See [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+
+use crate::ffi;
+
+/// C boolean of the engine
+///
+/// Unlike [`bool`](bool), any non-zero value is true, so it is safe to read from engine memory.
+/// Compares by truthiness, i.e. `QBoolean::from_raw(2) == QBoolean::TRUE`.
+///
+/// See `qboolean` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::shared::QBoolean;
+///
+/// let linked = QBoolean::from(true);
+/// assert_eq!(linked.raw(), 1);
+/// assert!(bool::from(QBoolean::from_raw(-1)));
+/// assert_eq!(QBoolean::default(), QBoolean::FALSE);
+/// ```
+#[repr(transparent)]
+#[derive(Clone, Copy, Default)]
+pub struct QBoolean(ffi::c_int);
+
+assert_layout!(QBoolean, size = 4, align = 4);
+
+impl QBoolean {
+    /// `qfalse`
+    pub const FALSE: Self = Self(0);
+    /// `qtrue`
+    pub const TRUE: Self = Self(1);
+
+    /// Wrap a raw C value
+    pub const fn from_raw(value: ffi::c_int) -> Self {
+        Self(value)
+    }
+
+    /// Raw C value
+    pub const fn raw(self) -> ffi::c_int {
+        self.0
+    }
+
+    /// Whether this is true, i.e. non-zero
+    pub const fn get(self) -> bool {
+        self.0 != 0
+    }
+}
+
+impl From<bool> for QBoolean {
+    fn from(value: bool) -> Self {
+        if value {
+            Self::TRUE
+        } else {
+            Self::FALSE
+        }
+    }
+}
+
+impl From<QBoolean> for bool {
+    fn from(value: QBoolean) -> Self {
+        value.get()
+    }
+}
+
+impl PartialEq for QBoolean {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for QBoolean {}
+
+impl std::hash::Hash for QBoolean {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.get().hash(state)
+    }
+}
+
+impl std::fmt::Debug for QBoolean {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            0 => f.write_str("qfalse"),
+            1 => f.write_str("qtrue"),
+            raw => write!(f, "qtrue({})", raw),
+        }
+    }
+}
+
+/// 3D vector, e.g. a position or angles
+pub type Vec3 = [f32; 3];
+
+/// Round all components to integers
+///
+/// The engine transmits positions as integers where possible to save bandwidth.
+///
+/// See `SnapVector` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub fn snap_vector(v: Vec3) -> Vec3 {
+    v.map(f32::round)
+}
+
+/// Identity rotation, the axes of an unrotated [`Orientation`](Orientation)
+///
+/// See `axisDefault` in [ioquake3's `qcommon/q_math.c`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_math.c).
+pub const AXIS_DEFAULT: [Vec3; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Position and rotation, e.g. of a tag of a model
+///
+/// See `orientation_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orientation {
+    /// Position
+    pub origin: Vec3,
+    /// Forward, left and up vectors of the rotation
+    pub axis: [Vec3; 3],
+}
+
+assert_layout!(Orientation, size = 48, align = 4);
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Self {
+            origin: [0.0; 3],
+            axis: AXIS_DEFAULT,
+        }
+    }
+}
+
+/// Trajectory types for [`Trajectory::tr_type`](Trajectory::tr_type)
+///
+/// See `trType_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum TrType {
+    /// Not moving
+    TR_STATIONARY = 0,
+    /// Interpolated between snapshots, e.g. players
+    TR_INTERPOLATE = 1,
+    /// Moving with constant velocity
+    TR_LINEAR = 2,
+    /// Moving with constant velocity for a duration
+    TR_LINEAR_STOP = 3,
+    /// Oscillating, e.g. bobbing items
+    TR_SINE = 4,
+    /// Falling
+    TR_GRAVITY = 5,
+}
+
+impl TryFrom<ffi::c_int> for TrType {
+    type Error = &'static str;
+
+    fn try_from(tr_type: ffi::c_int) -> Result<Self, Self::Error> {
+        match tr_type {
+            0 => Ok(Self::TR_STATIONARY),
+            1 => Ok(Self::TR_INTERPOLATE),
+            2 => Ok(Self::TR_LINEAR),
+            3 => Ok(Self::TR_LINEAR_STOP),
+            4 => Ok(Self::TR_SINE),
+            5 => Ok(Self::TR_GRAVITY),
+            _ => Err("Unknown trajectory type"),
+        }
+    }
+}
+
+/// Movement of an entity that clients can evaluate by themselves
+///
+/// See `trajectory_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Trajectory {
+    /// [`TrType`](TrType) of this trajectory
+    pub tr_type: ffi::c_int,
+    /// Start time of the trajectory
+    pub tr_time: ffi::c_int,
+    /// Duration for [`TrType::TR_LINEAR_STOP`](TrType::TR_LINEAR_STOP) and [`TrType::TR_SINE`](TrType::TR_SINE)
+    pub tr_duration: ffi::c_int,
+    /// Start position
+    pub tr_base: Vec3,
+    /// Velocity
+    pub tr_delta: Vec3,
+}
+
+assert_layout!(Trajectory, size = 36, align = 4);
+
+impl Trajectory {
+    /// Trajectory that stays at `base`
+    pub fn stationary(base: Vec3) -> Self {
+        Self {
+            tr_type: TrType::TR_STATIONARY as ffi::c_int,
+            tr_base: base,
+            ..Self::default()
+        }
+    }
+
+    /// Position at level time `at`
+    ///
+    /// Unknown trajectory types stay at [`tr_base`](Trajectory::tr_base), like [`TrType::TR_STATIONARY`](TrType::TR_STATIONARY).
+    ///
+    /// See `BG_EvaluateTrajectory` in [ioquake3's `game/bg_misc.c`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_misc.c).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quake3_native_vm::ffi;
+    /// use quake3_native_vm::shared::{TrType, Trajectory};
+    ///
+    /// let rocket = Trajectory {
+    ///     tr_type: TrType::TR_LINEAR as ffi::c_int,
+    ///     tr_time: 1000,
+    ///     tr_duration: 0,
+    ///     tr_base: [0.0, 0.0, 64.0],
+    ///     tr_delta: [900.0, 0.0, 0.0],
+    /// };
+    /// assert_eq!(rocket.evaluate(1500), [450.0, 0.0, 64.0]);
+    /// ```
+    pub fn evaluate(&self, at: ffi::c_int) -> Vec3 {
+        let seconds = |time: ffi::c_int| (time - self.tr_time) as f32 * 0.001;
+        let along = |scale: f32| {
+            let mut position = self.tr_base;
+            for (position, delta) in position.iter_mut().zip(self.tr_delta) {
+                *position += delta * scale;
+            }
+            position
+        };
+        match TrType::try_from(self.tr_type) {
+            Ok(TrType::TR_LINEAR) => along(seconds(at)),
+            Ok(TrType::TR_SINE) => {
+                let cycles = (at - self.tr_time) as f32 / self.tr_duration as f32;
+                along((cycles * std::f32::consts::TAU).sin())
+            }
+            Ok(TrType::TR_LINEAR_STOP) => {
+                let at = at.min(self.tr_time + self.tr_duration);
+                along(seconds(at).max(0.0))
+            }
+            Ok(TrType::TR_GRAVITY) => {
+                let t = seconds(at);
+                let mut position = along(t);
+                position[2] -= 0.5 * crate::bg::DEFAULT_GRAVITY * t * t;
+                position
+            }
+            Ok(TrType::TR_STATIONARY | TrType::TR_INTERPOLATE) | Err(_) => self.tr_base,
+        }
+    }
+}
+
+/// Part of an entity that is sent to clients
+///
+/// See `entityState_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct EntityState {
+    /// Entity index
+    pub number: ffi::c_int,
+    /// Entity type, see `entityType_t` in `bg_public.h`
+    pub e_type: ffi::c_int,
+    pub e_flags: ffi::c_int,
+    /// Position trajectory
+    pub pos: Trajectory,
+    /// Angle trajectory
+    pub apos: Trajectory,
+    pub time: ffi::c_int,
+    pub time2: ffi::c_int,
+    pub origin: Vec3,
+    pub origin2: Vec3,
+    pub angles: Vec3,
+    pub angles2: Vec3,
+    pub other_entity_num: ffi::c_int,
+    pub other_entity_num2: ffi::c_int,
+    pub ground_entity_num: ffi::c_int,
+    pub constant_light: ffi::c_int,
+    pub loop_sound: ffi::c_int,
+    pub modelindex: ffi::c_int,
+    pub modelindex2: ffi::c_int,
+    pub client_num: ffi::c_int,
+    pub frame: ffi::c_int,
+    pub solid: ffi::c_int,
+    /// Event, including [`EV_EVENT_BITS`](EV_EVENT_BITS)
+    pub event: ffi::c_int,
+    pub event_parm: ffi::c_int,
+    pub powerups: ffi::c_int,
+    pub weapon: ffi::c_int,
+    pub legs_anim: ffi::c_int,
+    pub torso_anim: ffi::c_int,
+    pub generic1: ffi::c_int,
+}
+
+assert_layout!(EntityState, size = 208, align = 4);
+
+/// Sequence bits of entity events
+///
+/// Toggled on every new event so that clients notice repeated identical events.
+///
+/// See `EV_EVENT_BITS` in [ioquake3's `game/bg_public.h`](https://github.com/ioquake/ioq3/blob/master/code/game/bg_public.h).
+pub const EV_EVENT_BITS: ffi::c_int = EV_EVENT_BIT1 | EV_EVENT_BIT2;
+
+/// Lower sequence bit of [`EV_EVENT_BITS`](EV_EVENT_BITS)
+pub const EV_EVENT_BIT1: ffi::c_int = 0x00000100;
+
+/// Upper sequence bit of [`EV_EVENT_BITS`](EV_EVENT_BITS)
+pub const EV_EVENT_BIT2: ffi::c_int = 0x00000200;
+
+/// Combine `event` with the sequence bits following those of `previous`
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::shared::{next_event, EV_EVENT_BITS};
+///
+/// let first = next_event(0, 7);
+/// let second = next_event(first, 7);
+/// assert_ne!(first, second);
+/// assert_eq!(second & !EV_EVENT_BITS, 7);
+/// ```
+pub fn next_event(previous: ffi::c_int, event: ffi::c_int) -> ffi::c_int {
+    let bits = ((previous & EV_EVENT_BITS) + EV_EVENT_BIT1) & EV_EVENT_BITS;
+    event | bits
+}
+
+/// Plane of a surface
+///
+/// See `cplane_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Plane {
+    /// Unit normal
+    pub normal: Vec3,
+    /// Distance from the origin along the normal
+    pub dist: f32,
+    /// Axis the plane is aligned to, `3` if it is not
+    pub plane_type: u8,
+    /// Sign bits of the normal components, for fast box tests
+    pub signbits: u8,
+    #[allow(missing_docs)]
+    pub pad: [u8; 2],
+}
+
+assert_layout!(Plane, size = 20, align = 4);
+
+bitflags::bitflags! {
+    /// Contents of brushes and entities, e.g. for [`Trace::contents`](Trace::contents)
+    ///
+    /// See [`TraceMask`](crate::bg::TraceMask) for the usual combinations to trace against.
+    ///
+    /// See `CONTENTS_SOLID` etc. in [ioquake3's `game/surfaceflags.h`](https://github.com/ioquake/ioq3/blob/master/code/game/surfaceflags.h).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quake3_native_vm::shared::{Contents, Trace};
+    ///
+    /// let trace = Trace { contents: 0x0200_0000, ..Default::default() };
+    /// assert_eq!(Contents::from_bits_retain(trace.contents), Contents::BODY);
+    /// ```
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct Contents: ffi::c_int {
+        /// Solid brush, e.g. a wall
+        const SOLID = 0x0000_0001;
+        /// Lava, hurts
+        const LAVA = 0x0000_0008;
+        /// Slime, hurts
+        const SLIME = 0x0000_0010;
+        /// Water
+        const WATER = 0x0000_0020;
+        /// Fog
+        const FOG = 0x0000_0040;
+        /// Not solid for players of the red team
+        const NOTTEAM1 = 0x0000_0080;
+        /// Not solid for players of the blue team
+        const NOTTEAM2 = 0x0000_0100;
+        /// Not solid for bots
+        const NOBOTCLIP = 0x0000_0200;
+        /// Portal between areas, e.g. in a door
+        const AREAPORTAL = 0x0000_8000;
+        /// Solid for players only
+        const PLAYERCLIP = 0x0001_0000;
+        /// Solid for monsters only, unused
+        const MONSTERCLIP = 0x0002_0000;
+        /// Teleporter, for bot navigation
+        const TELEPORTER = 0x0004_0000;
+        /// Jump pad, for bot navigation
+        const JUMPPAD = 0x0008_0000;
+        /// Portal between bot navigation clusters
+        const CLUSTERPORTAL = 0x0010_0000;
+        /// Bots must not enter
+        const DONOTENTER = 0x0020_0000;
+        /// Solid for bots only
+        const BOTCLIP = 0x0040_0000;
+        /// Mover, e.g. a door or platform
+        const MOVER = 0x0080_0000;
+        /// Origin brush, removed when compiling the map
+        const ORIGIN = 0x0100_0000;
+        /// Player or other living entity
+        const BODY = 0x0200_0000;
+        /// Dead body
+        const CORPSE = 0x0400_0000;
+        /// Detail brush, not used for visibility
+        const DETAIL = 0x0800_0000;
+        /// Structural brush, used for visibility
+        const STRUCTURAL = 0x1000_0000;
+        /// Translucent brush, doesn't block visibility
+        const TRANSLUCENT = 0x2000_0000;
+        /// Trigger, e.g. `trigger_multiple`
+        const TRIGGER = 0x4000_0000;
+        /// Items are removed instead of dropped here
+        const NODROP = ffi::c_int::MIN;
+    }
+}
+
+/// Result of tracing a box through the world
+///
+/// See `trace_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::shared::Trace;
+///
+/// assert_eq!(std::mem::size_of::<Trace>(), 56);
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Trace {
+    /// Whether the whole trace was inside a solid
+    pub allsolid: QBoolean,
+    /// Whether the trace started inside a solid
+    pub startsolid: QBoolean,
+    /// Fraction of the way that was traced, `1.0` if nothing was hit
+    pub fraction: f32,
+    /// End position of the trace
+    pub endpos: Vec3,
+    /// Surface that was hit
+    pub plane: Plane,
+    /// Surface flags of the surface that was hit
+    pub surface_flags: ffi::c_int,
+    /// Contents of the surface that was hit
+    pub contents: ffi::c_int,
+    /// Entity that was hit
+    pub entity_num: ffi::c_int,
+}
+
+assert_layout!(Trace, size = 56, align = 4);
+
+/// Maximum number of clients on a server
+///
+/// See `MAX_CLIENTS` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const MAX_CLIENTS: usize = 64;
+
+/// Maximum length of a path in the game's file system, including the NUL
+///
+/// See `MAX_QPATH` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const MAX_QPATH: usize = 64;
+
+/// Configstring with the serverinfo cvars, e.g. `mapname` and `sv_hostname`
+///
+/// Parse it with [`InfoString`](crate::info::InfoString).
+///
+/// See `CS_SERVERINFO` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const CS_SERVERINFO: ffi::c_int = 0;
+
+/// Configstring with the systeminfo cvars, e.g. `sv_pure` and `sv_paks`
+///
+/// Parse it with [`BigInfoString`](crate::info::BigInfoString).
+///
+/// See `CS_SYSTEMINFO` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const CS_SYSTEMINFO: ffi::c_int = 1;
+
+/// Size of the bit vector of visible areas, e.g. [`Snapshot::areamask`](crate::cgame::Snapshot::areamask)
+///
+/// See `MAX_MAP_AREA_BYTES` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const MAX_MAP_AREA_BYTES: usize = 32;
+
+/// Size of [`PlayerState::stats`](PlayerState::stats)
+pub const MAX_STATS: usize = 16;
+/// Size of [`PlayerState::persistant`](PlayerState::persistant)
+pub const MAX_PERSISTANT: usize = 16;
+/// Size of [`PlayerState::powerups`](PlayerState::powerups)
+pub const MAX_POWERUPS: usize = 16;
+/// Size of [`PlayerState::ammo`](PlayerState::ammo)
+pub const MAX_WEAPONS: usize = 16;
+/// Size of [`PlayerState::events`](PlayerState::events)
+pub const MAX_PS_EVENTS: usize = 2;
+
+/// Part of a client that is sent to that client only, or to spectators following it
+///
+/// See `playerState_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::bg::StatIndex;
+/// use quake3_native_vm::shared::PlayerState;
+///
+/// let mut ps = PlayerState::default();
+/// ps.stats[StatIndex::STAT_HEALTH as usize] = 125;
+/// assert_eq!(std::mem::size_of::<PlayerState>(), 468);
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(missing_docs)]
+pub struct PlayerState {
+    /// Time of the last executed user command
+    pub command_time: ffi::c_int,
+    /// [`PmType`](crate::bg::PmType) of this player
+    pub pm_type: ffi::c_int,
+    pub bob_cycle: ffi::c_int,
+    pub pm_flags: ffi::c_int,
+    pub pm_time: ffi::c_int,
+    pub origin: Vec3,
+    pub velocity: Vec3,
+    pub weapon_time: ffi::c_int,
+    pub gravity: ffi::c_int,
+    pub speed: ffi::c_int,
+    /// Added to the command angles to get the view angles
+    pub delta_angles: [ffi::c_int; 3],
+    pub ground_entity_num: ffi::c_int,
+    pub legs_timer: ffi::c_int,
+    pub legs_anim: ffi::c_int,
+    pub torso_timer: ffi::c_int,
+    pub torso_anim: ffi::c_int,
+    pub movement_dir: ffi::c_int,
+    pub grapple_point: Vec3,
+    pub e_flags: ffi::c_int,
+    pub event_sequence: ffi::c_int,
+    pub events: [ffi::c_int; MAX_PS_EVENTS],
+    pub event_parms: [ffi::c_int; MAX_PS_EVENTS],
+    pub external_event: ffi::c_int,
+    pub external_event_parm: ffi::c_int,
+    pub external_event_time: ffi::c_int,
+    pub client_num: ffi::c_int,
+    pub weapon: ffi::c_int,
+    pub weaponstate: ffi::c_int,
+    pub viewangles: Vec3,
+    pub viewheight: ffi::c_int,
+    pub damage_event: ffi::c_int,
+    pub damage_yaw: ffi::c_int,
+    pub damage_pitch: ffi::c_int,
+    pub damage_count: ffi::c_int,
+    /// Indexed by [`StatIndex`](crate::bg::StatIndex)
+    pub stats: [ffi::c_int; MAX_STATS],
+    /// Indexed by `persEnum_t`, kept across respawns
+    pub persistant: [ffi::c_int; MAX_PERSISTANT],
+    /// Level time a powerup runs out, indexed by [`Powerup`](crate::bg::Powerup)
+    pub powerups: [ffi::c_int; MAX_POWERUPS],
+    pub ammo: [ffi::c_int; MAX_WEAPONS],
+    pub generic1: ffi::c_int,
+    pub loop_sound: ffi::c_int,
+    pub jumppad_ent: ffi::c_int,
+    /// Not communicated over the net
+    pub ping: ffi::c_int,
+    pub pmove_framecount: ffi::c_int,
+    pub jumppad_frame: ffi::c_int,
+    pub entity_event_sequence: ffi::c_int,
+}
+
+assert_layout!(PlayerState, size = 468, align = 4);
+
+/// Input of a client for one frame
+///
+/// See `usercmd_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::shared::UserCmd;
+///
+/// assert_eq!(std::mem::size_of::<UserCmd>(), 24);
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UserCmd {
+    /// Server time the command is for
+    pub server_time: ffi::c_int,
+    /// View angles, as 16 bit shorts
+    pub angles: [ffi::c_int; 3],
+    /// `BUTTON_*` bits
+    pub buttons: ffi::c_int,
+    /// Selected weapon
+    pub weapon: u8,
+    /// Forward movement, negative for backward
+    pub forwardmove: i8,
+    /// Right movement, negative for left
+    pub rightmove: i8,
+    /// Up movement, negative for crouching
+    pub upmove: i8,
+}
+
+assert_layout!(UserCmd, size = 24, align = 4);
+
+/// The snapshot was delayed by the client's rate setting
+///
+/// See `SNAPFLAG_RATE_DELAYED` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+pub const SNAPFLAG_RATE_DELAYED: ffi::c_int = 1;
+/// The snapshot is from before the client entered the game, e.g. while connecting
+pub const SNAPFLAG_NOT_ACTIVE: ffi::c_int = 2;
+/// Toggled every map change or `map_restart`, so the client does not predict across it
+pub const SNAPFLAG_SERVERCOUNT: ffi::c_int = 4;
+
+/// Save the cvar to the config file
+pub const CVAR_ARCHIVE: ffi::c_int = 0x0001;
+/// Send the cvar to the server on connect and change
+pub const CVAR_USERINFO: ffi::c_int = 0x0002;
+/// Send the cvar in response to front end requests
+pub const CVAR_SERVERINFO: ffi::c_int = 0x0004;
+/// Send the cvar to all clients on connect and change
+pub const CVAR_SYSTEMINFO: ffi::c_int = 0x0008;
+/// Only allow setting the cvar from the command line
+pub const CVAR_INIT: ffi::c_int = 0x0010;
+/// Only apply changes of the cvar on the next map or restart
+pub const CVAR_LATCH: ffi::c_int = 0x0020;
+/// Do not allow changing the cvar by the user
+pub const CVAR_ROM: ffi::c_int = 0x0040;
+/// Do not save the cvar to the config file
+pub const CVAR_TEMP: ffi::c_int = 0x0100;
+/// Only allow changing the cvar with `sv_cheats 1`
+pub const CVAR_CHEAT: ffi::c_int = 0x0200;
+/// Do not clear the cvar when the game restarts
+pub const CVAR_NORESTART: ffi::c_int = 0x0400;
+
+bitflags::bitflags! {
+    /// Flags of a cvar, combinations of the `CVAR_*` constants
+    ///
+    /// See `CVAR_ARCHIVE` etc. in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quake3_native_vm::shared::{CvarFlags, CVAR_ARCHIVE, CVAR_SERVERINFO};
+    ///
+    /// let flags = CvarFlags::ARCHIVE | CvarFlags::SERVERINFO;
+    /// assert_eq!(flags.bits(), CVAR_ARCHIVE | CVAR_SERVERINFO);
+    /// assert_eq!(format!("{:?}", flags), "CvarFlags(ARCHIVE | SERVERINFO)");
+    ///
+    /// // Flags the engine doesn't know are rejected
+    /// assert_eq!(CvarFlags::from_bits(0x8000), None);
+    /// ```
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct CvarFlags: ffi::c_int {
+        /// See [`CVAR_ARCHIVE`](CVAR_ARCHIVE)
+        const ARCHIVE = CVAR_ARCHIVE;
+        /// See [`CVAR_USERINFO`](CVAR_USERINFO)
+        const USERINFO = CVAR_USERINFO;
+        /// See [`CVAR_SERVERINFO`](CVAR_SERVERINFO)
+        const SERVERINFO = CVAR_SERVERINFO;
+        /// See [`CVAR_SYSTEMINFO`](CVAR_SYSTEMINFO)
+        const SYSTEMINFO = CVAR_SYSTEMINFO;
+        /// See [`CVAR_INIT`](CVAR_INIT)
+        const INIT = CVAR_INIT;
+        /// See [`CVAR_LATCH`](CVAR_LATCH)
+        const LATCH = CVAR_LATCH;
+        /// See [`CVAR_ROM`](CVAR_ROM)
+        const ROM = CVAR_ROM;
+        /// See [`CVAR_TEMP`](CVAR_TEMP)
+        const TEMP = CVAR_TEMP;
+        /// See [`CVAR_CHEAT`](CVAR_CHEAT)
+        const CHEAT = CVAR_CHEAT;
+        /// See [`CVAR_NORESTART`](CVAR_NORESTART)
+        const NORESTART = CVAR_NORESTART;
+    }
+}
+
+bitflags::bitflags! {
+    /// Which part of the client gets key presses, e.g. the console or the `cgame` module
+    ///
+    /// See `KEYCATCH_CONSOLE` etc. in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use quake3_native_vm::shared::KeyCatcher;
+    ///
+    /// // Chat input on top of the console
+    /// let catcher = KeyCatcher::CONSOLE | KeyCatcher::MESSAGE;
+    /// assert!(!catcher.contains(KeyCatcher::CGAME));
+    /// assert_eq!(catcher.bits(), 0x0005);
+    /// ```
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct KeyCatcher: ffi::c_int {
+        /// The console is open
+        const CONSOLE = 0x0001;
+        /// The `ui` module, e.g. a menu
+        const UI = 0x0002;
+        /// The chat message line
+        const MESSAGE = 0x0004;
+        /// The `cgame` module, which gets `CG_KEY_EVENT` and `CG_MOUSE_EVENT`
+        const CGAME = 0x0008;
+    }
+}
+
+/// Connection state of the client
+///
+/// See `connstate_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[allow(non_camel_case_types)]
+pub enum ConnState {
+    /// The client is not initialized yet
+    CA_UNINITIALIZED = 0,
+    /// Not talking to a server
+    CA_DISCONNECTED = 1,
+    /// Not used any more, was checking the CD key
+    CA_AUTHORIZING = 2,
+    /// Sending request packets to the server
+    CA_CONNECTING = 3,
+    /// Sending challenge packets to the server
+    CA_CHALLENGING = 4,
+    /// Netchan established, getting gamestate
+    CA_CONNECTED = 5,
+    /// Only during `cgame` initialization, never during main loop
+    CA_LOADING = 6,
+    /// Got gamestate, waiting for the first frame
+    CA_PRIMED = 7,
+    /// Game views should be displayed
+    CA_ACTIVE = 8,
+    /// Playing a cinematic or a static picture, not connected to a server
+    CA_CINEMATIC = 9,
+}
+
+impl TryFrom<ffi::c_int> for ConnState {
+    type Error = &'static str;
+
+    fn try_from(state: ffi::c_int) -> Result<Self, Self::Error> {
+        match state {
+            0 => Ok(Self::CA_UNINITIALIZED),
+            1 => Ok(Self::CA_DISCONNECTED),
+            2 => Ok(Self::CA_AUTHORIZING),
+            3 => Ok(Self::CA_CONNECTING),
+            4 => Ok(Self::CA_CHALLENGING),
+            5 => Ok(Self::CA_CONNECTED),
+            6 => Ok(Self::CA_LOADING),
+            7 => Ok(Self::CA_PRIMED),
+            8 => Ok(Self::CA_ACTIVE),
+            9 => Ok(Self::CA_CINEMATIC),
+            _ => Err("Unknown connection state"),
+        }
+    }
+}
+
+/// Copy of a cvar in module memory
+///
+/// The engine fills this in on registration and on every update, e.g. with
+/// [`qagame::Syscalls::cvar_register`](crate::qagame::Syscalls::cvar_register) and
+/// [`qagame::Syscalls::cvar_update`](crate::qagame::Syscalls::cvar_update).
+///
+/// See `vmCvar_t` in [ioquake3's `qcommon/q_shared.h`](https://github.com/ioquake/ioq3/blob/master/code/qcommon/q_shared.h).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::shared::VmCvar;
+///
+/// let cvar = VmCvar::default();
+/// assert!(!cvar.as_bool());
+/// assert_eq!(cvar.as_i32(), 0);
+/// assert_eq!(cvar.string(), "");
+/// ```
+#[repr(C)]
+#[derive(Clone)]
+pub struct VmCvar {
+    handle: ffi::c_int,
+    modification_count: ffi::c_int,
+    value: f32,
+    integer: ffi::c_int,
+    string: [u8; crate::qagame::MAX_CVAR_VALUE_STRING],
+}
+
+assert_layout!(VmCvar, size = 272, align = 4);
+
+impl VmCvar {
+    /// Engine's handle of the cvar, `0` until registered
+    pub fn handle(&self) -> ffi::c_int {
+        self.handle
+    }
+
+    /// How often the cvar has been changed, as of the last update
+    pub fn modification_count(&self) -> ffi::c_int {
+        self.modification_count
+    }
+
+    /// Value as boolean, i.e. whether its integer value is non-zero
+    pub fn as_bool(&self) -> bool {
+        self.integer != 0
+    }
+
+    /// Value as integer, `0` if the value is not a number
+    pub fn as_i32(&self) -> i32 {
+        self.integer
+    }
+
+    /// Value as float, `0.0` if the value is not a number
+    pub fn as_f32(&self) -> f32 {
+        self.value
+    }
+
+    /// Value as string
+    pub fn string(&self) -> String {
+        crate::qagame::buffer_to_string(&self.string)
+    }
+
+    /// Value as set, for values that are not valid UTF-8
+    pub fn string_bytes(&self) -> &[u8] {
+        crate::qagame::buffer_bytes(&self.string)
+    }
+}
+
+impl Default for VmCvar {
+    fn default() -> Self {
+        Self {
+            handle: 0,
+            modification_count: 0,
+            value: 0.0,
+            integer: 0,
+            string: [0; crate::qagame::MAX_CVAR_VALUE_STRING],
+        }
+    }
+}
+
+impl std::fmt::Debug for VmCvar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VmCvar")
+            .field("handle", &self.handle)
+            .field("modification_count", &self.modification_count)
+            .field("string", &self.string())
+            .finish()
+    }
+}
@@ -0,0 +1,48 @@
+//! Sounds and music for `cgame` and `ui`
+//!
+//! Sounds are registered with [`AssetTraps::s_register_sound`](crate::asset::AssetTraps::s_register_sound)
+//! or an [`AssetCache`](crate::asset::AssetCache), and played with [`SoundTraps`](SoundTraps).
+//! Looping sounds of entities are only in `cgame`, see [`cgame::looping`](crate::cgame::looping).
+//!
+//! See [ioquake3's `client/snd_dma.c`](https://github.com/ioquake/ioq3/blob/master/code/client/snd_dma.c).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use quake3_native_vm::asset::SoundHandle;
+//! use quake3_native_vm::backend::{Mock, Recording};
+//! use quake3_native_vm::bg::SoundChannel;
+//! use quake3_native_vm::sound::SoundTraps;
+//! use quake3_native_vm::ui::{Imports, Syscalls};
+//!
+//! let engine = Recording::new(Mock(|_, _: &[isize]| 0));
+//! let syscalls = Syscalls::with_engine(&engine);
+//!
+//! syscalls.s_start_background_track("music/sonic1.wav", None);
+//! syscalls.s_start_local_sound(SoundHandle(3), SoundChannel::CHAN_LOCAL_SOUND);
+//!
+//! let calls = engine.take();
+//! assert_eq!(calls[0].num, isize::from(Imports::UI_S_STARTBACKGROUNDTRACK));
+//! assert_eq!(calls[1].args, [3, SoundChannel::CHAN_LOCAL_SOUND as isize]);
+//! ```
+
+use crate::asset::SoundHandle;
+use crate::bg::SoundChannel;
+
+/// Sound traps of a module, implemented by the `Syscalls` of [`cgame`](crate::cgame::Syscalls) and [`ui`](crate::ui::Syscalls)
+///
+/// See `trap_S_StartLocalSound` etc. in [ioquake3's `cgame/cg_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/cgame/cg_syscalls.c).
+pub trait SoundTraps {
+    /// Play `sfx` without a position, e.g. for menus and announcements
+    ///
+    /// A new sound on `channel` replaces the one playing on it.
+    fn s_start_local_sound(&self, sfx: SoundHandle, channel: SoundChannel);
+
+    /// Play the music `intro` once and then `looping` over and over, or `intro` again without it
+    ///
+    /// Replaces the music playing, if any.
+    fn s_start_background_track(&self, intro: &str, looping: Option<&str>);
+
+    /// Stop the music
+    fn s_stop_background_track(&self);
+}
@@ -0,0 +1,79 @@
+//! Game time
+//!
+//! The engine measures time in milliseconds since the level started, e.g. the `level_time`
+//! passed to [`qagame::Module::run_frame`](crate::qagame::Module::run_frame).
+
+use crate::ffi;
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub};
+use std::time::Duration;
+
+/// Point in time of the current level, in milliseconds
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::time::LevelTime;
+/// use std::time::Duration;
+///
+/// let now = LevelTime::from(1000);
+/// let later = now + Duration::from_secs(3);
+/// assert_eq!(later, LevelTime::from(4000));
+/// assert_eq!(later - now, Duration::from_secs(3));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LevelTime(pub ffi::c_int);
+
+impl LevelTime {
+    /// Milliseconds since the level started
+    pub fn millis(self) -> ffi::c_int {
+        self.0
+    }
+}
+
+impl From<ffi::c_int> for LevelTime {
+    fn from(millis: ffi::c_int) -> Self {
+        Self(millis)
+    }
+}
+
+impl From<LevelTime> for ffi::c_int {
+    fn from(time: LevelTime) -> Self {
+        time.0
+    }
+}
+
+/// Convert to milliseconds, saturating at the maximum level time
+fn duration_millis(duration: Duration) -> ffi::c_int {
+    ffi::c_int::try_from(duration.as_millis()).unwrap_or(ffi::c_int::MAX)
+}
+
+impl Add<Duration> for LevelTime {
+    type Output = Self;
+
+    fn add(self, duration: Duration) -> Self {
+        Self(self.0.saturating_add(duration_millis(duration)))
+    }
+}
+
+impl AddAssign<Duration> for LevelTime {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub for LevelTime {
+    type Output = Duration;
+
+    /// Time between both points in time, or zero if `other` is later
+    fn sub(self, other: Self) -> Duration {
+        let millis = self.0.saturating_sub(other.0).max(0);
+        Duration::from_millis(millis as u64)
+    }
+}
+
+impl fmt::Display for LevelTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}ms", self.0)
+    }
+}
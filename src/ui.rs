@@ -0,0 +1,901 @@
+//! Code for the `ui` Quake 3 module
+//!
+//! The `ui` module contains the menus of Quake 3, e.g.:
+//!
+//! * the main menu
+//! * the server browser
+//! * the loading screen while connecting
+//!
+//! Only the syscalls needed so far are wrapped, see [`Imports`](Imports).
+
+#[cfg(feature = "base")]
+pub mod base;
+
+use crate::asset::{AssetTraps, FontInfo, ModelHandle, ShaderHandle, SoundHandle};
+use crate::bg::SoundChannel;
+use crate::cinematic::{CinematicFlags, CinematicTraps};
+use crate::info::BIG_INFO_STRING;
+use crate::metrics;
+use crate::qagame::{buffer_bytes, buffer_to_string, to_cstring, CbufExec, MAX_STRING_CHARS};
+use crate::render::RenderTraps;
+use crate::shared::{ConnState, KeyCatcher, Orientation};
+use crate::sound::SoundTraps;
+use crate::{ffi, EngineSyscalls, Syscall, VmResult};
+
+/// Version of the module API, the engine refuses to load a module with another one
+///
+/// See `UI_API_VERSION` in [ioquake3's `ui/ui_public.h`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_public.h).
+pub const UI_API_VERSION: ffi::c_int = 6;
+
+/// Menu the engine asks the module to open with [`Module::set_active_menu`](Module::set_active_menu)
+///
+/// See `uiMenuCommand_t` in [ioquake3's `ui/ui_public.h`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum MenuCommand {
+    /// Close all menus
+    UIMENU_NONE = 0,
+    /// Main menu, e.g. after disconnecting
+    UIMENU_MAIN = 1,
+    /// In-game menu, e.g. on `Escape`
+    UIMENU_INGAME = 2,
+    /// Ask to insert the CD
+    UIMENU_NEED_CD = 3,
+    /// Tell the CD key is invalid
+    UIMENU_BAD_CD_KEY = 4,
+    /// Team selection, Team Arena only
+    UIMENU_TEAM = 5,
+    /// Scoreboard after the game, Team Arena only
+    UIMENU_POSTGAME = 6,
+}
+
+impl TryFrom<ffi::c_int> for MenuCommand {
+    type Error = &'static str;
+
+    fn try_from(menu: ffi::c_int) -> Result<Self, Self::Error> {
+        match menu {
+            0 => Ok(Self::UIMENU_NONE),
+            1 => Ok(Self::UIMENU_MAIN),
+            2 => Ok(Self::UIMENU_INGAME),
+            3 => Ok(Self::UIMENU_NEED_CD),
+            4 => Ok(Self::UIMENU_BAD_CD_KEY),
+            5 => Ok(Self::UIMENU_TEAM),
+            6 => Ok(Self::UIMENU_POSTGAME),
+            _ => Err("Unknown menu command"),
+        }
+    }
+}
+
+/// Connection of the client, for menus like the connect screen
+///
+/// See `uiClientState_t` in [ioquake3's `ui/ui_public.h`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_public.h).
+///
+/// # Examples
+///
+/// ```rust
+/// use quake3_native_vm::ffi;
+/// use quake3_native_vm::shared::ConnState;
+/// use quake3_native_vm::ui::UiClientState;
+///
+/// let mut state = UiClientState::default();
+/// state.conn_state = ConnState::CA_CHALLENGING as ffi::c_int;
+/// state.servername[..9].copy_from_slice(b"localhost");
+///
+/// assert!(state.conn_state() < Some(ConnState::CA_ACTIVE));
+/// assert_eq!(state.servername(), "localhost");
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UiClientState {
+    /// Raw [`ConnState`](ConnState), see [`conn_state`](UiClientState::conn_state)
+    pub conn_state: ffi::c_int,
+    /// Number of connection packets sent, e.g. to show the retries
+    pub connect_packet_count: ffi::c_int,
+    /// Client number on the server, once connected
+    pub client_num: ffi::c_int,
+    /// Address of the server, as given to `connect`
+    pub servername: [u8; MAX_STRING_CHARS],
+    /// Result of checking for an update of the engine
+    pub update_info_string: [u8; MAX_STRING_CHARS],
+    /// Message of the server, e.g. why it refused the connection
+    pub message_string: [u8; MAX_STRING_CHARS],
+}
+
+assert_layout!(UiClientState, size = 3084, align = 4);
+
+impl UiClientState {
+    /// Connection state, `None` for an unknown value
+    pub fn conn_state(&self) -> Option<ConnState> {
+        ConnState::try_from(self.conn_state).ok()
+    }
+
+    /// [`servername`](UiClientState::servername) up to its NUL
+    pub fn servername(&self) -> String {
+        buffer_to_string(&self.servername)
+    }
+
+    /// [`update_info_string`](UiClientState::update_info_string) up to its NUL
+    pub fn update_info_string(&self) -> String {
+        buffer_to_string(&self.update_info_string)
+    }
+
+    /// [`message_string`](UiClientState::message_string) up to its NUL
+    pub fn message_string(&self) -> String {
+        buffer_to_string(&self.message_string)
+    }
+}
+
+impl Default for UiClientState {
+    fn default() -> Self {
+        Self {
+            conn_state: ConnState::CA_UNINITIALIZED as ffi::c_int,
+            connect_packet_count: 0,
+            client_num: 0,
+            servername: [0; MAX_STRING_CHARS],
+            update_info_string: [0; MAX_STRING_CHARS],
+            message_string: [0; MAX_STRING_CHARS],
+        }
+    }
+}
+
+/// System traps provided by the engine
+///
+/// See `uiImport_t` in [ioquake3's `ui/ui_public.h`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Imports {
+    /// Quit with an error message
+    UI_ERROR = 0,
+    /// Print a message to the console
+    UI_PRINT = 1,
+    /// Get the time since the engine started
+    UI_MILLISECONDS = 2,
+    /// Get the number of arguments of the current command
+    UI_ARGC = 10,
+    /// Get an argument of the current command
+    UI_ARGV = 11,
+    /// Add text to the command buffer
+    UI_CMD_EXECUTETEXT = 12,
+    /// Load a model
+    UI_R_REGISTERMODEL = 18,
+    /// Load a shader without mipmaps
+    UI_R_REGISTERSHADERNOMIP = 20,
+    /// Render a frame right away, e.g. while loading
+    UI_UPDATESCREEN = 28,
+    /// Get the interpolated orientation of a tag of a model
+    UI_CM_LERPTAG = 29,
+    /// Load a sound
+    UI_S_REGISTERSOUND = 31,
+    /// Play a sound without a position
+    UI_S_STARTLOCALSOUND = 32,
+    /// Get which parts of the client get key presses
+    UI_KEY_GETCATCHER = 40,
+    /// Set which parts of the client get key presses
+    UI_KEY_SETCATCHER = 41,
+    /// Get the text of the system clipboard
+    UI_GETCLIPBOARDDATA = 42,
+    /// Get the connection state of the client
+    UI_GETCLIENTSTATE = 44,
+    /// Get a configstring of the current server
+    UI_GETCONFIGSTRING = 45,
+    /// Get the free memory of the engine hunk
+    UI_MEMORY_REMAINING = 52,
+    /// Load a font
+    UI_R_REGISTERFONT = 55,
+    /// Stop the music
+    UI_S_STOPBACKGROUNDTRACK = 62,
+    /// Start playing music
+    UI_S_STARTBACKGROUNDTRACK = 63,
+    /// Start playing a RoQ cinematic
+    UI_CIN_PLAYCINEMATIC = 75,
+    /// Stop a cinematic
+    UI_CIN_STOPCINEMATIC = 76,
+    /// Advance a cinematic to the current time
+    UI_CIN_RUNCINEMATIC = 77,
+    /// Draw the current frame of a cinematic
+    UI_CIN_DRAWCINEMATIC = 78,
+    /// Move and resize a cinematic
+    UI_CIN_SETEXTENTS = 79,
+}
+
+impl From<Imports> for isize {
+    fn from(import: Imports) -> Self {
+        match import {
+            Imports::UI_ERROR => 0,
+            Imports::UI_PRINT => 1,
+            Imports::UI_MILLISECONDS => 2,
+            Imports::UI_ARGC => 10,
+            Imports::UI_ARGV => 11,
+            Imports::UI_CMD_EXECUTETEXT => 12,
+            Imports::UI_R_REGISTERMODEL => 18,
+            Imports::UI_R_REGISTERSHADERNOMIP => 20,
+            Imports::UI_UPDATESCREEN => 28,
+            Imports::UI_CM_LERPTAG => 29,
+            Imports::UI_S_REGISTERSOUND => 31,
+            Imports::UI_S_STARTLOCALSOUND => 32,
+            Imports::UI_KEY_GETCATCHER => 40,
+            Imports::UI_KEY_SETCATCHER => 41,
+            Imports::UI_GETCLIPBOARDDATA => 42,
+            Imports::UI_GETCLIENTSTATE => 44,
+            Imports::UI_GETCONFIGSTRING => 45,
+            Imports::UI_MEMORY_REMAINING => 52,
+            Imports::UI_R_REGISTERFONT => 55,
+            Imports::UI_S_STOPBACKGROUNDTRACK => 62,
+            Imports::UI_S_STARTBACKGROUNDTRACK => 63,
+            Imports::UI_CIN_PLAYCINEMATIC => 75,
+            Imports::UI_CIN_STOPCINEMATIC => 76,
+            Imports::UI_CIN_RUNCINEMATIC => 77,
+            Imports::UI_CIN_DRAWCINEMATIC => 78,
+            Imports::UI_CIN_SETEXTENTS => 79,
+        }
+    }
+}
+
+impl Imports {
+    /// All wrapped traps, in ascending order
+    pub const ALL: [Imports; 26] = [
+        Imports::UI_ERROR,
+        Imports::UI_PRINT,
+        Imports::UI_MILLISECONDS,
+        Imports::UI_ARGC,
+        Imports::UI_ARGV,
+        Imports::UI_CMD_EXECUTETEXT,
+        Imports::UI_R_REGISTERMODEL,
+        Imports::UI_R_REGISTERSHADERNOMIP,
+        Imports::UI_UPDATESCREEN,
+        Imports::UI_CM_LERPTAG,
+        Imports::UI_S_REGISTERSOUND,
+        Imports::UI_S_STARTLOCALSOUND,
+        Imports::UI_KEY_GETCATCHER,
+        Imports::UI_KEY_SETCATCHER,
+        Imports::UI_GETCLIPBOARDDATA,
+        Imports::UI_GETCLIENTSTATE,
+        Imports::UI_GETCONFIGSTRING,
+        Imports::UI_MEMORY_REMAINING,
+        Imports::UI_R_REGISTERFONT,
+        Imports::UI_S_STOPBACKGROUNDTRACK,
+        Imports::UI_S_STARTBACKGROUNDTRACK,
+        Imports::UI_CIN_PLAYCINEMATIC,
+        Imports::UI_CIN_STOPCINEMATIC,
+        Imports::UI_CIN_RUNCINEMATIC,
+        Imports::UI_CIN_DRAWCINEMATIC,
+        Imports::UI_CIN_SETEXTENTS,
+    ];
+
+    /// Name of the `uiImport_t` value, e.g. `"UI_PRINT"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Imports::UI_ERROR => "UI_ERROR",
+            Imports::UI_PRINT => "UI_PRINT",
+            Imports::UI_MILLISECONDS => "UI_MILLISECONDS",
+            Imports::UI_ARGC => "UI_ARGC",
+            Imports::UI_ARGV => "UI_ARGV",
+            Imports::UI_CMD_EXECUTETEXT => "UI_CMD_EXECUTETEXT",
+            Imports::UI_R_REGISTERMODEL => "UI_R_REGISTERMODEL",
+            Imports::UI_R_REGISTERSHADERNOMIP => "UI_R_REGISTERSHADERNOMIP",
+            Imports::UI_UPDATESCREEN => "UI_UPDATESCREEN",
+            Imports::UI_CM_LERPTAG => "UI_CM_LERPTAG",
+            Imports::UI_S_REGISTERSOUND => "UI_S_REGISTERSOUND",
+            Imports::UI_S_STARTLOCALSOUND => "UI_S_STARTLOCALSOUND",
+            Imports::UI_KEY_GETCATCHER => "UI_KEY_GETCATCHER",
+            Imports::UI_KEY_SETCATCHER => "UI_KEY_SETCATCHER",
+            Imports::UI_GETCLIPBOARDDATA => "UI_GETCLIPBOARDDATA",
+            Imports::UI_GETCLIENTSTATE => "UI_GETCLIENTSTATE",
+            Imports::UI_GETCONFIGSTRING => "UI_GETCONFIGSTRING",
+            Imports::UI_MEMORY_REMAINING => "UI_MEMORY_REMAINING",
+            Imports::UI_R_REGISTERFONT => "UI_R_REGISTERFONT",
+            Imports::UI_S_STOPBACKGROUNDTRACK => "UI_S_STOPBACKGROUNDTRACK",
+            Imports::UI_S_STARTBACKGROUNDTRACK => "UI_S_STARTBACKGROUNDTRACK",
+            Imports::UI_CIN_PLAYCINEMATIC => "UI_CIN_PLAYCINEMATIC",
+            Imports::UI_CIN_STOPCINEMATIC => "UI_CIN_STOPCINEMATIC",
+            Imports::UI_CIN_RUNCINEMATIC => "UI_CIN_RUNCINEMATIC",
+            Imports::UI_CIN_DRAWCINEMATIC => "UI_CIN_DRAWCINEMATIC",
+            Imports::UI_CIN_SETEXTENTS => "UI_CIN_SETEXTENTS",
+        }
+    }
+}
+
+/// Functions exported by the module
+///
+/// See `uiExport_t` in [ioquake3's `ui/ui_public.h`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_public.h).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Exports {
+    /// Version of the module API, has to be [`UI_API_VERSION`](UI_API_VERSION)
+    UI_GETAPIVERSION = 0,
+    /// Initialize module
+    UI_INIT = 1,
+    /// Shutdown module
+    UI_SHUTDOWN = 2,
+    /// Key pressed or released while a menu is active
+    UI_KEY_EVENT = 3,
+    /// Mouse moved while a menu is active
+    UI_MOUSE_EVENT = 4,
+    /// Draw the active menu
+    UI_REFRESH = 5,
+    /// Whether the active menu covers the whole screen
+    UI_IS_FULLSCREEN = 6,
+    /// Open a menu
+    UI_SET_ACTIVE_MENU = 7,
+    /// Client console text command
+    UI_CONSOLE_COMMAND = 8,
+    /// Draw the loading screen while connecting
+    UI_DRAW_CONNECT_SCREEN = 9,
+    /// Whether the mod has its own CD key
+    UI_HASUNIQUECDKEY = 10,
+}
+
+impl TryFrom<ffi::c_int> for Exports {
+    type Error = &'static str;
+
+    fn try_from(cmd: ffi::c_int) -> Result<Self, Self::Error> {
+        match cmd {
+            0 => Ok(Self::UI_GETAPIVERSION),
+            1 => Ok(Self::UI_INIT),
+            2 => Ok(Self::UI_SHUTDOWN),
+            3 => Ok(Self::UI_KEY_EVENT),
+            4 => Ok(Self::UI_MOUSE_EVENT),
+            5 => Ok(Self::UI_REFRESH),
+            6 => Ok(Self::UI_IS_FULLSCREEN),
+            7 => Ok(Self::UI_SET_ACTIVE_MENU),
+            8 => Ok(Self::UI_CONSOLE_COMMAND),
+            9 => Ok(Self::UI_DRAW_CONNECT_SCREEN),
+            10 => Ok(Self::UI_HASUNIQUECDKEY),
+            _ => Err("Unknown command"),
+        }
+    }
+}
+
+impl Exports {
+    /// All functions, in ascending order
+    pub const ALL: [Exports; 11] = [
+        Exports::UI_GETAPIVERSION,
+        Exports::UI_INIT,
+        Exports::UI_SHUTDOWN,
+        Exports::UI_KEY_EVENT,
+        Exports::UI_MOUSE_EVENT,
+        Exports::UI_REFRESH,
+        Exports::UI_IS_FULLSCREEN,
+        Exports::UI_SET_ACTIVE_MENU,
+        Exports::UI_CONSOLE_COMMAND,
+        Exports::UI_DRAW_CONNECT_SCREEN,
+        Exports::UI_HASUNIQUECDKEY,
+    ];
+
+    /// Name of the `uiExport_t` value, e.g. `"UI_INIT"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Exports::UI_GETAPIVERSION => "UI_GETAPIVERSION",
+            Exports::UI_INIT => "UI_INIT",
+            Exports::UI_SHUTDOWN => "UI_SHUTDOWN",
+            Exports::UI_KEY_EVENT => "UI_KEY_EVENT",
+            Exports::UI_MOUSE_EVENT => "UI_MOUSE_EVENT",
+            Exports::UI_REFRESH => "UI_REFRESH",
+            Exports::UI_IS_FULLSCREEN => "UI_IS_FULLSCREEN",
+            Exports::UI_SET_ACTIVE_MENU => "UI_SET_ACTIVE_MENU",
+            Exports::UI_CONSOLE_COMMAND => "UI_CONSOLE_COMMAND",
+            Exports::UI_DRAW_CONNECT_SCREEN => "UI_DRAW_CONNECT_SCREEN",
+            Exports::UI_HASUNIQUECDKEY => "UI_HASUNIQUECDKEY",
+        }
+    }
+}
+
+/// `ui` specific wrapper around generic [`Syscall`](Syscall)
+///
+/// This is a `Copy` handle to the engine, so every part of a mod can keep its own.
+/// Traps must only be called from the engine's thread, while it is running the module.
+///
+/// See [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+///
+/// The engine is any [`EngineSyscalls`](EngineSyscalls) backend, the default being the engine's [`Syscall`](Syscall).
+#[derive(Clone, Copy)]
+pub struct Syscalls<S = Syscall> {
+    engine: S,
+}
+
+impl Syscalls {
+    /// See `dllEntry` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn new(syscall: Syscall) -> Self {
+        Self::with_engine(syscall)
+    }
+}
+
+impl<S: EngineSyscalls> Syscalls<S> {
+    /// Wrapper around another backend than the engine's [`Syscall`](Syscall), e.g. a [`backend::Mock`](crate::backend::Mock)
+    pub fn with_engine(engine: S) -> Self {
+        Self { engine }
+    }
+
+    fn call(&self, import: Imports, args: &[ffi::intptr_t]) -> ffi::intptr_t {
+        metrics::global().count(import);
+        self.engine.syscall(import.into(), args)
+    }
+
+    /// Quit with an error message
+    ///
+    /// The engine does not return from this, it `longjmp`s out of the module or exits.
+    ///
+    /// See `trap_Error` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn error<T: Into<Vec<u8>>>(&self, text: T) -> ! {
+        let msg = to_cstring(text);
+        self.call(Imports::UI_ERROR, &[ffi::ptr_to_intptr(msg.as_ptr())]);
+        unreachable!("UI_ERROR returned")
+    }
+
+    /// Print a message to the console
+    ///
+    /// See `trap_Print` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn print<T: Into<Vec<u8>>>(&self, text: T) {
+        let msg = to_cstring(text);
+        self.call(Imports::UI_PRINT, &[ffi::ptr_to_intptr(msg.as_ptr())]);
+    }
+
+    /// Number of arguments of the current console command, including the command name
+    ///
+    /// See `trap_Argc` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn argc(&self) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::UI_ARGC, &[]))
+    }
+
+    /// Argument `n` of the current console command, `0` being the command name
+    ///
+    /// See `trap_Argv` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn argv(&self, n: ffi::c_int) -> String {
+        String::from_utf8_lossy(&self.argv_bytes(n)).into_owned()
+    }
+
+    /// Argument `n` as sent, for arguments that are not valid UTF-8
+    pub fn argv_bytes(&self, n: ffi::c_int) -> Vec<u8> {
+        let mut buffer = vec![0u8; MAX_STRING_CHARS];
+        self.call(
+            Imports::UI_ARGV,
+            &[
+                ffi::int_to_intptr(n),
+                ffi::ptr_to_intptr(buffer.as_mut_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+            ],
+        );
+        buffer_bytes(&buffer).to_vec()
+    }
+    /// Time since the engine started, in milliseconds
+    ///
+    /// See `trap_Milliseconds` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn milliseconds(&self) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::UI_MILLISECONDS, &[]))
+    }
+
+    /// Run `text` as console commands, e.g. `"connect localhost\n"` from a menu
+    ///
+    /// See `trap_Cmd_ExecuteText` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn cmd_execute_text<T: Into<Vec<u8>>>(&self, exec_when: CbufExec, text: T) {
+        let text = to_cstring(text);
+        self.call(
+            Imports::UI_CMD_EXECUTETEXT,
+            &[
+                ffi::int_to_intptr(exec_when as ffi::c_int),
+                ffi::ptr_to_intptr(text.as_ptr()),
+            ],
+        );
+    }
+
+    /// Which parts of the client get key presses
+    ///
+    /// See `trap_Key_GetCatcher` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn key_get_catcher(&self) -> KeyCatcher {
+        KeyCatcher::from_bits_retain(ffi::intptr_to_int(
+            self.call(Imports::UI_KEY_GETCATCHER, &[]),
+        ))
+    }
+
+    /// Set which parts of the client get key presses, e.g. [`KeyCatcher::UI`](KeyCatcher::UI) while a menu is open
+    ///
+    /// See `trap_Key_SetCatcher` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn key_set_catcher(&self, catcher: KeyCatcher) {
+        self.call(
+            Imports::UI_KEY_SETCATCHER,
+            &[ffi::int_to_intptr(catcher.bits())],
+        );
+    }
+
+    /// Text of the system clipboard, e.g. to paste a server address, empty if there is none
+    ///
+    /// See `trap_GetClipboardData` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn get_clipboard_data(&self) -> String {
+        let mut buffer = vec![0u8; MAX_STRING_CHARS];
+        self.call(
+            Imports::UI_GETCLIPBOARDDATA,
+            &[
+                ffi::ptr_to_intptr(buffer.as_mut_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+            ],
+        );
+        buffer_to_string(&buffer)
+    }
+
+    /// Free memory of the engine hunk in bytes, e.g. to warn before loading a big map
+    ///
+    /// See `trap_MemoryRemaining` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn memory_remaining(&self) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::UI_MEMORY_REMAINING, &[]))
+    }
+
+    /// Connection state of the client
+    ///
+    /// See `trap_GetClientState` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn get_client_state(&self) -> UiClientState {
+        let mut state = UiClientState::default();
+        self.call(
+            Imports::UI_GETCLIENTSTATE,
+            &[ffi::ptr_to_intptr(&mut state as *mut UiClientState)],
+        );
+        state
+    }
+
+    /// Configstring `num` of the current server, `None` if `num` is out of range
+    ///
+    /// E.g. parse [`CS_SERVERINFO`](crate::shared::CS_SERVERINFO) with [`InfoString`](crate::info::InfoString) for the `mapname`.
+    ///
+    /// ```rust,no_run
+    /// use quake3_native_vm::info::InfoString;
+    /// use quake3_native_vm::shared::CS_SERVERINFO;
+    /// use quake3_native_vm::ui::Syscalls;
+    ///
+    /// # fn example(syscalls: &Syscalls) -> Option<()> {
+    /// let serverinfo = InfoString::parse(&syscalls.get_config_string(CS_SERVERINFO)?).ok()?;
+    /// let map = serverinfo.get("mapname").unwrap_or("");
+    /// # Some(())
+    /// # }
+    /// ```
+    ///
+    /// See `trap_GetConfigString` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    pub fn get_config_string(&self, num: ffi::c_int) -> Option<String> {
+        // Big enough for `CS_SYSTEMINFO`
+        let mut buffer = vec![0u8; BIG_INFO_STRING];
+        let valid = self.call(
+            Imports::UI_GETCONFIGSTRING,
+            &[
+                ffi::int_to_intptr(num),
+                ffi::ptr_to_intptr(buffer.as_mut_ptr()),
+                ffi::len_to_intptr(buffer.len()),
+            ],
+        );
+        (ffi::intptr_to_int(valid) != 0).then(|| buffer_to_string(&buffer))
+    }
+}
+
+/// See `trap_R_RegisterModel` etc. in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+impl<S: EngineSyscalls> AssetTraps for Syscalls<S> {
+    fn r_register_model(&self, name: &str) -> ModelHandle {
+        let name = to_cstring(name);
+        ModelHandle(ffi::intptr_to_int(self.call(
+            Imports::UI_R_REGISTERMODEL,
+            &[ffi::ptr_to_intptr(name.as_ptr())],
+        )))
+    }
+
+    /// The `ui` module can only register shaders without mipmaps, as it draws nothing in 3D
+    fn r_register_shader(&self, name: &str) -> ShaderHandle {
+        let name = to_cstring(name);
+        ShaderHandle(ffi::intptr_to_int(self.call(
+            Imports::UI_R_REGISTERSHADERNOMIP,
+            &[ffi::ptr_to_intptr(name.as_ptr())],
+        )))
+    }
+
+    fn r_register_shader_nomip(&self, name: &str) -> ShaderHandle {
+        let name = to_cstring(name);
+        ShaderHandle(ffi::intptr_to_int(self.call(
+            Imports::UI_R_REGISTERSHADERNOMIP,
+            &[ffi::ptr_to_intptr(name.as_ptr())],
+        )))
+    }
+
+    fn s_register_sound(&self, name: &str, compressed: bool) -> SoundHandle {
+        let name = to_cstring(name);
+        SoundHandle(ffi::intptr_to_int(self.call(
+            Imports::UI_S_REGISTERSOUND,
+            &[
+                ffi::ptr_to_intptr(name.as_ptr()),
+                ffi::int_to_intptr(compressed.into()),
+            ],
+        )))
+    }
+
+    fn r_register_font(&self, name: &str, point_size: ffi::c_int, font: &mut FontInfo) {
+        let name = to_cstring(name);
+        self.call(
+            Imports::UI_R_REGISTERFONT,
+            &[
+                ffi::ptr_to_intptr(name.as_ptr()),
+                ffi::int_to_intptr(point_size),
+                ffi::ptr_to_intptr(font as *mut FontInfo),
+            ],
+        );
+    }
+}
+
+/// See `trap_CM_LerpTag` etc. in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+impl<S: EngineSyscalls> RenderTraps for Syscalls<S> {
+    fn r_lerp_tag(
+        &self,
+        model: ModelHandle,
+        start_frame: ffi::c_int,
+        end_frame: ffi::c_int,
+        fraction: f32,
+        tag_name: &str,
+    ) -> Option<Orientation> {
+        let tag_name = to_cstring(tag_name);
+        let mut tag = Orientation::default();
+        let found = self.call(
+            Imports::UI_CM_LERPTAG,
+            &[
+                ffi::ptr_to_intptr(&mut tag as *mut Orientation),
+                ffi::int_to_intptr(model.0),
+                ffi::int_to_intptr(start_frame),
+                ffi::int_to_intptr(end_frame),
+                ffi::float_to_intptr(fraction),
+                ffi::ptr_to_intptr(tag_name.as_ptr()),
+            ],
+        );
+        (ffi::intptr_to_int(found) != 0).then_some(tag)
+    }
+
+    fn update_screen(&self) {
+        self.call(Imports::UI_UPDATESCREEN, &[]);
+    }
+}
+
+/// See `trap_S_StartLocalSound` etc. in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+impl<S: EngineSyscalls> SoundTraps for Syscalls<S> {
+    fn s_start_local_sound(&self, sfx: SoundHandle, channel: SoundChannel) {
+        self.call(
+            Imports::UI_S_STARTLOCALSOUND,
+            &[
+                ffi::int_to_intptr(sfx.0),
+                ffi::int_to_intptr(channel as ffi::c_int),
+            ],
+        );
+    }
+
+    fn s_start_background_track(&self, intro: &str, looping: Option<&str>) {
+        let intro = to_cstring(intro);
+        // The engine loops the intro for an empty name
+        let looping = to_cstring(looping.unwrap_or(""));
+        self.call(
+            Imports::UI_S_STARTBACKGROUNDTRACK,
+            &[
+                ffi::ptr_to_intptr(intro.as_ptr()),
+                ffi::ptr_to_intptr(looping.as_ptr()),
+            ],
+        );
+    }
+
+    fn s_stop_background_track(&self) {
+        self.call(Imports::UI_S_STOPBACKGROUNDTRACK, &[]);
+    }
+}
+
+/// See `trap_CIN_PlayCinematic` etc. in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+impl<S: EngineSyscalls> CinematicTraps for Syscalls<S> {
+    fn cin_play_cinematic(
+        &self,
+        name: &str,
+        x: ffi::c_int,
+        y: ffi::c_int,
+        width: ffi::c_int,
+        height: ffi::c_int,
+        flags: CinematicFlags,
+    ) -> ffi::c_int {
+        let name = to_cstring(name);
+        ffi::intptr_to_int(self.call(
+            Imports::UI_CIN_PLAYCINEMATIC,
+            &[
+                ffi::ptr_to_intptr(name.as_ptr()),
+                ffi::int_to_intptr(x),
+                ffi::int_to_intptr(y),
+                ffi::int_to_intptr(width),
+                ffi::int_to_intptr(height),
+                ffi::int_to_intptr(flags.bits()),
+            ],
+        ))
+    }
+
+    fn cin_stop_cinematic(&self, handle: ffi::c_int) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::UI_CIN_STOPCINEMATIC, &[ffi::int_to_intptr(handle)]))
+    }
+
+    fn cin_run_cinematic(&self, handle: ffi::c_int) -> ffi::c_int {
+        ffi::intptr_to_int(self.call(Imports::UI_CIN_RUNCINEMATIC, &[ffi::int_to_intptr(handle)]))
+    }
+
+    fn cin_draw_cinematic(&self, handle: ffi::c_int) {
+        self.call(Imports::UI_CIN_DRAWCINEMATIC, &[ffi::int_to_intptr(handle)]);
+    }
+
+    fn cin_set_extents(
+        &self,
+        handle: ffi::c_int,
+        x: ffi::c_int,
+        y: ffi::c_int,
+        width: ffi::c_int,
+        height: ffi::c_int,
+    ) {
+        self.call(
+            Imports::UI_CIN_SETEXTENTS,
+            &[
+                ffi::int_to_intptr(handle),
+                ffi::int_to_intptr(x),
+                ffi::int_to_intptr(y),
+                ffi::int_to_intptr(width),
+                ffi::int_to_intptr(height),
+            ],
+        );
+    }
+}
+
+/// See `vmMain` in [ioquake3's `q3_ui/ui_main.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_main.c).
+pub trait Module: 'static + Sync + Send {
+    /// See `dllEntry` in [ioquake3's `ui/ui_syscalls.c`](https://github.com/ioquake/ioq3/blob/master/code/ui/ui_syscalls.c).
+    fn dll_entry(syscalls: Syscalls) -> Box<Self>
+    where
+        Self: Sized;
+
+    /// Version of the module API
+    ///
+    /// The engine refuses to load the module unless this is [`UI_API_VERSION`](UI_API_VERSION).
+    fn api_version(&self) -> ffi::c_int {
+        UI_API_VERSION
+    }
+
+    /// See `UI_Init` in [ioquake3's `q3_ui/ui_atoms.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_atoms.c).
+    fn init(&self, in_game_load: bool);
+
+    /// See `UI_Shutdown` in [ioquake3's `q3_ui/ui_atoms.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_atoms.c).
+    fn shutdown(&self);
+
+    /// See `UI_KeyEvent` in [ioquake3's `q3_ui/ui_atoms.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_atoms.c).
+    fn key_event(&self, key: ffi::c_int, down: bool);
+
+    /// See `UI_MouseEvent` in [ioquake3's `q3_ui/ui_atoms.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_atoms.c).
+    fn mouse_event(&self, dx: ffi::c_int, dy: ffi::c_int);
+
+    /// See `UI_Refresh` in [ioquake3's `q3_ui/ui_atoms.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_atoms.c).
+    fn refresh(&self, realtime: ffi::c_int);
+
+    /// See `UI_IsFullscreen` in [ioquake3's `q3_ui/ui_atoms.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_atoms.c).
+    fn is_fullscreen(&self) -> bool;
+
+    /// Open `menu`, or close all menus for [`MenuCommand::UIMENU_NONE`](MenuCommand::UIMENU_NONE)
+    ///
+    /// The module has to set [`KeyCatcher::UI`](KeyCatcher::UI) itself to get key events,
+    /// see [`Syscalls::key_set_catcher`](Syscalls::key_set_catcher).
+    ///
+    /// See `UI_SetActiveMenu` in [ioquake3's `q3_ui/ui_atoms.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_atoms.c).
+    fn set_active_menu(&self, menu: MenuCommand);
+
+    /// Return whether the command was handled
+    ///
+    /// See `UI_ConsoleCommand` in [ioquake3's `q3_ui/ui_atoms.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_atoms.c).
+    fn console_command(&self, realtime: ffi::c_int) -> bool;
+
+    /// See `UI_DrawConnectScreen` in [ioquake3's `q3_ui/ui_connect.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_connect.c).
+    fn draw_connect_screen(&self, overlay: bool);
+
+    /// Whether the mod has its own CD key, always `false` for mods without one
+    fn has_unique_cd_key(&self) -> bool {
+        false
+    }
+}
+
+/// Call the [`Module`](Module) function for `command` with its arguments from `args`
+///
+/// # Panics
+///
+/// Panics if `command` is not a known [`Exports`](Exports) value,
+/// or the menu of [`Exports::UI_SET_ACTIVE_MENU`](Exports::UI_SET_ACTIVE_MENU) not a known [`MenuCommand`](MenuCommand).
+///
+/// See `vmMain` in [ioquake3's `q3_ui/ui_main.c`](https://github.com/ioquake/ioq3/blob/master/code/q3_ui/ui_main.c).
+pub fn dispatch(module: &dyn Module, command: ffi::c_int, args: [ffi::c_int; 12]) -> VmResult {
+    let command = match Exports::try_from(command) {
+        Ok(command) => command,
+        Err(_) => panic!("Unknown ui command {:?}", command),
+    };
+    metrics::global().count(command);
+
+    match command {
+        Exports::UI_GETAPIVERSION => VmResult::int(module.api_version()),
+        Exports::UI_INIT => {
+            module.init(args[0] != 0);
+            VmResult::ok()
+        }
+        Exports::UI_SHUTDOWN => {
+            module.shutdown();
+            VmResult::ok()
+        }
+        Exports::UI_KEY_EVENT => {
+            module.key_event(args[0], args[1] != 0);
+            VmResult::ok()
+        }
+        Exports::UI_MOUSE_EVENT => {
+            module.mouse_event(args[0], args[1]);
+            VmResult::ok()
+        }
+        Exports::UI_REFRESH => {
+            module.refresh(args[0]);
+            VmResult::ok()
+        }
+        Exports::UI_IS_FULLSCREEN => VmResult::bool(module.is_fullscreen()),
+        Exports::UI_SET_ACTIVE_MENU => {
+            let menu = match MenuCommand::try_from(args[0]) {
+                Ok(menu) => menu,
+                Err(_) => panic!("Unknown ui menu {:?}", args[0]),
+            };
+            module.set_active_menu(menu);
+            VmResult::ok()
+        }
+        Exports::UI_CONSOLE_COMMAND => VmResult::bool(module.console_command(args[0])),
+        Exports::UI_DRAW_CONNECT_SCREEN => {
+            module.draw_connect_screen(args[0] != 0);
+            VmResult::ok()
+        }
+        Exports::UI_HASUNIQUECDKEY => VmResult::bool(module.has_unique_cd_key()),
+    }
+}
+
+/// Create a [NativeVM](crate::NativeVM) impl for the id Quake 3 `ui` module
+///
+/// Export names can be given like for [`native_vm!`](crate::native_vm), e.g.
+/// `ui_module!(MyModule, dll_entry = ["dllEntry"], vm_main = ["vmMain"])`.
+#[macro_export]
+macro_rules! ui_module {
+    ($ty:ty $(, $($exports:tt)+)?) => {
+        // Anonymous const, so the wrapper can't clash with names in the caller's module
+        const _: () = {
+            struct ModuleWrapper {
+                module: ::std::boxed::Box<dyn $crate::ui::Module>,
+            }
+
+            impl $crate::NativeVM for ModuleWrapper {
+                fn dll_entry(syscall: $crate::Syscall) -> ::std::boxed::Box<Self> {
+                    ::std::boxed::Box::new(ModuleWrapper {
+                        module: <$ty as $crate::ui::Module>::dll_entry($crate::ui::Syscalls::new(
+                            syscall,
+                        )),
+                    })
+                }
+
+                fn vm_main(
+                    &self,
+                    command: $crate::ffi::c_int,
+                    arg0: $crate::ffi::c_int,
+                    arg1: $crate::ffi::c_int,
+                    arg2: $crate::ffi::c_int,
+                    arg3: $crate::ffi::c_int,
+                    arg4: $crate::ffi::c_int,
+                    arg5: $crate::ffi::c_int,
+                    arg6: $crate::ffi::c_int,
+                    arg7: $crate::ffi::c_int,
+                    arg8: $crate::ffi::c_int,
+                    arg9: $crate::ffi::c_int,
+                    arg10: $crate::ffi::c_int,
+                    arg11: $crate::ffi::c_int,
+                ) -> $crate::ffi::intptr_t {
+                    $crate::ui::dispatch(
+                        &*self.module,
+                        command,
+                        [
+                            arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8, arg9, arg10,
+                            arg11,
+                        ],
+                    )
+                    .into_raw()
+                }
+            }
+
+            $crate::native_vm!(ModuleWrapper $(, $($exports)+)?);
+        };
+    };
+}
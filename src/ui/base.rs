@@ -0,0 +1,74 @@
+//! Minimal menus to build upon
+//!
+//! Requires the `base` feature.
+//!
+//! [`BaseUi`](BaseUi) reports the right [`UI_API_VERSION`](super::UI_API_VERSION) so the engine loads it,
+//! but has no menus. Use the console to connect, e.g. `connect localhost`.
+//! Mods can keep one and delegate to it until they have menus of their own.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use quake3_native_vm::ui::base::BaseUi;
+//! use quake3_native_vm::ui_module;
+//!
+//! ui_module!(BaseUi);
+//! # fn main() {}
+//! ```
+
+use super::{MenuCommand, Module, Syscalls};
+use crate::ffi;
+use std::fmt;
+
+/// [`Module`](super::Module) that satisfies the engine, but has no menus
+pub struct BaseUi {
+    syscalls: Syscalls,
+}
+
+impl BaseUi {
+    /// Create the module, to be initialized by the engine with [`init`](BaseUi::init)
+    pub fn new(syscalls: Syscalls) -> Self {
+        Self { syscalls }
+    }
+
+    /// Syscalls of the engine
+    pub fn syscalls(&self) -> &Syscalls {
+        &self.syscalls
+    }
+}
+
+impl Module for BaseUi {
+    fn dll_entry(syscalls: Syscalls) -> Box<Self> {
+        Box::new(Self::new(syscalls))
+    }
+
+    fn init(&self, _in_game_load: bool) {
+        self.syscalls.print("BaseUi initialized, use the console\n");
+    }
+
+    fn shutdown(&self) {}
+
+    fn key_event(&self, _key: ffi::c_int, _down: bool) {}
+
+    fn mouse_event(&self, _dx: ffi::c_int, _dy: ffi::c_int) {}
+
+    fn refresh(&self, _realtime: ffi::c_int) {}
+
+    fn is_fullscreen(&self) -> bool {
+        false
+    }
+
+    fn set_active_menu(&self, _menu: MenuCommand) {}
+
+    fn console_command(&self, _realtime: ffi::c_int) -> bool {
+        false
+    }
+
+    fn draw_connect_screen(&self, _overlay: bool) {}
+}
+
+impl fmt::Debug for BaseUi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BaseUi").finish_non_exhaustive()
+    }
+}
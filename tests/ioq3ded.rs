@@ -0,0 +1,161 @@
+//! End-to-end test of a native module in a headless ioquake3 dedicated server
+//!
+//! Ignored by default, run it with the environment variable `IOQ3DED` set to the path of an `ioq3ded` binary, e.g.
+//! `IOQ3DED=~/ioquake3/ioq3ded.x86_64 cargo test --test ioq3ded -- --ignored --nocapture`.
+//!
+//! The server needs the game data to load a map, i.e. `baseq3/pak0.pk3` in its directory or in
+//! `IOQ3_BASEPATH`. The map is `q3dm1` unless set with `IOQ3_MAP`.
+//!
+//! The `hello` example is built and installed into a temporary `fs_homepath` with
+//! [`install::deploy`](quake3_native_vm::install::deploy), then loaded with `+set vm_game 0`.
+
+use quake3_native_vm::dll::ModuleKind;
+use quake3_native_vm::install;
+use std::env;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Environment variable with the path of the `ioq3ded` binary
+const IOQ3DED_VAR: &str = "IOQ3DED";
+/// Environment variable with the `fs_basepath` of the server, defaults to the directory of the binary
+const BASEPATH_VAR: &str = "IOQ3_BASEPATH";
+/// Environment variable with the map to load
+const MAP_VAR: &str = "IOQ3_MAP";
+
+/// How long the server may take to load the map and quit
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Build an example as `cdylib` with the profile of this test, returning the path of the library
+fn build_example(name: &str) -> PathBuf {
+    // e.g. `target/debug/deps/ioq3ded-0123abcd`
+    let exe = env::current_exe().unwrap();
+    let profile_dir = exe.parent().and_then(Path::parent).unwrap();
+
+    let mut cargo = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()));
+    cargo
+        .args(["build", "--example", name])
+        .current_dir(env!("CARGO_MANIFEST_DIR"));
+    if profile_dir.ends_with("release") {
+        cargo.arg("--release");
+    }
+    let status = cargo.status().unwrap();
+    assert!(status.success(), "Could not build example {}", name);
+
+    profile_dir.join("examples").join(format!(
+        "{}{}{}",
+        env::consts::DLL_PREFIX,
+        name,
+        env::consts::DLL_SUFFIX
+    ))
+}
+
+/// Console output of a dedicated server running `args`, up to when it exits or [`TIMEOUT`](TIMEOUT)
+fn run_server(ioq3ded: &str, args: &[String]) -> Vec<String> {
+    let mut server = Command::new(ioq3ded)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|e| panic!("Could not launch {}: {}", ioq3ded, e));
+
+    let (sender, lines) = mpsc::channel();
+    let stdout = server.stdout.take().unwrap();
+    let stderr = server.stderr.take().unwrap();
+    let readers = [
+        thread::spawn({
+            let sender = sender.clone();
+            move || {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    let _ = sender.send(line);
+                }
+            }
+        }),
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                let _ = sender.send(line);
+            }
+        }),
+    ];
+
+    let deadline = Instant::now() + TIMEOUT;
+    let mut output = Vec::new();
+    loop {
+        match lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => output.push(line),
+            // Both pipes closed, i.e. the server exited
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let _ = server.kill();
+                break;
+            }
+        }
+    }
+    let _ = server.wait();
+    for reader in readers {
+        let _ = reader.join();
+    }
+    output
+}
+
+#[test]
+#[ignore = "needs an ioq3ded binary in IOQ3DED"]
+fn hello_in_ioq3ded() {
+    let ioq3ded = env::var(IOQ3DED_VAR)
+        .unwrap_or_else(|_| panic!("Set {} to the path of an ioq3ded binary", IOQ3DED_VAR));
+
+    let library = build_example("hello");
+    let home = env::temp_dir().join(format!("quake3-native-vm-ioq3ded-{}", std::process::id()));
+    install::deploy(library, &home, "rust", ModuleKind::Game).unwrap();
+
+    let mut args: Vec<String> = vec![
+        "+set".into(),
+        "dedicated".into(),
+        "1".into(),
+        "+set".into(),
+        "fs_homepath".into(),
+        home.display().to_string(),
+        "+set".into(),
+        "fs_game".into(),
+        "rust".into(),
+        "+set".into(),
+        "vm_game".into(),
+        "0".into(),
+        "+set".into(),
+        "sv_pure".into(),
+        "0".into(),
+    ];
+    if let Ok(basepath) = env::var(BASEPATH_VAR) {
+        args.extend(["+set".into(), "fs_basepath".into(), basepath]);
+    }
+    let map = env::var(MAP_VAR).unwrap_or_else(|_| "q3dm1".to_owned());
+    args.extend(["+map".into(), map, "+quit".into()]);
+
+    let output = run_server(&ioq3ded, &args);
+    let _ = std::fs::remove_dir_all(&home);
+
+    let console = output.join("\n");
+    assert!(
+        output
+            .iter()
+            .any(|line| line.starts_with("Loading DLL file")),
+        "ioq3ded did not load the native module:\n{}",
+        console
+    );
+    assert!(
+        output
+            .iter()
+            .any(|line| line.starts_with("init: level_time=")),
+        "Module was not initialized:\n{}",
+        console
+    );
+    assert!(
+        output.iter().any(|line| line == "shutdown: restart=false"),
+        "Module was not shut down:\n{}",
+        console
+    );
+}
@@ -0,0 +1,13 @@
+//! Invocations of the exported macros in places other than the crate root
+
+#[test]
+fn native_vm() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/native_vm_*.rs");
+}
+
+#[test]
+fn game_module() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/game_module_*.rs");
+}
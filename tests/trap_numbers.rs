@@ -0,0 +1,220 @@
+//! Trap numbers of the wrappers against the enums in the engine headers
+//!
+//! Requires the `check-headers` feature, run with `cargo test --features check-headers`.
+//! Unlike the `bindgen` feature this parses the headers itself, so it does not need libclang.
+//!
+//! The headers are verbatim copies of the upstream files in `include/upstream/`, made by `include/vendor.sh`
+//! at the commit recorded in the `COMMIT` file next to them. The hand-written excerpts in `include/ioq3/`
+//! that `bindgen` reads are checked against them as well.
+
+use quake3_native_vm::engine::Engine;
+use quake3_native_vm::{cgame, ffi, qagame, ui};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Directory in `include/upstream/` with the headers of `engine`
+fn headers(engine: Engine) -> &'static str {
+    match engine {
+        Engine::Ioquake3 => "ioq3",
+        Engine::Quake3 => "quake3",
+    }
+}
+
+/// `path` in `include/`
+fn include(path: &[&str]) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "include"]
+        .iter()
+        .chain(path)
+        .collect()
+}
+
+/// Upstream commit the headers of `engine` were copied from
+fn commit(engine: Engine) -> String {
+    let path = include(&["upstream", headers(engine), "COMMIT"]);
+    let commit = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "{}: {}, copy the upstream headers with include/vendor.sh",
+            path.display(),
+            e
+        )
+    });
+    let commit = commit.trim().to_owned();
+    assert!(
+        commit.len() == 40 && commit.bytes().all(|b| b.is_ascii_hexdigit()),
+        "{} is not a full commit hash: {:?}",
+        path.display(),
+        commit
+    );
+    commit
+}
+
+/// `text` without `/* */` and `//` comments, which may contain commas
+fn strip_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = [rest.find("/*"), rest.find("//")]
+        .into_iter()
+        .flatten()
+        .min()
+    {
+        stripped.push_str(&rest[..start]);
+        let comment = &rest[start..];
+        rest = if comment.starts_with("/*") {
+            // Comments separate tokens like whitespace
+            stripped.push(' ');
+            comment.split_once("*/").map_or("", |(_, tail)| tail)
+        } else {
+            comment.find('\n').map_or("", |end| &comment[end..])
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Values of the constants of the C enum `name` in the header `text`
+fn parse_enum_text(text: &str, name: &str) -> Option<BTreeMap<String, isize>> {
+    let text = strip_comments(text);
+    let end = text.find(&format!("}} {};", name))?;
+    let start = text[..end].rfind("typedef enum {")? + "typedef enum {".len();
+
+    let mut values = BTreeMap::new();
+    let mut next = 0;
+    for item in text[start..end].split(',') {
+        let item = item.trim();
+        if item.is_empty() {
+            continue;
+        }
+        let constant = match item.split_once('=') {
+            Some((constant, value)) => {
+                next = value.trim().parse().unwrap();
+                constant.trim()
+            }
+            None => item,
+        };
+        values.insert(constant.to_owned(), next);
+        next += 1;
+    }
+    Some(values)
+}
+
+/// Values of the constants of the C enum `name` in the upstream `header` of `engine`
+fn parse_enum(engine: Engine, header: &str, name: &str) -> BTreeMap<String, isize> {
+    // Point to include/vendor.sh if the headers are missing
+    commit(engine);
+    parse_enum_file(include(&["upstream", headers(engine), header]), name)
+}
+
+/// Values of the constants of the C enum `name` in the file `path`
+fn parse_enum_file(path: PathBuf, name: &str) -> BTreeMap<String, isize> {
+    let text = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    parse_enum_text(&text, name).unwrap_or_else(|| panic!("No enum {} in {}", name, path.display()))
+}
+
+fn assert_value(values: &BTreeMap<String, isize>, engine: Engine, name: &str, value: isize) {
+    assert_eq!(
+        values.get(name),
+        Some(&value),
+        "{} for {:?} at {}",
+        name,
+        engine,
+        commit(engine)
+    );
+}
+
+#[test]
+fn qagame() {
+    for engine in Engine::ALL {
+        let imports = parse_enum(engine, "game/g_public.h", "gameImport_t");
+        for import in qagame::Imports::ALL {
+            let name = import.name();
+            assert_value(&imports, engine, name, import.number(engine));
+        }
+
+        let exports = parse_enum(engine, "game/g_public.h", "gameExport_t");
+        for export in qagame::Exports::ALL {
+            let name = export.name();
+            assert_value(&exports, engine, name, export as ffi::c_int as isize);
+        }
+    }
+}
+
+#[test]
+fn cgame() {
+    for engine in Engine::ALL {
+        let imports = parse_enum(engine, "cgame/cg_public.h", "cgameImport_t");
+        for import in cgame::Imports::ALL {
+            let name = import.name();
+            assert_value(&imports, engine, name, import.into());
+        }
+
+        let exports = parse_enum(engine, "cgame/cg_public.h", "cgameExport_t");
+        for export in cgame::Exports::ALL {
+            let name = export.name();
+            assert_value(&exports, engine, name, export as ffi::c_int as isize);
+        }
+    }
+}
+
+#[test]
+fn ui() {
+    for engine in Engine::ALL {
+        let imports = parse_enum(engine, "ui/ui_public.h", "uiImport_t");
+        for import in ui::Imports::ALL {
+            let name = import.name();
+            assert_value(&imports, engine, name, import.into());
+        }
+
+        let exports = parse_enum(engine, "ui/ui_public.h", "uiExport_t");
+        for export in ui::Exports::ALL {
+            let name = export.name();
+            assert_value(&exports, engine, name, export as ffi::c_int as isize);
+        }
+    }
+}
+
+#[test]
+fn excerpts() {
+    let enums = [
+        ("game/g_public.h", "gameImport_t"),
+        ("game/g_public.h", "gameExport_t"),
+        ("cgame/cg_public.h", "cgameImport_t"),
+        ("cgame/cg_public.h", "cgameExport_t"),
+        ("ui/ui_public.h", "uiImport_t"),
+        ("ui/ui_public.h", "uiExport_t"),
+    ];
+    for (header, name) in enums {
+        let upstream = parse_enum(Engine::Ioquake3, header, name);
+        let excerpt = parse_enum_file(include(&["ioq3", header]), name);
+        for (constant, &value) in &excerpt {
+            assert_value(&upstream, Engine::Ioquake3, constant, value);
+        }
+    }
+}
+
+#[test]
+fn parse_with_comments() {
+    let header = r#"
+/* typedef enum { NOT_THIS } gameImport_t; */
+typedef enum {
+	G_PRINT,		// ( const char *string );
+	// print message on the local console
+
+	G_ERROR,		// ( const char *string );
+	/* abort the game, see G_PRINT, G_MILLISECONDS */
+
+	BOTLIB_SETUP = 200,	// ( void );
+	BOTLIB_SHUTDOWN
+} gameImport_t;
+"#;
+    let values = parse_enum_text(header, "gameImport_t").unwrap();
+    assert_eq!(
+        values.into_iter().collect::<Vec<_>>(),
+        [
+            ("BOTLIB_SETUP".to_owned(), 200),
+            ("BOTLIB_SHUTDOWN".to_owned(), 201),
+            ("G_ERROR".to_owned(), 1),
+            ("G_PRINT".to_owned(), 0),
+        ]
+    );
+}
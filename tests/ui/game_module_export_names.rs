@@ -0,0 +1,42 @@
+// Export names and aliases, together with the crash report
+use quake3_native_vm::ffi;
+use quake3_native_vm::qagame::{Deny, Module, Syscalls};
+
+struct Game;
+
+impl Module for Game {
+    fn dll_entry(_syscalls: Syscalls) -> Box<Self> {
+        Box::new(Game)
+    }
+    fn init(&self, _level_time: ffi::c_int, _random_seed: ffi::c_int, _restart: bool) {}
+    fn shutdown(&self, _restart: bool) {}
+    fn client_connect(
+        &self,
+        _client_number: ffi::c_int,
+        _first_time: bool,
+        _is_bot: bool,
+    ) -> Result<(), Deny> {
+        Ok(())
+    }
+    fn client_think(&self, _client_number: ffi::c_int) {}
+    fn client_userinfo_changed(&self, _client_number: ffi::c_int) {}
+    fn client_disconnect(&self, _client_number: ffi::c_int) {}
+    fn client_begin(&self, _client_number: ffi::c_int) {}
+    fn client_command(&self, _client_number: ffi::c_int) {}
+    fn run_frame(&self, _level_time: ffi::c_int) {}
+    fn console_command(&self) -> bool {
+        false
+    }
+    fn botai_start_frame(&self, _level_time: ffi::c_int) -> bool {
+        false
+    }
+}
+
+quake3_native_vm::game_module!(
+    Game,
+    crash_report,
+    dll_entry = ["dllEntry"],
+    vm_main = ["vmMain", "VM_Main"],
+);
+
+fn main() {}
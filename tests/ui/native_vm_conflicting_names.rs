@@ -0,0 +1,44 @@
+// Names the macro used to bring into scope or define itself
+use std::sync::{Arc, RwLock};
+
+use quake3_native_vm::{ffi, native_vm, Syscall};
+
+#[allow(dead_code)]
+static _VM_IMPL: Option<Arc<RwLock<()>>> = None;
+
+#[allow(dead_code)]
+mod once_cell {}
+
+#[allow(dead_code)]
+trait NativeVM {}
+
+struct Game;
+
+impl quake3_native_vm::NativeVM for Game {
+    fn dll_entry(_syscall: Syscall) -> Box<Self> {
+        Box::new(Game)
+    }
+
+    fn vm_main(
+        &self,
+        _command: ffi::c_int,
+        _arg0: ffi::c_int,
+        _arg1: ffi::c_int,
+        _arg2: ffi::c_int,
+        _arg3: ffi::c_int,
+        _arg4: ffi::c_int,
+        _arg5: ffi::c_int,
+        _arg6: ffi::c_int,
+        _arg7: ffi::c_int,
+        _arg8: ffi::c_int,
+        _arg9: ffi::c_int,
+        _arg10: ffi::c_int,
+        _arg11: ffi::c_int,
+    ) -> ffi::intptr_t {
+        0
+    }
+}
+
+native_vm!(Game);
+
+fn main() {}
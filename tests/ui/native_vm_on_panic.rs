@@ -0,0 +1,43 @@
+// Panic handler and export names
+use quake3_native_vm::{ffi, native_vm, Syscall};
+use std::any::Any;
+
+fn report_panic(payload: &(dyn Any + Send), command: Option<ffi::c_int>) {
+    eprintln!("{} in {:?}", quake3_native_vm::panic_message(payload), command);
+}
+
+struct Game;
+
+impl quake3_native_vm::NativeVM for Game {
+    fn dll_entry(_syscall: Syscall) -> Box<Self> {
+        Box::new(Game)
+    }
+
+    fn vm_main(
+        &self,
+        _command: ffi::c_int,
+        _arg0: ffi::c_int,
+        _arg1: ffi::c_int,
+        _arg2: ffi::c_int,
+        _arg3: ffi::c_int,
+        _arg4: ffi::c_int,
+        _arg5: ffi::c_int,
+        _arg6: ffi::c_int,
+        _arg7: ffi::c_int,
+        _arg8: ffi::c_int,
+        _arg9: ffi::c_int,
+        _arg10: ffi::c_int,
+        _arg11: ffi::c_int,
+    ) -> ffi::intptr_t {
+        0
+    }
+}
+
+native_vm!(
+    Game,
+    on_panic = report_panic,
+    dll_entry = ["dllEntry"],
+    vm_main = ["vmMain", "VM_Main"],
+);
+
+fn main() {}
@@ -0,0 +1,43 @@
+// `vmMain` arguments of other engines
+use quake3_native_vm::{ffi, native_vm, Syscall};
+struct Game;
+
+impl quake3_native_vm::NativeVM for Game {
+    fn dll_entry(_syscall: Syscall) -> Box<Self> {
+        Box::new(Game)
+    }
+
+    fn vm_main(
+        &self,
+        _command: ffi::c_int,
+        _arg0: ffi::c_int,
+        _arg1: ffi::c_int,
+        _arg2: ffi::c_int,
+        _arg3: ffi::c_int,
+        _arg4: ffi::c_int,
+        _arg5: ffi::c_int,
+        _arg6: ffi::c_int,
+        _arg7: ffi::c_int,
+        _arg8: ffi::c_int,
+        _arg9: ffi::c_int,
+        _arg10: ffi::c_int,
+        _arg11: ffi::c_int,
+    ) -> ffi::intptr_t {
+        0
+    }
+}
+
+native_vm!(Game, vm_main_args = [intptr_t; 7]);
+
+mod without_args {
+    use quake3_native_vm::native_vm;
+
+    native_vm!(
+        super::Game,
+        vm_main_args = [int; 0],
+        dll_entry = ["dllEntryNoArgs"],
+        vm_main = ["vmMainNoArgs"],
+    );
+}
+
+fn main() {}